@@ -9,6 +9,7 @@ import com.afoxer.xxx.ffi.StructSimple;
 import com.afoxer.xxx.ffi.TestContract1;
 
 import java.util.Arrays;
+import java.util.Map;
 
 
 public class MainActivity extends AppCompatActivity {
@@ -73,6 +74,44 @@ public class MainActivity extends AppCompatActivity {
                 return true;
             }
 
+            @Override
+            public byte[] on_callback_u8_vec(byte[] arg1) {
+                return arg1;
+            }
+
+            @Override
+            public byte[] on_callback_i8_vec(byte[] arg1) {
+                return arg1;
+            }
+
+            @Override
+            public String on_callback_return_str(int arg1) {
+                return "java " + arg1;
+            }
+
+            @Override
+            public StructSimple on_callback_return_struct(int arg1) {
+                StructSimple result = new StructSimple();
+                result.arg1 = arg1;
+                result.arg3 = "java";
+                return result;
+            }
+
+            @Override
+            public StructSimple[] on_callback_return_vec(int arg1) {
+                return new StructSimple[]{on_callback_return_struct(arg1)};
+            }
+
+            @Override
+            public String on_callback_return_option(boolean arg1) {
+                return arg1 ? "java" : null;
+            }
+
+            @Override
+            public void on_callback_map(Map<String, Long> arg1) {
+                Log.i(TAG, "on_callback_map: " + arg1);
+            }
+
             @Override
             public void on_empty_callback() {
                 Log.i(TAG, "on_empty_callback: ");
@@ -49,6 +49,34 @@ class ViewController: UIViewController, Callback{
         return true
     }
     
+    func on_callback_u8_vec(arg1: [Int8]) -> [Int8] {
+        return arg1
+    }
+    
+    func on_callback_i8_vec(arg1: [Int8]) -> [Int8] {
+        return arg1
+    }
+    
+    func on_callback_return_str(arg1: Int) -> String {
+        return "swift \(arg1)"
+    }
+    
+    func on_callback_return_struct(arg1: Int) -> StructSimple {
+        return StructSimple(arg1: arg1, arg2: 0, arg3: "swift", arg4: true, arg5: 0.0, art6: 0.0)
+    }
+    
+    func on_callback_return_vec(arg1: Int) -> [StructSimple] {
+        return [on_callback_return_struct(arg1: arg1)]
+    }
+    
+    func on_callback_return_option(arg1: Bool) -> String? {
+        return arg1 ? "swift" : nil
+    }
+    
+    func on_callback_map(arg1: [String: Int64]) {
+        print("on_callback_map: \(arg1)")
+    }
+    
     func update_progress(key: String, status: Int, bytes_transferred: Int64, bytes_total: Int64) -> Bool {
         print("update process \(key), \(status), \(bytes_transferred), \(bytes_total)")
         return true
@@ -58,24 +86,24 @@ class ViewController: UIViewController, Callback{
     override func viewDidLoad() {
         super.viewDidLoad()
         
-        let result = TestContract1.test_struct_vec()
+        let result = try! TestContract1.test_struct_vec()
         print("result = \(result)")
         // Do any additional setup after loading the view, typically from a nib.
         
-        TestContract1.test_arg_callback(arg: self)
-        let struct_imp = TestContract1.test_struct();
+        try! TestContract1.test_arg_callback(arg: self)
+        let struct_imp = try! TestContract1.test_struct();
         print("struct = \(struct_imp)")
         
-        let struct_vec = TestContract1.test_struct_vec()
+        let struct_vec = try! TestContract1.test_struct_vec()
         print("struct_vec = \(struct_imp)")
         
-        let return_vec = TestContract1.test_return_vec(arg: 43)
+        let return_vec = try! TestContract1.test_return_vec(arg: 43)
         print("return_vec = \(return_vec)")
         
-        let byte_result = TestContract1.test_byte(arg: 44)
+        let byte_result = try! TestContract1.test_byte(arg: 44)
         print("byte result = \(byte_result)")
         
-        let i8_result = TestContract1.test_byte_i8(arg: 55)
+        let i8_result = try! TestContract1.test_byte_i8(arg: 55)
         print("i8 result = \(i8_result)")
 
     }
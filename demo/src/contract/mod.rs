@@ -1 +1,2 @@
 pub mod test_contract1;
+pub mod test_contract2;
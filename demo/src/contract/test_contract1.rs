@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 pub trait TestContract1 {
     fn test_byte(arg: u8) -> u8;
     fn test_byte_i8(arg: i8) -> i8;
@@ -11,7 +13,17 @@ pub trait TestContract1 {
     fn test_return_vec_u8(input: Vec<u8>) -> Vec<u8>;
     fn test_return_vec_i8(input: Vec<i8>) -> Vec<i8>;
     fn test_no_return();
-    //    fn test_return_callback(arg: bool) -> Box<Callback>;
+    fn test_enum(arg: EnumSimple) -> EnumSimple;
+    fn test_data_enum(arg: i32) -> EnumWithData;
+    fn test_option(arg: Option<String>) -> Option<i32>;
+    fn test_result(arg: i32) -> Result<String, ErrorSimple>;
+    fn test_return_callback(arg: bool) -> Box<Callback>;
+}
+
+pub trait Counter {
+    fn new(start: i32) -> Self;
+    fn increase(&mut self, step: i32) -> i32;
+    fn current(&self) -> i32;
 }
 
 pub trait Callback: Sync {
@@ -22,8 +34,13 @@ pub trait Callback: Sync {
     fn on_callback_complex(&self, arg1: StructSimple) -> bool;
     fn on_callback_arg_vec(&self, arg1: Vec<StructSimple>) -> bool;
     fn on_callback_arg_vec_simple(&self, arg1: Vec<String>) -> bool;
-    // fn on_callback_u8_vec(arg1: Vec<u8>) -> Vec<u8>;
-    // fn on_callback_i8_vec(arg1: Vec<i8>) -> Vec<i8>;
+    fn on_callback_u8_vec(&self, arg1: Vec<u8>) -> Vec<u8>;
+    fn on_callback_i8_vec(&self, arg1: Vec<i8>) -> Vec<i8>;
+    fn on_callback_return_str(&self, arg1: i32) -> String;
+    fn on_callback_return_struct(&self, arg1: i32) -> StructSimple;
+    fn on_callback_return_vec(&self, arg1: i32) -> Vec<StructSimple>;
+    fn on_callback_return_option(&self, arg1: bool) -> Option<String>;
+    fn on_callback_map(&self, arg1: HashMap<String, i64>);
     fn on_empty_callback(&self);
 }
 
@@ -35,3 +52,20 @@ pub struct StructSimple {
     pub arg5: f32,
     pub art6: f64,
 }
+
+pub struct ErrorSimple {
+    pub code: i32,
+    pub msg: String,
+}
+
+pub enum EnumSimple {
+    First,
+    Second = 5,
+    Third,
+}
+
+pub enum EnumWithData {
+    Named { arg1: i32, arg2: String },
+    Unnamed(String, bool),
+    Unit,
+}
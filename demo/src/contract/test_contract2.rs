@@ -0,0 +1,18 @@
+use contract::test_contract1::EnumSimple;
+use contract::test_contract1::StructSimple;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+pub trait TestContract2 {
+    fn test_struct_nested(arg: i32) -> StructNested;
+    fn test_map(arg: HashMap<String, i32>) -> BTreeMap<i32, StructSimple>;
+}
+
+pub struct StructNested {
+    pub name: String,
+    pub simple: StructSimple,
+    pub simples: Vec<StructSimple>,
+    pub optional_simple: Option<StructSimple>,
+    pub enums: Vec<EnumSimple>,
+    pub attrs: HashMap<String, String>,
+}
@@ -1 +1,2 @@
 pub mod test_contract1_imp;
+pub mod test_contract2_imp;
@@ -1,6 +1,11 @@
 use contract::test_contract1::Callback;
+use contract::test_contract1::Counter;
+use contract::test_contract1::EnumSimple;
+use contract::test_contract1::EnumWithData;
+use contract::test_contract1::ErrorSimple;
 use contract::test_contract1::StructSimple;
 use contract::test_contract1::TestContract1;
+use std::collections::HashMap;
 
 pub struct TestContract1Imp {}
 
@@ -49,6 +54,15 @@ impl TestContract1 for TestContract1Imp {
         // info!("10101010110");
         arg.on_callback_i8(4i8);
         // info!("1111111111");
+        arg.on_callback_u8_vec(vec![1u8, 2u8]);
+        arg.on_callback_i8_vec(vec![-1i8, 2i8]);
+        let str_result = arg.on_callback_return_str(12);
+        let struct_result = arg.on_callback_return_struct(13);
+        let vec_result = arg.on_callback_return_vec(14);
+        let option_result = arg.on_callback_return_option(true);
+        let mut map_arg = HashMap::new();
+        map_arg.insert("rust".to_owned(), 1i64);
+        arg.on_callback_map(map_arg);
         33u8
     }
 
@@ -102,7 +116,135 @@ impl TestContract1 for TestContract1Imp {
 
     fn test_no_return() {}
 
-    //    fn test_return_callback(arg: bool) -> Box<Callback> {
-    //
-    //    }
+    fn test_enum(arg: EnumSimple) -> EnumSimple {
+        match arg {
+            EnumSimple::First => EnumSimple::Second,
+            EnumSimple::Second => EnumSimple::Third,
+            EnumSimple::Third => EnumSimple::First,
+        }
+    }
+
+    fn test_data_enum(arg: i32) -> EnumWithData {
+        match arg {
+            0 => EnumWithData::Named {
+                arg1: arg,
+                arg2: "named".to_owned(),
+            },
+            1 => EnumWithData::Unnamed("unnamed".to_owned(), true),
+            _ => EnumWithData::Unit,
+        }
+    }
+
+    fn test_option(arg: Option<String>) -> Option<i32> {
+        arg.map(|arg| arg.len() as i32)
+    }
+
+    fn test_result(arg: i32) -> Result<String, ErrorSimple> {
+        if arg >= 0 {
+            Ok(arg.to_string())
+        } else {
+            Err(ErrorSimple {
+                code: arg,
+                msg: "negative arg".to_owned(),
+            })
+        }
+    }
+
+    fn test_return_callback(arg: bool) -> Box<Callback> {
+        Box::new(CallbackImp { flag: arg })
+    }
+}
+
+pub struct CallbackImp {
+    flag: bool,
+}
+
+impl Callback for CallbackImp {
+    fn on_callback_u8(&self, arg1: u8) -> u8 {
+        arg1
+    }
+
+    fn on_callback_i8(&self, arg1: i8) -> i8 {
+        arg1
+    }
+
+    fn on_callback(&self, arg1: i32, arg2: String, arg3: bool, arg4: f32, arg5: f64) -> i32 {
+        arg1 + arg2.len() as i32
+    }
+
+    fn on_callback2(&self, arg1: bool) -> bool {
+        arg1 && self.flag
+    }
+
+    fn on_callback_complex(&self, arg1: StructSimple) -> bool {
+        arg1.arg4 && self.flag
+    }
+
+    fn on_callback_arg_vec(&self, arg1: Vec<StructSimple>) -> bool {
+        !arg1.is_empty() && self.flag
+    }
+
+    fn on_callback_arg_vec_simple(&self, arg1: Vec<String>) -> bool {
+        !arg1.is_empty() && self.flag
+    }
+
+    fn on_callback_u8_vec(&self, arg1: Vec<u8>) -> Vec<u8> {
+        arg1
+    }
+
+    fn on_callback_i8_vec(&self, arg1: Vec<i8>) -> Vec<i8> {
+        arg1
+    }
+
+    fn on_callback_return_str(&self, arg1: i32) -> String {
+        arg1.to_string()
+    }
+
+    fn on_callback_return_struct(&self, arg1: i32) -> StructSimple {
+        StructSimple {
+            arg1,
+            arg2: 0,
+            arg3: "rust".to_owned(),
+            arg4: self.flag,
+            arg5: 0.0,
+            art6: 0.0,
+        }
+    }
+
+    fn on_callback_return_vec(&self, arg1: i32) -> Vec<StructSimple> {
+        vec![self.on_callback_return_struct(arg1)]
+    }
+
+    fn on_callback_return_option(&self, arg1: bool) -> Option<String> {
+        if arg1 {
+            Some("rust".to_owned())
+        } else {
+            None
+        }
+    }
+
+    fn on_callback_map(&self, arg1: HashMap<String, i64>) {
+        println!("on_callback_map: {:?}", arg1);
+    }
+
+    fn on_empty_callback(&self) {}
+}
+
+pub struct CounterImp {
+    count: i32,
+}
+
+impl Counter for CounterImp {
+    fn new(start: i32) -> Self {
+        CounterImp { count: start }
+    }
+
+    fn increase(&mut self, step: i32) -> i32 {
+        self.count += step;
+        self.count
+    }
+
+    fn current(&self) -> i32 {
+        self.count
+    }
 }
@@ -0,0 +1,45 @@
+use contract::test_contract1::EnumSimple;
+use contract::test_contract1::StructSimple;
+use contract::test_contract2::StructNested;
+use contract::test_contract2::TestContract2;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+pub struct TestContract2Imp {}
+
+impl TestContract2 for TestContract2Imp {
+    fn test_struct_nested(arg: i32) -> StructNested {
+        let simple = StructSimple {
+            arg1: arg,
+            arg2: 1,
+            arg3: "nested".to_owned(),
+            arg4: true,
+            arg5: 2.0,
+            art6: 3.0,
+        };
+        StructNested {
+            name: "nested".to_owned(),
+            simples: vec![],
+            optional_simple: None,
+            simple,
+            enums: vec![EnumSimple::First, EnumSimple::Third],
+            attrs: HashMap::new(),
+        }
+    }
+
+    fn test_map(arg: HashMap<String, i32>) -> BTreeMap<i32, StructSimple> {
+        arg.into_iter()
+            .map(|(key, value)| {
+                let simple = StructSimple {
+                    arg1: value,
+                    arg2: 1,
+                    arg3: key,
+                    arg4: true,
+                    arg5: 2.0,
+                    art6: 3.0,
+                };
+                (value, simple)
+            })
+            .collect()
+    }
+}
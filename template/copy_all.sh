@@ -2,4 +2,7 @@
 ./template-android/copy.sh
 ./template-bridge-android/copy.sh
 ./template-ios/copy.sh
-./template-bridge-ios/copy.sh
\ No newline at end of file
+./template-bridge-ios/copy.sh
+./template-bridge-jvm/copy.sh
+./template-bridge-c/copy.sh
+./template-bridge-node/copy.sh
\ No newline at end of file
@@ -7,8 +7,8 @@ extern crate serde_derive;
 extern crate serde;
 
 
-use $(*521%-host_crate_underscore)::contract;
-use $(*521%-host_crate_underscore)::imp;
+use $(*521%-host_crate_underscore)::$(*521%-contract_mod) as contract;
+use $(*521%-host_crate_underscore)::$(*521%-imp_mod) as imp;
 
 pub mod java;
 #[macro_use]
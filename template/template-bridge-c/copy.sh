@@ -0,0 +1,4 @@
+#!/usr/bin/env bash
+
+zip -q -r template_bridge_c.zip ./
+mv template_bridge_c.zip ../../tools-rsbind/src/c/res/
\ No newline at end of file
@@ -0,0 +1,4 @@
+#!/usr/bin/env bash
+
+zip -q -r template_bridge_jvm.zip ./
+mv template_bridge_jvm.zip ../../tools-rsbind/src/jvm/res/
\ No newline at end of file
@@ -0,0 +1,19 @@
+#![allow(warnings)]
+
+extern crate $(*521%-host_crate_underscore);
+extern crate jni;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+
+
+use $(*521%-host_crate_underscore)::$(*521%-contract_mod) as contract;
+use $(*521%-host_crate_underscore)::$(*521%-imp_mod) as imp;
+
+pub mod java;
+#[macro_use]
+extern crate lazy_static;
+
+#[macro_use] extern crate log;
+
+use log::Level;
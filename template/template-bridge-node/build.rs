@@ -0,0 +1,9 @@
+use std::env;
+
+fn main() {
+    // the N-API symbols are provided by node when the addon is loaded.
+    if env::var("CARGO_CFG_TARGET_OS").map(|os| os == "macos").unwrap_or(false) {
+        println!("cargo:rustc-cdylib-link-arg=-undefined");
+        println!("cargo:rustc-cdylib-link-arg=dynamic_lookup");
+    }
+}
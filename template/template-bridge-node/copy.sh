@@ -0,0 +1,4 @@
+#!/usr/bin/env bash
+
+zip -q -r template_bridge_node.zip ./
+mv template_bridge_node.zip ../../tools-rsbind/src/node/res/
\ No newline at end of file
@@ -0,0 +1,12 @@
+#![allow(warnings)]
+
+extern crate $(*521%-host_crate_underscore);
+extern crate napi_sys;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde;
+
+use $(*521%-host_crate_underscore)::$(*521%-contract_mod) as contract;
+use $(*521%-host_crate_underscore)::$(*521%-imp_mod) as imp;
+
+pub mod node;
@@ -0,0 +1,94 @@
+//!
+//! The `#[rsbind]` attributes used in contract files. They don't change the items, rsbind reads
+//! them when it parses the contract, so they only need to be removed for compiling.
+//!
+//! The attributes on methods, fields and variants are removed by the attribute on the item
+//! containing them, so the item must be marked too, like by a plain `#[rsbind]`.
+//!
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+
+///
+/// `#[rsbind::export]`, the trait is exported to other languages.
+///
+#[proc_macro_attribute]
+pub fn export(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    strip(item)
+}
+
+///
+/// `#[rsbind::callback]`, the trait is implemented by other languages.
+///
+#[proc_macro_attribute]
+pub fn callback(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    strip(item)
+}
+
+///
+/// `#[rsbind::skip]`, the item is not visible to other languages.
+///
+#[proc_macro_attribute]
+pub fn skip(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    strip(item)
+}
+
+///
+/// `#[rsbind(export, skip, rename = "...")]`.
+///
+#[proc_macro_attribute]
+pub fn rsbind(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    strip(item)
+}
+
+fn strip(item: TokenStream) -> TokenStream {
+    let mut item: syn::Item = match syn::parse(item.clone()) {
+        Ok(item) => item,
+        Err(_) => return item,
+    };
+
+    match item {
+        syn::Item::Trait(ref mut trait_inner) => {
+            strip_attrs(&mut trait_inner.attrs);
+            for trait_item in trait_inner.items.iter_mut() {
+                if let syn::TraitItem::Method(ref mut method) = *trait_item {
+                    strip_attrs(&mut method.attrs);
+                }
+            }
+        }
+        syn::Item::Struct(ref mut struct_inner) => {
+            strip_attrs(&mut struct_inner.attrs);
+            strip_fields(&mut struct_inner.fields);
+        }
+        syn::Item::Enum(ref mut enum_inner) => {
+            strip_attrs(&mut enum_inner.attrs);
+            for variant in enum_inner.variants.iter_mut() {
+                strip_attrs(&mut variant.attrs);
+                strip_fields(&mut variant.fields);
+            }
+        }
+        _ => (),
+    }
+
+    let tokens = quote!(#item);
+    tokens.into()
+}
+
+fn strip_fields(fields: &mut syn::Fields) {
+    for field in fields.iter_mut() {
+        strip_attrs(&mut field.attrs);
+    }
+}
+
+fn strip_attrs(attrs: &mut Vec<syn::Attribute>) {
+    attrs.retain(|attr| {
+        attr.path
+            .segments
+            .first()
+            .map_or(true, |segment| segment.value().ident != "rsbind")
+    });
+}
@@ -1,5 +1,5 @@
 use super::callback::*;
-use ast::contract::desc::{ArgDesc, MethodDesc, StructDesc, TraitDesc};
+use ast::contract::desc::{ArgDesc, EnumDesc, MethodDesc, MethodKind, StructDesc, TraitDesc};
 use ast::imp::desc::*;
 use ast::types::*;
 use bridge::file::*;
@@ -16,14 +16,18 @@ pub(crate) fn new_gen<'a>(
     out_dir: &'a PathBuf,
     trait_descs: &'a Vec<TraitDesc>,
     struct_descs: &'a Vec<StructDesc>,
+    enum_descs: &'a Vec<EnumDesc>,
     imp_desc: &'a Vec<ImpDesc>,
+    foreign_types: &'a Vec<ForeignType>,
     java_namespace: &'a str,
 ) -> BridgeFileGen<'a, JniFileGenStrategy<'a>> {
     BridgeFileGen {
         out_dir,
         trait_descs,
         struct_descs,
+        enum_descs,
         imp_desc,
+        foreign_types,
         strategy: JniFileGenStrategy {
             java_namespace,
             java_callback_strategy: JavaCallbackStrategy {
@@ -38,6 +42,25 @@ pub(crate) struct JniFileGenStrategy<'a> {
     java_callback_strategy: JavaCallbackStrategy,
 }
 
+impl<'a> JniFileGenStrategy<'a> {
+    ///
+    /// the value returned to java when an exception is thrown, which is ignored by java.
+    ///
+    fn quote_placeholder(&self, return_type: &AstType) -> TokenStream {
+        match *return_type {
+            AstType::Void => quote!(),
+            AstType::Float | AstType::Double => quote!(0.0),
+            AstType::Byte
+            | AstType::Int
+            | AstType::Long
+            | AstType::Boolean
+            | AstType::Enum
+            | AstType::Callback => quote!(0),
+            _ => quote!(JObject::null().into_inner()),
+        }
+    }
+}
+
 impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
     fn gen_sdk_file(&self, mod_names: &Vec<String>) -> Result<TokenStream> {
         let mod_idents = mod_names
@@ -69,15 +92,16 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
 
     fn quote_common_use_part(&self) -> Result<TokenStream> {
         Ok(quote! {
+            use super::common::*;
+            use std::panic::{catch_unwind, AssertUnwindSafe};
             use jni::JNIEnv;
             use jni::JavaVM;
             use jni::objects::{JClass, JObject, JString, JValue};
-            use jni::sys::{jint, jlong, jstring, jbyteArray};
+            use jni::sys::{jint, jlong, jobject, jstring, jbyteArray};
             use std::os::raw::c_void;
             use jni::sys::JNI_VERSION_1_6;
             use std::sync::RwLock;
             use log::Level;
-            use android_logger::Filter;
             use std::sync::Arc;
         })
     }
@@ -112,13 +136,39 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
             .iter()
             .map(|field| Ident::new(&field.name, Span::call_site()))
             .collect::<Vec<Ident>>();
-        let arg_names = names.clone();
+        let values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(field.rust_name(), Span::call_site());
+                quote_mirror_field_value(field, quote!(origin.#name))
+            })
+            .collect::<Vec<TokenStream>>();
+        let mirror_values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(&field.name, Span::call_site());
+                quote_mirror_field_value(field, quote!(mirror.#name))
+            })
+            .collect::<Vec<TokenStream>>();
         let origin_arg_names = names.clone();
-        let tys = struct_desc
+        // mirrors use the foreign names of renamed fields, origin structs keep the rust names.
+        let mirror_arg_names = struct_desc
             .fields
             .iter()
-            .map(|field| Ident::new(&field.origin_ty, Span::call_site()))
+            .map(|field| Ident::new(field.rust_name(), Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let skipped_names = struct_desc
+            .skipped_fields
+            .iter()
+            .map(|field| Ident::new(field, Span::call_site()))
             .collect::<Vec<Ident>>();
+        let tys = struct_desc
+            .fields
+            .iter()
+            .map(|field| quote_mirror_field_ty(field))
+            .collect::<Vec<TokenStream>>();
         Ok(quote! {
             #[derive(Serialize, Deserialize)]
             pub struct #struct_name {
@@ -127,12 +177,25 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
 
             impl From<#origin_struct_name> for #struct_name {
                 fn from(origin: #origin_struct_name) -> Self {
-                    #struct_name{#(#origin_arg_names: origin.#arg_names),*}
+                    #struct_name{#(#origin_arg_names: #values),*}
+                }
+            }
+
+            impl From<#struct_name> for #origin_struct_name {
+                fn from(mirror: #struct_name) -> Self {
+                    #origin_struct_name{
+                        #(#mirror_arg_names: #mirror_values,)*
+                        #(#skipped_names: Default::default(),)*
+                    }
                 }
             }
         })
     }
 
+    fn quote_for_enums(&self, enum_desc: &EnumDesc) -> Result<TokenStream> {
+        quote_enum_mirror(enum_desc)
+    }
+
     ///
     /// quote the method signature for java bridge file.
     ///
@@ -152,19 +215,38 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
             &method.name.replace("_", "_1")
         );
         let method_name_ident = Ident::new(&method_name, Span::call_site());
-        let arg_names = method
+        let mut arg_names = method
             .args
             .iter()
             .map(|arg| Ident::new(&arg.name, Span::call_site()))
             .collect::<Vec<Ident>>();
 
-        let arg_types = method
+        let mut arg_types = method
             .args
             .iter()
-            .map(|arg| self.ty_to_tokens(&arg.ty, TypeDirection::Argument).unwrap())
-            .collect::<Vec<TokenStream>>();
+            .map(|arg| {
+                if arg.binary {
+                    Ok(quote!(jbyteArray))
+                } else {
+                    self.ty_to_tokens(&arg.ty, TypeDirection::Argument)
+                }
+            })
+            .collect::<Result<Vec<TokenStream>>>()?;
 
-        let ret_ty_tokens = self.ty_to_tokens(&method.return_type, TypeDirection::Return)?;
+        // methods of objects are called with the handle of the object.
+        match method.kind {
+            MethodKind::Ref | MethodKind::RefMut => {
+                arg_names.insert(0, Ident::new("handle", Span::call_site()));
+                arg_types.insert(0, quote!(i64));
+            }
+            _ => (),
+        }
+
+        let ret_ty_tokens = if method.binary_return {
+            quote!(jbyteArray)
+        } else {
+            self.ty_to_tokens(&method.return_type, TypeDirection::Return)?
+        };
 
         let method_sig = if arg_names.len() <= 0 {
             match method.return_type {
@@ -201,6 +283,7 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
     fn quote_arg_convert(
         &self,
         trait_desc: &TraitDesc,
+        _method: &MethodDesc,
         arg: &ArgDesc,
         callbacks: &Vec<&TraitDesc>,
     ) -> Result<TokenStream> {
@@ -212,6 +295,16 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
         let _class_name =
             format!("{}.{}", &self.java_namespace, &trait_desc.name).replace(".", "/");
 
+        // encoded values are transferred as byte arrays.
+        if arg.binary {
+            let bytes_arg_ident = Ident::new(&format!("bytes_{}", &arg.name), Span::call_site());
+            let value = quote_binary_arg(arg, quote!(&#bytes_arg_ident));
+            return Ok(quote! {
+                let #bytes_arg_ident = env.convert_byte_array(#arg_name_ident).unwrap();
+                #value
+            });
+        }
+
         Ok(match arg.ty {
             AstType::Byte | AstType::Int | AstType::Long | AstType::Float | AstType::Double => {
                 let origin_type_ident = Ident::new(&arg.origin_ty, Span::call_site());
@@ -229,6 +322,14 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                     let #rust_arg_name: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
                 }
             }
+            AstType::Enum => {
+                let mirror_ident =
+                    Ident::new(&format!("Enum_{}", &arg.origin_ty), Span::call_site());
+                let origin_type_ident = Ident::new(&arg.origin_ty, Span::call_site());
+                quote! {
+                    let #rust_arg_name: #origin_type_ident = #mirror_ident::from_i32(#arg_name_ident).into();
+                }
+            }
             AstType::Vec(base) => match base {
                 AstBaseType::Byte => {
                     if arg.origin_ty.contains("i8") {
@@ -256,6 +357,31 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                         }
                     }
                 }
+                AstBaseType::Enum => {
+                    let enum_name = arg.origin_ty.replace("Vec<", "").replace(">", "");
+                    let enum_ident = Ident::new(&enum_name, Span::call_site());
+                    let mirror_ident =
+                        Ident::new(&format!("Enum_{}", &enum_name), Span::call_site());
+                    let json_arg_ident =
+                        Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                    let mirror_arg_ident =
+                        Ident::new(&format!("mirror_{}", &arg.name), Span::call_site());
+                    quote! {
+                        let #json_arg_ident: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
+                        let #mirror_arg_ident: Vec<#mirror_ident> = serde_json::from_str(&#json_arg_ident).unwrap();
+                        let #rust_arg_name = #mirror_arg_ident.into_iter().map(|each| each.into()).collect::<Vec<#enum_ident>>();
+                    }
+                }
+                AstBaseType::Struct => {
+                    let json_arg_ident =
+                        Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                    let value =
+                        quote_struct_from_json(&arg.ty, &arg.origin_ty, quote!(#json_arg_ident))?;
+                    quote! {
+                        let #json_arg_ident: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
+                        let #rust_arg_name = #value;
+                    }
+                }
                 _ => {
                     let json_arg_ident =
                         Ident::new(&format!("json_{}", &arg.name), Span::call_site());
@@ -265,9 +391,48 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                     }
                 }
             },
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let value = quote_boxed_to_optional(base, &arg.origin_ty, quote!(#arg_name_ident))?;
+                quote! {
+                    let #rust_arg_name = #value;
+                }
+            }
+            AstType::Optional(ref base) => {
+                let inner = arg.origin_ty.replace("Option<", "").replace(">", "");
+                let inner_ident = Ident::new(&inner, Span::call_site());
+                let tmp_arg_name = Ident::new(&format!("tmp_{}", &arg.name), Span::call_site());
+                let inner_value =
+                    quote_optional_from_str(base, &arg.origin_ty, quote!(#tmp_arg_name))?;
+                quote! {
+                    let #rust_arg_name: Option<#inner_ident> = if #arg_name_ident.is_null() {
+                        None
+                    } else {
+                        let #tmp_arg_name: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
+                        Some(#inner_value)
+                    };
+                }
+            }
+            AstType::Struct => {
+                let json_arg_ident = Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                let value =
+                    quote_struct_from_json(&arg.ty, &arg.origin_ty, quote!(#json_arg_ident))?;
+                quote! {
+                    let #json_arg_ident: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
+                    let #rust_arg_name = #value;
+                }
+            }
+            AstType::Map(ref key, ref value) => {
+                let json_arg_ident = Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                let map_value =
+                    quote_map_from_json(key, value, &arg.origin_ty, quote!(#json_arg_ident))?;
+                quote! {
+                    let #json_arg_ident: String = env.get_string(#arg_name_ident).expect("Couldn't get java string!").into();
+                    let #rust_arg_name = #map_value;
+                }
+            }
             AstType::Callback => self
                 .java_callback_strategy
-                .arg_convert(arg, trait_desc, callbacks),
+                .arg_convert(arg, trait_desc, callbacks)?,
             _ => {
                 return Err(
                     GenerateError(format!("find unsupported type in arg, {:?}", &arg.ty)).into(),
@@ -276,14 +441,20 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
         })
     }
 
-    fn quote_return_convert(
-        &self,
-        return_ty: &AstType,
-        ret_name: &str,
-        origin_ty: &str,
-    ) -> Result<TokenStream> {
+    fn quote_return_convert(&self, method: &MethodDesc, ret_name: &str) -> Result<TokenStream> {
+        let return_ty = &method.return_type;
+        let origin_ty: &str = &method.origin_return_ty;
         let ret_name_ident = Ident::new(ret_name, Span::call_site());
 
+        // encoded values are transferred as byte arrays.
+        if method.binary_return {
+            let bytes_ret = quote_binary_return(method, ret_name);
+            return Ok(quote! {
+                let bytes_ret = #bytes_ret;
+                env.byte_array_from_slice(&bytes_ret).unwrap()
+            });
+        }
+
         Ok(match *return_ty {
             AstType::Void => quote!(),
             AstType::Boolean => quote! {
@@ -303,6 +474,16 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                         env.new_string(json_ret.unwrap()).expect("Couldn't create java string").into_inner()
                     }
                 }
+                AstBaseType::Enum => {
+                    let enum_name = origin_ty.to_owned().replace("Vec<", "").replace(">", "");
+                    let mirror_ident =
+                        Ident::new(&format!("Enum_{}", &enum_name), Span::call_site());
+                    quote! {
+                        let ret_value = ret_value.into_iter().map(|each| #mirror_ident::from(each)).collect::<Vec<#mirror_ident>>();
+                        let json_ret = serde_json::to_string(&ret_value);
+                        env.new_string(json_ret.unwrap()).expect("Couldn't create java string").into_inner()
+                    }
+                }
                 AstBaseType::Byte => {
                     if origin_ty.contains("i8") {
                         quote! {
@@ -336,10 +517,38 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                     env.new_string(json_ret.unwrap()).expect("Couldn't create java string").into_inner()
                 }
             }
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let boxed_ret = quote_optional_to_boxed(base, origin_ty, quote!(#ret_name_ident))?;
+                quote! {
+                    let boxed_ret = #boxed_ret;
+                    boxed_ret.into_inner()
+                }
+            }
+            AstType::Optional(ref base) => {
+                let str_ret = quote_optional_to_str(base, origin_ty, quote!(ret_value))?;
+                quote! {
+                    match #ret_name_ident {
+                        Some(ret_value) => {
+                            let str_ret = #str_ret;
+                            env.new_string(str_ret).expect("Couldn't create java string").into_inner()
+                        }
+                        None => JObject::null().into_inner(),
+                    }
+                }
+            }
+            AstType::Map(ref key, ref value) => {
+                let json_ret = quote_map_to_json(key, value, origin_ty, quote!(#ret_name_ident))?;
+                quote! {
+                    let json_ret = #json_ret;
+                    env.new_string(json_ret).expect("Couldn't create java string").into_inner()
+                }
+            }
+            // the boxed callback is boxed again as the wrapper object, whose address is the handle.
+            AstType::Callback => quote! {
+                Box::into_raw(Box::new(#ret_name_ident)) as i64
+            },
             _ => {
-                let ty_ident = self
-                    .ty_to_tokens(&return_ty, TypeDirection::Return)
-                    .unwrap();
+                let ty_ident = self.ty_to_tokens(&return_ty, TypeDirection::Return)?;
                 quote! {
                     #ret_name_ident as #ty_ident
                 }
@@ -347,6 +556,58 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
         })
     }
 
+    ///
+    /// throw the generated java exception of the error type, and return a placeholder value
+    /// which is ignored by java.
+    ///
+    fn quote_error_return(&self, method: &MethodDesc) -> Result<TokenStream> {
+        let exception_class = format!(
+            "{}.{}Exception",
+            self.java_namespace, &method.origin_error_ty
+        )
+        .replace(".", "/");
+        let error_str = quote_error_to_str(method, quote!(err))?;
+        let placeholder = self.quote_placeholder(&method.return_type);
+
+        Ok(quote! {
+            let error_str = #error_str;
+            let _ = env.throw_new(#exception_class, error_str);
+            return #placeholder;
+        })
+    }
+
+    ///
+    /// throw a RuntimeException with the panic message.
+    ///
+    fn quote_panic_return(
+        &self,
+        trait_desc: &TraitDesc,
+        method: &MethodDesc,
+    ) -> Result<TokenStream> {
+        let panic_prefix = format!("rsbind panic in {}.{}", &trait_desc.name, &method.name);
+        let placeholder = self.quote_placeholder(&method.return_type);
+
+        Ok(quote! {
+            let panic_str = format!("{}: {}", #panic_prefix, panic_message(&err));
+            let _ = env.throw_new("java/lang/RuntimeException", panic_str);
+            #placeholder
+        })
+    }
+
+    fn quote_free_object_sig(&self, trait_desc: &TraitDesc) -> Result<TokenStream> {
+        let namespace = self.java_namespace.replace(".", "_");
+        let method_name = format!(
+            "Java_{}_{}_native_1free_1object",
+            &namespace, &trait_desc.name
+        );
+        let method_name_ident = Ident::new(&method_name, Span::call_site());
+        Ok(quote! {
+            #[no_mangle]
+            #[allow(non_snake_case)]
+            pub extern "C" fn #method_name_ident(env: JNIEnv, class: JClass, handle: i64)
+        })
+    }
+
     fn ty_to_tokens(&self, ast_type: &AstType, direction: TypeDirection) -> Result<TokenStream> {
         let mut tokens = TokenStream::new();
         match *ast_type {
@@ -370,14 +631,85 @@ impl<'a> FileGenStrategy for JniFileGenStrategy<'a> {
                     _ => tokens.append(Ident::new("jstring", Span::call_site())),
                 },
             },
-            AstType::Struct => match direction {
+            // options of primitives are boxed, like `java.lang.Integer`.
+            AstType::Optional(ref base) if base.is_primitive() => match direction {
+                TypeDirection::Argument => tokens.append(Ident::new("JObject", Span::call_site())),
+                TypeDirection::Return => tokens.append(Ident::new("jobject", Span::call_site())),
+            },
+            AstType::Struct | AstType::Optional(_) | AstType::Map(_, _) => match direction {
                 TypeDirection::Argument => tokens.append(Ident::new("JString", Span::call_site())),
                 TypeDirection::Return => tokens.append(Ident::new("jstring", Span::call_site())),
             },
             AstType::Callback => tokens.append(Ident::new("i64", Span::call_site())),
+            AstType::Enum => tokens.append(Ident::new("i32", Span::call_site())),
             AstType::Void => (),
         };
 
         Ok(tokens)
     }
 }
+
+///
+/// Convert a boxed java primitive, like `java.lang.Integer`, to an `Option<T>`, null is None.
+///
+pub(crate) fn quote_boxed_to_optional(
+    base: &AstBaseType,
+    origin_ty: &str,
+    value: TokenStream,
+) -> Result<TokenStream> {
+    let inner = origin_ty.replace("Option<", "").replace(">", "");
+    let inner_ident = Ident::new(&inner, Span::call_site());
+    let (unbox_method, unbox_sig, unboxed) = match *base {
+        AstBaseType::Byte => ("byteValue", "()B", quote!(b())),
+        AstBaseType::Int => ("intValue", "()I", quote!(i())),
+        AstBaseType::Long => ("longValue", "()J", quote!(j())),
+        AstBaseType::Float => ("floatValue", "()F", quote!(f())),
+        AstBaseType::Double => ("doubleValue", "()D", quote!(d())),
+        AstBaseType::Boolean => ("booleanValue", "()Z", quote!(z())),
+        _ => {
+            return Err(GenerateError(format!(
+                "find non-primitive type in boxed Option, {}",
+                origin_ty
+            ))
+            .into());
+        }
+    };
+    Ok(quote! {
+        if #value.is_null() {
+            None
+        } else {
+            let unboxed = env.call_method(#value, #unbox_method, #unbox_sig, &[]).unwrap().#unboxed.unwrap();
+            Some(unboxed as #inner_ident)
+        }
+    })
+}
+
+///
+/// Convert an `Option<T>` of primitives to a boxed java primitive, None is null.
+///
+pub(crate) fn quote_optional_to_boxed(
+    base: &AstBaseType,
+    origin_ty: &str,
+    value: TokenStream,
+) -> Result<TokenStream> {
+    let boxed_class = base.to_java_boxed_class().ok_or_else(|| {
+        GenerateError(format!(
+            "find non-primitive type in boxed Option, {}",
+            origin_ty
+        ))
+    })?;
+    let (box_sig, boxed_value) = match *base {
+        AstBaseType::Byte => ("(B)V", quote!(JValue::Byte(value as i8))),
+        AstBaseType::Int => ("(I)V", quote!(JValue::Int(value as i32))),
+        AstBaseType::Long => ("(J)V", quote!(JValue::Long(value as i64))),
+        AstBaseType::Float => ("(F)V", quote!(JValue::Float(value as f32))),
+        AstBaseType::Double => ("(D)V", quote!(JValue::Double(value as f64))),
+        _ => ("(Z)V", quote!(JValue::Bool(value as u8))),
+    };
+    Ok(quote! {
+        match #value {
+            Some(value) => env.new_object(#boxed_class, #box_sig, &[#boxed_value]).unwrap(),
+            None => JObject::null(),
+        }
+    })
+}
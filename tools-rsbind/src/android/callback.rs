@@ -1,6 +1,8 @@
+use super::bridge::{quote_boxed_to_optional, quote_optional_to_boxed};
 use ast::contract::desc::*;
 use ast::types::*;
 use bridge::file::*;
+use errors::ErrorKind::*;
 use errors::*;
 use proc_macro2::{Ident, Literal, Span, TokenStream};
 use quote::TokenStreamExt;
@@ -15,7 +17,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
         arg: &ArgDesc,
         trait_desc: &TraitDesc,
         callbacks: &Vec<&TraitDesc>,
-    ) -> TokenStream {
+    ) -> Result<TokenStream> {
         let rust_arg_name = Ident::new(
             &format!("{}_{}", TMP_ARG_PREFIX, &arg.name),
             Span::call_site(),
@@ -41,7 +43,12 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
         };
 
         let _callback_methods = TokenStream::new();
-        let callback_desc = callback_desc.unwrap();
+        let callback_desc = callback_desc.ok_or_else(|| {
+            GenerateError(format!(
+                "can't find the callback {} of argument {}",
+                &arg.origin_ty, &arg.name
+            ))
+        })?;
         let mut methods_result = TokenStream::new();
         for method in callback_desc.methods.iter() {
             // arguments converting in callback
@@ -68,6 +75,18 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                         let cb_tmp_arg_name =
                             Ident::new(&format!("j_tmp_{}", cb_arg.name), Span::call_site());
                         match base_ty {
+                            AstBaseType::Byte => {
+                                if cb_arg.origin_ty.contains("i8") {
+                                    quote! {
+                                        let #cb_tmp_arg_name = #cb_origin_arg_name.into_iter().map(|each| each as u8).collect::<Vec<u8>>();
+                                        let #cb_arg_name = JObject::from(env.byte_array_from_slice(&#cb_tmp_arg_name).unwrap());
+                                    }
+                                } else {
+                                    quote! {
+                                        let #cb_arg_name = JObject::from(env.byte_array_from_slice(&#cb_origin_arg_name).unwrap());
+                                    }
+                                }
+                            }
                             AstBaseType::Struct => {
                                 let struct_name = cb_arg
                                     .origin_ty
@@ -88,6 +107,24 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                                     let #cb_arg_name = env.new_string(#cb_tmp_arg_name.unwrap()).unwrap().into();
                                 }
                             }
+                            AstBaseType::Enum => {
+                                let enum_name = cb_arg
+                                    .origin_ty
+                                    .to_owned()
+                                    .replace("Vec<", "")
+                                    .replace(">", "");
+                                let mirror_ident =
+                                    Ident::new(&format!("Enum_{}", &enum_name), Span::call_site());
+                                let cb_tmp_vec_arg_name = Ident::new(
+                                    &format!("j_tmp_vec_{}", cb_arg.name),
+                                    Span::call_site(),
+                                );
+                                quote! {
+                                    let #cb_tmp_vec_arg_name = #cb_origin_arg_name.into_iter().map(|each| #mirror_ident::from(each)).collect::<Vec<#mirror_ident>>();
+                                    let #cb_tmp_arg_name = serde_json::to_string(&#cb_tmp_vec_arg_name);
+                                    let #cb_arg_name = env.new_string(#cb_tmp_arg_name.unwrap()).unwrap().into();
+                                }
+                            }
                             _ => {
                                 quote! {
                                     let #cb_tmp_arg_name = serde_json::to_string(&#cb_origin_arg_name);
@@ -106,10 +143,43 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                             let #cb_arg_name = env.new_string(#cb_tmp_arg_name.unwrap()).unwrap().into();
                         }
                     }
+                    AstType::Map(ref key, ref value) => {
+                        let json_value = quote_map_to_json(
+                            key,
+                            value,
+                            &cb_arg.origin_ty,
+                            quote!(#cb_origin_arg_name),
+                        )?;
+                        quote! {
+                            let #cb_arg_name = env.new_string(#json_value).unwrap().into();
+                        }
+                    }
+                    AstType::Optional(ref base) if base.is_primitive() => {
+                        let boxed_value = quote_optional_to_boxed(
+                            base,
+                            &cb_arg.origin_ty,
+                            quote!(#cb_origin_arg_name),
+                        )?;
+                        quote! {
+                            let #cb_arg_name = #boxed_value;
+                        }
+                    }
+                    AstType::Optional(ref base) => {
+                        let str_value = quote_optional_to_str(
+                            base,
+                            &cb_arg.origin_ty,
+                            quote!(#cb_origin_arg_name),
+                        )?;
+                        quote! {
+                            let #cb_arg_name = match #cb_origin_arg_name {
+                                Some(#cb_origin_arg_name) => env.new_string(#str_value).unwrap().into(),
+                                None => JObject::null(),
+                            };
+                        }
+                    }
                     _ => {
-                        let arg_ty_ident = self
-                            .ty_to_tokens(&cb_arg.ty, TypeDirection::Argument)
-                            .unwrap();
+                        let arg_ty_ident =
+                            self.ty_to_tokens(&cb_arg.ty, TypeDirection::Argument)?;
                         quote! {
                             let #cb_arg_name = #cb_origin_arg_name as #arg_ty_ident;
                         }
@@ -121,7 +191,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                         JValue::Byte(#cb_arg_name),
                     },
 
-                    AstType::Boolean | AstType::Int => quote! {
+                    AstType::Boolean | AstType::Int | AstType::Enum => quote! {
                         JValue::Int(#cb_arg_name),
                     },
 
@@ -169,27 +239,10 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
             let arg_types = &method
                 .args
                 .iter()
-                .map(|arg| match arg.ty {
-                    AstType::Vec(_base_ty) => {
-                        let vec_inner_name =
-                            arg.origin_ty.clone().replace("Vec<", "").replace(">", "");
-                        let vec_innder_ident = Ident::new(&vec_inner_name, Span::call_site());
-                        quote!(Vec<#vec_innder_ident>)
-                    }
-                    _ => {
-                        let ident = Ident::new(&arg.origin_ty, Span::call_site());
-                        quote!(#ident)
-                    }
-                })
+                .map(|arg| quote_callback_ty(&arg.ty, &arg.origin_ty))
                 .collect::<Vec<TokenStream>>();
 
-            let ret_ty_tokens = match method.return_type {
-                AstType::Void => quote!(()),
-                _ => {
-                    let ident = Ident::new(&method.origin_return_ty, Span::call_site());
-                    quote!(#ident)
-                }
-            };
+            let ret_ty_tokens = quote_callback_ty(&method.return_type, &method.origin_return_ty);
 
             let return_convert = match method.return_type {
                 AstType::Void => quote!(),
@@ -230,6 +283,21 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                         let s_result = r_result.unwrap() as #origin_return_ty_ident;
                     }
                 }
+                AstType::Enum => {
+                    let mirror_ident = Ident::new(
+                        &format!("Enum_{}", &method.origin_return_ty),
+                        Span::call_site(),
+                    );
+                    quote! {
+                        let mut r_result = None;
+                        match result.unwrap() {
+                            JValue::Int(value) => r_result = Some(value),
+                            _ => assert!(false)
+                        }
+
+                        let s_result = #mirror_ident::from_i32(r_result.unwrap()).into();
+                    }
+                }
                 AstType::Long => {
                     let origin_return_ty_ident =
                         Ident::new(&method.origin_return_ty, Span::call_site());
@@ -257,7 +325,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                     }
                 }
                 AstType::Double => {
-                    let _origin_return_ty_ident =
+                    let origin_return_ty_ident =
                         Ident::new(&method.origin_return_ty, Span::call_site());
                     quote! {
                         let mut r_result = None;
@@ -265,12 +333,18 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                             JValue::Double(value) => r_result = Some(value),
                             _ => assert!(false)
                         }
-                        let s_result = env.get_string(r_result.unwrap()).expect("Couldn't get java string!").into();
+
+                        let s_result = r_result.unwrap() as #origin_return_ty_ident;
                     }
                 }
-                AstType::String => {
-                    let origin_return_ty_ident =
-                        Ident::new(&method.origin_return_ty, Span::call_site());
+                // byte arrays are copied, and the local reference is deleted as the callback may
+                // be called many times before returning to java.
+                AstType::Vec(AstBaseType::Byte) => {
+                    let bytes_convert = if method.origin_return_ty.contains("i8") {
+                        quote!(r_bytes.into_iter().map(|each| each as i8).collect())
+                    } else {
+                        quote!(r_bytes)
+                    };
                     quote! {
                         let mut r_result = None;
                         match result.unwrap() {
@@ -278,7 +352,64 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
                             _ => assert!(false)
                         }
 
-                        let s_result = r_result.unwrap() as #origin_return_ty_ident;
+                        let r_result = r_result.unwrap();
+                        let r_bytes = env.convert_byte_array(r_result.into_inner()).unwrap();
+                        let _ = env.delete_local_ref(r_result);
+                        let s_result = #bytes_convert;
+                    }
+                }
+                AstType::String | AstType::Vec(_) | AstType::Struct => {
+                    let value = quote_callback_return_from_str(
+                        &method.return_type,
+                        &method.origin_return_ty,
+                        quote!(r_str),
+                    )?;
+                    quote! {
+                        let mut r_result = None;
+                        match result.unwrap() {
+                            JValue::Object(value) => r_result = Some(value),
+                            _ => assert!(false)
+                        }
+
+                        let r_result = r_result.unwrap();
+                        let r_str: String = env.get_string(JString::from(r_result)).expect("Couldn't get java string!").into();
+                        let _ = env.delete_local_ref(r_result);
+                        let s_result = #value;
+                    }
+                }
+                AstType::Optional(ref base) if base.is_primitive() => {
+                    let value =
+                        quote_boxed_to_optional(base, &method.origin_return_ty, quote!(r_result))?;
+                    quote! {
+                        let mut r_result = None;
+                        match result.unwrap() {
+                            JValue::Object(value) => r_result = Some(value),
+                            _ => assert!(false)
+                        }
+
+                        let r_result = r_result.unwrap();
+                        let s_result = #value;
+                        let _ = env.delete_local_ref(r_result);
+                    }
+                }
+                AstType::Optional(ref base) => {
+                    let value =
+                        quote_optional_from_str(base, &method.origin_return_ty, quote!(r_str))?;
+                    quote! {
+                        let mut r_result = None;
+                        match result.unwrap() {
+                            JValue::Object(value) => r_result = Some(value),
+                            _ => assert!(false)
+                        }
+
+                        let r_result = r_result.unwrap();
+                        let s_result = if r_result.is_null() {
+                            None
+                        } else {
+                            let r_str: String = env.get_string(JString::from(r_result)).expect("Couldn't get java string!").into();
+                            let _ = env.delete_local_ref(r_result);
+                            Some(#value)
+                        };
                     }
                 }
                 _ => {
@@ -294,7 +425,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
             };
 
             // methods calls on impl
-            let method_name = Ident::new(&method.name, Span::call_site());
+            let method_name = Ident::new(method.rust_name(), Span::call_site());
             let java_method_name = format!("invoke_{}_{}", &callback_desc.name, &method.name);
 
             methods_result = quote! {
@@ -322,7 +453,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
 
         let callback_ident = Ident::new(&callback_desc.name, Span::call_site());
         let _callback_str_ident = Ident::new(&format!("r_{}_str", &arg.name), Span::call_site());
-        quote! {
+        Ok(quote! {
             #callback_struct
 
             impl #callback_ident for #struct_ident {
@@ -342,7 +473,7 @@ impl CallbackGenStrategy for JavaCallbackStrategy {
             }
 
             let #rust_arg_name: Box<#struct_ident> = Box::new(#struct_ident{index: #arg_name_ident});
-        }
+        })
     }
 }
 
@@ -364,11 +495,16 @@ impl JavaCallbackStrategy {
                 TypeDirection::Argument => tokens.append(Ident::new("JString", Span::call_site())),
                 TypeDirection::Return => tokens.append(Ident::new("jstring", Span::call_site())),
             },
-            AstType::Struct => match direction {
+            AstType::Optional(ref base) if base.is_primitive() => match direction {
+                TypeDirection::Argument => tokens.append(Ident::new("JObject", Span::call_site())),
+                TypeDirection::Return => tokens.append(Ident::new("jobject", Span::call_site())),
+            },
+            AstType::Struct | AstType::Optional(_) | AstType::Map(_, _) => match direction {
                 TypeDirection::Argument => tokens.append(Ident::new("JString", Span::call_site())),
                 TypeDirection::Return => tokens.append(Ident::new("jstring", Span::call_site())),
             },
             AstType::Callback => tokens.append(Ident::new("i64", Span::call_site())),
+            AstType::Enum => tokens.append(Ident::new("i32", Span::call_site())),
             _ => (),
         };
 
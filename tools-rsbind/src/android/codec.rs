@@ -0,0 +1,303 @@
+//!
+//! Java side of the binary wire format, values are written in big-endian, integers narrower
+//! than i32 are written as int, strings and arrays are prefixed by their int lengths, and
+//! nullable values by a byte telling whether there is a value.
+//!
+use ast::types::AstBaseType;
+use ast::types::AstType;
+use genco::java::{self, *};
+use genco::Tokens;
+
+pub(crate) const WRITER_CLASS: &str = "RsbindWriter";
+pub(crate) const READER_CLASS: &str = "RsbindReader";
+
+///
+/// The type of encoded values passed through jni.
+///
+pub(crate) fn bytes_ty() -> Java<'static> {
+    java::local("byte[]")
+}
+
+///
+/// A growable byte array the values are written to.
+///
+pub(crate) fn writer_class() -> Tokens<'static, Java<'static>> {
+    let arrays = java::imported("java.util", "Arrays");
+    let charsets = java::imported("java.nio.charset", "StandardCharsets");
+
+    let mut tokens = Tokens::new();
+    tokens.push(toks!("public final class ", WRITER_CLASS, " {"));
+    tokens.nested({
+        let mut body = Tokens::new();
+        body.push("private byte[] bytes = new byte[64];");
+        body.push("private int len = 0;");
+
+        body.push("private void ensure(int more) {");
+        body.nested({
+            let mut ensure = Tokens::new();
+            ensure.push("if (len + more > bytes.length) {");
+            ensure.nested(toks!(
+                "bytes = ",
+                arrays.clone(),
+                ".copyOf(bytes, Math.max(bytes.length * 2, len + more));"
+            ));
+            ensure.push("}");
+            ensure
+        });
+        body.push("}");
+
+        body.push("public void writeByte(byte value) {");
+        body.nested({
+            let mut write = Tokens::new();
+            write.push("ensure(1);");
+            write.push("bytes[len++] = value;");
+            write
+        });
+        body.push("}");
+
+        body.push("public void writeBoolean(boolean value) {");
+        body.nested("writeByte((byte) (value ? 1 : 0));");
+        body.push("}");
+
+        body.push("public void writeInt(int value) {");
+        body.nested({
+            let mut write = Tokens::new();
+            write.push("ensure(4);");
+            write.push("bytes[len++] = (byte) (value >>> 24);");
+            write.push("bytes[len++] = (byte) (value >>> 16);");
+            write.push("bytes[len++] = (byte) (value >>> 8);");
+            write.push("bytes[len++] = (byte) value;");
+            write
+        });
+        body.push("}");
+
+        body.push("public void writeLong(long value) {");
+        body.nested({
+            let mut write = Tokens::new();
+            write.push("writeInt((int) (value >>> 32));");
+            write.push("writeInt((int) value);");
+            write
+        });
+        body.push("}");
+
+        body.push("public void writeFloat(float value) {");
+        body.nested("writeInt(Float.floatToIntBits(value));");
+        body.push("}");
+
+        body.push("public void writeDouble(double value) {");
+        body.nested("writeLong(Double.doubleToLongBits(value));");
+        body.push("}");
+
+        body.push("public void writeString(String value) {");
+        body.nested({
+            let mut write = Tokens::new();
+            write.push(toks!("byte[] utf8 = value.getBytes(", charsets, ".UTF_8);"));
+            write.push("writeInt(utf8.length);");
+            write.push("ensure(utf8.length);");
+            write.push("System.arraycopy(utf8, 0, bytes, len, utf8.length);");
+            write.push("len += utf8.length;");
+            write
+        });
+        body.push("}");
+
+        body.push("public byte[] toByteArray() {");
+        body.nested(toks!("return ", arrays, ".copyOf(bytes, len);"));
+        body.push("}");
+        body
+    });
+    tokens.push("}");
+    tokens
+}
+
+///
+/// Reads the values from the bytes returned by rust.
+///
+pub(crate) fn reader_class() -> Tokens<'static, Java<'static>> {
+    let byte_buffer = java::imported("java.nio", "ByteBuffer");
+    let charsets = java::imported("java.nio.charset", "StandardCharsets");
+
+    let mut tokens = Tokens::new();
+    tokens.push(toks!("public final class ", READER_CLASS, " {"));
+    tokens.nested({
+        let mut body = Tokens::new();
+        body.push(toks!("private final ", byte_buffer.clone(), " buffer;"));
+
+        body.push(toks!("public ", READER_CLASS, "(byte[] bytes) {"));
+        body.nested(toks!("buffer = ", byte_buffer, ".wrap(bytes);"));
+        body.push("}");
+
+        let reads = [
+            ("byte", "readByte", "buffer.get()"),
+            ("boolean", "readBoolean", "buffer.get() != 0"),
+            ("int", "readInt", "buffer.getInt()"),
+            ("long", "readLong", "buffer.getLong()"),
+            ("float", "readFloat", "buffer.getFloat()"),
+            ("double", "readDouble", "buffer.getDouble()"),
+        ];
+        for &(ty, name, value) in reads.iter() {
+            body.push(toks!("public ", ty, " ", name, "() {"));
+            body.nested(toks!("return ", value, ";"));
+            body.push("}");
+        }
+
+        body.push("public String readString() {");
+        body.nested({
+            let mut read = Tokens::new();
+            read.push("byte[] utf8 = new byte[buffer.getInt()];");
+            read.push("buffer.get(utf8);");
+            read.push(toks!("return new String(utf8, ", charsets, ".UTF_8);"));
+            read
+        });
+        body.push("}");
+        body
+    });
+    tokens.push("}");
+    tokens
+}
+
+///
+/// Push the statements writing `value` to the writer named `writer`.
+///
+pub(crate) fn push_encode(
+    body: &mut Tokens<'static, Java<'static>>,
+    ty: AstType,
+    origin_ty: &str,
+    value: &str,
+    writer: &str,
+) {
+    match ty {
+        AstType::Vec(base) => {
+            let inner = origin_ty.replace("Vec<", "").replace(">", "");
+            body.push(toks!(
+                writer.to_owned(),
+                ".writeInt(",
+                value.to_owned(),
+                ".length);"
+            ));
+            body.push(toks!(
+                "for (",
+                item_ty(base, &inner),
+                " each : ",
+                value.to_owned(),
+                ") {"
+            ));
+            body.nested(encode_value(AstType::from(base), "each", writer));
+            body.push("}");
+        }
+        AstType::Optional(base) => {
+            body.push(toks!("if (", value.to_owned(), " == null) {"));
+            body.nested(toks!(writer.to_owned(), ".writeBoolean(false);"));
+            body.push("} else {");
+            body.nested({
+                let mut some = Tokens::new();
+                some.push(toks!(writer.to_owned(), ".writeBoolean(true);"));
+                some.push(encode_value(AstType::from(base), value, writer));
+                some
+            });
+            body.push("}");
+        }
+        _ => body.push(encode_value(ty, value, writer)),
+    }
+}
+
+///
+/// Push the statements reading a value from the reader named `reader` to `target`.
+///
+pub(crate) fn push_decode(
+    body: &mut Tokens<'static, Java<'static>>,
+    ty: AstType,
+    origin_ty: &str,
+    target: &str,
+    reader: &str,
+) {
+    match ty {
+        AstType::Vec(base) => {
+            let inner = origin_ty.replace("Vec<", "").replace(">", "");
+            body.push(toks!(
+                target.to_owned(),
+                " = new ",
+                item_ty(base, &inner),
+                "[",
+                reader.to_owned(),
+                ".readInt()];"
+            ));
+            body.push(toks!(
+                "for (int i = 0; i < ",
+                target.to_owned(),
+                ".length; i++) {"
+            ));
+            body.nested(toks!(
+                target.to_owned(),
+                "[i] = ",
+                decode_value(AstType::from(base), &inner, reader),
+                ";"
+            ));
+            body.push("}");
+        }
+        AstType::Optional(base) => {
+            let inner = origin_ty.replace("Option<", "").replace(">", "");
+            body.push(toks!(
+                target.to_owned(),
+                " = ",
+                reader.to_owned(),
+                ".readBoolean() ? ",
+                decode_value(AstType::from(base), &inner, reader),
+                " : null;"
+            ));
+        }
+        _ => body.push(toks!(
+            target.to_owned(),
+            " = ",
+            decode_value(ty, origin_ty, reader),
+            ";"
+        )),
+    }
+}
+
+///
+/// The type of items in arrays, which are boxed like the arrays transferred by json, except
+/// byte arrays.
+///
+fn item_ty(base: AstBaseType, inner: &str) -> Java<'static> {
+    match base {
+        AstBaseType::Byte => java::BYTE,
+        AstBaseType::Int => java::INTEGER.as_boxed(),
+        AstBaseType::Long => java::LONG.as_boxed(),
+        AstBaseType::Float => java::FLOAT.as_boxed(),
+        AstBaseType::Double => java::DOUBLE.as_boxed(),
+        AstBaseType::Boolean => java::BOOLEAN.as_boxed(),
+        AstBaseType::String => java::imported("java.lang", "String"),
+        _ => java::local(inner.to_owned()),
+    }
+}
+
+fn encode_value(ty: AstType, value: &str, writer: &str) -> Tokens<'static, Java<'static>> {
+    let writer = writer.to_owned();
+    let value = value.to_owned();
+    match ty {
+        AstType::Byte => toks!(writer, ".writeByte(", value, ");"),
+        AstType::Int => toks!(writer, ".writeInt(", value, ");"),
+        AstType::Long => toks!(writer, ".writeLong(", value, ");"),
+        AstType::Float => toks!(writer, ".writeFloat(", value, ");"),
+        AstType::Double => toks!(writer, ".writeDouble(", value, ");"),
+        AstType::Boolean => toks!(writer, ".writeBoolean(", value, ");"),
+        AstType::String => toks!(writer, ".writeString(", value, ");"),
+        AstType::Enum => toks!(writer, ".writeInt(", value, ".getValue());"),
+        _ => toks!(value, ".encodeTo(", writer, ");"),
+    }
+}
+
+fn decode_value(ty: AstType, origin_ty: &str, reader: &str) -> Tokens<'static, Java<'static>> {
+    let reader = reader.to_owned();
+    match ty {
+        AstType::Byte => toks!(reader, ".readByte()"),
+        AstType::Int => toks!(reader, ".readInt()"),
+        AstType::Long => toks!(reader, ".readLong()"),
+        AstType::Float => toks!(reader, ".readFloat()"),
+        AstType::Double => toks!(reader, ".readDouble()"),
+        AstType::Boolean => toks!(reader, ".readBoolean()"),
+        AstType::String => toks!(reader, ".readString()"),
+        AstType::Enum => toks!(origin_ty.to_owned(), ".fromValue(", reader, ".readInt())"),
+        _ => toks!(origin_ty.to_owned(), ".decodeFrom(", reader, ")"),
+    }
+}
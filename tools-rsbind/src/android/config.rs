@@ -20,6 +20,18 @@ pub struct Android {
     pub so_name: Option<String>,
     pub ext_lib: Option<Vec<String>>,
     pub features_def: Option<Vec<String>>,
+    pub language: Option<AndroidLanguage>,
+}
+
+///
+/// The language of the generated bindings, both are built into the same aar.
+///
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum AndroidLanguage {
+    Java,
+    /// `object` wrappers, data classes and fun interfaces, compiled by the kotlin gradle plugin.
+    Kotlin,
 }
 
 impl Default for Android {
@@ -52,7 +64,8 @@ impl Default for Android {
           namespace: Some(NAMESPACE.to_owned()),
           so_name: Some(SO_NAME.to_owned()),
           ext_lib: None,
-          features_def: None
+          features_def: None,
+          language: None
        }
    }
 }
@@ -160,4 +173,11 @@ impl Android {
             None => vec![],
         }
     }
+
+    pub fn language(&self) -> AndroidLanguage {
+        match self.language {
+            Some(language) => language,
+            None => AndroidLanguage::Java,
+        }
+    }
 }
@@ -1,9 +1,15 @@
+use android::codec::{self, READER_CLASS, WRITER_CLASS};
+use ast::contract::desc::EnumDesc;
 use ast::contract::desc::MethodDesc;
+use ast::contract::desc::MethodKind;
 use ast::contract::desc::StructDesc;
 use ast::contract::desc::TraitDesc;
+use ast::types::split_map_origin_ty;
 use ast::types::AstBaseType;
 use ast::types::AstType;
 use ast::AstResult;
+use config::WireFormat;
+use errors::ErrorKind::*;
 use errors::*;
 use genco::java::{self, *};
 use genco::Custom;
@@ -21,12 +27,15 @@ pub(crate) struct JavaCodeGen<'a> {
     pub namespace: String,
     pub so_name: String,
     pub ext_libs: String,
+    /// annotate the optional values with `@Nullable` of the android support library, which
+    /// can't be found out of android.
+    pub nullable: bool,
 }
 
 impl<'a> JavaCodeGen<'a> {
     pub(crate) fn gen_java_code(&self) -> Result<()> {
         // get the java_gen dir string
-        println!("get java_gen dir string");
+        debug!("get java_gen dir string");
         // fs::write(&java_gen_path, );
 
         // collect all the callbacks.
@@ -45,14 +54,25 @@ impl<'a> JavaCodeGen<'a> {
             let gen = CallbackGen {
                 desc: &each,
                 pkg: self.namespace.clone(),
+                nullable: self.nullable,
             };
 
-            let callback_str = gen.gen()?;
+            let callback_str = gen.gen().chain_err(|| {
+                GenerateError(format!("can't generate java callback {}", &each.name))
+            })?;
             let file_name = format!("{}.java", &each.name);
             let callback_path = self.java_gen_dir.clone().join(file_name);
             fs::write(callback_path, callback_str)?;
         }
 
+        // collect all the enums.
+        let mut enums = vec![];
+        for (_key, enum_descs) in self.ast.enum_descs.iter() {
+            for enum_desc in enum_descs.iter() {
+                enums.push(enum_desc.clone());
+            }
+        }
+
         // generate all the traits.
         for desc in self.ast.trait_descs.iter() {
             let descs = desc.1;
@@ -64,8 +84,12 @@ impl<'a> JavaCodeGen<'a> {
                         so_name: self.so_name.clone(),
                         ext_libs: self.ext_libs.clone(),
                         callbacks: callbacks.clone(),
+                        enums: enums.clone(),
+                        nullable: self.nullable,
                     };
-                    let strs = gen.gen()?;
+                    let strs = gen.gen().chain_err(|| {
+                        GenerateError(format!("can't generate java class {}", &each.name))
+                    })?;
                     let file_name = format!("{}.java", &each.name);
                     let path = self.java_gen_dir.clone().join(file_name);
                     fs::write(path, strs)?;
@@ -79,15 +103,78 @@ impl<'a> JavaCodeGen<'a> {
                 let gen = StructGen {
                     desc: struct_desc,
                     pkg: self.namespace.clone(),
+                    nullable: self.nullable,
                 };
 
-                let struct_str = gen.gen()?;
+                let struct_str = gen.gen().chain_err(|| {
+                    GenerateError(format!("can't generate java struct {}", &struct_desc.name))
+                })?;
                 let file_name = format!("{}.java", &struct_desc.name);
                 let path = self.java_gen_dir.join(file_name);
                 fs::write(path, struct_str)?
             }
         }
 
+        // generate exceptions for all the error types of Result.
+        let mut error_tys: Vec<String> = vec![];
+        for (_key, trait_descs) in self.ast.trait_descs.iter() {
+            for method in trait_descs
+                .iter()
+                .filter(|desc| !desc.is_callback)
+                .flat_map(|desc| desc.methods.iter())
+            {
+                if method.error_type != AstType::Void
+                    && !error_tys.contains(&method.origin_error_ty)
+                {
+                    error_tys.push(method.origin_error_ty.clone());
+                    let gen = ExceptionGen {
+                        error_type: method.error_type,
+                        origin_error_ty: method.origin_error_ty.clone(),
+                        pkg: self.namespace.clone(),
+                        enums: &enums,
+                    };
+
+                    let exception_str = gen.gen().chain_err(|| {
+                        GenerateError(format!(
+                            "can't generate java exception for {}",
+                            &method.origin_error_ty
+                        ))
+                    })?;
+                    let file_name = format!("{}.java", exception_name(&method.origin_error_ty));
+                    let path = self.java_gen_dir.join(file_name);
+                    fs::write(path, exception_str)?
+                }
+            }
+        }
+
+        // generate the codec of the binary wire format.
+        if self.ast.wire_format == WireFormat::Binary {
+            let codecs = vec![
+                (WRITER_CLASS, codec::writer_class()),
+                (READER_CLASS, codec::reader_class()),
+            ];
+            for (name, tokens) in codecs.into_iter() {
+                let codec_str = to_java_file(self.namespace.as_ref(), tokens)?;
+                let path = self.java_gen_dir.join(format!("{}.java", name));
+                fs::write(path, codec_str)?
+            }
+        }
+
+        // generate all the enums
+        for enum_desc in enums.iter() {
+            let gen = EnumGen {
+                desc: enum_desc,
+                pkg: self.namespace.clone(),
+            };
+
+            let enum_str = gen.gen().chain_err(|| {
+                GenerateError(format!("can't generate java enum {}", &enum_desc.name))
+            })?;
+            let file_name = format!("{}.java", &enum_desc.name);
+            let path = self.java_gen_dir.join(file_name);
+            fs::write(path, enum_str)?
+        }
+
         Ok(())
     }
 }
@@ -95,6 +182,7 @@ impl<'a> JavaCodeGen<'a> {
 pub(crate) struct StructGen<'a> {
     pub desc: &'a StructDesc,
     pub pkg: String,
+    pub nullable: bool,
 }
 
 impl<'a> StructGen<'a> {
@@ -109,16 +197,293 @@ impl<'a> StructGen<'a> {
             let field_ty = JavaType::new(field.ty, self.pkg.clone(), field.origin_ty.clone());
             let mut java_field = Field::new(Java::from(field_ty), field.name.clone());
             java_field.modifiers = vec![Modifier::Public];
+            if let (true, AstType::Optional(_)) = (self.nullable, field.ty) {
+                java_field.annotation(nullable());
+            }
             class.fields.push(java_field);
         }
 
+        if self.desc.binary {
+            self.build_binary_methods(&mut class);
+        }
+
+        to_java_file(self.pkg.as_ref(), class.into_tokens())
+    }
+
+    ///
+    /// Structs transferred by the binary wire format are written field by field.
+    ///
+    fn build_binary_methods(&self, class: &mut Class) {
+        let name = self.desc.name.clone();
+
+        let mut encode = Method::new("encodeTo");
+        encode.modifiers = vec![Modifier::Public];
+        let mut writer_arg = Argument::new(java::local(WRITER_CLASS), "writer");
+        writer_arg.modifiers = vec![];
+        encode.arguments.push(writer_arg);
+        let mut encode_body = Tokens::new();
+        for field in self.desc.fields.iter() {
+            let value = format!("this.{}", &field.name);
+            codec::push_encode(
+                &mut encode_body,
+                field.ty,
+                &field.origin_ty,
+                &value,
+                "writer",
+            );
+        }
+        encode.body = encode_body;
+        class.methods.push(encode);
+
+        let mut decode = Method::new("decodeFrom");
+        decode.modifiers = vec![Modifier::Public, Modifier::Static];
+        decode.returns = java::local(name.clone());
+        let mut reader_arg = Argument::new(java::local(READER_CLASS), "reader");
+        reader_arg.modifiers = vec![];
+        decode.arguments.push(reader_arg);
+        let mut decode_body = Tokens::new();
+        decode_body.push(toks!(name.clone(), " value = new ", name, "();"));
+        for field in self.desc.fields.iter() {
+            let target = format!("value.{}", &field.name);
+            codec::push_decode(
+                &mut decode_body,
+                field.ty,
+                &field.origin_ty,
+                &target,
+                "reader",
+            );
+        }
+        decode_body.push("return value;");
+        decode.body = decode_body;
+        class.methods.push(decode);
+    }
+}
+
+pub(crate) struct EnumGen<'a> {
+    pub desc: &'a EnumDesc,
+    pub pkg: String,
+}
+
+impl<'a> EnumGen<'a> {
+    pub(crate) fn gen(&self) -> Result<String> {
+        if self.desc.is_c_like {
+            self.gen_c_like()
+        } else {
+            self.gen_sealed()
+        }
+    }
+
+    ///
+    /// C-like enums are transferred by their discriminant value.
+    ///
+    fn gen_c_like(&self) -> Result<String> {
+        let name = self.desc.name.clone();
+        let mut tokens = Tokens::new();
+        tokens.push(toks!("public enum ", name.clone(), " {"));
+        tokens.nested({
+            let mut body = Tokens::new();
+            for (index, variant) in self.desc.variants.iter().enumerate() {
+                let end = if index == self.desc.variants.len() - 1 {
+                    ";"
+                } else {
+                    ","
+                };
+                body.push(toks!(
+                    variant.name.clone(),
+                    "(",
+                    variant.value.to_string(),
+                    ")",
+                    end
+                ));
+            }
+            body.push("private final int value;");
+            body.push(toks!(name.clone(), "(int value) {"));
+            body.nested("this.value = value;");
+            body.push("}");
+            body.push("public int getValue() {");
+            body.nested("return value;");
+            body.push("}");
+            body.push(toks!(
+                "public static ",
+                name.clone(),
+                " fromValue(int value) {"
+            ));
+            body.nested({
+                let mut from_value = Tokens::new();
+                from_value.push(toks!("for (", name.clone(), " each : values()) {"));
+                from_value.nested({
+                    let mut find = Tokens::new();
+                    find.push("if (each.value == value) {");
+                    find.nested("return each;");
+                    find.push("}");
+                    find
+                });
+                from_value.push("}");
+                from_value.push(toks!(
+                    "throw new IllegalArgumentException(\"unknown value \" + value + \" for enum ",
+                    name.clone(),
+                    "\");"
+                ));
+                from_value
+            });
+            body.push("}");
+            body
+        });
+        tokens.push("}");
+
+        to_java_file(self.pkg.as_ref(), tokens)
+    }
+
+    ///
+    /// Data-carrying enums are transferred by json with a "type" tag, they are generated as
+    /// an abstract class with one nested class for every variant.
+    ///
+    fn gen_sealed(&self) -> Result<String> {
+        let name = self.desc.name.clone();
+        let mut class = Class::new(name.clone());
+        class.modifiers = vec![Modifier::Public, Modifier::Abstract];
+        class
+            .implements
+            .push(java::imported("java.io", "Serializable"));
+
+        let json = java::imported("com.alibaba.fastjson", "JSON");
+        let json_object = java::imported("com.alibaba.fastjson", "JSONObject");
+
+        let mut body = Tokens::new();
+        body.push(toks!("private ", name.clone(), "() {"));
+        body.push("}");
+        for variant in self.desc.variants.iter() {
+            body.push(toks!(
+                "public static final class ",
+                variant.name.clone(),
+                " extends ",
+                name.clone(),
+                " {"
+            ));
+            body.nested({
+                let mut fields = Tokens::new();
+                for field in variant.fields.iter() {
+                    let field_ty =
+                        JavaType::new(field.ty, self.pkg.clone(), field.origin_ty.clone());
+                    fields.push(toks!(
+                        "public ",
+                        Java::from(field_ty),
+                        " ",
+                        field.name.clone(),
+                        ";"
+                    ));
+                }
+                fields
+            });
+            body.push("}");
+        }
+        class.body = body;
+
+        let mut from_json = Method::new("fromJson");
+        from_json.modifiers = vec![Modifier::Public, Modifier::Static];
+        from_json.returns = java::local(name.clone());
+        let mut json_arg = java::Argument::new(java::imported("java.lang", "String"), "json");
+        json_arg.modifiers = vec![];
+        from_json.arguments = vec![json_arg];
+        from_json.body = toks!(
+            "return fromJsonObject(",
+            json.clone(),
+            ".parseObject(json));"
+        );
+        class.methods.push(from_json);
+
+        let mut from_json_object = Method::new("fromJsonObject");
+        from_json_object.modifiers = vec![Modifier::Public, Modifier::Static];
+        from_json_object.returns = java::local(name.clone());
+        let mut object_arg = java::Argument::new(json_object, "object");
+        object_arg.modifiers = vec![];
+        from_json_object.arguments = vec![object_arg];
+        let mut from_body = Tokens::new();
+        from_body.push("String type = object.getString(\"type\");");
+        for variant in self.desc.variants.iter() {
+            from_body.push(toks!("if (\"", variant.name.clone(), "\".equals(type)) {"));
+            from_body.nested(toks!(
+                "return ",
+                json.clone(),
+                ".toJavaObject(object, ",
+                variant.name.clone(),
+                ".class);"
+            ));
+            from_body.push("}");
+        }
+        from_body.push(toks!(
+            "throw new IllegalArgumentException(\"unknown type \" + type + \" for enum ",
+            name.clone(),
+            "\");"
+        ));
+        from_json_object.body = from_body;
+        class.methods.push(from_json_object);
+
         to_java_file(self.pkg.as_ref(), class.into_tokens())
     }
 }
 
+///
+/// Errors of `Result<T, E>` are thrown as checked exceptions, which hold the error value.
+///
+pub(crate) struct ExceptionGen<'a> {
+    pub error_type: AstType,
+    pub origin_error_ty: String,
+    pub pkg: String,
+    pub enums: &'a Vec<EnumDesc>,
+}
+
+impl<'a> ExceptionGen<'a> {
+    pub(crate) fn gen(&self) -> Result<String> {
+        let name = exception_name(&self.origin_error_ty);
+        let error_ty = self.origin_error_ty.clone();
+        let is_data_enum = self
+            .enums
+            .iter()
+            .any(|each| each.name == error_ty && !each.is_c_like);
+        let parse_error = match self.error_type {
+            AstType::Enum => toks!(error_ty.clone(), ".fromValue(Integer.parseInt(message))"),
+            AstType::Struct if is_data_enum => toks!(error_ty.clone(), ".fromJson(message)"),
+            _ => {
+                let json = java::imported("com.alibaba.fastjson", "JSON");
+                toks!(json, ".parseObject(message, ", error_ty.clone(), ".class)")
+            }
+        };
+
+        let mut tokens = Tokens::new();
+        tokens.push(toks!(
+            "public class ",
+            name.clone(),
+            " extends ",
+            java::imported("java.lang", "Exception"),
+            " {"
+        ));
+        tokens.nested({
+            let mut body = Tokens::new();
+            body.push(toks!("private final ", error_ty.clone(), " error;"));
+            body.push(toks!("public ", name.clone(), "(String message) {"));
+            body.nested({
+                let mut constructor = Tokens::new();
+                constructor.push("super(message);");
+                constructor.push(toks!("this.error = ", parse_error, ";"));
+                constructor
+            });
+            body.push("}");
+            body.push(toks!("public ", error_ty.clone(), " getError() {"));
+            body.nested("return error;");
+            body.push("}");
+            body
+        });
+        tokens.push("}");
+
+        to_java_file(self.pkg.as_ref(), tokens)
+    }
+}
+
 pub(crate) struct CallbackGen<'a> {
     pub desc: &'a TraitDesc,
     pub pkg: String,
+    pub nullable: bool,
 }
 
 impl<'a> CallbackGen<'a> {
@@ -135,6 +500,9 @@ impl<'a> CallbackGen<'a> {
                 self.pkg.clone(),
                 method.origin_return_ty.clone(),
             ));
+            if let (true, AstType::Optional(_)) = (self.nullable, method.return_type) {
+                m.annotation(nullable());
+            }
             for arg in method.args.iter() {
                 let arg_ty = Java::from(JavaType::new(
                     arg.ty,
@@ -143,6 +511,9 @@ impl<'a> CallbackGen<'a> {
                 ));
                 let mut argument = java::Argument::new(arg_ty, arg.name.as_ref());
                 argument.modifiers = vec![];
+                if let (true, AstType::Optional(_)) = (self.nullable, arg.ty) {
+                    argument.annotation(nullable());
+                }
 
                 m.arguments.push(argument);
             }
@@ -159,15 +530,27 @@ pub(crate) struct TraitGen<'a> {
     pub so_name: String,
     pub ext_libs: String,
     pub callbacks: Vec<TraitDesc>,
+    pub enums: Vec<EnumDesc>,
+    pub nullable: bool,
 }
 
 impl<'a> TraitGen<'a> {
     pub(crate) fn gen(&self) -> Result<String> {
         let mut class = Class::new(self.desc.name.clone());
         class.modifiers = vec![Modifier::Public];
-        class
-            .implements
-            .push(java::imported("java.io", "Serializable"));
+        if self.desc.is_object {
+            class
+                .implements
+                .push(java::imported("java.io", "Closeable"));
+            // callbacks returned from rust are called like the ones implemented in java.
+            if let Some(ref callback) = self.desc.wraps_callback {
+                class.implements.push(java::local(callback.clone()));
+            }
+        } else {
+            class
+                .implements
+                .push(java::imported("java.io", "Serializable"));
+        }
 
         let mut body = Tokens::new();
         body.push("static {");
@@ -206,13 +589,27 @@ impl<'a> TraitGen<'a> {
         free_method.body = toks!("globalCallbacks.remove(index);");
         class.methods.push(free_method);
 
+        if self.desc.is_object {
+            self.build_object_methods(&mut class);
+        }
+
         let mut sel_callbacks = vec![];
 
         let methods = self.desc.methods.clone();
         let methods = methods.into_iter();
         for method in methods {
-            let mut m = java::Method::new(method.name.clone());
-            m.modifiers = vec![Modifier::Public, Modifier::Static];
+            let is_instance = method.kind == MethodKind::Ref || method.kind == MethodKind::RefMut;
+            // `new` is a keyword in java.
+            let method_name = match method.kind {
+                MethodKind::Constructor if method.name == "new" => "create".to_owned(),
+                _ => method.name.clone(),
+            };
+            let mut m = java::Method::new(method_name);
+            if is_instance {
+                m.modifiers = vec![Modifier::Public];
+            } else {
+                m.modifiers = vec![Modifier::Public, Modifier::Static];
+            }
 
             let return_ty = JavaType::new(
                 method.return_type.clone(),
@@ -220,8 +617,26 @@ impl<'a> TraitGen<'a> {
                 method.origin_return_ty.clone(),
             );
             m.returns = Java::from(return_ty.clone());
+            if method.kind == MethodKind::Constructor {
+                m.returns = java::local(self.desc.name.clone());
+            }
+            if let (true, AstType::Optional(_)) = (self.nullable, method.return_type) {
+                m.annotation(nullable());
+            }
+            if method.error_type != AstType::Void {
+                m.throws = Some(toks!(exception_name(&method.origin_error_ty)));
+            }
 
             let mut method_body: Tokens<Java> = Tokens::new();
+            if is_instance {
+                method_body.push("if (handle == 0) {");
+                method_body.nested(toks!(
+                    "throw new IllegalStateException(\"",
+                    self.desc.name.clone(),
+                    " is closed\");"
+                ));
+                method_body.push("}");
+            }
             for arg in method.args.clone().into_iter() {
                 // Add arguments
                 match arg.ty {
@@ -231,6 +646,9 @@ impl<'a> TraitGen<'a> {
                             JavaType::new(arg.ty.clone(), self.pkg.clone(), arg.origin_ty.clone());
                         let mut argument = Argument::new(java, arg.name.clone());
                         argument.modifiers = vec![];
+                        if let (true, AstType::Optional(_)) = (self.nullable, arg.ty) {
+                            argument.annotation(nullable());
+                        }
                         m.arguments.push(argument);
                     }
                 }
@@ -243,7 +661,7 @@ impl<'a> TraitGen<'a> {
                             .iter()
                             .filter(|callback| callback.name == arg.origin_ty)
                             .collect::<Vec<&TraitDesc>>();
-                        println!("callback xxxx is {:?}", callback.clone());
+                        debug!("callback xxxx is {:?}", callback.clone());
                         if callback.len() > 0 && !sel_callbacks.contains(&callback[0]) {
                             sel_callbacks.push(callback[0]);
                         }
@@ -255,6 +673,32 @@ impl<'a> TraitGen<'a> {
             // Argument convert
             for arg in method.args.clone().into_iter() {
                 let converted = format!("r_{}", &arg.name);
+                if arg.binary {
+                    let writer = format!("{}_writer", &arg.name);
+                    method_body.push(toks!(
+                        WRITER_CLASS,
+                        " ",
+                        writer.clone(),
+                        " = new ",
+                        WRITER_CLASS,
+                        "();"
+                    ));
+                    codec::push_encode(
+                        &mut method_body,
+                        arg.ty,
+                        &arg.origin_ty,
+                        &arg.name,
+                        &writer,
+                    );
+                    method_body.push(toks!(
+                        "byte[] ",
+                        converted,
+                        " = ",
+                        writer,
+                        ".toByteArray();"
+                    ));
+                    continue;
+                }
                 match arg.ty {
                     AstType::Void => (),
                     AstType::Callback => {
@@ -289,6 +733,36 @@ impl<'a> TraitGen<'a> {
                             " ? 1 : 0;"
                         ));
                     }
+                    AstType::Enum => {
+                        method_body.push(toks!(
+                            "int ",
+                            converted.clone(),
+                            " = ",
+                            arg.name.clone(),
+                            ".getValue();"
+                        ));
+                    }
+                    AstType::Optional(base) => {
+                        let java = JavaType::new(arg.ty, self.pkg.clone(), arg.origin_ty.clone());
+                        method_body.push(toks!(
+                            java.to_transfer(),
+                            " ",
+                            converted.clone(),
+                            " = ",
+                            optional_to_transfer(base, arg.name.clone()),
+                            ";"
+                        ));
+                    }
+                    AstType::Map(_, value) => {
+                        self.check_map_value(value, &arg.origin_ty)?;
+                        method_body.push(toks!(
+                            "String ",
+                            converted.clone(),
+                            " = ",
+                            map_to_transfer(arg.name.clone()),
+                            ";"
+                        ));
+                    }
                     AstType::Vec(base) => match base {
                         AstBaseType::Byte => {
                             let java = JavaType::new(
@@ -306,6 +780,25 @@ impl<'a> TraitGen<'a> {
                                 ";"
                             ));
                         }
+                        // Java enums are transferred by their names, like serde does.
+                        AstBaseType::Enum => {
+                            let json_cls = java::imported("com.alibaba.fastjson", "JSON");
+                            let feature = java::imported(
+                                "com.alibaba.fastjson.serializer",
+                                "SerializerFeature",
+                            );
+                            method_body.push(toks!(
+                                "String ",
+                                converted.clone(),
+                                " = ",
+                                json_cls,
+                                ".toJSONString(",
+                                arg.name.clone(),
+                                ", ",
+                                feature,
+                                ".WriteEnumUsingToString);"
+                            ));
+                        }
                         _ => {
                             let json_cls = java::imported("com.alibaba.fastjson", "JSON");
                             method_body.push(toks!(
@@ -319,6 +812,18 @@ impl<'a> TraitGen<'a> {
                             ));
                         }
                     },
+                    AstType::Struct => {
+                        let json_cls = java::imported("com.alibaba.fastjson", "JSON");
+                        method_body.push(toks!(
+                            "String ",
+                            converted.clone(),
+                            " = ",
+                            json_cls,
+                            ".toJSONString(",
+                            arg.name.clone(),
+                            ");"
+                        ));
+                    }
                     _ => {
                         let java =
                             JavaType::new(arg.ty.clone(), self.pkg.clone(), arg.origin_ty.clone());
@@ -329,7 +834,11 @@ impl<'a> TraitGen<'a> {
             }
 
             // Call native method
-            let return_java_ty = return_ty.to_transfer();
+            let return_java_ty = if method.binary_return {
+                codec::bytes_ty()
+            } else {
+                return_ty.to_transfer()
+            };
             match return_ty.ast_type.clone() {
                 AstType::Void => {
                     method_body.push(toks!("native_", method.name.clone(), "("));
@@ -344,6 +853,13 @@ impl<'a> TraitGen<'a> {
                 }
             }
 
+            if is_instance {
+                if method.args.len() > 0 {
+                    method_body.append(toks!("handle, "));
+                } else {
+                    method_body.append(toks!("handle"));
+                }
+            }
             for (index, item) in method.args.clone().into_iter().enumerate() {
                 let converted = format!("r_{}", &item.name);
                 if index == method.args.len() - 1 {
@@ -356,11 +872,40 @@ impl<'a> TraitGen<'a> {
 
             // Return type convert
             match return_ty.ast_type.clone() {
+                _ if method.kind == MethodKind::Constructor => {
+                    method_body.push(toks!("return new ", self.desc.name.clone(), "(ret);"));
+                }
+                _ if method.binary_return => {
+                    method_body.push(toks!(
+                        READER_CLASS,
+                        " reader = new ",
+                        READER_CLASS,
+                        "(ret);"
+                    ));
+                    method_body.push(toks!(Java::from(return_ty.clone()), " value;"));
+                    codec::push_decode(
+                        &mut method_body,
+                        method.return_type,
+                        &method.origin_return_ty,
+                        "value",
+                        "reader",
+                    );
+                    method_body.push("return value;");
+                }
                 AstType::Void => (),
                 AstType::Vec(base) => match base {
                     AstBaseType::Byte => {
                         method_body.push(toks!("return ret;"));
                     }
+                    AstBaseType::Struct
+                        if self.is_data_enum(
+                            &return_ty.origin_ty.replace("Vec<", "").replace(">", ""),
+                        ) =>
+                    {
+                        let sub_ty = return_ty.clone().get_base_ty();
+                        self.push_data_enum_array(&mut method_body, sub_ty, "ret", "array");
+                        method_body.push(toks!("return array;"));
+                    }
                     _ => {
                         let sub_ty = return_ty.clone().get_base_ty();
                         let json = java::imported("com.alibaba.fastjson", "JSON");
@@ -386,6 +931,29 @@ impl<'a> TraitGen<'a> {
                 AstType::Boolean => {
                     method_body.push(toks!("return ret > 0 ? true : false;"));
                 }
+                AstType::Enum => {
+                    method_body.push(toks!(
+                        "return ",
+                        method.origin_return_ty,
+                        ".fromValue(ret);"
+                    ));
+                }
+                AstType::Optional(base) => {
+                    let value = self.optional_from_transfer(
+                        base,
+                        method.origin_return_ty.clone(),
+                        "ret".to_owned(),
+                    );
+                    method_body.push(toks!("return ", value, ";"));
+                }
+                AstType::Struct if self.is_data_enum(&method.origin_return_ty) => {
+                    method_body.push(toks!("return ", method.origin_return_ty, ".fromJson(ret);"));
+                }
+                AstType::Map(_, value) => {
+                    self.check_map_value(value, &method.origin_return_ty)?;
+                    let value = map_from_transfer(return_ty.clone(), "ret".to_owned());
+                    method_body.push(toks!("return ", value, ";"));
+                }
                 AstType::Struct => {
                     let json = java::imported("com.alibaba.fastjson", "JSON");
                     method_body.push(toks!(
@@ -396,6 +964,9 @@ impl<'a> TraitGen<'a> {
                         ".class);"
                     ));
                 }
+                AstType::Callback => {
+                    method_body.push(toks!("return new Rust", method.origin_return_ty, "(ret);"));
+                }
                 _ => {
                     method_body.push(toks!("return ret;"));
                 }
@@ -455,6 +1026,62 @@ impl<'a> TraitGen<'a> {
                                 " > 0 ? true : false;"
                             ));
                         }
+                        AstType::Enum => {
+                            body.push(toks!(
+                                arg.origin_ty.clone(),
+                                " j_",
+                                arg.name.clone(),
+                                " = ",
+                                arg.origin_ty.clone(),
+                                ".fromValue(",
+                                arg.name.clone(),
+                                ");"
+                            ));
+                        }
+                        AstType::Optional(base) => {
+                            let java =
+                                JavaType::new(arg.ty, self.pkg.clone(), arg.origin_ty.clone());
+                            let value = self.optional_from_transfer(
+                                base,
+                                arg.origin_ty.clone(),
+                                arg.name.clone(),
+                            );
+                            body.push(toks!(
+                                Java::from(java),
+                                " j_",
+                                arg.name.clone(),
+                                " = ",
+                                value,
+                                ";"
+                            ));
+                        }
+                        AstType::Struct if self.is_data_enum(&arg.origin_ty) => {
+                            body.push(toks!(
+                                arg.origin_ty.clone(),
+                                " j_",
+                                arg.name.clone(),
+                                " = ",
+                                arg.origin_ty.clone(),
+                                ".fromJson(",
+                                arg.name.clone(),
+                                ");"
+                            ));
+                        }
+                        AstType::Vec(AstBaseType::Struct)
+                            if self.is_data_enum(
+                                &arg.origin_ty.replace("Vec<", "").replace(">", ""),
+                            ) =>
+                        {
+                            let java =
+                                JavaType::new(arg.ty, self.pkg.clone(), arg.origin_ty.clone());
+                            let converted = format!("j_{}", &arg.name);
+                            self.push_data_enum_array(
+                                &mut body,
+                                java.get_base_ty(),
+                                &arg.name,
+                                &converted,
+                            );
+                        }
                         AstType::Struct => {
                             let json = java::imported("com.alibaba.fastjson", "JSON");
                             body.push(toks!(
@@ -470,6 +1097,28 @@ impl<'a> TraitGen<'a> {
                                 ".class);"
                             ));
                         }
+                        AstType::Map(_, value) => {
+                            self.check_map_value(value, &arg.origin_ty)?;
+                            let java =
+                                JavaType::new(arg.ty, self.pkg.clone(), arg.origin_ty.clone());
+                            body.push(toks!(
+                                Java::from(java.clone()),
+                                " j_",
+                                arg.name.clone(),
+                                " = ",
+                                map_from_transfer(java, arg.name.clone()),
+                                ";"
+                            ));
+                        }
+                        AstType::Vec(AstBaseType::Byte) => {
+                            body.push(toks!(
+                                "byte[] j_",
+                                arg.name.clone(),
+                                " = ",
+                                arg.name.clone(),
+                                ";"
+                            ));
+                        }
                         AstType::Vec(_) => {
                             let list = java::imported("java.util", "List");
                             let json = java::imported("com.alibaba.fastjson", "JSON");
@@ -564,6 +1213,40 @@ impl<'a> TraitGen<'a> {
                     AstType::Boolean => {
                         body.push(toks!("return result ? 1 : 0;"));
                     }
+                    AstType::Enum => {
+                        body.push(toks!("return result.getValue();"));
+                    }
+                    // results are transferred like arguments of native methods.
+                    AstType::Struct => {
+                        let json = java::imported("com.alibaba.fastjson", "JSON");
+                        body.push(toks!("return ", json, ".toJSONString(result);"));
+                    }
+                    AstType::Vec(AstBaseType::Byte) => {
+                        body.push(toks!("return result;"));
+                    }
+                    AstType::Vec(AstBaseType::Enum) => {
+                        let json = java::imported("com.alibaba.fastjson", "JSON");
+                        let feature =
+                            java::imported("com.alibaba.fastjson.serializer", "SerializerFeature");
+                        body.push(toks!(
+                            "return ",
+                            json,
+                            ".toJSONString(result, ",
+                            feature,
+                            ".WriteEnumUsingToString);"
+                        ));
+                    }
+                    AstType::Vec(_) => {
+                        let json = java::imported("com.alibaba.fastjson", "JSON");
+                        body.push(toks!("return ", json, ".toJSONString(result);"));
+                    }
+                    AstType::Optional(base) => {
+                        body.push(toks!(
+                            "return ",
+                            optional_to_transfer(base, "result".to_owned()),
+                            ";"
+                        ));
+                    }
                     AstType::Void => (),
                     _ => {
                         body.push(toks!("return result;"));
@@ -581,6 +1264,175 @@ impl<'a> TraitGen<'a> {
         to_java_file(self.pkg.as_ref(), class.into_tokens())
     }
 
+    ///
+    /// Objects hold the handle of a boxed rust object, which is freed by `close()`, or by the
+    /// finalizer if it is never closed.
+    ///
+    fn build_object_methods(&self, class: &mut Class) {
+        let mut handle_field = Field::new(java::LONG, "handle");
+        handle_field.modifiers = vec![Modifier::Private];
+        class.fields.push(handle_field);
+
+        // returned callbacks are created by the classes returning them.
+        let visibility = match self.desc.wraps_callback {
+            Some(_) => "",
+            None => "private ",
+        };
+        class
+            .body
+            .push(toks!(visibility, self.desc.name.clone(), "(long handle) {"));
+        class.body.nested("this.handle = handle;");
+        class.body.push("}");
+
+        let mut close = Method::new("close");
+        close.modifiers = vec![Modifier::Public];
+        close.annotation(toks!("@Override"));
+        let mut close_body = Tokens::new();
+        close_body.push("synchronized (this) {");
+        close_body.nested({
+            let mut free = Tokens::new();
+            free.push("if (handle != 0) {");
+            free.nested("native_free_object(handle);");
+            free.nested("handle = 0;");
+            free.push("}");
+            free
+        });
+        close_body.push("}");
+        close.body = close_body;
+        class.methods.push(close);
+
+        let mut finalize = Method::new("finalize");
+        finalize.modifiers = vec![Modifier::Protected];
+        finalize.annotation(toks!("@Override"));
+        finalize.throws = Some(toks!("Throwable"));
+        let mut finalize_body = Tokens::new();
+        finalize_body.push("try {");
+        finalize_body.nested("close();");
+        finalize_body.push("} finally {");
+        finalize_body.nested("super.finalize();");
+        finalize_body.push("}");
+        finalize.body = finalize_body;
+        class.methods.push(finalize);
+
+        let mut free_object = Method::new("native_free_object");
+        free_object.modifiers = vec![Modifier::Private, Modifier::Static, Modifier::Native];
+        let mut handle_arg = Argument::new(java::LONG, "handle");
+        handle_arg.modifiers = vec![];
+        free_object.arguments.push(handle_arg);
+        class.methods.push(free_object);
+    }
+
+    ///
+    /// Data-carrying enums are abstract classes in java, so they can't be parsed by fastjson directly.
+    ///
+    fn is_data_enum(&self, origin_ty: &str) -> bool {
+        self.enums
+            .iter()
+            .any(|each| each.name == origin_ty && !each.is_c_like)
+    }
+
+    ///
+    /// Convert the nullable string transferred through jni to the value of `Option<T>`,
+    /// primitives are transferred as they are boxed.
+    ///
+    fn optional_from_transfer(
+        &self,
+        base: AstBaseType,
+        origin_ty: String,
+        value: String,
+    ) -> Tokens<'static, Java<'static>> {
+        let inner = origin_ty.replace("Option<", "").replace(">", "");
+        let converted = match base {
+            AstBaseType::String => return toks!(value),
+            AstBaseType::Enum => toks!(
+                inner.clone(),
+                ".fromValue(Integer.parseInt(",
+                value.clone(),
+                "))"
+            ),
+            AstBaseType::Struct if self.is_data_enum(&inner) => {
+                toks!(inner.clone(), ".fromJson(", value.clone(), ")")
+            }
+            AstBaseType::Struct => {
+                let json = java::imported("com.alibaba.fastjson", "JSON");
+                toks!(
+                    json,
+                    ".parseObject(",
+                    value.clone(),
+                    ", ",
+                    inner.clone(),
+                    ".class)"
+                )
+            }
+            _ => return toks!(value),
+        };
+        toks!(value, " == null ? null : ", converted)
+    }
+
+    ///
+    /// Maps are parsed by fastjson as a whole, which can't parse data-carrying enums.
+    ///
+    fn check_map_value(&self, value: AstBaseType, origin_ty: &str) -> Result<()> {
+        let (_, _, value_ty) = split_map_origin_ty(origin_ty);
+        match value {
+            AstBaseType::Struct if self.is_data_enum(&value_ty) => Err(GenerateError(format!(
+                "enums with data can't be values of map, found {}",
+                origin_ty
+            ))
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    ///
+    /// parse a json array of data-carrying enums one by one.
+    ///
+    fn push_data_enum_array(
+        &self,
+        body: &mut Tokens<Java>,
+        sub_ty: Java<'static>,
+        json_name: &str,
+        array_name: &str,
+    ) {
+        let json = java::imported("com.alibaba.fastjson", "JSON");
+        let json_array = java::imported("com.alibaba.fastjson", "JSONArray");
+        let list_name = format!("{}_list", json_name);
+        body.push(toks!(
+            json_array,
+            " ",
+            list_name.clone(),
+            " = ",
+            json,
+            ".parseArray(",
+            json_name.to_owned(),
+            ");"
+        ));
+        body.push(toks!(
+            sub_ty.clone(),
+            "[] ",
+            array_name.to_owned(),
+            " = new ",
+            sub_ty.clone(),
+            "[",
+            list_name.clone(),
+            ".size()];"
+        ));
+        body.push(toks!(
+            "for (int i = 0; i < ",
+            list_name.clone(),
+            ".size(); i++) {"
+        ));
+        body.nested(toks!(
+            array_name.to_owned(),
+            "[i] = ",
+            sub_ty.clone(),
+            ".fromJsonObject(",
+            list_name.clone(),
+            ".getJSONObject(i));"
+        ));
+        body.push("}");
+    }
+
     ///
     /// build native methods for accessing .so
     ///
@@ -601,6 +1453,18 @@ impl<'a> TraitGen<'a> {
                     m.returns = java.to_transfer();
                 }
             }
+            if method.binary_return {
+                m.returns = codec::bytes_ty();
+            }
+            if method.error_type != AstType::Void {
+                m.throws = Some(toks!(exception_name(&method.origin_error_ty)));
+            }
+
+            if method.kind == MethodKind::Ref || method.kind == MethodKind::RefMut {
+                let mut handle_arg = Argument::new(java::LONG, "handle");
+                handle_arg.modifiers = vec![];
+                m.arguments.push(handle_arg);
+            }
 
             let args = method.args.clone();
             for arg in args.iter() {
@@ -609,7 +1473,12 @@ impl<'a> TraitGen<'a> {
                     _ => {
                         let java =
                             JavaType::new(arg.ty.clone(), self.pkg.clone(), arg.origin_ty.clone());
-                        let mut argument = Argument::new(java.to_transfer(), arg.name.clone());
+                        let transfer = if arg.binary {
+                            codec::bytes_ty()
+                        } else {
+                            java.to_transfer()
+                        };
+                        let mut argument = Argument::new(transfer, arg.name.clone());
                         argument.modifiers = vec![];
                         m.arguments.push(argument);
                     }
@@ -647,14 +1516,21 @@ impl JavaType {
         self.to_java_array(base_name, true)
     }
 
+    pub(crate) fn to_boxed(&self) -> Java<'static> {
+        Java::from(self.clone()).as_boxed()
+    }
+
     pub(crate) fn to_transfer(&self) -> Java<'static> {
         match self.ast_type {
-            AstType::Boolean => java::INTEGER,
+            AstType::Boolean | AstType::Enum => java::INTEGER,
             AstType::Vec(base) => match base {
                 AstBaseType::Byte => Java::from(self.clone()),
                 _ => java::imported("java.lang", "String"),
             },
-            AstType::Struct => java::imported("java.lang", "String"),
+            AstType::Optional(base) if base.is_primitive() => Java::from(self.clone()),
+            AstType::Struct | AstType::Optional(_) | AstType::Map(_, _) => {
+                java::imported("java.lang", "String")
+            }
             AstType::Callback => java::LONG,
             _ => Java::from(self.clone()),
         }
@@ -664,7 +1540,7 @@ impl JavaType {
     pub(crate) fn get_base_ty(&self) -> Java<'static> {
         match self.ast_type {
             AstType::Vec(base) => match base {
-                AstBaseType::Struct => {
+                AstBaseType::Struct | AstBaseType::Enum => {
                     let sub_origin_ty = self.origin_ty.replace("Vec<", "").replace(">", "");
                     java::local(sub_origin_ty)
                 }
@@ -702,7 +1578,7 @@ impl From<JavaType> for Java<'static> {
             AstType::Double => java::DOUBLE,
             AstType::String => java::imported("java.lang", "String"),
             AstType::Vec(base) => match base {
-                AstBaseType::Struct => {
+                AstBaseType::Struct | AstBaseType::Enum => {
                     let sub_origin_ty = item.origin_ty.replace("Vec<", "").replace(">", "");
                     JavaType::new(AstType::from(base), item.pkg.clone(), sub_origin_ty.clone())
                         .to_array()
@@ -723,12 +1599,112 @@ impl From<JavaType> for Java<'static> {
                 )
                 .to_boxed_array(),
             },
+            // Option<T> is nullable, so primitive types are boxed.
+            AstType::Optional(base) => match base {
+                AstBaseType::Struct | AstBaseType::Enum => {
+                    let sub_origin_ty = item.origin_ty.replace("Option<", "").replace(">", "");
+                    java::local(sub_origin_ty)
+                }
+                _ => JavaType::new(
+                    AstType::from(base),
+                    item.pkg.clone(),
+                    item.origin_ty.clone(),
+                )
+                .to_boxed(),
+            },
+            // keys and values of maps are boxed, structs and enums are used as they are.
+            AstType::Map(key, value) => {
+                let (_, key_ty, value_ty) = split_map_origin_ty(&item.origin_ty);
+                let key = JavaType::new(AstType::from(key), item.pkg.clone(), key_ty).to_boxed();
+                let value = match value {
+                    AstBaseType::Struct | AstBaseType::Enum => java::local(value_ty),
+                    _ => JavaType::new(AstType::from(value), item.pkg.clone(), value_ty).to_boxed(),
+                };
+                java::imported("java.util", "Map").with_arguments(vec![key, value])
+            }
             AstType::Void => java::VOID,
-            AstType::Callback | AstType::Struct => java::local(item.origin_ty.clone()),
+            AstType::Callback | AstType::Struct | AstType::Enum => {
+                java::local(item.origin_ty.clone())
+            }
         }
     }
 }
 
+///
+/// Convert the value of `Option<T>` to the nullable string transferred through jni,
+/// primitives are transferred as they are boxed.
+///
+fn optional_to_transfer(base: AstBaseType, value: String) -> Tokens<'static, Java<'static>> {
+    match base {
+        AstBaseType::String => toks!(value),
+        AstBaseType::Enum => toks!(
+            value.clone(),
+            " == null ? null : String.valueOf(",
+            value,
+            ".getValue())"
+        ),
+        AstBaseType::Struct => {
+            let json = java::imported("com.alibaba.fastjson", "JSON");
+            toks!(
+                value.clone(),
+                " == null ? null : ",
+                json,
+                ".toJSONString(",
+                value,
+                ")"
+            )
+        }
+        _ => toks!(value),
+    }
+}
+
+///
+/// Convert a map to the json transferred through jni. Keys of json are always strings, and
+/// java enums are transferred by their names, like serde does.
+///
+fn map_to_transfer(value: String) -> Tokens<'static, Java<'static>> {
+    let json = java::imported("com.alibaba.fastjson", "JSON");
+    let feature = java::imported("com.alibaba.fastjson.serializer", "SerializerFeature");
+    toks!(
+        json,
+        ".toJSONString(",
+        value,
+        ", ",
+        feature.clone(),
+        ".WriteNonStringKeyAsString, ",
+        feature,
+        ".WriteEnumUsingToString)"
+    )
+}
+
+///
+/// Parse a map from the json transferred through jni.
+///
+fn map_from_transfer(java: JavaType, value: String) -> Tokens<'static, Java<'static>> {
+    let json = java::imported("com.alibaba.fastjson", "JSON");
+    let type_reference = java::imported("com.alibaba.fastjson", "TypeReference")
+        .with_arguments(vec![Java::from(java)]);
+    toks!(
+        json,
+        ".parseObject(",
+        value,
+        ", new ",
+        type_reference,
+        "() {})"
+    )
+}
+
+fn exception_name(origin_error_ty: &str) -> String {
+    format!("{}Exception", origin_error_ty)
+}
+
+fn nullable() -> Tokens<'static, Java<'static>> {
+    toks!(
+        "@",
+        java::imported("android.support.annotation", "Nullable")
+    )
+}
+
 fn to_java_file(pkg: &str, tokens: Tokens<Java>) -> Result<String> {
     let mut buf = String::new();
     {
@@ -0,0 +1,1137 @@
+//!
+//! Kotlin bindings for android, selected by `language = "kotlin"` in `[android]`.
+//!
+//! They are called by the same jni bridge as the java ones, so the classes, the native methods
+//! and the static methods called from rust keep the names and the signatures of java.
+//!
+use ast::contract::desc::ArgDesc;
+use ast::contract::desc::EnumDesc;
+use ast::contract::desc::MethodDesc;
+use ast::contract::desc::MethodKind;
+use ast::contract::desc::StructDesc;
+use ast::contract::desc::TraitDesc;
+use ast::types::split_map_origin_ty;
+use ast::types::AstBaseType;
+use ast::types::AstType;
+use ast::AstResult;
+use config::WireFormat;
+use errors::ErrorKind::*;
+use errors::*;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+
+const JSON: &str = "com.alibaba.fastjson.JSON";
+const JSON_OBJECT: &str = "com.alibaba.fastjson.JSONObject";
+const TYPE_REFERENCE: &str = "com.alibaba.fastjson.TypeReference";
+const SERIALIZER_FEATURE: &str = "com.alibaba.fastjson.serializer.SerializerFeature";
+const SERIALIZABLE: &str = "java.io.Serializable";
+
+/// keywords of kotlin which are not keywords of rust, so they can be names in contracts.
+const KEYWORDS: [&str; 14] = [
+    "class",
+    "do",
+    "fun",
+    "interface",
+    "is",
+    "null",
+    "object",
+    "package",
+    "this",
+    "throw",
+    "typealias",
+    "val",
+    "var",
+    "when",
+];
+
+pub(crate) struct KotlinCodeGen<'a> {
+    pub kotlin_gen_dir: &'a PathBuf,
+    pub ast: &'a AstResult,
+    pub namespace: String,
+    pub so_name: String,
+    pub ext_libs: String,
+}
+
+impl<'a> KotlinCodeGen<'a> {
+    pub(crate) fn gen_kotlin_code(&self) -> Result<()> {
+        if self.ast.wire_format == WireFormat::Binary {
+            return Err(GenerateError(
+                "the binary wire format is not supported by kotlin yet, use java instead"
+                    .to_string(),
+            )
+            .into());
+        }
+
+        // collect all the callbacks.
+        let callbacks = self
+            .ast
+            .trait_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .filter(|desc| desc.is_callback)
+            .cloned()
+            .collect::<Vec<TraitDesc>>();
+
+        // collect all the enums.
+        let enums = self
+            .ast
+            .enum_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .cloned()
+            .collect::<Vec<EnumDesc>>();
+
+        for each in callbacks.iter() {
+            let callback_str = CallbackGen { desc: each }.gen(&self.namespace);
+            self.write(&each.name, callback_str)?;
+        }
+
+        for (_key, trait_descs) in self.ast.trait_descs.iter() {
+            for each in trait_descs.iter().filter(|desc| !desc.is_callback) {
+                let gen = TraitGen {
+                    desc: each,
+                    so_name: &self.so_name,
+                    ext_libs: &self.ext_libs,
+                    callbacks: &callbacks,
+                    enums: &enums,
+                };
+                let trait_str = gen.gen(&self.namespace).chain_err(|| {
+                    GenerateError(format!("can't generate kotlin class {}", &each.name))
+                })?;
+                self.write(&each.name, trait_str)?;
+            }
+        }
+
+        for (_key, struct_descs) in self.ast.struct_descs.iter() {
+            for struct_desc in struct_descs.iter() {
+                let gen = StructGen {
+                    desc: struct_desc,
+                    enums: &enums,
+                };
+                self.write(&struct_desc.name, gen.gen(&self.namespace))?;
+            }
+        }
+
+        // generate exceptions for all the error types of Result.
+        let mut error_tys: Vec<String> = vec![];
+        for (_key, trait_descs) in self.ast.trait_descs.iter() {
+            for method in trait_descs
+                .iter()
+                .filter(|desc| !desc.is_callback)
+                .flat_map(|desc| desc.methods.iter())
+            {
+                if method.error_type != AstType::Void
+                    && !error_tys.contains(&method.origin_error_ty)
+                {
+                    error_tys.push(method.origin_error_ty.clone());
+                    let gen = ExceptionGen {
+                        error_type: method.error_type,
+                        origin_error_ty: &method.origin_error_ty,
+                        enums: &enums,
+                    };
+                    self.write(
+                        &exception_name(&method.origin_error_ty),
+                        gen.gen(&self.namespace),
+                    )?;
+                }
+            }
+        }
+
+        for enum_desc in enums.iter() {
+            let gen = EnumGen {
+                desc: enum_desc,
+                enums: &enums,
+            };
+            self.write(&enum_desc.name, gen.gen(&self.namespace))?;
+        }
+
+        Ok(())
+    }
+
+    fn write(&self, name: &str, content: String) -> Result<()> {
+        let path = self.kotlin_gen_dir.join(format!("{}.kt", name));
+        fs::write(&path, content).chain_err(|| FileError(format!("can't write {:?}", &path)))
+    }
+}
+
+///
+/// Structs are data classes whose properties all have defaults, so they have a constructor
+/// without arguments, which fastjson needs.
+///
+struct StructGen<'a> {
+    desc: &'a StructDesc,
+    enums: &'a [EnumDesc],
+}
+
+impl<'a> StructGen<'a> {
+    fn gen(&self, pkg: &str) -> String {
+        let mut file = KotlinFile::new();
+        let serializable = file.imported(SERIALIZABLE);
+        push_data_class(
+            &mut file,
+            &self.desc.name,
+            &self.desc.fields,
+            &format!(" : {}", serializable),
+            self.enums,
+        );
+        file.to_kotlin(pkg)
+    }
+}
+
+struct EnumGen<'a> {
+    desc: &'a EnumDesc,
+    enums: &'a [EnumDesc],
+}
+
+impl<'a> EnumGen<'a> {
+    fn gen(&self, pkg: &str) -> String {
+        if self.desc.is_c_like {
+            self.gen_c_like(pkg)
+        } else {
+            self.gen_sealed(pkg)
+        }
+    }
+
+    ///
+    /// C-like enums are transferred by their discriminant value.
+    ///
+    fn gen_c_like(&self, pkg: &str) -> String {
+        let name = &self.desc.name;
+        let mut file = KotlinFile::new();
+        file.open(format!("enum class {}(val value: Int) {{", name));
+        for (index, variant) in self.desc.variants.iter().enumerate() {
+            let end = if index == self.desc.variants.len() - 1 {
+                ";"
+            } else {
+                ","
+            };
+            file.line(format!("{}({}){}", &variant.name, variant.value, end));
+        }
+
+        file.separate();
+        file.open("companion object {");
+        file.line("@JvmStatic");
+        file.line(format!(
+            "fun fromValue(value: Int): {} = values().firstOrNull {{ it.value == value }}",
+            name
+        ));
+        file.line(format!(
+            "    ?: throw IllegalArgumentException(\"unknown value $value for enum {}\")",
+            name
+        ));
+        file.close("}");
+        file.close("}");
+        file.to_kotlin(pkg)
+    }
+
+    ///
+    /// Data-carrying enums are transferred by json with a "type" tag, they are generated as a
+    /// sealed class, with a data class for every variant with fields and an object for the
+    /// others.
+    ///
+    fn gen_sealed(&self, pkg: &str) -> String {
+        let name = &self.desc.name;
+        let mut file = KotlinFile::new();
+        let serializable = file.imported(SERIALIZABLE);
+        let json = file.imported(JSON);
+        let json_object = file.imported(JSON_OBJECT);
+
+        file.open(format!("sealed class {} : {} {{", name, serializable));
+        for variant in self.desc.variants.iter() {
+            file.separate();
+            if variant.fields.is_empty() {
+                file.line(format!("object {} : {}()", &variant.name, name));
+            } else {
+                push_data_class(
+                    &mut file,
+                    &variant.name,
+                    &variant.fields,
+                    &format!(" : {}()", name),
+                    self.enums,
+                );
+            }
+        }
+
+        file.separate();
+        file.open("companion object {");
+        file.line("@JvmStatic");
+        file.line(format!(
+            "fun fromJson(json: String): {} = fromJsonObject({}.parseObject(json))",
+            name, json
+        ));
+        file.separate();
+        file.line("@JvmStatic");
+        file.open(format!(
+            "fun fromJsonObject(value: {}): {} {{",
+            json_object, name
+        ));
+        file.open("return when (val type = value.getString(\"type\")) {");
+        for variant in self.desc.variants.iter() {
+            if variant.fields.is_empty() {
+                file.line(format!("\"{}\" -> {}", &variant.name, &variant.name));
+            } else {
+                file.line(format!(
+                    "\"{}\" -> {}.toJavaObject(value, {}::class.java)",
+                    &variant.name, json, &variant.name
+                ));
+            }
+        }
+        file.line(format!(
+            "else -> throw IllegalArgumentException(\"unknown type $type for enum {}\")",
+            name
+        ));
+        file.close("}");
+        file.close("}");
+        file.close("}");
+        file.close("}");
+        file.to_kotlin(pkg)
+    }
+}
+
+///
+/// Errors of `Result<T, E>` are thrown as exceptions, which hold the error value.
+///
+struct ExceptionGen<'a> {
+    error_type: AstType,
+    origin_error_ty: &'a str,
+    enums: &'a [EnumDesc],
+}
+
+impl<'a> ExceptionGen<'a> {
+    fn gen(&self, pkg: &str) -> String {
+        let mut file = KotlinFile::new();
+        let error_ty = self.origin_error_ty;
+        let parse_error = match self.error_type {
+            AstType::Enum => format!("{}.fromValue(message.toInt())", error_ty),
+            AstType::Struct if is_data_enum(self.enums, error_ty) => {
+                format!("{}.fromJson(message)", error_ty)
+            }
+            _ => {
+                let json = file.imported(JSON);
+                format!("{}.parseObject(message, {}::class.java)", json, error_ty)
+            }
+        };
+
+        // rust throws it by the constructor taking the message.
+        file.open(format!(
+            "class {}(message: String) : Exception(message) {{",
+            exception_name(error_ty)
+        ));
+        file.line(format!("val error: {} = {}", error_ty, parse_error));
+        file.close("}");
+        file.to_kotlin(pkg)
+    }
+}
+
+///
+/// Callbacks with one method are fun interfaces, so lambdas can be passed for them.
+///
+struct CallbackGen<'a> {
+    desc: &'a TraitDesc,
+}
+
+impl<'a> CallbackGen<'a> {
+    fn gen(&self, pkg: &str) -> String {
+        let mut file = KotlinFile::new();
+        let kind = if self.desc.methods.len() == 1 {
+            "fun interface"
+        } else {
+            "interface"
+        };
+        file.open(format!("{} {} {{", kind, &self.desc.name));
+        for method in self.desc.methods.iter() {
+            file.line(format!(
+                "fun {}({}){}",
+                ident(&method.name),
+                args_str(method),
+                returns_str(method.return_type, &method.origin_return_ty)
+            ));
+        }
+        file.close("}");
+        file.to_kotlin(pkg)
+    }
+}
+
+///
+/// Traits are objects with static methods, traits with constructors are classes holding the
+/// handle of a boxed rust object, whose static methods are in the companion object.
+///
+struct TraitGen<'a> {
+    desc: &'a TraitDesc,
+    so_name: &'a str,
+    ext_libs: &'a str,
+    callbacks: &'a [TraitDesc],
+    enums: &'a [EnumDesc],
+}
+
+impl<'a> TraitGen<'a> {
+    fn gen(&self, pkg: &str) -> Result<String> {
+        let mut file = KotlinFile::new();
+        let name = &self.desc.name;
+        if !self.desc.is_object {
+            file.open(format!("object {} {{", name));
+            self.push_statics(&mut file)?;
+            file.close("}");
+            return Ok(file.to_kotlin(pkg));
+        }
+
+        // callbacks returned from rust are called like the ones implemented in kotlin, and
+        // created by the classes returning them.
+        let closeable = file.imported("java.io.Closeable");
+        let (visibility, supers) = match self.desc.wraps_callback {
+            Some(ref callback) => ("internal", format!("{}, {}", closeable, callback)),
+            None => ("private", closeable),
+        };
+        file.open(format!(
+            "class {} {} constructor(private var handle: Long) : {} {{",
+            name, visibility, supers
+        ));
+        file.open("companion object {");
+        self.push_statics(&mut file)?;
+        file.separate();
+        file.line("@JvmStatic");
+        file.line("private external fun native_free_object(handle: Long)");
+        file.close("}");
+
+        for method in self
+            .desc
+            .methods
+            .iter()
+            .filter(|method| is_instance(method))
+        {
+            file.separate();
+            self.push_method(&mut file, method)?;
+        }
+
+        file.separate();
+        file.open("override fun close() {");
+        file.open("synchronized(this) {");
+        file.open("if (handle != 0L) {");
+        file.line("native_free_object(handle)");
+        file.line("handle = 0");
+        file.close("}");
+        file.close("}");
+        file.close("}");
+
+        file.separate();
+        file.open("protected fun finalize() {");
+        file.line("close()");
+        file.close("}");
+        file.close("}");
+        Ok(file.to_kotlin(pkg))
+    }
+
+    ///
+    /// the members which are static in java, rust calls the native methods, `invoke_*` and
+    /// `free_callback` on the class.
+    ///
+    fn push_statics(&self, file: &mut KotlinFile) -> Result<()> {
+        let atomic_long = file.imported("java.util.concurrent.atomic.AtomicLong");
+        let concurrent_map = file.imported("java.util.concurrent.ConcurrentHashMap");
+
+        file.open("init {");
+        file.line(format!("System.loadLibrary(\"{}\")", self.so_name));
+        for ext_lib in self.ext_libs.split(",").filter(|lib| !lib.is_empty()) {
+            file.line(format!("System.loadLibrary(\"{}\")", ext_lib));
+        }
+        file.close("}");
+
+        file.separate();
+        file.line(format!("private val globalIndex = {}(0)", atomic_long));
+        file.line(format!(
+            "private val globalCallbacks = {}<Long, Any>()",
+            concurrent_map
+        ));
+
+        file.separate();
+        file.line("@JvmStatic");
+        file.open("fun free_callback(index: Long) {");
+        file.line("globalCallbacks.remove(index)");
+        file.close("}");
+
+        for method in self
+            .desc
+            .methods
+            .iter()
+            .filter(|method| !is_instance(method))
+        {
+            file.separate();
+            self.push_method(file, method)?;
+        }
+
+        // the callbacks in arguments are called by rust through these.
+        let mut sel_callbacks: Vec<&TraitDesc> = vec![];
+        for arg in self
+            .desc
+            .methods
+            .iter()
+            .flat_map(|method| method.args.iter())
+        {
+            if arg.ty != AstType::Callback {
+                continue;
+            }
+            let callback = self
+                .callbacks
+                .iter()
+                .find(|callback| callback.name == arg.origin_ty);
+            if let Some(callback) = callback {
+                if !sel_callbacks.contains(&callback) {
+                    sel_callbacks.push(callback);
+                }
+            }
+        }
+        for callback in sel_callbacks.iter() {
+            for method in callback.methods.iter() {
+                file.separate();
+                self.push_invoke(file, &callback.name, method)?;
+            }
+        }
+
+        for method in self.desc.methods.iter() {
+            file.separate();
+            self.push_native(file, method);
+        }
+
+        Ok(())
+    }
+
+    fn push_method(&self, file: &mut KotlinFile, method: &MethodDesc) -> Result<()> {
+        let is_instance = is_instance(method);
+        // `create` like java, so the bindings are called the same way from java.
+        let method_name = match method.kind {
+            MethodKind::Constructor if method.name == "new" => "create".to_owned(),
+            _ => ident(&method.name),
+        };
+        let returns = match method.kind {
+            MethodKind::Constructor => format!(": {}", &self.desc.name),
+            _ => returns_str(method.return_type, &method.origin_return_ty),
+        };
+
+        if !is_instance {
+            file.line("@JvmStatic");
+        }
+        if method.error_type != AstType::Void {
+            file.line(format!(
+                "@Throws({}::class)",
+                exception_name(&method.origin_error_ty)
+            ));
+        }
+        let modifier = match self.desc.wraps_callback {
+            Some(_) if is_instance => "override ",
+            _ => "",
+        };
+        file.open(format!(
+            "{}fun {}({}){} {{",
+            modifier,
+            method_name,
+            args_str(method),
+            returns
+        ));
+
+        if is_instance {
+            file.line(format!(
+                "check(handle != 0L) {{ \"{} is closed\" }}",
+                &self.desc.name
+            ));
+        }
+
+        let mut call_args = vec![];
+        if is_instance {
+            call_args.push("handle".to_owned());
+        }
+        for arg in method.args.iter() {
+            let converted = format!("r_{}", &arg.name);
+            let value = match arg.ty {
+                AstType::Callback => {
+                    let index_name = format!("{}_callback_index", &arg.name);
+                    file.line(format!(
+                        "val {} = globalIndex.incrementAndGet()",
+                        &index_name
+                    ));
+                    file.line(format!(
+                        "globalCallbacks[{}] = {}",
+                        &index_name,
+                        ident(&arg.name)
+                    ));
+                    index_name
+                }
+                AstType::Map(_, value) => {
+                    self.check_map_value(value, &arg.origin_ty)?;
+                    to_transfer(file, arg.ty, &ident(&arg.name))
+                }
+                _ => to_transfer(file, arg.ty, &ident(&arg.name)),
+            };
+            file.line(format!("val {} = {}", &converted, value));
+            call_args.push(converted);
+        }
+
+        let call = format!("native_{}({})", &method.name, call_args.join(", "));
+        match method.return_type {
+            AstType::Void => file.line(call),
+            _ => file.line(format!("val ret = {}", call)),
+        }
+
+        if let AstType::Map(_, value) = method.return_type {
+            self.check_map_value(value, &method.origin_return_ty)?;
+        }
+        match method.return_type {
+            _ if method.kind == MethodKind::Constructor => {
+                file.line(format!("return {}(ret)", &self.desc.name));
+            }
+            AstType::Void => (),
+            AstType::Callback => {
+                file.line(format!("return Rust{}(ret)", &method.origin_return_ty));
+            }
+            _ => {
+                let value =
+                    self.from_transfer(file, method.return_type, &method.origin_return_ty, "ret");
+                file.line(format!("return {}", value));
+            }
+        }
+
+        file.close("}");
+        Ok(())
+    }
+
+    ///
+    /// `invoke_<callback>_<method>` converts the arguments from rust, and calls the callback
+    /// put in `globalCallbacks` when it was passed.
+    ///
+    fn push_invoke(
+        &self,
+        file: &mut KotlinFile,
+        callback_name: &str,
+        method: &MethodDesc,
+    ) -> Result<()> {
+        let mut args = vec!["index: Long".to_owned()];
+        for arg in method.args.iter() {
+            args.push(format!(
+                "{}: {}",
+                ident(&arg.name),
+                transfer_type(arg.ty, &arg.origin_ty)
+            ));
+        }
+        let returns = match method.return_type {
+            AstType::Void => "".to_owned(),
+            _ => format!(
+                ": {}",
+                transfer_type(method.return_type, &method.origin_return_ty)
+            ),
+        };
+
+        file.line("@JvmStatic");
+        file.open(format!(
+            "fun invoke_{}_{}({}){} {{",
+            callback_name,
+            &method.name,
+            args.join(", "),
+            returns
+        ));
+
+        let mut call_args = vec![];
+        for arg in method.args.iter() {
+            if let AstType::Map(_, value) = arg.ty {
+                self.check_map_value(value, &arg.origin_ty)?;
+            }
+            let converted = format!("j_{}", &arg.name);
+            let value = self.from_transfer(file, arg.ty, &arg.origin_ty, &ident(&arg.name));
+            file.line(format!("val {} = {}", &converted, value));
+            call_args.push(converted);
+        }
+
+        file.line(format!(
+            "val callback = globalCallbacks[index] as {}",
+            callback_name
+        ));
+        let call = format!("callback.{}({})", ident(&method.name), call_args.join(", "));
+        match method.return_type {
+            AstType::Void => file.line(call),
+            _ => {
+                file.line(format!("val result = {}", call));
+                let value = to_transfer(file, method.return_type, "result");
+                file.line(format!("return {}", value));
+            }
+        }
+
+        file.close("}");
+        Ok(())
+    }
+
+    fn push_native(&self, file: &mut KotlinFile, method: &MethodDesc) {
+        let mut args = vec![];
+        if is_instance(method) {
+            args.push("handle: Long".to_owned());
+        }
+        for arg in method.args.iter() {
+            args.push(format!(
+                "{}: {}",
+                ident(&arg.name),
+                transfer_type(arg.ty, &arg.origin_ty)
+            ));
+        }
+        let returns = match method.return_type {
+            AstType::Void => "".to_owned(),
+            _ => format!(
+                ": {}",
+                transfer_type(method.return_type, &method.origin_return_ty)
+            ),
+        };
+
+        file.line("@JvmStatic");
+        file.line(format!(
+            "private external fun native_{}({}){}",
+            &method.name,
+            args.join(", "),
+            returns
+        ));
+    }
+
+    ///
+    /// Convert the value transferred through jni to the kotlin value of `ty`.
+    ///
+    fn from_transfer(
+        &self,
+        file: &mut KotlinFile,
+        ty: AstType,
+        origin_ty: &str,
+        value: &str,
+    ) -> String {
+        match ty {
+            AstType::Boolean => format!("{} > 0", value),
+            AstType::Enum => format!("{}.fromValue({})", origin_ty, value),
+            AstType::Optional(AstBaseType::String) => value.to_owned(),
+            // primitives are transferred boxed, which are nullable kotlin types already.
+            AstType::Optional(base) if base.is_primitive() => value.to_owned(),
+            AstType::Optional(AstBaseType::Enum) => format!(
+                "{}?.let {{ {}.fromValue(it.toInt()) }}",
+                value,
+                inner_ty(origin_ty, "Option<")
+            ),
+            AstType::Optional(base) => {
+                let inner = inner_ty(origin_ty, "Option<");
+                let converted = self.from_transfer(file, AstType::from(base), &inner, "it");
+                format!("{}?.let {{ {} }}", value, converted)
+            }
+            AstType::Struct if is_data_enum(self.enums, origin_ty) => {
+                format!("{}.fromJson({})", origin_ty, value)
+            }
+            AstType::Struct => {
+                let json = file.imported(JSON);
+                format!("{}.parseObject({}, {}::class.java)", json, value, origin_ty)
+            }
+            // `Map` is covariant, which would be a wildcard type in java, so `HashMap` is used.
+            AstType::Map(_, _) => {
+                let json = file.imported(JSON);
+                let type_reference = file.imported(TYPE_REFERENCE);
+                format!(
+                    "{}.parseObject({}, object : {}<Hash{}>() {{}})",
+                    json,
+                    value,
+                    type_reference,
+                    kotlin_type(ty, origin_ty)
+                )
+            }
+            AstType::Vec(AstBaseType::Byte) => value.to_owned(),
+            AstType::Vec(base) => {
+                let json = file.imported(JSON);
+                let inner = inner_ty(origin_ty, "Vec<");
+                if base == AstBaseType::Struct && is_data_enum(self.enums, &inner) {
+                    // parsed one by one, fastjson can't parse sealed classes.
+                    let json_object = file.imported(JSON_OBJECT);
+                    return format!(
+                        "{}.parseArray({}).map {{ {}.fromJsonObject(it as {}) }}",
+                        json, value, inner, json_object
+                    );
+                }
+                let class = match base {
+                    AstBaseType::Struct | AstBaseType::Enum | AstBaseType::String => {
+                        format!("{}::class.java", kotlin_type(AstType::from(base), &inner))
+                    }
+                    _ => format!(
+                        "{}::class.javaObjectType",
+                        kotlin_type(AstType::from(base), &inner)
+                    ),
+                };
+                format!("{}.parseArray({}, {})", json, value, class)
+            }
+            _ => value.to_owned(),
+        }
+    }
+
+    ///
+    /// Maps are parsed by fastjson as a whole, which can't parse data-carrying enums.
+    ///
+    fn check_map_value(&self, value: AstBaseType, origin_ty: &str) -> Result<()> {
+        let (_, _, value_ty) = split_map_origin_ty(origin_ty);
+        match value {
+            AstBaseType::Struct if is_data_enum(self.enums, &value_ty) => {
+                Err(GenerateError(format!(
+                    "enums with data can't be values of map, found {}",
+                    origin_ty
+                ))
+                .into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+///
+/// A kotlin source file, genco can't write kotlin, so the lines are indented here.
+///
+struct KotlinFile {
+    imports: BTreeSet<String>,
+    body: String,
+    level: usize,
+}
+
+impl KotlinFile {
+    fn new() -> Self {
+        KotlinFile {
+            imports: BTreeSet::new(),
+            body: String::new(),
+            level: 0,
+        }
+    }
+
+    ///
+    /// import the class of `path`, and return its simple name.
+    ///
+    fn imported(&mut self, path: &str) -> String {
+        self.imports.insert(path.to_owned());
+        path.rsplit(".").next().unwrap_or(path).to_owned()
+    }
+
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        let line = line.as_ref();
+        if !line.is_empty() {
+            for _ in 0..self.level {
+                self.body.push_str("    ");
+            }
+            self.body.push_str(line);
+        }
+        self.body.push('\n');
+    }
+
+    fn open<S: AsRef<str>>(&mut self, line: S) {
+        self.line(line);
+        self.level += 1;
+    }
+
+    fn close<S: AsRef<str>>(&mut self, line: S) {
+        self.level -= 1;
+        self.line(line);
+    }
+
+    ///
+    /// separate members by an empty line, unless it is the first one of a block.
+    ///
+    fn separate(&mut self) {
+        if !self.body.is_empty() && !self.body.ends_with("{\n") && !self.body.ends_with("\n\n") {
+            self.body.push('\n');
+        }
+    }
+
+    fn to_kotlin(&self, pkg: &str) -> String {
+        let mut content = format!("package {}\n\n", pkg);
+        for import in self.imports.iter() {
+            content.push_str(&format!("import {}\n", import));
+        }
+        if !self.imports.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(&self.body);
+        content
+    }
+}
+
+///
+/// push a data class, or a class for no fields, which can't be a data class.
+///
+fn push_data_class(
+    file: &mut KotlinFile,
+    name: &str,
+    fields: &[ArgDesc],
+    supers: &str,
+    enums: &[EnumDesc],
+) {
+    if fields.is_empty() {
+        file.line(format!("class {}{}", name, supers));
+        return;
+    }
+
+    file.open(format!("data class {}(", name));
+    for (index, field) in fields.iter().enumerate() {
+        let end = if index == fields.len() - 1 { "" } else { "," };
+        file.line(format!(
+            "var {}: {} = {}{}",
+            ident(&field.name),
+            kotlin_type(field.ty, &field.origin_ty),
+            default_value(field.ty, &field.origin_ty, enums),
+            end
+        ));
+    }
+    file.close(format!("){}", supers));
+}
+
+///
+/// the defaults of properties, the first variant is taken for enums.
+///
+fn default_value(ty: AstType, origin_ty: &str, enums: &[EnumDesc]) -> String {
+    match ty {
+        AstType::Byte | AstType::Int => "0".to_owned(),
+        AstType::Long => "0L".to_owned(),
+        AstType::Float => "0f".to_owned(),
+        AstType::Double => "0.0".to_owned(),
+        AstType::Boolean => "false".to_owned(),
+        AstType::String => "\"\"".to_owned(),
+        AstType::Vec(AstBaseType::Byte) => "ByteArray(0)".to_owned(),
+        AstType::Vec(_) => "listOf()".to_owned(),
+        AstType::Map(_, _) => "mapOf()".to_owned(),
+        AstType::Optional(_) => "null".to_owned(),
+        _ => {
+            let variant = enums
+                .iter()
+                .find(|each| each.name == origin_ty)
+                .and_then(|each| each.variants.first());
+            match variant {
+                Some(variant) if variant.fields.is_empty() => {
+                    format!("{}.{}", origin_ty, &variant.name)
+                }
+                Some(variant) => format!("{}.{}()", origin_ty, &variant.name),
+                None => format!("{}()", origin_ty),
+            }
+        }
+    }
+}
+
+///
+/// Convert the kotlin value of `ty` to the value transferred through jni, except callbacks,
+/// which are transferred by their indexes in `globalCallbacks`.
+///
+fn to_transfer(file: &mut KotlinFile, ty: AstType, value: &str) -> String {
+    match ty {
+        AstType::Boolean => format!("if ({}) 1 else 0", value),
+        AstType::Enum => format!("{}.value", value),
+        AstType::Optional(AstBaseType::String) => value.to_owned(),
+        AstType::Optional(AstBaseType::Enum) => format!("{}?.value?.toString()", value),
+        AstType::Optional(AstBaseType::Struct) => {
+            let json = file.imported(JSON);
+            format!("{}?.let {{ {}.toJSONString(it) }}", value, json)
+        }
+        // primitives are transferred boxed.
+        AstType::Optional(_) => value.to_owned(),
+        // keys of json are always strings, and enums are transferred by their names, like
+        // serde does.
+        AstType::Map(_, _) => {
+            let json = file.imported(JSON);
+            let feature = file.imported(SERIALIZER_FEATURE);
+            format!(
+                "{}.toJSONString({}, {}.WriteNonStringKeyAsString, {}.WriteEnumUsingToString)",
+                json, value, feature, feature
+            )
+        }
+        AstType::Vec(AstBaseType::Byte) => value.to_owned(),
+        AstType::Vec(AstBaseType::Enum) => {
+            let json = file.imported(JSON);
+            let feature = file.imported(SERIALIZER_FEATURE);
+            format!(
+                "{}.toJSONString({}, {}.WriteEnumUsingToString)",
+                json, value, feature
+            )
+        }
+        AstType::Vec(_) | AstType::Struct => {
+            let json = file.imported(JSON);
+            format!("{}.toJSONString({})", json, value)
+        }
+        _ => value.to_owned(),
+    }
+}
+
+fn kotlin_type(ty: AstType, origin_ty: &str) -> String {
+    match ty {
+        AstType::Void => "Unit".to_owned(),
+        AstType::Byte => "Byte".to_owned(),
+        AstType::Int => "Int".to_owned(),
+        AstType::Long => "Long".to_owned(),
+        AstType::Float => "Float".to_owned(),
+        AstType::Double => "Double".to_owned(),
+        AstType::Boolean => "Boolean".to_owned(),
+        AstType::String => "String".to_owned(),
+        AstType::Vec(AstBaseType::Byte) => "ByteArray".to_owned(),
+        AstType::Vec(base) => format!(
+            "List<{}>",
+            kotlin_type(AstType::from(base), &inner_ty(origin_ty, "Vec<"))
+        ),
+        AstType::Optional(base) => format!(
+            "{}?",
+            kotlin_type(AstType::from(base), &inner_ty(origin_ty, "Option<"))
+        ),
+        AstType::Map(key, value) => {
+            let (_, key_ty, value_ty) = split_map_origin_ty(origin_ty);
+            format!(
+                "Map<{}, {}>",
+                kotlin_type(AstType::from(key), &key_ty),
+                kotlin_type(AstType::from(value), &value_ty)
+            )
+        }
+        AstType::Callback | AstType::Struct | AstType::Enum => origin_ty.to_owned(),
+    }
+}
+
+///
+/// the types transferred through jni, they must be the same as the java ones.
+///
+fn transfer_type(ty: AstType, origin_ty: &str) -> String {
+    match ty {
+        AstType::Boolean | AstType::Enum => "Int".to_owned(),
+        AstType::Vec(AstBaseType::Byte) => "ByteArray".to_owned(),
+        AstType::Vec(_) | AstType::Struct | AstType::Map(_, _) => "String".to_owned(),
+        AstType::Optional(base) if base.is_primitive() => kotlin_type(ty, origin_ty),
+        AstType::Optional(_) => "String?".to_owned(),
+        AstType::Callback => "Long".to_owned(),
+        _ => kotlin_type(ty, origin_ty),
+    }
+}
+
+fn args_str(method: &MethodDesc) -> String {
+    method
+        .args
+        .iter()
+        .map(|arg| {
+            format!(
+                "{}: {}",
+                ident(&arg.name),
+                kotlin_type(arg.ty, &arg.origin_ty)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn returns_str(ty: AstType, origin_ty: &str) -> String {
+    match ty {
+        AstType::Void => "".to_owned(),
+        _ => format!(": {}", kotlin_type(ty, origin_ty)),
+    }
+}
+
+fn is_instance(method: &MethodDesc) -> bool {
+    method.kind == MethodKind::Ref || method.kind == MethodKind::RefMut
+}
+
+fn is_data_enum(enums: &[EnumDesc], origin_ty: &str) -> bool {
+    enums
+        .iter()
+        .any(|each| each.name == origin_ty && !each.is_c_like)
+}
+
+fn inner_ty(origin_ty: &str, outer: &str) -> String {
+    origin_ty.replace(outer, "").replace(">", "")
+}
+
+///
+/// names which are keywords of kotlin are quoted by backticks.
+///
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("`{}`", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn exception_name(origin_error_ty: &str) -> String {
+    format!("{}Exception", origin_error_ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::AstHandler;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn generates_kotlin_sources_of_a_crate() {
+        let prj = TempDir::with_files(
+            "kotlin_gen",
+            &[
+                ("src/contract/mod.rs", "pub mod demo;"),
+                (
+                    "src/contract/demo.rs",
+                    r#"
+                    pub trait Greeter {
+                        fn greet(person: Person, listener: Box<dyn Listener>) -> String;
+                    }
+                    pub trait Listener: Sync {
+                        fn on_greeted(&self, times: i32);
+                    }
+                    pub struct Person {
+                        pub name: String,
+                        pub age: i32,
+                    }
+                    "#,
+                ),
+                ("src/imp/mod.rs", "pub mod demo;"),
+                (
+                    "src/imp/demo.rs",
+                    r#"
+                    use contract::demo::*;
+                    pub struct GreeterImp {}
+                    impl Greeter for GreeterImp {
+                        fn greet(person: Person, listener: Box<dyn Listener>) -> String {
+                            listener.on_greeted(1);
+                            format!("hello {}", person.name)
+                        }
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let ast = AstHandler::new("demo".to_owned(), &Common::default())
+            .parse(prj.path())
+            .unwrap();
+        let out = prj.path().join("kotlin");
+        fs::create_dir_all(&out).unwrap();
+        KotlinCodeGen {
+            kotlin_gen_dir: &out,
+            ast: &ast,
+            namespace: "com.demo".to_owned(),
+            so_name: "demo".to_owned(),
+            ext_libs: "".to_owned(),
+        }
+        .gen_kotlin_code()
+        .unwrap();
+
+        let read = |name: &str| fs::read_to_string(out.join(name)).unwrap();
+        assert_eq!(
+            read("Person.kt"),
+            "package com.demo\n\
+             \n\
+             import java.io.Serializable\n\
+             \n\
+             data class Person(\n    \
+             var name: String = \"\",\n    \
+             var age: Int = 0\n\
+             ) : Serializable\n"
+        );
+        assert_eq!(
+            read("Listener.kt"),
+            "package com.demo\n\
+             \n\
+             fun interface Listener {\n    \
+             fun on_greeted(times: Int)\n\
+             }\n"
+        );
+
+        let greeter = read("Greeter.kt");
+        assert!(greeter.contains("object Greeter {"));
+        assert!(greeter.contains("fun greet(person: Person, listener: Listener): String {"));
+        assert!(greeter.contains("val r_person = JSON.toJSONString(person)"));
+        assert!(greeter.contains("fun invoke_Listener_on_greeted(index: Long, times: Int) {"));
+        assert!(greeter
+            .contains("private external fun native_greet(person: String, listener: Long): String"));
+    }
+}
@@ -1,5 +1,7 @@
 pub(crate) mod bridge;
 mod callback;
-mod dest;
+mod codec;
+pub(crate) mod dest;
+mod kotlin;
 pub(crate) mod process;
 pub(crate) mod config;
@@ -2,21 +2,32 @@ use ast::AstResult;
 use bridge::prj::Unpack;
 use bridges::BridgeGen::JavaGen;
 use android::dest::JavaCodeGen;
+use android::kotlin::KotlinCodeGen;
 use errors::ErrorKind::*;
 use errors::*;
 use fs_extra;
 use fs_extra::dir::CopyOptions;
-use process::BuildProcess;
+use process::{print_output, BuildProcess};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use unzip;
-use super::config::Android;
+use super::config::{Android, AndroidLanguage};
 
 const MAGIC_NUM: &'static str = "*521%";
 
+/// the files of the android template with placeholders.
+const TEMPLATE_FILES: [&str; 4] = [
+    "rustlib/src/main/AndroidManifest.xml",
+    "build.gradle",
+    "rustlib/build.gradle",
+    "gradle/wrapper/gradle-wrapper.properties",
+];
+const KOTLIN_VERSION: &str = "1.4.32";
+const FASTJSON: &str = "com.alibaba.fastjson";
+const FASTJSON_VERSION: &str = "1.1.70.android";
+
 pub(crate) struct AndroidProcess<'a> {
     origin_prj_path: &'a PathBuf,
     dest_prj_path: &'a PathBuf,
@@ -69,6 +80,50 @@ impl<'a> AndroidProcess<'a> {
             None => Android::default()
         }
     }
+
+    ///
+    /// the values of placeholders in the android template, kotlin needs its gradle plugin,
+    /// which needs newer gradle and android plugins, and fastjson is only needed by the
+    /// sources using it.
+    ///
+    fn template_values(&self, uses_json: bool) -> Vec<(&'static str, String)> {
+        let mut values = vec![("namespace", self.config().namespace())];
+        let fastjson = if uses_json {
+            format!("implementation 'com.alibaba:fastjson:{}'", FASTJSON_VERSION)
+        } else {
+            "".to_owned()
+        };
+        values.push(("fastjson", fastjson));
+        match self.config().language() {
+            AndroidLanguage::Java => {
+                values.push(("android_plugin", "3.1.1".to_owned()));
+                values.push(("gradle", "4.4".to_owned()));
+                values.push(("kotlin_classpath", "".to_owned()));
+                values.push(("kotlin_plugin", "".to_owned()));
+                values.push(("kotlin_stdlib", "".to_owned()));
+            }
+            AndroidLanguage::Kotlin => {
+                values.push(("android_plugin", "3.6.4".to_owned()));
+                values.push(("gradle", "6.1.1".to_owned()));
+                values.push((
+                    "kotlin_classpath",
+                    format!(
+                        "classpath 'org.jetbrains.kotlin:kotlin-gradle-plugin:{}'",
+                        KOTLIN_VERSION
+                    ),
+                ));
+                values.push(("kotlin_plugin", "apply plugin: 'kotlin-android'".to_owned()));
+                values.push((
+                    "kotlin_stdlib",
+                    format!(
+                        "implementation 'org.jetbrains.kotlin:kotlin-stdlib:{}'",
+                        KOTLIN_VERSION
+                    ),
+                ));
+            }
+        }
+        values
+    }
 }
 
 impl<'a> BuildProcess for AndroidProcess<'a> {
@@ -77,13 +132,16 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
     }
 
     fn gen_bridge_src(&self) -> Result<()> {
-        println!("begin unzip rust template for android");
+        info!("begin unzip rust template for android");
         // unpack the bridge project.
         {
             let buf: &[u8] = include_bytes!("res/template_bridge_android.zip");
             let unpack = Unpack {
                 path: self.bridge_prj_path,
                 host_crate: self.host_crate_name,
+                host_path: self.origin_prj_path,
+                contract_mod: &self.ast_result.contract_mod,
+                imp_mod: &self.ast_result.imp_mod,
                 buf,
                 features: &self.config().features(),
             };
@@ -100,7 +158,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
             self.config().namespace(),
         )
         .gen_bridges()
-        .unwrap();
+        .chain_err(|| GenerateError("can't generate java bridges".to_string()))?;
 
         let _ = Command::new("cargo")
             .arg("fmt")
@@ -111,7 +169,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
     }
 
     fn build_bridge_prj(&self) -> Result<()> {
-        println!("building android bridge project");
+        info!("building android bridge project");
 
         let phone_archs = self.config().phone_archs();
         let mut build_cmds = String::from("true");
@@ -179,7 +237,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
 
         let cmds = format!("{} && {}", &build_cmds, &strip_cmds);
 
-        println!("run building => {}", &cmds);
+        debug!("run building => {}", &cmds);
 
         let output = Command::new("sh")
             .arg("-c")
@@ -187,8 +245,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
             .current_dir(self.bridge_prj_path)
             .output()?;
 
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
+        print_output(&output)?;
 
         if !output.status.success() {
             return Err(
@@ -214,7 +271,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
             depth: 65535,
         };
 
-        println!("copy output files to android project.");
+        info!("copy output files to android project.");
 
         let debug_release = if self.config().is_release() {
             "release"
@@ -253,39 +310,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
     }
 
     fn gen_bind_code(&self) -> Result<()> {
-        // unpack the dest java project
-        {
-            println!("begin unzip android template");
-            if self.dest_prj_path.exists() {
-                fs::remove_dir_all(&self.dest_prj_path).unwrap();
-            }
-            fs::create_dir_all(&self.dest_prj_path).unwrap();
-            let android_template_buf: &[u8] = include_bytes!("res/template_android.zip");
-            unzip::unzip_to(android_template_buf, &self.dest_prj_path).unwrap();
-
-            let manifest_path = self
-                .dest_prj_path
-                .join("rustlib")
-                .join("src")
-                .join("main")
-                .join("AndroidManifest.xml");
-            let manifest_text = fs::read_to_string(&manifest_path).map_err(|e| {
-                FileError(format!(
-                    "read android dest project AndroidManifest.xml error: {:?}",
-                    e
-                ))
-            })?;
-            let replaced =
-                manifest_text.replace(&format!("$({}-namespace)", MAGIC_NUM), &self.config().namespace());
-            fs::write(manifest_path, replaced).map_err(|e| {
-                FileError(format!(
-                    "write android dest project AndroidManifest  error {:?}",
-                    e
-                ))
-            })?;
-        }
-
-        println!("generate java code.");
+        info!("generate {:?} code.", self.config().language());
         let parent = self
             .dest_prj_path
             .parent()
@@ -296,26 +321,61 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
         }
         fs::create_dir_all(&java_gen_path)?;
         
-        JavaCodeGen{
-            origin_prj: self.origin_prj_path,
-            java_gen_dir: &java_gen_path,
-            ast: &self.ast_result,
-            namespace: self.config().namespace(),
-            so_name: self.config().so_name(),
-            ext_libs: self.config().ext_libs()
-        }.gen_java_code()?;
+        // kotlin sources are put in the java directory too, which is compiled by both.
+        match self.config().language() {
+            AndroidLanguage::Java => JavaCodeGen{
+                origin_prj: self.origin_prj_path,
+                java_gen_dir: &java_gen_path,
+                ast: &self.ast_result,
+                namespace: self.config().namespace(),
+                so_name: self.config().so_name(),
+                ext_libs: self.config().ext_libs(),
+                nullable: true
+            }.gen_java_code()?,
+            AndroidLanguage::Kotlin => KotlinCodeGen {
+                kotlin_gen_dir: &java_gen_path,
+                ast: &self.ast_result,
+                namespace: self.config().namespace(),
+                so_name: self.config().so_name(),
+                ext_libs: self.config().ext_libs(),
+            }.gen_kotlin_code()?,
+        }
+
+        // unpack the dest java project, after the sources telling whether fastjson is needed.
+        {
+            info!("begin unzip android template");
+            // the project is kept between runs, so gradle can reuse what it built.
+            fs::create_dir_all(&self.dest_prj_path)?;
+            let android_template_buf: &[u8] = include_bytes!("res/template_android.zip");
+            let values = self.template_values(uses_fastjson(&java_gen_path)?);
+            unzip::unzip_with(android_template_buf, &self.dest_prj_path, |name, content| {
+                if !TEMPLATE_FILES.contains(&name) {
+                    return Ok(content);
+                }
+                let text = String::from_utf8(content).map_err(|e| {
+                    FileError(format!(
+                        "read android dest project {} error: {:?}",
+                        name, e
+                    ))
+                })?;
+                let replaced = values.iter().fold(text, |text, (key, value)| {
+                    text.replace(&format!("$({}-{})", MAGIC_NUM, key), value)
+                });
+                Ok(replaced.into_bytes())
+            })?;
+        }
 
         // get the output dir string
-        println!("get output dir string");
+        debug!("get output dir string");
         let mut output_dir = self.dest_prj_path
             .join("rustlib")
             .join("src")
             .join("main")
             .join("java");
         if output_dir.exists() {
-            fs::remove_dir_all(&output_dir).unwrap();
+            fs::remove_dir_all(&output_dir)?;
         }
-        fs::create_dir_all(&output_dir).unwrap();
+        fs::create_dir_all(&output_dir)?;
 
         let namespace = self.config().namespace();
         let pkg_split = namespace.split(".").collect::<Vec<&str>>();
@@ -332,14 +392,13 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
         };
 
         fs_extra::copy_items(&vec![java_gen_path], &output_dir, &options)
-            .map_err(|e| FileError(format!("copy android bridge outputs failed. {:?}", e)))
-            .unwrap();
+            .map_err(|e| FileError(format!("copy android bridge outputs failed. {:?}", e)))?;
 
         Ok(())
     }
 
     fn build_dest_prj(&self) -> Result<()> {
-        println!("build java dest project.");
+        info!("build java dest project.");
 
         let build_cmd = format!("chmod a+x ./gradlew && ./gradlew aR");
 
@@ -349,8 +408,7 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
             .current_dir(self.dest_prj_path)
             .output()?;
 
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
+        print_output(&output)?;
 
         if !output.status.success() {
             return Err(CommandError(format!("run building java dest project failed.")).into());
@@ -373,14 +431,32 @@ impl<'a> BuildProcess for AndroidProcess<'a> {
             .join("rustlib-release.aar");
         let target = self.origin_prj_path.join("target").join("android");
         if target.exists() {
-            fs::remove_dir_all(&target).unwrap();
+            fs::remove_dir_all(&target)?;
         }
-        fs::create_dir_all(&target).unwrap();
+        fs::create_dir_all(&target)?;
 
         fs_extra::copy_items(&vec![src_arr], &target, &options)
-            .map_err(|e| FileError(format!("copy android bridge outputs failed. {:?}", e)))
-            .unwrap();
+            .map_err(|e| FileError(format!("copy android bridge outputs failed. {:?}", e)))?;
 
         Ok(())
     }
 }
+
+///
+/// whether the generated sources in `dir` use fastjson, for options, maps, errors and the
+/// structs which aren't transferred by the binary wire format.
+///
+fn uses_fastjson(dir: &Path) -> Result<bool> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let uses = if path.is_dir() {
+            uses_fastjson(&path)?
+        } else {
+            fs::read_to_string(&path)?.contains(FASTJSON)
+        };
+        if uses {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
@@ -1,18 +1,110 @@
 use super::super::types::*;
 
+///
+/// Where an item is defined in the contract files, lines and columns start from 1 like rustc.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Default)]
+pub(crate) struct Location {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+///
+/// A construct in the contract files which can't be bridged, reported by the validation.
+///
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) struct Problem {
+    pub location: Location,
+    pub message: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) struct ArgDesc {
     pub name: String,
     pub ty: AstType,
     pub origin_ty: String,
+    /// path of the contract type in `ty`, see `refers_to`.
+    #[serde(default)]
+    pub ty_path: String,
+    /// transferred by the binary wire format instead of json, only for arguments of methods.
+    #[serde(default)]
+    pub binary: bool,
+    /// the name in rust, for fields renamed by `#[rsbind(rename = "...")]`.
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// where it is defined in the contract files.
+    #[serde(default)]
+    pub location: Location,
+}
+
+// asts flushed by older versions only have public items.
+fn default_public() -> bool {
+    true
+}
+
+///
+/// Whether the path of a contract type, like `net::http::User`, refers to the item named
+/// `rust_name` in the mod `mod_path`. Types imported by globs are only known by their names,
+/// their paths have no mods.
+///
+fn refers_to(ty_path: &str, mod_path: &str, rust_name: &str) -> bool {
+    match ty_path.rfind("::") {
+        Some(pos) => &ty_path[..pos] == mod_path && &ty_path[pos + 2..] == rust_name,
+        None => ty_path == rust_name,
+    }
+}
+
+impl ArgDesc {
+    pub(crate) fn rust_name(&self) -> &str {
+        self.origin_name.as_ref().unwrap_or(&self.name)
+    }
+}
+
+///
+/// How a method is called, static methods and constructors are called on the type, the others
+/// on an object created by a constructor.
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub(crate) enum MethodKind {
+    Static,
+    /// returns `Self`, the object is boxed and transferred as a handle.
+    Constructor,
+    Ref,
+    RefMut,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub(crate) struct MethodDesc {
     pub name: String,
+    pub kind: MethodKind,
     pub return_type: AstType,
     pub origin_return_ty: String,
+    /// path of the contract type in `return_type`.
+    #[serde(default)]
+    pub return_ty_path: String,
+    /// error type of methods returning `Result<T, E>`, Void if the method can't fail.
+    pub error_type: AstType,
+    pub origin_error_ty: String,
+    /// path of the contract type in `error_type`.
+    #[serde(default)]
+    pub error_ty_path: String,
     pub args: Vec<ArgDesc>,
+    /// the return value is transferred by the binary wire format instead of json.
+    #[serde(default)]
+    pub binary_return: bool,
+    /// the name in rust, for methods renamed by `#[rsbind(rename = "...")]`.
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// where it is defined in the contract files.
+    #[serde(default)]
+    pub location: Location,
+}
+
+impl MethodDesc {
+    pub(crate) fn rust_name(&self) -> &str {
+        self.origin_name.as_ref().unwrap_or(&self.name)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -20,9 +112,28 @@ pub(crate) struct TraitDesc {
     pub name: String,
     pub ty: String,
     pub mod_name: String,
+    /// path of the mod in the contract directory, like `net::http` for `mod_name` `net_http`.
+    #[serde(default)]
+    pub mod_path: String,
     pub crate_name: String,
     pub is_callback: bool,
+    /// has constructors, so the objects are implemented in rust and owned by the caller.
+    pub is_object: bool,
+    /// for objects wrapping a callback implemented in rust, the name of the callback trait.
+    pub wraps_callback: Option<String>,
     pub methods: Vec<MethodDesc>,
+    /// declared by `#[rsbind::export]`, so it must be implemented in the imp mod.
+    #[serde(default)]
+    pub exported: bool,
+    /// the name in rust, for traits renamed by `#[rsbind(rename = "...")]`.
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// declared `pub`, others can't be used by the bridge crate.
+    #[serde(default = "default_public")]
+    pub is_public: bool,
+    /// where it is defined in the contract files.
+    #[serde(default)]
+    pub location: Location,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -30,6 +141,79 @@ pub(crate) struct StructDesc {
     pub name: String,
     pub ty: String,
     pub mod_name: String,
+    /// path of the mod in the contract directory, like `net::http` for `mod_name` `net_http`.
+    #[serde(default)]
+    pub mod_path: String,
     pub crate_name: String,
     pub fields: Vec<ArgDesc>,
+    /// all the fields can be encoded by the binary wire format.
+    #[serde(default)]
+    pub binary: bool,
+    /// fields marked by `#[rsbind::skip]`, which are not transferred and set to their defaults.
+    #[serde(default)]
+    pub skipped_fields: Vec<String>,
+    /// the name in rust, for structs renamed by `#[rsbind(rename = "...")]`.
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// declared `pub`, others can't be used by the bridge crate.
+    #[serde(default = "default_public")]
+    pub is_public: bool,
+    /// where it is defined in the contract files.
+    #[serde(default)]
+    pub location: Location,
+}
+
+impl StructDesc {
+    pub(crate) fn rust_name(&self) -> &str {
+        self.origin_name.as_ref().unwrap_or(&self.name)
+    }
+
+    pub(crate) fn is_referred_by(&self, ty_path: &str) -> bool {
+        refers_to(ty_path, &self.mod_path, self.rust_name())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct VariantDesc {
+    pub name: String,
+    /// discriminant of C-like variants, transferred as i32 between languages.
+    pub value: i32,
+    /// named fields, or `_0`, `_1`... for tuple variants.
+    pub fields: Vec<ArgDesc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub(crate) struct EnumDesc {
+    pub name: String,
+    pub ty: String,
+    pub mod_name: String,
+    /// path of the mod in the contract directory, like `net::http` for `mod_name` `net_http`.
+    #[serde(default)]
+    pub mod_path: String,
+    pub crate_name: String,
+    /// no variant carries data, so the enum can be transferred by its discriminant.
+    pub is_c_like: bool,
+    pub variants: Vec<VariantDesc>,
+    /// C-like enums are encoded by their discriminants in the binary wire format.
+    #[serde(default)]
+    pub binary: bool,
+    /// the name in rust, for enums renamed by `#[rsbind(rename = "...")]`.
+    #[serde(default)]
+    pub origin_name: Option<String>,
+    /// declared `pub`, others can't be used by the bridge crate.
+    #[serde(default = "default_public")]
+    pub is_public: bool,
+    /// where it is defined in the contract files.
+    #[serde(default)]
+    pub location: Location,
+}
+
+impl EnumDesc {
+    pub(crate) fn rust_name(&self) -> &str {
+        self.origin_name.as_ref().unwrap_or(&self.name)
+    }
+
+    pub(crate) fn is_referred_by(&self, ty_path: &str) -> bool {
+        refers_to(ty_path, &self.mod_path, self.rust_name())
+    }
 }
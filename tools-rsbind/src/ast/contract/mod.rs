@@ -1,2 +1,3 @@
 pub(crate) mod desc;
 pub(crate) mod parser;
+pub(crate) mod symbols;
@@ -1,147 +1,649 @@
 //!
 //! Parse files that standing for contract of ffi.
 //!
+//! The constructs which can't be parsed are recorded as problems with their locations, so the
+//! parsing goes on and all of them are reported together by the validation.
+//!
+use super::super::modules::ModItems;
 use super::super::types::*;
 use super::desc::*;
+use super::symbols::SymbolTable;
+use proc_macro2::Span;
 use syn;
+use syn::spanned::Spanned;
 
-use errors::ErrorKind::*;
 use errors::*;
-use std::fs::File;
-use std::io::Read;
-use std::path::PathBuf;
 
 ///
-/// parse a syn file to TraitDesc which depicting the structure of the trait.
+/// The contract file being parsed, and the problems found in it.
 ///
-pub(crate) fn parse(
-    crate_name: String,
-    file_path: &PathBuf,
-) -> Result<(Vec<TraitDesc>, Vec<StructDesc>)> {
-    let mut file = File::open(file_path).map_err(|e| ParseError(e.to_string()))?;
+struct Source<'a> {
+    file: &'a str,
+    /// path of the contract mod in the crate, like `["contract"]`.
+    contract: &'a [String],
+    problems: Vec<Problem>,
+}
 
-    let mut src = String::new();
-    file.read_to_string(&mut src)
-        .map_err(|e| ParseError(e.to_string()))?;
+impl<'a> Source<'a> {
+    fn location(&self, span: Span) -> Location {
+        location(self.file, span)
+    }
 
-    let syn_file = syn::parse_file(&src).map_err(|e| ParseError(e.to_string()))?;
+    fn report(&mut self, span: Span, message: String) {
+        let location = self.location(span);
+        self.problems.push(Problem { location, message });
+    }
+}
 
+///
+/// The traits, structs and enums of a contract module, and the problems found in it.
+///
+pub(crate) type Contracts = (Vec<TraitDesc>, Vec<StructDesc>, Vec<EnumDesc>, Vec<Problem>);
+
+///
+/// parse the items of a contract module to TraitDesc which depicting the structure of the trait.
+/// Modules only grouping submodules have no items, so the results may be empty.
+///
+pub(crate) fn parse(
+    crate_name: String,
+    contract_mod: &str,
+    module: &ModItems,
+) -> Result<Contracts> {
     let mut trait_descs = vec![];
     let mut struct_descs = vec![];
+    let mut enum_descs = vec![];
+
+    // aliases and renamed imports are replaced by the types they refer to.
+    let contract = contract_mod
+        .split("::")
+        .map(|segment| segment.trim().to_owned())
+        .collect::<Vec<String>>();
+    let mut mod_path = contract.clone();
+    mod_path.extend(module.path.iter().cloned());
+    let symbols = SymbolTable::new(&module.items, mod_path);
+    let items = symbols.resolve_items(&module.items)?;
+    let file = module.file.to_string_lossy().into_owned();
+    let mut source = Source {
+        file: &file,
+        contract: &contract,
+        problems: vec![],
+    };
 
     // loop all the trait
-    for item in syn_file.items.iter() {
-        let mod_name = PathBuf::from(file_path)
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .to_string();
+    for item in items.iter() {
+        let mod_name = module.mod_name();
+        let mod_path = module.mod_path();
 
         match *item {
             syn::Item::Trait(ref trait_inner) => {
-                let trait_name = trait_inner.ident.to_string();
-                println!("found trait => {}", trait_inner.ident);
+                let attrs = parse_rsbind_attrs(&trait_inner.attrs, &mut source);
+                if attrs.skip {
+                    debug!("skip trait => {}", trait_inner.ident);
+                    continue;
+                }
+                if attrs.export && attrs.callback {
+                    source.report(
+                        trait_inner.ident.span(),
+                        format!(
+                            "trait `{}` can't be both exported and a callback",
+                            &trait_inner.ident
+                        ),
+                    );
+                }
+
+                let (trait_name, origin_name) = attrs.names(trait_inner.ident.to_string());
+                debug!("found trait => {}", trait_inner.ident);
+
+                let methods = parse_methods(&trait_name, &trait_inner.items, &mut source);
+                if methods.0.is_empty() {
+                    source.report(
+                        trait_inner.ident.span(),
+                        format!("trait `{}` has no methods", &trait_name),
+                    );
+                }
+                let is_object = methods
+                    .0
+                    .iter()
+                    .any(|method| method.kind == MethodKind::Constructor);
 
-                let methods = parse_methods(&trait_inner.items)?;
+                // declared intents win over the guess by the methods.
+                let is_callback = if attrs.callback {
+                    true
+                } else if attrs.export {
+                    false
+                } else {
+                    methods.1 && !is_object
+                };
 
                 let trait_desc = TraitDesc {
                     name: trait_name,
                     ty: "trait".to_string(),
                     mod_name,
+                    mod_path,
                     crate_name: crate_name.clone(),
-                    is_callback: methods.1,
+                    is_callback,
+                    is_object,
+                    wraps_callback: None,
                     methods: methods.0,
+                    exported: attrs.export,
+                    origin_name,
+                    is_public: is_public(&trait_inner.vis),
+                    location: source.location(trait_inner.ident.span()),
                 };
 
                 trait_descs.push(trait_desc);
             }
             syn::Item::Struct(ref struct_inner) => {
-                println!("found struct => {}", &struct_inner.ident);
-                let stuct_name = struct_inner.ident.to_string();
-
-                let mut field_descs = vec![];
-                let fields = &struct_inner.fields;
-                for field in fields.iter() {
-                    let field_name = match field.ident {
-                        Some(ref value) => value.to_owned().to_string(),
-                        _ => "".to_owned(),
-                    };
-
-                    let (field_ty, field_origin_ty) = match field.ty {
-                        syn::Type::Path(ref type_path) => {
-                            let segments = &(type_path.path.segments);
-                            let ident = &(segments[segments.len() - 1].ident);
-                            let origin = ident.to_string();
-                            (AstType::from(ident.to_string()), origin)
-                        }
-                        _ => (AstType::Void, "".to_owned()),
-                    };
-                    let field_desc = ArgDesc {
-                        name: field_name,
-                        ty: field_ty,
-                        origin_ty: field_origin_ty,
-                    };
-                    field_descs.push(field_desc);
+                let attrs = parse_rsbind_attrs(&struct_inner.attrs, &mut source);
+                if attrs.skip {
+                    debug!("skip struct => {}", &struct_inner.ident);
+                    continue;
                 }
+                attrs.check_no_markers(
+                    &struct_inner.ident.to_string(),
+                    struct_inner.ident.span(),
+                    &mut source,
+                );
+
+                debug!("found struct => {}", &struct_inner.ident);
+                let (stuct_name, origin_name) = attrs.names(struct_inner.ident.to_string());
+                let (fields, skipped_fields) =
+                    parse_fields(&stuct_name, &struct_inner.fields, &mut source);
 
                 let struct_desc = StructDesc {
                     name: stuct_name,
                     ty: "struct".to_string(),
                     mod_name,
+                    mod_path,
                     crate_name: crate_name.clone(),
-                    fields: field_descs,
+                    fields,
+                    binary: false,
+                    skipped_fields,
+                    origin_name,
+                    is_public: is_public(&struct_inner.vis),
+                    location: source.location(struct_inner.ident.span()),
                 };
                 struct_descs.push(struct_desc);
             }
+            syn::Item::Enum(ref enum_inner) => {
+                let attrs = parse_rsbind_attrs(&enum_inner.attrs, &mut source);
+                if attrs.skip {
+                    debug!("skip enum => {}", &enum_inner.ident);
+                    continue;
+                }
+                attrs.check_no_markers(
+                    &enum_inner.ident.to_string(),
+                    enum_inner.ident.span(),
+                    &mut source,
+                );
+
+                debug!("found enum => {}", &enum_inner.ident);
+                let (enum_name, origin_name) = attrs.names(enum_inner.ident.to_string());
+                let variants = parse_variants(&enum_name, &enum_inner.variants, &mut source);
+                let is_c_like = variants.iter().all(|variant| variant.fields.len() == 0);
+
+                let enum_desc = EnumDesc {
+                    name: enum_name,
+                    ty: "enum".to_string(),
+                    mod_name,
+                    mod_path,
+                    crate_name: crate_name.clone(),
+                    is_c_like,
+                    variants,
+                    binary: false,
+                    origin_name,
+                    is_public: is_public(&enum_inner.vis),
+                    location: source.location(enum_inner.ident.span()),
+                };
+                enum_descs.push(enum_desc);
+            }
             _ => (),
         }
     }
 
-    if trait_descs.len() > 0 || struct_descs.len() > 0 {
-        println!("final trait desc => {:#?}", trait_descs);
-        Ok((trait_descs, struct_descs))
+    debug!("final trait desc => {:#?}", trait_descs);
+    Ok((trait_descs, struct_descs, enum_descs, source.problems))
+}
+
+///
+/// The intents declared by `#[rsbind::export]`, `#[rsbind::callback]`, `#[rsbind::skip]` and
+/// `#[rsbind(rename = "...")]`, the markers can also be written like `#[rsbind(skip)]`.
+///
+#[derive(Default)]
+struct RsbindAttrs {
+    export: bool,
+    callback: bool,
+    skip: bool,
+    rename: Option<String>,
+}
+
+impl RsbindAttrs {
+    fn set_marker(&mut self, marker: &syn::Ident, source: &mut Source) {
+        match marker.to_string().as_ref() {
+            "export" => self.export = true,
+            "callback" => self.callback = true,
+            "skip" => self.skip = true,
+            _ => source.report(
+                marker.span(),
+                format!("unknown rsbind attribute `{}`", marker),
+            ),
+        }
+    }
+
+    ///
+    /// export and callback are only meaningful for traits.
+    ///
+    fn check_no_markers(&self, item: &str, span: Span, source: &mut Source) {
+        if self.export || self.callback {
+            source.report(
+                span,
+                format!(
+                    "only traits can be marked as export or callback, but `{}` is marked",
+                    item
+                ),
+            );
+        }
+    }
+
+    ///
+    /// the name used by other languages, and the name in rust if it is renamed.
+    ///
+    fn names(&self, rust_name: String) -> (String, Option<String>) {
+        match self.rename {
+            Some(ref rename) if rename != &rust_name => (rename.clone(), Some(rust_name)),
+            _ => (rust_name, None),
+        }
+    }
+}
+
+fn parse_rsbind_attrs(attrs: &Vec<syn::Attribute>, source: &mut Source) -> RsbindAttrs {
+    let mut result = RsbindAttrs::default();
+    for attr in attrs.iter() {
+        let segments = &attr.path.segments;
+        if segments.is_empty() || segments[0].ident != "rsbind" {
+            continue;
+        }
+
+        // #[rsbind::export]
+        if segments.len() == 2 {
+            result.set_marker(&segments[1].ident, source);
+            continue;
+        }
+
+        // #[rsbind(skip, rename = "...")], a plain #[rsbind] declares nothing.
+        let list = match attr.interpret_meta() {
+            Some(syn::Meta::List(list)) => list,
+            Some(syn::Meta::Word(_)) => continue,
+            _ => {
+                source.report(attr.span(), "can't parse rsbind attribute".to_owned());
+                continue;
+            }
+        };
+        for nested in list.nested.iter() {
+            match *nested {
+                syn::NestedMeta::Meta(syn::Meta::Word(ref word)) => result.set_marker(word, source),
+                syn::NestedMeta::Meta(syn::Meta::NameValue(ref name_value))
+                    if name_value.ident == "rename" =>
+                {
+                    match name_value.lit {
+                        syn::Lit::Str(ref lit) => result.rename = Some(lit.value()),
+                        _ => source.report(
+                            name_value.lit.span(),
+                            "rename of rsbind attribute must be a string".to_owned(),
+                        ),
+                    }
+                }
+                _ => source.report(nested.span(), "can't parse rsbind attribute".to_owned()),
+            }
+        }
+    }
+    result
+}
+
+///
+/// items which are not public can't be used by the bridge crate, they are reported by the
+/// validation unless skipped.
+///
+fn is_public(vis: &syn::Visibility) -> bool {
+    match *vis {
+        syn::Visibility::Public(_) => true,
+        _ => false,
+    }
+}
+
+///
+/// parse the fields of a struct or an enum variant, unnamed fields are named by their index.
+/// The names of fields marked by `#[rsbind::skip]` are returned separately.
+///
+fn parse_fields(
+    owner: &str,
+    fields: &syn::Fields,
+    source: &mut Source,
+) -> (Vec<ArgDesc>, Vec<String>) {
+    let mut field_descs = vec![];
+    let mut skipped_fields = vec![];
+    for (index, field) in fields.iter().enumerate() {
+        let (field_name, span) = match field.ident {
+            Some(ref value) => (value.to_string(), value.span()),
+            _ => (format!("_{}", index), field.ty.span()),
+        };
+
+        let attrs = parse_rsbind_attrs(&field.attrs, source);
+        attrs.check_no_markers(&field_name, span, source);
+        if attrs.skip {
+            skipped_fields.push(field_name);
+            continue;
+        }
+        let (field_name, origin_name) = attrs.names(field_name);
+
+        let (field_ty, field_origin_ty, ty_path) = match parse_type(&field.ty, source.contract) {
+            Some(parsed) => parsed,
+            None => {
+                source.report(
+                    field.ty.span(),
+                    format!(
+                        "field `{}` of `{}` has a type which is not supported",
+                        &field_name, owner
+                    ),
+                );
+                void_ty()
+            }
+        };
+        let field_desc = ArgDesc {
+            name: field_name,
+            ty: field_ty,
+            origin_ty: field_origin_ty,
+            ty_path,
+            binary: false,
+            origin_name,
+            location: source.location(span),
+        };
+        field_descs.push(field_desc);
+    }
+
+    (field_descs, skipped_fields)
+}
+
+///
+/// parse all the variants of an enum, discriminants are counted like rustc does.
+///
+fn parse_variants(
+    owner: &str,
+    variants: &syn::punctuated::Punctuated<syn::Variant, syn::token::Comma>,
+    source: &mut Source,
+) -> Vec<VariantDesc> {
+    let mut variant_descs = vec![];
+    let mut next_value: i128 = 0;
+    for variant in variants.iter() {
+        debug!("found variant => {}", &variant.ident);
+        let value = match variant.discriminant {
+            Some((_, ref expr)) => match parse_discriminant(expr) {
+                Some(value) => value,
+                None => {
+                    source.report(
+                        expr.span(),
+                        format!(
+                            "only integer literals are supported as discriminant of `{}`",
+                            &variant.ident
+                        ),
+                    );
+                    next_value
+                }
+            },
+            None => next_value,
+        };
+        // discriminants are transferred as i32 between languages.
+        if value < i32::min_value() as i128 || value > i32::max_value() as i128 {
+            source.report(
+                variant.ident.span(),
+                format!(
+                    "discriminant {} of `{}` is out of the range of i32",
+                    value, &variant.ident
+                ),
+            );
+        }
+        let value = value as i32;
+        next_value = value as i128 + 1;
+
+        let variant_owner = format!("{}::{}", owner, &variant.ident);
+        let (fields, skipped_fields) = parse_fields(&variant_owner, &variant.fields, source);
+        if skipped_fields.len() > 0 || fields.iter().any(|field| field.origin_name.is_some()) {
+            source.report(
+                variant.ident.span(),
+                format!(
+                    "fields of variant `{}` can't be skipped or renamed",
+                    &variant.ident
+                ),
+            );
+        }
+
+        variant_descs.push(VariantDesc {
+            name: variant.ident.to_string(),
+            value,
+            fields,
+        });
+    }
+
+    variant_descs
+}
+
+///
+/// A parsed type: the ast type, its name in the generated code, and the path of the contract
+/// type in it from the contract mod, like `net::User`.
+///
+type ParsedTy = (AstType, String, String);
+
+fn void_ty() -> ParsedTy {
+    (AstType::Void, "".to_owned(), "".to_owned())
+}
+
+///
+/// parse the type of a field, an argument or a return value. Vectors, options and maps can only
+/// contain types without generic arguments, like `Vec<User>`, None for the types not supported.
+///
+fn parse_type(ty: &syn::Type, contract: &[String]) -> Option<ParsedTy> {
+    let segments = match *ty {
+        syn::Type::Path(ref type_path) if type_path.qself.is_none() => &type_path.path.segments,
+        _ => return None,
+    };
+    let segment = &segments[segments.len() - 1];
+    let args = match segment.arguments {
+        syn::PathArguments::None => {
+            return parse_base_type(ty, contract)
+                .map(|(base, name, ty_path)| (AstType::from(base), name, ty_path));
+        }
+        syn::PathArguments::AngleBracketed(ref generics) if segments.len() == 1 => generics
+            .args
+            .iter()
+            .map(|arg| match *arg {
+                syn::GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            })
+            .collect::<Option<Vec<&syn::Type>>>()?,
+        _ => return None,
+    };
+
+    let container = segment.ident.to_string();
+    match (container.as_ref(), args.len()) {
+        ("Vec", 1) => {
+            let (base, name, ty_path) = parse_base_type(args[0], contract)?;
+            Some((AstType::Vec(base), format!("Vec<{}>", name), ty_path))
+        }
+        ("Option", 1) => {
+            let (base, name, ty_path) = parse_base_type(args[0], contract)?;
+            Some((
+                AstType::Optional(base),
+                format!("Option<{}>", name),
+                ty_path,
+            ))
+        }
+        ("HashMap", 2) | ("BTreeMap", 2) => {
+            let (key, key_name, _) = parse_base_type(args[0], contract)?;
+            let (value, value_name, ty_path) = parse_base_type(args[1], contract)?;
+            Some((
+                AstType::Map(key, value),
+                format!("{}<{}, {}>", container, key_name, value_name),
+                ty_path,
+            ))
+        }
+        // Right now, all callbacks are wrapped with Box, they are named by the traits.
+        ("Box", 1) => {
+            let path = match *args[0] {
+                syn::Type::Path(ref type_path) if type_path.qself.is_none() => &type_path.path,
+                syn::Type::TraitObject(ref trait_object) => {
+                    match trait_object.bounds.first().map(|bound| bound.into_value()) {
+                        Some(syn::TypeParamBound::Trait(ref bound)) => &bound.path,
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            };
+            let (name, ty_path) = parse_path(path, contract)?;
+            Some((AstType::Callback, name, ty_path))
+        }
+        _ => None,
+    }
+}
+
+///
+/// parse a type without generic arguments, like `i32` or `User`.
+///
+fn parse_base_type(ty: &syn::Type, contract: &[String]) -> Option<(AstBaseType, String, String)> {
+    let path = match *ty {
+        syn::Type::Path(ref type_path) if type_path.qself.is_none() => &type_path.path,
+        _ => return None,
+    };
+    let (name, ty_path) = parse_path(path, contract)?;
+    if path.segments.len() > 1 {
+        return Some((AstBaseType::Struct, name, ty_path));
+    }
+    match AstBaseType::from(name.as_ref()) {
+        // a bare `Box` without the callback.
+        AstBaseType::Callback => None,
+        AstBaseType::Struct => Some((AstBaseType::Struct, name, ty_path)),
+        base => Some((base, name, "".to_owned())),
+    }
+}
+
+///
+/// the name of a path without generic arguments, and the path from the contract mod. The types
+/// defined in the crate are resolved to paths from the crate root by the SymbolTable, like
+/// `crate::contract::net::User`, the ones out of the contract mod are not supported. Single
+/// segments are builtin types or the types only known by their names.
+///
+fn parse_path(path: &syn::Path, contract: &[String]) -> Option<(String, String)> {
+    if path
+        .segments
+        .iter()
+        .any(|segment| !segment.arguments.is_empty())
+    {
+        return None;
+    }
+    let idents = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<String>>();
+    let name = idents[idents.len() - 1].clone();
+    if idents.len() == 1 {
+        return Some((name.clone(), name));
+    }
+
+    let in_contract = idents.len() > contract.len() + 1
+        && idents[0] == "crate"
+        && &idents[1..contract.len() + 1] == contract;
+    if in_contract {
+        Some((name, idents[contract.len() + 1..].join("::")))
     } else {
-        Err(ParseError("Can't find invalid trait and struct.".to_string()).into())
+        None
+    }
+}
+
+fn parse_discriminant(expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(ref expr_lit) => match expr_lit.lit {
+            syn::Lit::Int(ref lit_int) => Some(lit_int.value() as i128),
+            _ => None,
+        },
+        syn::Expr::Unary(ref expr_unary) => match expr_unary.op {
+            syn::UnOp::Neg(_) => parse_discriminant(&expr_unary.expr).map(|value| -value),
+            _ => None,
+        },
+        _ => None,
     }
 }
 
 ///
-/// Loop all the methods
+/// Loop all the methods, and tell whether any of them takes `&self`.
 ///
-fn parse_methods(items: &Vec<syn::TraitItem>) -> Result<(Vec<MethodDesc>, bool)> {
+fn parse_methods(
+    owner: &str,
+    items: &Vec<syn::TraitItem>,
+    source: &mut Source,
+) -> (Vec<MethodDesc>, bool) {
     let mut method_descs: Vec<MethodDesc> = vec![];
     let mut is_callback = false;
     for method in items.iter() {
         match method {
             syn::TraitItem::Method(ref method_inner) => {
-                let method_name: String = method_inner.sig.ident.to_string();
+                let attrs = parse_rsbind_attrs(&method_inner.attrs, source);
+                if attrs.skip {
+                    debug!("skip method => {}", method_inner.sig.ident);
+                    continue;
+                }
+                attrs.check_no_markers(
+                    &method_inner.sig.ident.to_string(),
+                    method_inner.sig.ident.span(),
+                    source,
+                );
+                let (method_name, origin_name) = attrs.names(method_inner.sig.ident.to_string());
+                let method_owner = format!("{}::{}", owner, &method_name);
                 let mut args: Vec<ArgDesc> = vec![];
+                let mut kind = MethodKind::Static;
 
-                println!("found method => {}", method_inner.sig.ident);
+                debug!("found method => {}", method_inner.sig.ident);
 
-                let (return_type, origin_return_ty) =
-                    parse_return_type(&method_inner.sig.decl.output)?;
+                let (
+                    (return_type, origin_return_ty, return_ty_path),
+                    (error_type, origin_error_ty, error_ty_path),
+                ) = parse_return_type(&method_owner, &method_inner.sig.decl.output, source);
 
                 // arguments
                 for input in method_inner.sig.decl.inputs.iter() {
                     match input {
-                        syn::FnArg::SelfRef(ref _arg) => {
+                        syn::FnArg::SelfRef(ref arg) => {
                             is_callback = true;
+                            kind = match arg.mutability {
+                                Some(_) => MethodKind::RefMut,
+                                None => MethodKind::Ref,
+                            };
                             continue;
                         }
                         _ => {
-                            let arg = parse_one_arg(input)?;
-                            args.push(arg);
+                            if let Some(arg) = parse_one_arg(&method_owner, input, source) {
+                                args.push(arg);
+                            }
                         }
                     }
                 }
 
+                if kind == MethodKind::Static && origin_return_ty == "Self" {
+                    kind = MethodKind::Constructor;
+                }
+
                 let method_desc = MethodDesc {
                     name: method_name,
+                    kind,
                     return_type,
                     origin_return_ty,
+                    return_ty_path,
+                    error_type,
+                    origin_error_ty,
+                    error_ty_path,
                     args,
+                    binary_return: false,
+                    origin_name,
+                    location: source.location(method_inner.sig.ident.span()),
                 };
                 method_descs.push(method_desc);
             }
@@ -150,173 +652,425 @@ fn parse_methods(items: &Vec<syn::TraitItem>) -> Result<(Vec<MethodDesc>, bool)>
         }
     }
 
-    if method_descs.len() > 0 {
-        Ok((method_descs, is_callback))
-    } else {
-        Err(ParseError("Can't parse methods.".to_string()).into())
+    (method_descs, is_callback)
+}
+
+///
+/// parse return type, and the error type if it is a `Result<T, E>`.
+///
+fn parse_return_type(
+    owner: &str,
+    output: &syn::ReturnType,
+    source: &mut Source,
+) -> (ParsedTy, ParsedTy) {
+    let ty = match *output {
+        syn::ReturnType::Type(_, ref ty) => &**ty,
+        syn::ReturnType::Default => return (void_ty(), void_ty()),
+    };
+    if let syn::Type::Path(ref type_path) = *ty {
+        let segments = &type_path.path.segments;
+        if segments.len() == 1 && segments[0].ident == "Result" {
+            return parse_result_type(owner, ty, &segments[0], source);
+        }
     }
+    (parse_return_ty(owner, ty, source), void_ty())
 }
 
 ///
-/// parse return type
-///
-fn parse_return_type(output: &syn::ReturnType) -> Result<(AstType, String)> {
-    // return type
-    match output {
-        syn::ReturnType::Type(_, ref boxed) => {
-            let path = &**boxed;
-            match path {
-                syn::Type::Path(ref type_path) => {
-                    let segments = &(type_path.path.segments);
-                    let ident = &(segments[segments.len() - 1].ident);
-
-                    // Generic parsing
-                    let mut generic_ident = None;
-                    let argument = &(segments[segments.len() - 1].arguments);
-                    match argument {
-                        syn::PathArguments::None => (),
-                        syn::PathArguments::AngleBracketed(t) => {
-                            match t.args[t.args.len() - 1] {
-                                syn::GenericArgument::Type(ref gen_ty_path) => match gen_ty_path {
-                                    syn::Type::Path(arg_ty_path) => {
-                                        let generic_segments = &(arg_ty_path.path.segments);
-                                        generic_ident = Some(
-                                            &(generic_segments[generic_segments.len() - 1].ident),
-                                        );
-                                    }
-                                    _ => (),
-                                },
-                                _ => (),
-                            }
-                            println!("angle bracketed = {:?}", t)
-                        }
-                        _ => (),
-                    }
+/// parse `Result<T, E>`, T is parsed like other return types, E must be a struct or an enum.
+///
+fn parse_result_type(
+    owner: &str,
+    ty: &syn::Type,
+    segment: &syn::PathSegment,
+    source: &mut Source,
+) -> (ParsedTy, ParsedTy) {
+    let generic_tys = match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref t) => t
+            .args
+            .iter()
+            .filter_map(|arg| match *arg {
+                syn::GenericArgument::Type(ref ty) => Some(ty),
+                _ => None,
+            })
+            .collect::<Vec<&syn::Type>>(),
+        _ => vec![],
+    };
+    if generic_tys.len() != 2 {
+        source.report(
+            ty.span(),
+            format!(
+                "the return type of `{}` is a `Result` without its types",
+                owner
+            ),
+        );
+        return (void_ty(), void_ty());
+    }
 
-                    println!("found return type => {:?}", ident);
-                    return if ident.to_owned().to_string() == "Vec" {
-                        match generic_ident {
-                            Some(generic_ident) => {
-                                let ast = AstType::Vec(AstBaseType::from(
-                                    generic_ident.to_owned().to_string(),
-                                ));
-                                Ok((
-                                    ast,
-                                    format!("Vec<{}>", generic_ident.to_owned().to_string()),
-                                ))
-                            }
-                            None => {
-                                let origin = ident.to_string();
-                                Ok((AstType::from(ident.to_string()), origin))
-                            }
-                        }
-                    } else if ident.to_owned().to_owned() == "Box" {
-                        let origin = generic_ident.unwrap().to_owned().to_string();
-                        Ok((AstType::from("Box".to_owned()), origin.to_owned()))
-                    } else {
-                        let origin = ident.to_string();
-                        Ok((AstType::from(ident.to_string()), origin))
-                    };
-                }
+    let return_ty = parse_return_ty(owner, generic_tys[0], source);
+    let error_ty = match parse_base_type(generic_tys[1], source.contract) {
+        Some((AstBaseType::Struct, origin_error_ty, error_ty_path)) => {
+            (AstType::Struct, origin_error_ty, error_ty_path)
+        }
+        Some((_, origin_error_ty, _)) => {
+            source.report(
+                generic_tys[1].span(),
+                format!(
+                    "the error type `{}` of `{}` must be a struct or an enum in contract",
+                    &origin_error_ty, owner
+                ),
+            );
+            void_ty()
+        }
+        None => {
+            source.report(
+                generic_tys[1].span(),
+                format!("the error type of `{}` is not supported", owner),
+            );
+            void_ty()
+        }
+    };
+    (return_ty, error_ty)
+}
 
-                _ => (),
-            }
+///
+/// parse return type without Result, constructors return `Self`.
+///
+fn parse_return_ty(owner: &str, ty: &syn::Type, source: &mut Source) -> ParsedTy {
+    match *ty {
+        syn::Type::Tuple(ref tuple) if tuple.elems.is_empty() => return void_ty(),
+        syn::Type::Path(ref type_path)
+            if type_path.qself.is_none() && type_path.path.is_ident("Self") =>
+        {
+            // objects are transferred by the address of the boxed object.
+            return (AstType::Long, "Self".to_owned(), "".to_owned());
+        }
+        _ => (),
+    }
+    match parse_type(ty, source.contract) {
+        Some(parsed) => parsed,
+        None => {
+            source.report(
+                ty.span(),
+                format!("the return type of `{}` is not supported", owner),
+            );
+            void_ty()
         }
-        syn::ReturnType::Default => return Ok((AstType::Void, "".to_owned())),
     }
+}
 
-    Err(ParseError("can't parse return type".to_string()).into())
+///
+/// parse one argument, None if it can't be parsed.
+///
+fn parse_one_arg(owner: &str, input: &syn::FnArg, source: &mut Source) -> Option<ArgDesc> {
+    let arg = match *input {
+        syn::FnArg::Captured(ref arg) => arg,
+        syn::FnArg::SelfValue(_) => {
+            source.report(
+                input.span(),
+                format!("`{}` takes `self` by value, which is not supported", owner),
+            );
+            return None;
+        }
+        _ => {
+            source.report(
+                input.span(),
+                format!("arguments of `{}` must be named", owner),
+            );
+            return None;
+        }
+    };
+    let name = match arg.pat {
+        syn::Pat::Ident(ref pat_ident) => pat_ident.ident.to_string(),
+        _ => {
+            source.report(
+                arg.pat.span(),
+                format!("arguments of `{}` must be named by identifiers", owner),
+            );
+            return None;
+        }
+    };
+    debug!("found arg => {}", &name);
+
+    let (ty, origin_ty, ty_path) = match parse_type(&arg.ty, source.contract) {
+        Some(parsed) => parsed,
+        None => {
+            source.report(
+                arg.ty.span(),
+                format!(
+                    "argument `{}` of `{}` has a type which is not supported",
+                    &name, owner
+                ),
+            );
+            void_ty()
+        }
+    };
+
+    Some(ArgDesc {
+        name,
+        ty,
+        origin_ty,
+        ty_path,
+        binary: false,
+        origin_name: None,
+        location: source.location(input.span()),
+    })
 }
 
 ///
-/// parse one argument
+/// the location of a span in a contract file, proc-macro2 counts columns from 0.
 ///
-fn parse_one_arg(input: &syn::FnArg) -> Result<ArgDesc> {
-    let mut arg_name: Option<String> = Some("".to_owned());
-    let mut arg_type: Option<AstType> = Some(AstType::Void);
-    let mut origin_arg_ty: Option<String> = Some("".to_owned());
-    match input {
-        syn::FnArg::Captured(ref arg) => {
-            match arg.pat {
-                syn::Pat::Ident(ref pat_ident) => {
-                    arg_name = Some(pat_ident.ident.to_string());
-                    println!("found arg pat = {:?}", pat_ident.ident.to_string());
-                }
-                _ => (),
+fn location(file: &str, span: Span) -> Location {
+    let start = span.start();
+    Location {
+        file: file.to_owned(),
+        line: start.line,
+        column: start.column + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn parse_src(src: &str) -> Contracts {
+        let module = ModItems {
+            path: vec!["demo".to_owned()],
+            items: syn::parse_file(src).unwrap().items,
+            file: PathBuf::from("src/contract/demo.rs"),
+        };
+        parse("demo".to_owned(), "contract", &module).unwrap()
+    }
+
+    ///
+    /// the problems found by the parser, with their lines and columns.
+    ///
+    fn problems(src: &str) -> Vec<String> {
+        parse_src(src)
+            .3
+            .into_iter()
+            .map(|problem| {
+                let location = &problem.location;
+                format!(
+                    "{}:{}: {}",
+                    location.line, location.column, &problem.message
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn counts_discriminants_like_rustc() {
+        let (_, _, enums, _) = parse_src(
+            r#"
+            pub enum Level { Low, Middle = 10, High, Lowest = -2, Lower }
+            "#,
+        );
+        let values = enums[0]
+            .variants
+            .iter()
+            .map(|variant| (variant.name.as_ref(), variant.value))
+            .collect::<Vec<(&str, i32)>>();
+        assert_eq!(
+            values,
+            vec![
+                ("Low", 0),
+                ("Middle", 10),
+                ("High", 11),
+                ("Lowest", -2),
+                ("Lower", -1)
+            ]
+        );
+        assert!(enums[0].is_c_like);
+    }
+
+    #[test]
+    fn reports_discriminants_out_of_i32() {
+        let src = r#"
+            pub enum Level {
+                Low = 2147483647,
+                High,
+                Higher = LIMIT,
             }
+            "#;
+        assert_eq!(
+            problems(src),
+            vec![
+                "4:17: discriminant 2147483648 of `High` is out of the range of i32",
+                "5:26: only integer literals are supported as discriminant of `Higher`",
+            ]
+        );
 
-            match arg.ty {
-                syn::Type::Path(ref type_path) => {
-                    let segments = &(type_path.path.segments);
-                    let ident = (&segments[segments.len() - 1].ident).to_string();
-                    if ident.clone() == "Box" {
-                        println!("found Box argument.");
-                        let angle_bracketed = &segments[segments.len() - 1].arguments;
-                        match angle_bracketed {
-                            syn::PathArguments::AngleBracketed(t) => {
-                                let arg = &t.args[0];
-                                match arg {
-                                    syn::GenericArgument::Type(ty) => match ty {
-                                        syn::Type::Path(ref type_path) => {
-                                            println!("found boxed types = {:?})", type_path);
-                                            let segments = &(type_path.path.segments);
-                                            let ident =
-                                                (&segments[segments.len() - 1].ident).to_string();
-                                            arg_type = Some(AstType::from("Box".clone()));
-                                            origin_arg_ty = Some(ident.clone());
-                                        }
-                                        _ => {}
-                                    },
-                                    _ => {}
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else if ident.clone() == "Vec" {
-                        println!("found Vec argument.");
-                        let angle_bracketed = &segments[segments.len() - 1].arguments;
-                        match angle_bracketed {
-                            syn::PathArguments::AngleBracketed(t) => {
-                                let arg = &t.args[0];
-                                match arg {
-                                    syn::GenericArgument::Type(ty) => match ty {
-                                        syn::Type::Path(ref type_path) => {
-                                            println!("found vec types = {:?})", type_path);
-                                            let segments = &(type_path.path.segments);
-                                            let ident =
-                                                (&segments[segments.len() - 1].ident).to_string();
-                                            arg_type = Some(AstType::Vec(AstBaseType::from(
-                                                ident.clone(),
-                                            )));
-                                            origin_arg_ty = Some(format!("Vec<{}>", ident.clone()));
-                                        }
-                                        _ => {}
-                                    },
-                                    _ => {}
-                                }
-                            }
-                            _ => {}
-                        }
-                    } else {
-                        // normal arguments
-                        arg_type = Some(AstType::from(ident.clone()));
-                        origin_arg_ty = Some(ident.clone());
-                        println!("found args type => {:?}", ident);
-                    }
-                }
+        assert_eq!(
+            problems("pub enum Level { Low = -2147483649 }"),
+            vec!["1:18: discriminant -2147483649 of `Low` is out of the range of i32"]
+        );
+    }
 
-                _ => (),
+    #[test]
+    fn parses_data_carrying_enums() {
+        let (_, _, enums, _) = parse_src(
+            r#"
+            pub enum Event {
+                Closed,
+                Moved(i32, Option<String>),
+                Renamed { names: Vec<String> },
             }
-        }
-        _ => (),
+            "#,
+        );
+        let desc = &enums[0];
+        assert!(!desc.is_c_like);
+        assert_eq!(desc.variants[0].fields.len(), 0);
+
+        let moved = &desc.variants[1].fields;
+        assert_eq!(moved[0].name, "_0");
+        assert_eq!(moved[0].ty, AstType::Int);
+        assert_eq!(moved[1].name, "_1");
+        assert_eq!(moved[1].ty, AstType::Optional(AstBaseType::String));
+        assert_eq!(moved[1].origin_ty, "Option<String>");
+
+        let renamed = &desc.variants[2].fields;
+        assert_eq!(renamed[0].name, "names");
+        assert_eq!(renamed[0].ty, AstType::Vec(AstBaseType::String));
+    }
+
+    #[test]
+    fn parses_options() {
+        let (traits, structs, _, _) = parse_src(
+            r#"
+            pub struct User { pub age: Option<i32>, pub name: Option<String> }
+            pub trait Users {
+                fn find(id: Option<i64>, user: Option<User>) -> Option<User>;
+            }
+            "#,
+        );
+        let fields = &structs[0].fields;
+        assert_eq!(fields[0].ty, AstType::Optional(AstBaseType::Int));
+        assert_eq!(fields[1].ty, AstType::Optional(AstBaseType::String));
+
+        let method = &traits[0].methods[0];
+        assert_eq!(method.args[0].ty, AstType::Optional(AstBaseType::Long));
+        assert_eq!(method.args[1].ty, AstType::Optional(AstBaseType::Struct));
+        assert_eq!(method.args[1].origin_ty, "Option<User>");
+        assert_eq!(method.return_type, AstType::Optional(AstBaseType::Struct));
+        assert_eq!(method.origin_return_ty, "Option<User>");
+        assert_eq!(method.error_type, AstType::Void);
+    }
+
+    #[test]
+    fn parses_results() {
+        let (traits, _, _, _) = parse_src(
+            r#"
+            pub struct Error { pub code: i32 }
+            pub trait Users {
+                fn count() -> Result<i32, Error>;
+                fn clear() -> Result<(), Error>;
+                fn find() -> Result<Option<String>, Error>;
+            }
+            "#,
+        );
+        let methods = &traits[0].methods;
+        assert_eq!(methods[0].return_type, AstType::Int);
+        assert_eq!(methods[0].error_type, AstType::Struct);
+        assert_eq!(methods[0].origin_error_ty, "Error");
+        assert_eq!(methods[1].return_type, AstType::Void);
+        assert_eq!(
+            methods[2].return_type,
+            AstType::Optional(AstBaseType::String)
+        );
+    }
+
+    #[test]
+    fn reports_results_of_primitive_errors() {
+        let src = r#"
+            pub trait Users {
+                fn count() -> Result<i32, String>;
+                fn find() -> Result<i32>;
+            }
+            "#;
+        assert_eq!(
+            problems(src),
+            vec![
+                "3:43: the error type `String` of `Users::count` must be a struct or an enum \
+                 in contract",
+                "4:30: the return type of `Users::find` is a `Result` without its types",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_attrs_args_and_traits_which_cant_be_parsed() {
+        let src = r#"
+            #[rsbind::exported]
+            pub trait Users {
+                fn find(self, (a, b): (i32, i32));
+            }
+            #[rsbind::callback]
+            pub struct User { #[rsbind(rename = 1)] pub name: String }
+            pub trait Empty {}
+            "#;
+        assert_eq!(
+            problems(src),
+            vec![
+                "2:23: unknown rsbind attribute `exported`",
+                "4:25: `Users::find` takes `self` by value, which is not supported",
+                "4:31: arguments of `Users::find` must be named by identifiers",
+                "7:24: only traits can be marked as export or callback, but `User` is marked",
+                "7:49: rename of rsbind attribute must be a string",
+                "8:23: trait `Empty` has no methods",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_paths_of_contract_types() {
+        let (traits, structs, _, _) = parse_src(
+            r#"
+            use contract::net::User as NetUser;
+            pub struct String { pub value: i32 }
+            pub struct Group { pub users: Vec<other::User>, pub name: std::string::String }
+            pub trait Users {
+                fn find(user: NetUser, name: String) -> Option<Group>;
+            }
+            "#,
+        );
+        let fields = &structs[1].fields;
+        assert_eq!(fields[0].ty, AstType::Vec(AstBaseType::Struct));
+        assert_eq!(fields[0].origin_ty, "Vec<User>");
+        assert_eq!(fields[0].ty_path, "demo::other::User");
+        assert_eq!(fields[1].ty, AstType::String);
+        assert_eq!(fields[1].ty_path, "");
+
+        let method = &traits[0].methods[0];
+        assert_eq!(method.args[0].origin_ty, "User");
+        assert_eq!(method.args[0].ty_path, "net::User");
+        assert_eq!(method.args[1].ty, AstType::Struct);
+        assert_eq!(method.args[1].ty_path, "demo::String");
+        assert_eq!(method.return_ty_path, "demo::Group");
     }
 
-    match (arg_name, arg_type, origin_arg_ty) {
-        (Some(arg_name), Some(arg_type), Some(origin_ty)) => Ok(ArgDesc {
-            name: arg_name,
-            ty: arg_type,
-            origin_ty,
-        }),
-        _ => Err(ParseError("parse argments error!".to_string()).into()),
+    #[test]
+    fn reports_nested_generics_as_unsupported() {
+        let src = r#"
+            pub struct User { pub tags: Option<Vec<String>>, pub owner: crate::imp::User }
+            pub trait Users {
+                fn find(ids: Vec<Option<i64>>) -> Option<Vec<i32>>;
+            }
+            "#;
+        let (traits, structs, _, _) = parse_src(src);
+        assert_eq!(structs[0].fields[0].ty, AstType::Void);
+        assert_eq!(structs[0].fields[1].ty, AstType::Void);
+        assert_eq!(traits[0].methods[0].args[0].ty, AstType::Void);
+        assert_eq!(traits[0].methods[0].return_type, AstType::Void);
+        assert_eq!(
+            problems(src),
+            vec![
+                "2:41: field `tags` of `User` has a type which is not supported",
+                "2:73: field `owner` of `User` has a type which is not supported",
+                "4:51: the return type of `Users::find` is not supported",
+                "4:30: argument `ids` of `Users::find` has a type which is not supported",
+            ]
+        );
     }
 }
@@ -0,0 +1,587 @@
+//!
+//! Resolve the types used in a contract module to their real types, so the parser doesn't take
+//! aliases and renamed imports as structs. Types defined in the crate are resolved to their
+//! paths from the crate root, like `crate::contract::net::User`, so they are told apart from the
+//! builtin types and the types of the same name in other mods.
+//!
+use errors::ErrorKind::*;
+use errors::*;
+use std::collections::{HashMap, HashSet};
+use syn;
+
+/// aliases referring to each other deeper than this are taken as recursive.
+const MAX_DEPTH: usize = 32;
+
+/// crates whose types are known by their last segment, like `std::string::String`.
+const STD_CRATES: &[&str] = &["std", "core", "alloc"];
+
+///
+/// A `type` alias, like `type UserId = u64;` or `type Res<T> = Result<T, Error>;`.
+///
+struct Alias {
+    params: Vec<String>,
+    ty: syn::Type,
+}
+
+///
+/// The type aliases, `use` declarations and types defined in one contract module.
+///
+pub(crate) struct SymbolTable {
+    aliases: HashMap<String, Alias>,
+    /// names imported by `use`, to the paths they refer to.
+    imports: HashMap<String, Vec<syn::Ident>>,
+    /// structs, enums and traits defined in the module, they shadow the builtin types.
+    locals: HashSet<String>,
+    /// path of the module from the crate root, like `["contract", "net"]`.
+    mod_path: Vec<String>,
+}
+
+impl SymbolTable {
+    pub(crate) fn new(items: &Vec<syn::Item>, mod_path: Vec<String>) -> SymbolTable {
+        let mut table = SymbolTable {
+            aliases: HashMap::new(),
+            imports: HashMap::new(),
+            locals: HashSet::new(),
+            mod_path,
+        };
+
+        for item in items.iter() {
+            match *item {
+                syn::Item::Type(ref type_inner) => {
+                    let params = type_inner
+                        .generics
+                        .params
+                        .iter()
+                        .filter_map(|param| match *param {
+                            syn::GenericParam::Type(ref ty_param) => {
+                                Some(ty_param.ident.to_string())
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    table.aliases.insert(
+                        type_inner.ident.to_string(),
+                        Alias {
+                            params,
+                            ty: (*type_inner.ty).clone(),
+                        },
+                    );
+                }
+                syn::Item::Use(ref use_inner) => table.add_imports(&use_inner.tree, vec![]),
+                syn::Item::Struct(ref struct_inner) => {
+                    table.locals.insert(struct_inner.ident.to_string());
+                }
+                syn::Item::Enum(ref enum_inner) => {
+                    table.locals.insert(enum_inner.ident.to_string());
+                }
+                syn::Item::Trait(ref trait_inner) => {
+                    table.locals.insert(trait_inner.ident.to_string());
+                }
+                _ => (),
+            }
+        }
+
+        table
+    }
+
+    fn add_imports(&mut self, tree: &syn::UseTree, prefix: Vec<syn::Ident>) {
+        match *tree {
+            syn::UseTree::Path(ref use_path) => {
+                let mut prefix = prefix;
+                prefix.push(use_path.ident.clone());
+                self.add_imports(&use_path.tree, prefix);
+            }
+            // `use a::b::{self}` imports `b`.
+            syn::UseTree::Name(ref use_name) if use_name.ident == "self" => {
+                if let Some(last) = prefix.last().cloned() {
+                    self.imports.insert(last.to_string(), prefix);
+                }
+            }
+            syn::UseTree::Name(ref use_name) => {
+                let mut path = prefix;
+                path.push(use_name.ident.clone());
+                self.imports.insert(use_name.ident.to_string(), path);
+            }
+            syn::UseTree::Rename(ref use_rename) => {
+                let mut path = prefix;
+                path.push(use_rename.ident.clone());
+                self.imports.insert(use_rename.rename.to_string(), path);
+            }
+            syn::UseTree::Group(ref group) => {
+                for each in group.items.iter() {
+                    self.add_imports(each, prefix.clone());
+                }
+            }
+            syn::UseTree::Glob(_) => (),
+        }
+    }
+
+    ///
+    /// replace the types in the signatures of traits and the fields of structs and enums by
+    /// their real types.
+    ///
+    pub(crate) fn resolve_items(&self, items: &Vec<syn::Item>) -> Result<Vec<syn::Item>> {
+        let mut result = items.clone();
+        for item in result.iter_mut() {
+            match *item {
+                syn::Item::Trait(ref mut trait_inner) => {
+                    for trait_item in trait_inner.items.iter_mut() {
+                        if let syn::TraitItem::Method(ref mut method) = *trait_item {
+                            for input in method.sig.decl.inputs.iter_mut() {
+                                if let syn::FnArg::Captured(ref mut captured) = *input {
+                                    captured.ty = self.resolve_ty(&captured.ty, 0)?;
+                                }
+                            }
+                            if let syn::ReturnType::Type(_, ref mut ty) = method.sig.decl.output {
+                                let resolved = self.resolve_ty(ty, 0)?;
+                                **ty = resolved;
+                            }
+                        }
+                    }
+                }
+                syn::Item::Struct(ref mut struct_inner) => {
+                    self.resolve_fields(&mut struct_inner.fields)?;
+                }
+                syn::Item::Enum(ref mut enum_inner) => {
+                    for variant in enum_inner.variants.iter_mut() {
+                        self.resolve_fields(&mut variant.fields)?;
+                    }
+                }
+                _ => (),
+            }
+        }
+        Ok(result)
+    }
+
+    fn resolve_fields(&self, fields: &mut syn::Fields) -> Result<()> {
+        for field in fields.iter_mut() {
+            field.ty = self.resolve_ty(&field.ty, 0)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_ty(&self, ty: &syn::Type, depth: usize) -> Result<syn::Type> {
+        match *ty {
+            syn::Type::Path(ref type_path) => self.resolve_path(type_path, depth),
+            syn::Type::Reference(ref reference) => {
+                let mut reference = reference.clone();
+                reference.elem = Box::new(self.resolve_ty(&reference.elem, depth)?);
+                Ok(syn::Type::Reference(reference))
+            }
+            _ => Ok(ty.clone()),
+        }
+    }
+
+    fn resolve_path(&self, type_path: &syn::TypePath, depth: usize) -> Result<syn::Type> {
+        if depth > MAX_DEPTH {
+            let path = &type_path.path;
+            return Err(ParseError(format!(
+                "can't resolve type {}, the aliases are recursive",
+                quote!(#path)
+            ))
+            .into());
+        }
+
+        let (mut path, imported) = self.expand_import(&type_path.path);
+        for segment in path.segments.iter_mut() {
+            if let syn::PathArguments::AngleBracketed(ref mut generics) = segment.arguments {
+                for arg in generics.args.iter_mut() {
+                    if let syn::GenericArgument::Type(ref mut ty) = *arg {
+                        let resolved = self.resolve_ty(ty, depth + 1)?;
+                        *ty = resolved;
+                    }
+                }
+            }
+        }
+
+        // std::collections::HashMap is known as HashMap.
+        let is_std = path.segments.first().map_or(false, |first| {
+            STD_CRATES.contains(&first.value().ident.to_string().as_ref())
+        });
+        if is_std && path.segments.len() > 1 {
+            let last = path.segments.pop().map(|last| last.into_value());
+            path = syn::Path {
+                leading_colon: None,
+                segments: last.into_iter().collect(),
+            };
+        }
+
+        if type_path.qself.is_none() && path.segments.len() == 1 {
+            let segment = &path.segments[0];
+            if let Some(alias) = self.aliases.get(&segment.ident.to_string()) {
+                let args = generic_types(segment);
+                if args.len() != alias.params.len() {
+                    return Err(ParseError(format!(
+                        "type alias {} expects {} generic arguments, but {} are given",
+                        &segment.ident,
+                        alias.params.len(),
+                        args.len()
+                    ))
+                    .into());
+                }
+                let params = alias
+                    .params
+                    .iter()
+                    .cloned()
+                    .zip(args.into_iter())
+                    .collect::<HashMap<String, syn::Type>>();
+                return self.resolve_ty(&substitute(&alias.ty, &params), depth + 1);
+            }
+        }
+
+        if type_path.qself.is_none() && !is_std {
+            if let Some(crate_path) = self.crate_path(&path, imported) {
+                path = crate_path;
+            }
+        }
+
+        Ok(syn::Type::Path(syn::TypePath {
+            qself: type_path.qself.clone(),
+            path,
+        }))
+    }
+
+    ///
+    /// the path from the crate root of a type defined in the crate, like `crate::contract::User`,
+    /// None for builtin types and the types only known by their names, like the ones imported by
+    /// globs. Paths of `use` are relative to the crate root in rust 2015, the others are relative
+    /// to the module.
+    ///
+    fn crate_path(&self, path: &syn::Path, imported: bool) -> Option<syn::Path> {
+        let idents = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string())
+            .collect::<Vec<String>>();
+
+        let mut absolute = if idents.len() == 1 {
+            if !self.locals.contains(&idents[0]) {
+                return None;
+            }
+            self.mod_path.clone()
+        } else if path.leading_colon.is_some() || idents[0] == "crate" {
+            vec![]
+        } else if idents[0] == "self" || idents[0] == "super" {
+            self.mod_path.clone()
+        } else if imported && self.mod_path.first() == Some(&idents[0]) {
+            vec![]
+        } else {
+            self.mod_path.clone()
+        };
+
+        let mut segments = path
+            .segments
+            .iter()
+            .cloned()
+            .collect::<Vec<syn::PathSegment>>();
+        if idents[0] == "crate" || idents[0] == "self" {
+            segments.remove(0);
+        }
+        while segments.len() > 1 && segments[0].ident == "super" {
+            absolute.pop()?;
+            segments.remove(0);
+        }
+
+        let span = path.segments[0].ident.span();
+        let mut result = vec![syn::PathSegment::from(syn::Ident::new("crate", span))];
+        result.extend(
+            absolute
+                .iter()
+                .map(|ident| syn::PathSegment::from(syn::Ident::new(ident, span))),
+        );
+        result.extend(segments);
+        Some(syn::Path {
+            leading_colon: None,
+            segments: result.into_iter().collect(),
+        })
+    }
+
+    ///
+    /// replace the first segment of a path imported by `use` with the path it refers to, and
+    /// tell whether it is imported.
+    ///
+    fn expand_import(&self, path: &syn::Path) -> (syn::Path, bool) {
+        let first = match path.segments.first() {
+            Some(first) if path.leading_colon.is_none() => (*first.value()).clone(),
+            _ => return (path.clone(), false),
+        };
+        let imported = match self.imports.get(&first.ident.to_string()) {
+            Some(imported) => imported,
+            None => return (path.clone(), false),
+        };
+
+        let mut segments = imported
+            .iter()
+            .map(|ident| syn::PathSegment::from(ident.clone()))
+            .collect::<Vec<syn::PathSegment>>();
+        if let Some(last) = segments.last_mut() {
+            last.arguments = first.arguments.clone();
+        }
+        segments.extend(path.segments.iter().skip(1).cloned());
+        let path = syn::Path {
+            leading_colon: None,
+            segments: segments.into_iter().collect(),
+        };
+        (path, true)
+    }
+}
+
+fn generic_types(segment: &syn::PathSegment) -> Vec<syn::Type> {
+    match segment.arguments {
+        syn::PathArguments::AngleBracketed(ref generics) => generics
+            .args
+            .iter()
+            .filter_map(|arg| match *arg {
+                syn::GenericArgument::Type(ref ty) => Some(ty.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+///
+/// replace the generic parameters of an alias in its type by the arguments.
+///
+fn substitute(ty: &syn::Type, params: &HashMap<String, syn::Type>) -> syn::Type {
+    match *ty {
+        syn::Type::Path(ref type_path) => {
+            if type_path.qself.is_none() && type_path.path.segments.len() == 1 {
+                let segment = &type_path.path.segments[0];
+                let is_bare = match segment.arguments {
+                    syn::PathArguments::None => true,
+                    _ => false,
+                };
+                if let Some(arg) = params.get(&segment.ident.to_string()) {
+                    if is_bare {
+                        return arg.clone();
+                    }
+                }
+            }
+
+            let mut type_path = type_path.clone();
+            for segment in type_path.path.segments.iter_mut() {
+                if let syn::PathArguments::AngleBracketed(ref mut generics) = segment.arguments {
+                    for arg in generics.args.iter_mut() {
+                        if let syn::GenericArgument::Type(ref mut ty) = *arg {
+                            *ty = substitute(ty, params);
+                        }
+                    }
+                }
+            }
+            syn::Type::Path(type_path)
+        }
+        syn::Type::Reference(ref reference) => {
+            let mut reference = reference.clone();
+            reference.elem = Box::new(substitute(&reference.elem, params));
+            syn::Type::Reference(reference)
+        }
+        _ => ty.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(src: &str) -> Result<Vec<syn::Item>> {
+        let items = syn::parse_file(src).unwrap().items;
+        let mod_path = vec!["contract".to_owned(), "demo".to_owned()];
+        SymbolTable::new(&items, mod_path).resolve_items(&items)
+    }
+
+    ///
+    /// the types of the arguments and the return type of the first method of trait `name`.
+    ///
+    fn signature(items: &Vec<syn::Item>, name: &str) -> Vec<String> {
+        let trait_inner = items
+            .iter()
+            .filter_map(|item| match *item {
+                syn::Item::Trait(ref trait_inner) if trait_inner.ident == name => Some(trait_inner),
+                _ => None,
+            })
+            .next()
+            .unwrap();
+        let method = match trait_inner.items[0] {
+            syn::TraitItem::Method(ref method) => method,
+            _ => panic!("{} has no methods", name),
+        };
+
+        let mut result = method
+            .sig
+            .decl
+            .inputs
+            .iter()
+            .filter_map(|input| match *input {
+                syn::FnArg::Captured(ref captured) => {
+                    let ty = &captured.ty;
+                    Some(quote!(#ty).to_string())
+                }
+                _ => None,
+            })
+            .collect::<Vec<String>>();
+        if let syn::ReturnType::Type(_, ref ty) = method.sig.decl.output {
+            result.push(quote!(#ty).to_string());
+        }
+        result
+    }
+
+    #[test]
+    fn resolves_aliases() {
+        let items = resolve(
+            r#"
+            type UserId = u64;
+            type Names = Vec<String>;
+            pub trait Users {
+                fn find(id: UserId, names: &Names) -> Option<UserId>;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature(&items, "Users"),
+            vec!["u64", "& Vec < String >", "Option < u64 >"]
+        );
+    }
+
+    #[test]
+    fn resolves_generic_aliases() {
+        let items = resolve(
+            r#"
+            pub struct Error { pub code: i32 }
+            type Res<T> = Result<T, Error>;
+            type Pair<K, V> = HashMap<K, Vec<V>>;
+            pub trait Users {
+                fn find(pairs: Pair<String, i32>) -> Res<Option<i64>>;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature(&items, "Users"),
+            vec![
+                "HashMap < String , Vec < i32 > >",
+                "Result < Option < i64 > , crate :: contract :: demo :: Error >"
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_renamed_imports() {
+        let items = resolve(
+            r#"
+            use std::collections::HashMap as Dict;
+            use std::string::{self, String as Text};
+            pub trait Users {
+                fn names(all: Dict<Text, i32>, one: string::String) -> std::vec::Vec<Text>;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature(&items, "Users"),
+            vec!["HashMap < String , i32 >", "String", "Vec < String >"]
+        );
+    }
+
+    #[test]
+    fn resolves_aliases_of_fields() {
+        let items = resolve(
+            r#"
+            type Count = i32;
+            pub struct User { pub count: Count }
+            pub enum Event { Changed { count: Count } }
+            "#,
+        )
+        .unwrap();
+        let tys = items
+            .iter()
+            .flat_map(|item| match *item {
+                syn::Item::Struct(ref struct_inner) => struct_inner.fields.iter().collect(),
+                syn::Item::Enum(ref enum_inner) => enum_inner.variants[0].fields.iter().collect(),
+                _ => vec![],
+            })
+            .map(|field| {
+                let ty = &field.ty;
+                quote!(#ty).to_string()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(tys, vec!["i32", "i32"]);
+    }
+
+    #[test]
+    fn rejects_wrong_generic_arguments() {
+        let err = resolve(
+            r#"
+            type Res<T> = Result<T, String>;
+            pub trait Users {
+                fn find() -> Res<i32, i64>;
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "parse error: type alias Res expects 1 generic arguments, but 2 are given"
+        );
+    }
+
+    #[test]
+    fn rejects_recursive_aliases() {
+        let err = resolve(
+            r#"
+            type A = Vec<B>;
+            type B = Vec<A>;
+            pub trait Users {
+                fn find() -> A;
+            }
+            "#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("the aliases are recursive"));
+    }
+
+    #[test]
+    fn tells_local_types_from_builtin_names() {
+        let items = resolve(
+            r#"
+            pub struct String { pub value: i32 }
+            pub trait Users {
+                fn find(name: String, names: Vec<std::string::String>) -> Option<String>;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature(&items, "Users"),
+            vec![
+                "crate :: contract :: demo :: String",
+                "Vec < String >",
+                "Option < crate :: contract :: demo :: String >"
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_paths_of_other_mods() {
+        let items = resolve(
+            r#"
+            use contract::net::User;
+            use super::shared::{Error as SharedError};
+            pub trait Users {
+                fn find(user: User, other: other::User, error: SharedError) -> ::contract::User;
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            signature(&items, "Users"),
+            vec![
+                "crate :: contract :: net :: User",
+                "crate :: contract :: demo :: other :: User",
+                "crate :: contract :: shared :: Error",
+                "crate :: contract :: User"
+            ]
+        );
+    }
+}
@@ -3,4 +3,6 @@ pub struct ImpDesc {
     pub name: String,
     pub contract: String,
     pub mod_name: String,
+    /// path of the mod in the imp directory, like `net::http`.
+    pub mod_path: String,
 }
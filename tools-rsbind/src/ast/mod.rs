@@ -1,22 +1,27 @@
 pub(crate) mod contract;
 pub(crate) mod imp;
+pub(crate) mod modules;
 pub(crate) mod types;
+pub(crate) mod validate;
 
 use self::contract::desc::*;
 use self::imp::desc::*;
+use self::types::*;
+use config::{Common, WireFormat};
 use errors::ErrorKind::*;
 use errors::*;
+use incremental::{remove_stale, write_if_changed};
 use serde_json;
 use std::collections::HashMap;
-use std::fs;
-use std::io::Write;
 use std::path::PathBuf;
 
-const CONTRACT_DIR: &str = "src/contract";
-const IMP_DIR: &str = "src/imp";
+const SRC_DIR: &str = "src";
 
 pub(crate) struct AstHandler {
     crate_name: String,
+    wire_format: WireFormat,
+    contract_mod: String,
+    imp_mod: String,
 }
 
 /// The ast result after parsing contract and imp directories.
@@ -25,83 +30,511 @@ pub(crate) struct AstResult {
     pub trait_descs: HashMap<String, Vec<TraitDesc>>,
     /// All the struct in contract directory, key is mod name , value is all structs.
     pub struct_descs: HashMap<String, Vec<StructDesc>>,
+    /// All the enums in contract directory, key is mod name, value is all enums.
+    pub enum_descs: HashMap<String, Vec<EnumDesc>>,
     /// All the implementations.
     pub imp_desc: Vec<ImpDesc>,
+    /// How structs and vectors are transferred, configured in Rsbind.toml.
+    pub wire_format: WireFormat,
+    /// path of the contract module in the host crate, configured in Rsbind.toml.
+    pub contract_mod: String,
+    /// path of the imp module in the host crate, configured in Rsbind.toml.
+    pub imp_mod: String,
+    /// the files of contract and imp mods, which are hashed to skip generating if unchanged.
+    pub sources: Vec<PathBuf>,
+    /// the constructs the parser can't bridge, reported by the validation.
+    pub problems: Vec<Problem>,
 }
 
 impl AstHandler {
-    pub(crate) fn new(crate_name: String) -> AstHandler {
-        AstHandler { crate_name }
+    pub(crate) fn new(crate_name: String, common: &Common) -> AstHandler {
+        AstHandler {
+            crate_name,
+            wire_format: common.wire_format(),
+            contract_mod: common.contract_mod(),
+            imp_mod: common.imp_mod(),
+        }
     }
 
     pub(crate) fn parse(&self, origin_prj_path: &PathBuf) -> Result<AstResult> {
-        let imp_dir_path = origin_prj_path.join(IMP_DIR);
-        let imp_desc = imp::parser::parse_dir(&imp_dir_path)?;
+        let src_path = origin_prj_path.join(SRC_DIR);
+        let imp_mods = modules::walk_mod(&src_path, &self.imp_mod)
+            .chain_err(|| ParseError(format!("can't parse imp mod {}", &self.imp_mod)))?;
+        let imp_desc = imp::parser::parse_mods(&imp_mods)
+            .chain_err(|| ParseError(format!("can't parse imp mod {}", &self.imp_mod)))?;
+        let contract_mods = modules::walk_mod(&src_path, &self.contract_mod)?;
+
+        let mut sources = contract_mods
+            .iter()
+            .chain(imp_mods.iter())
+            .map(|module| module.file.clone())
+            .collect::<Vec<PathBuf>>();
+        sources.sort();
+        sources.dedup();
 
         let mut trait_descs = HashMap::new();
         let mut struct_descs = HashMap::new();
-        let contract_dir_path = origin_prj_path.join(CONTRACT_DIR);
-        let contract_dir = fs::read_dir(&contract_dir_path)?;
-        for file in contract_dir {
-            let path = file?.path();
-
-            // skip mod.rs
-            if path.ends_with("mod.rs") {
+        let mut enum_descs = HashMap::new();
+        let mut problems = vec![];
+        for module in contract_mods.iter() {
+            debug!("begin parse contract mod for {}.", module.mod_path());
+            let results =
+                contract::parser::parse(self.crate_name.clone(), &self.contract_mod, module)
+                    .chain_err(|| {
+                        ParseError(format!(
+                            "can't parse contract mod {} in {:?}",
+                            module.mod_path(),
+                            &module.file
+                        ))
+                    })?;
+            problems.extend(results.3);
+            if results.0.len() == 0 && results.1.len() == 0 && results.2.len() == 0 {
                 continue;
             }
 
-            println!("begin parse contract file for {:?}.", &path);
-            let mod_name = PathBuf::from(&path)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string();
-            let results = contract::parser::parse(self.crate_name.clone(), &path).unwrap();
+            // nested mods are flattened into names like `net_http`, which must stay unique.
+            let mod_name = module.mod_name();
+            if trait_descs.contains_key(&mod_name) {
+                return Err(ParseError(format!(
+                    "mod {} conflicts with another mod named {}",
+                    module.mod_path(),
+                    &mod_name
+                ))
+                .into());
+            }
             trait_descs.insert(mod_name.to_owned(), results.0);
             struct_descs.insert(mod_name.to_owned(), results.1);
+            enum_descs.insert(mod_name.to_owned(), results.2);
         }
 
-        Ok(AstResult {
+        let mut ast_result = AstResult {
             trait_descs,
             struct_descs,
+            enum_descs,
             imp_desc,
-        })
+            wire_format: self.wire_format,
+            contract_mod: self.contract_mod.clone(),
+            imp_mod: self.imp_mod.clone(),
+            sources,
+            problems,
+        };
+        ast_result.resolve_renames();
+        ast_result.resolve_enums();
+        ast_result.resolve_returned_callbacks();
+        ast_result.resolve_wire_format();
+        Ok(ast_result)
     }
 }
 
 impl AstResult {
-    pub(crate) fn flush(self, ast_dir: &PathBuf) -> Result<Self> {
-        for each_mod in self.trait_descs.iter() {
-            let trait_descs = each_mod.1;
-            for trait_desc in trait_descs {
-                let json =
-                    serde_json::to_string(trait_desc).map_err(|e| GenerateError(e.to_string()))?;
+    ///
+    /// Types renamed by `#[rsbind(rename = "...")]` are known by their new names everywhere,
+    /// the bridge files import them under the new names, so only the type names in the origin
+    /// types and the contracts of imps need to be replaced.
+    ///
+    fn resolve_renames(&mut self) {
+        let mut renames: HashMap<String, String> = HashMap::new();
+        for desc in self.trait_descs.values().flat_map(|descs| descs.iter()) {
+            if let Some(ref origin_name) = desc.origin_name {
+                renames.insert(origin_name.clone(), desc.name.clone());
+            }
+        }
+        for desc in self.struct_descs.values().flat_map(|descs| descs.iter()) {
+            if let Some(ref origin_name) = desc.origin_name {
+                renames.insert(origin_name.clone(), desc.name.clone());
+            }
+        }
+        for desc in self.enum_descs.values().flat_map(|descs| descs.iter()) {
+            if let Some(ref origin_name) = desc.origin_name {
+                renames.insert(origin_name.clone(), desc.name.clone());
+            }
+        }
+        if renames.is_empty() {
+            return;
+        }
+
+        for imp in self.imp_desc.iter_mut() {
+            if let Some(name) = renames.get(&imp.contract) {
+                imp.contract = name.clone();
+            }
+        }
+
+        let rename_args = |args: &mut Vec<ArgDesc>| {
+            for arg in args.iter_mut() {
+                arg.origin_ty = rename_ty(&arg.origin_ty, &renames);
+            }
+        };
+        for trait_desc in self
+            .trait_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            for method in trait_desc.methods.iter_mut() {
+                method.origin_return_ty = rename_ty(&method.origin_return_ty, &renames);
+                method.origin_error_ty = rename_ty(&method.origin_error_ty, &renames);
+                rename_args(&mut method.args);
+            }
+        }
+        for struct_desc in self
+            .struct_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            rename_args(&mut struct_desc.fields);
+        }
+        for enum_desc in self
+            .enum_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            for variant in enum_desc.variants.iter_mut() {
+                rename_args(&mut variant.fields);
+            }
+        }
+    }
+
+    ///
+    /// The parser treats every unknown ident as a struct, which is also how data-carrying enums
+    /// are transferred. Here we mark the ones referring to C-like enums, which are transferred as
+    /// i32. Types of the same name in other mods are told apart by their paths.
+    ///
+    fn resolve_enums(&mut self) {
+        let c_like_enums = self
+            .enum_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .filter(|desc| desc.is_c_like)
+            .cloned()
+            .collect::<Vec<EnumDesc>>();
+        let is_c_like =
+            |ty_path: &str| c_like_enums.iter().any(|desc| desc.is_referred_by(ty_path));
+
+        let resolve = |ty: &mut AstType, ty_path: &str| {
+            if !is_c_like(ty_path) {
+                return;
+            }
+            match *ty {
+                AstType::Struct => *ty = AstType::Enum,
+                AstType::Vec(AstBaseType::Struct) => *ty = AstType::Vec(AstBaseType::Enum),
+                AstType::Optional(AstBaseType::Struct) => {
+                    *ty = AstType::Optional(AstBaseType::Enum)
+                }
+                AstType::Map(key, AstBaseType::Struct) => {
+                    *ty = AstType::Map(key, AstBaseType::Enum)
+                }
+                _ => (),
+            }
+        };
+
+        for trait_desc in self
+            .trait_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            for method in trait_desc.methods.iter_mut() {
+                resolve(&mut method.return_type, &method.return_ty_path);
+                resolve(&mut method.error_type, &method.error_ty_path);
+                for arg in method.args.iter_mut() {
+                    resolve(&mut arg.ty, &arg.ty_path);
+                }
+            }
+        }
+
+        for struct_desc in self
+            .struct_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            for field in struct_desc.fields.iter_mut() {
+                resolve(&mut field.ty, &field.ty_path);
+            }
+        }
+
+        for enum_desc in self
+            .enum_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            for variant in enum_desc.variants.iter_mut() {
+                for field in variant.fields.iter_mut() {
+                    resolve(&mut field.ty, &field.ty_path);
+                }
+            }
+        }
+    }
+
+    ///
+    /// Callbacks returned from rust are implemented in rust, so they are wrapped in an object
+    /// named `Rust{Callback}`, which holds the boxed trait object by a handle and implements the
+    /// callback interface in java and swift. The rust implementations of these callbacks are not
+    /// contract implementations, so the generators ignore the imps of callbacks. Contract types
+    /// conflicting with the wrappers are reported by the validation.
+    ///
+    fn resolve_returned_callbacks(&mut self) {
+        let mut returned = self
+            .trait_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .filter(|desc| !desc.is_callback)
+            .flat_map(|desc| desc.methods.iter())
+            .filter(|method| method.return_type == AstType::Callback)
+            .map(|method| method.origin_return_ty.clone())
+            .collect::<Vec<String>>();
+        returned.sort();
+        returned.dedup();
 
-                let file_name = ast_dir.join(&format!(
-                    "{}_{}.json",
-                    &trait_desc.mod_name, &trait_desc.name
-                ));
-                let mut ast_file = fs::File::create(&file_name)?;
-                ast_file.write_all(&json.into_bytes())?;
+        let mut wrappers = vec![];
+        for callback in returned.iter() {
+            let callback_desc = self
+                .trait_descs
+                .values()
+                .flat_map(|descs| descs.iter())
+                .find(|desc| desc.is_callback && &desc.name == callback);
+            if let Some(callback_desc) = callback_desc {
+                wrappers.push(TraitDesc {
+                    name: format!("Rust{}", &callback_desc.name),
+                    ty: callback_desc.ty.clone(),
+                    mod_name: callback_desc.mod_name.clone(),
+                    mod_path: callback_desc.mod_path.clone(),
+                    crate_name: callback_desc.crate_name.clone(),
+                    is_callback: false,
+                    is_object: true,
+                    wraps_callback: Some(callback_desc.name.clone()),
+                    methods: callback_desc.methods.clone(),
+                    exported: false,
+                    origin_name: None,
+                    is_public: true,
+                    location: callback_desc.location.clone(),
+                });
             }
         }
 
-        for each_mod in self.struct_descs.iter() {
-            let struct_descs = each_mod.1;
-            for struct_desc in struct_descs {
-                let json =
-                    serde_json::to_string(struct_desc).map_err(|e| GenerateError(e.to_string()))?;
+        for wrapper in wrappers.into_iter() {
+            self.trait_descs
+                .entry(wrapper.mod_name.clone())
+                .or_insert_with(Vec::new)
+                .push(wrapper);
+        }
+    }
 
-                let file_name = ast_dir.join(&format!(
-                    "{}_{}.json",
-                    &struct_desc.mod_name, &struct_desc.name
-                ));
-                let mut ast_file = fs::File::create(&file_name)?;
-                ast_file.write_all(&json.into_bytes())?;
+    ///
+    /// With the binary wire format, structs whose fields can all be encoded in binary are
+    /// marked, and so are the arguments and return values of methods using them or vectors.
+    /// Maps, data-carrying enums and everything passed through callbacks stay in json.
+    ///
+    fn resolve_wire_format(&mut self) {
+        if self.wire_format != WireFormat::Binary {
+            return;
+        }
+
+        // structs may contain structs of other mods, so repeat until nothing changes.
+        let mut binary_structs: Vec<String> = vec![];
+        loop {
+            let found = self
+                .struct_descs
+                .values()
+                .flat_map(|descs| descs.iter())
+                .filter(|desc| !binary_structs.contains(&desc.name))
+                .filter(|desc| {
+                    desc.fields
+                        .iter()
+                        .all(|field| is_binary_ty(&field.ty, &field.origin_ty, &binary_structs))
+                })
+                .map(|desc| desc.name.clone())
+                .collect::<Vec<String>>();
+            if found.len() == 0 {
+                break;
             }
+            binary_structs.extend(found);
+        }
+
+        for struct_desc in self
+            .struct_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            struct_desc.binary = binary_structs.contains(&struct_desc.name);
+        }
+
+        for enum_desc in self
+            .enum_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+        {
+            enum_desc.binary = enum_desc.is_c_like;
+        }
+
+        // only structs and vectors are worth it, byte arrays are transferred as they are.
+        let is_binary_transfer = |ty: &AstType, origin_ty: &str| match *ty {
+            AstType::Vec(AstBaseType::Byte) => false,
+            AstType::Struct | AstType::Vec(_) => is_binary_ty(ty, origin_ty, &binary_structs),
+            _ => false,
+        };
+
+        for trait_desc in self
+            .trait_descs
+            .values_mut()
+            .flat_map(|descs| descs.iter_mut())
+            .filter(|desc| !desc.is_callback)
+        {
+            for method in trait_desc.methods.iter_mut() {
+                method.binary_return =
+                    is_binary_transfer(&method.return_type, &method.origin_return_ty);
+                for arg in method.args.iter_mut() {
+                    arg.binary = is_binary_transfer(&arg.ty, &arg.origin_ty);
+                }
+            }
+        }
+    }
+
+    ///
+    /// write the descs as json files into `ast_dir`, files of the last run are only rewritten
+    /// if they change, and the ones of removed descs are deleted.
+    ///
+    pub(crate) fn flush(self, ast_dir: &PathBuf) -> Result<Self> {
+        let mut files = vec![];
+        for trait_desc in self.trait_descs.values().flat_map(|descs| descs.iter()) {
+            let json =
+                serde_json::to_string(trait_desc).map_err(|e| GenerateError(e.to_string()))?;
+            let file_name = ast_dir.join(&format!(
+                "{}_{}.json",
+                &trait_desc.mod_name, &trait_desc.name
+            ));
+            write_if_changed(&file_name, json.as_bytes())?;
+            files.push(file_name);
+        }
+
+        for struct_desc in self.struct_descs.values().flat_map(|descs| descs.iter()) {
+            let json =
+                serde_json::to_string(struct_desc).map_err(|e| GenerateError(e.to_string()))?;
+            let file_name = ast_dir.join(&format!(
+                "{}_{}.json",
+                &struct_desc.mod_name, &struct_desc.name
+            ));
+            write_if_changed(&file_name, json.as_bytes())?;
+            files.push(file_name);
+        }
+
+        for enum_desc in self.enum_descs.values().flat_map(|descs| descs.iter()) {
+            let json =
+                serde_json::to_string(enum_desc).map_err(|e| GenerateError(e.to_string()))?;
+            let file_name =
+                ast_dir.join(&format!("{}_{}.json", &enum_desc.mod_name, &enum_desc.name));
+            write_if_changed(&file_name, json.as_bytes())?;
+            files.push(file_name);
         }
 
+        remove_stale(ast_dir, "json", &files)?;
         Ok(self)
     }
 }
+
+///
+/// Replace the renamed type names in an origin type, like `Vec<Foo>` to `Vec<Bar>`.
+///
+fn rename_ty(origin_ty: &str, renames: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut ident = String::new();
+    for c in origin_ty.chars().chain(Some(' ')) {
+        if c.is_alphanumeric() || c == '_' {
+            ident.push(c);
+            continue;
+        }
+        if !ident.is_empty() {
+            result.push_str(renames.get(&ident).unwrap_or(&ident));
+            ident.clear();
+        }
+        result.push(c);
+    }
+    result.pop();
+    result
+}
+
+///
+/// Whether a value of the type can be encoded by the binary wire format, `binary_structs` are
+/// the structs known to be encodable.
+///
+fn is_binary_ty(ty: &AstType, origin_ty: &str, binary_structs: &Vec<String>) -> bool {
+    let is_binary_base = |base: AstBaseType, inner: &str| match base {
+        AstBaseType::Byte
+        | AstBaseType::Int
+        | AstBaseType::Long
+        | AstBaseType::Float
+        | AstBaseType::Double
+        | AstBaseType::Boolean
+        | AstBaseType::String
+        | AstBaseType::Enum => true,
+        AstBaseType::Struct => binary_structs.iter().any(|name| name == inner),
+        _ => false,
+    };
+
+    match *ty {
+        AstType::Vec(base) => is_binary_base(base, &origin_ty.replace("Vec<", "").replace(">", "")),
+        AstType::Optional(base) => {
+            is_binary_base(base, &origin_ty.replace("Option<", "").replace(">", ""))
+        }
+        AstType::Map(_, _) | AstType::Callback | AstType::Void => false,
+        AstType::Struct => is_binary_base(AstBaseType::Struct, origin_ty),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::modules::ModItems;
+    use super::*;
+    use syn;
+
+    ///
+    /// parse contract mods given by their names and sources, without imps.
+    ///
+    fn ast_result(mods: &[(&str, &str)]) -> AstResult {
+        let mut ast_result = AstResult {
+            trait_descs: HashMap::new(),
+            struct_descs: HashMap::new(),
+            enum_descs: HashMap::new(),
+            imp_desc: vec![],
+            wire_format: WireFormat::Json,
+            contract_mod: "contract".to_owned(),
+            imp_mod: "imp".to_owned(),
+            sources: vec![],
+            problems: vec![],
+        };
+        for &(name, src) in mods.iter() {
+            let module = ModItems {
+                path: vec![name.to_owned()],
+                items: syn::parse_file(src).unwrap().items,
+                file: PathBuf::from(format!("src/contract/{}.rs", name)),
+            };
+            let results = contract::parser::parse("demo".to_owned(), "contract", &module).unwrap();
+            ast_result.trait_descs.insert(name.to_owned(), results.0);
+            ast_result.struct_descs.insert(name.to_owned(), results.1);
+            ast_result.enum_descs.insert(name.to_owned(), results.2);
+        }
+        ast_result
+    }
+
+    #[test]
+    fn resolves_enums_by_their_mods() {
+        let mut ast_result = ast_result(&[
+            ("a", "pub enum Status { On, Off }"),
+            (
+                "b",
+                r#"
+                pub struct Status { pub code: i32 }
+                pub struct Report {
+                    pub status: Status,
+                    pub statuses: Vec<super::a::Status>,
+                    pub last: Option<Status>,
+                }
+                "#,
+            ),
+        ]);
+        ast_result.resolve_enums();
+
+        let report = &ast_result.struct_descs["b"][1];
+        assert_eq!(report.fields[0].ty, AstType::Struct);
+        assert_eq!(report.fields[1].ty, AstType::Vec(AstBaseType::Enum));
+        assert_eq!(report.fields[2].ty, AstType::Optional(AstBaseType::Struct));
+    }
+}
@@ -0,0 +1,389 @@
+//!
+//! Walk the module tree of the contract and imp directories, following `mod` declarations
+//! like rustc does, including nested directories, inline modules and `#[path]` attributes.
+//!
+use errors::ErrorKind::*;
+use errors::*;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use syn;
+
+///
+/// The items of one module, without its submodules.
+///
+pub(crate) struct ModItems {
+    /// module path relative to the root directory, like `["net", "http"]`.
+    pub path: Vec<String>,
+    pub items: Vec<syn::Item>,
+    /// the file defining the items, for inline modules it is the file declaring them.
+    pub file: PathBuf,
+}
+
+impl ModItems {
+    ///
+    /// the flat name used for bridge files and ffi symbols, like `net_http`.
+    ///
+    pub(crate) fn mod_name(&self) -> String {
+        self.path.join("_")
+    }
+
+    ///
+    /// the path used in `use` declarations, like `net::http`.
+    ///
+    pub(crate) fn mod_path(&self) -> String {
+        self.path.join("::")
+    }
+}
+
+///
+/// Walk all the submodules of the module `mod_path` in `src_dir`, which is defined in
+/// `{mod_path}/mod.rs` or `{mod_path}.rs`. Items of the module itself are not included.
+///
+pub(crate) fn walk_mod(src_dir: &PathBuf, mod_path: &str) -> Result<Vec<ModItems>> {
+    let dir = mod_path
+        .split("::")
+        .fold(src_dir.clone(), |dir, segment| dir.join(segment.trim()));
+    let file = dir.with_extension("rs");
+    if dir.join("mod.rs").exists() || !file.exists() {
+        return walk(&dir);
+    }
+
+    let mut result = vec![];
+    let items = parse_file(&file)?;
+    walk_children(
+        &dir,
+        &src_dir_of(&file),
+        &file,
+        &vec![],
+        &items,
+        &mut result,
+    )?;
+    Ok(result)
+}
+
+///
+/// Walk all the modules under `dir`. The modules are the ones declared in `dir/mod.rs`, or all
+/// the files in `dir` if there is no `mod.rs`. Items of `dir/mod.rs` itself are not included.
+///
+pub(crate) fn walk(dir: &PathBuf) -> Result<Vec<ModItems>> {
+    let mut result = vec![];
+    let root = dir.join("mod.rs");
+    if root.exists() {
+        let items = parse_file(&root)?;
+        walk_children(dir, dir, &root, &vec![], &items, &mut result)?;
+        return Ok(result);
+    }
+
+    let files = fs::read_dir(dir).map_err(|e| ParseError(e.to_string()))?;
+    for file in files {
+        let path = file.map_err(|e| ParseError(e.to_string()))?.path();
+        if path.extension().map_or(true, |ext| ext != "rs") {
+            continue;
+        }
+        let name = file_stem(&path)?;
+        walk_file(&path, &dir.join(&name), vec![name], &mut result)?;
+    }
+    Ok(result)
+}
+
+///
+/// walk a module file, whose submodules are in `child_dir`.
+///
+fn walk_file(
+    file: &PathBuf,
+    child_dir: &PathBuf,
+    path: Vec<String>,
+    result: &mut Vec<ModItems>,
+) -> Result<()> {
+    debug!("begin walking mod file {:?}.", file);
+    let items = parse_file(file)?;
+    walk_children(child_dir, &src_dir_of(file), file, &path, &items, result)?;
+    result.push(ModItems {
+        path,
+        items: without_mods(items),
+        file: file.clone(),
+    });
+    Ok(())
+}
+
+///
+/// walk the modules declared in `items` of `file`. Declared files are looked up in `child_dir`,
+/// and `#[path]` attributes are relative to `attr_dir`, the directory of the declaring file.
+///
+fn walk_children(
+    child_dir: &PathBuf,
+    attr_dir: &PathBuf,
+    file: &PathBuf,
+    path: &Vec<String>,
+    items: &Vec<syn::Item>,
+    result: &mut Vec<ModItems>,
+) -> Result<()> {
+    for item in items.iter() {
+        let item_mod = match *item {
+            syn::Item::Mod(ref item_mod) => item_mod,
+            _ => continue,
+        };
+
+        let name = item_mod.ident.to_string();
+        let mut mod_path = path.clone();
+        mod_path.push(name.clone());
+
+        match item_mod.content {
+            Some((_, ref content)) => {
+                // files declared in inline modules are in a directory named after the module.
+                let inline_dir = child_dir.join(&name);
+                walk_children(&inline_dir, &inline_dir, file, &mod_path, content, result)?;
+                result.push(ModItems {
+                    path: mod_path,
+                    items: without_mods(content.clone()),
+                    file: file.clone(),
+                });
+            }
+            None => match path_attr(&item_mod.attrs) {
+                Some(custom) => {
+                    // files named by #[path] keep their submodules beside them, like mod.rs.
+                    let file = attr_dir.join(custom);
+                    walk_file(&file, &src_dir_of(&file), mod_path, result)?;
+                }
+                None => {
+                    let file = child_dir.join(format!("{}.rs", &name));
+                    let mod_dir = child_dir.join(&name);
+                    if file.exists() {
+                        walk_file(&file, &mod_dir, mod_path, result)?;
+                    } else {
+                        walk_file(&mod_dir.join("mod.rs"), &mod_dir, mod_path, result)?;
+                    }
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+fn parse_file(file: &PathBuf) -> Result<Vec<syn::Item>> {
+    let mut src = String::new();
+    fs::File::open(file)
+        .and_then(|mut file| file.read_to_string(&mut src))
+        .map_err(|e| ParseError(format!("can't read {:?}: {}", file, e)))?;
+
+    let syn_file = syn::parse_file(&src).map_err(|e| ParseError(e.to_string()))?;
+    Ok(syn_file.items)
+}
+
+fn src_dir_of(file: &PathBuf) -> PathBuf {
+    file.parent().map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn file_stem(file: &PathBuf) -> Result<String> {
+    file.file_stem()
+        .and_then(|stem| stem.to_str())
+        .map(|stem| stem.to_owned())
+        .ok_or_else(|| ParseError(format!("can't get mod name from {:?}", file)).into())
+}
+
+fn path_attr(attrs: &Vec<syn::Attribute>) -> Option<String> {
+    attrs
+        .iter()
+        .filter_map(|attr| attr.interpret_meta())
+        .filter_map(|meta| match meta {
+            syn::Meta::NameValue(ref name_value) if name_value.ident == "path" => {
+                match name_value.lit {
+                    syn::Lit::Str(ref lit) => Some(lit.value()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .next()
+}
+
+fn without_mods(items: Vec<syn::Item>) -> Vec<syn::Item> {
+    items
+        .into_iter()
+        .filter(|item| match *item {
+            syn::Item::Mod(_) => false,
+            _ => true,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    ///
+    /// the paths of the walked modules, the names of their items and their files.
+    ///
+    fn walked(src: &TempDir, mod_path: &str) -> Vec<(String, Vec<String>, String)> {
+        let mut result = walk_mod(src.path(), mod_path)
+            .unwrap()
+            .into_iter()
+            .map(|module| {
+                let items = module.items.iter().filter_map(item_name).collect();
+                let file = module
+                    .file
+                    .strip_prefix(src.path())
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                (module.mod_path(), items, file)
+            })
+            .collect::<Vec<(String, Vec<String>, String)>>();
+        result.sort();
+        result
+    }
+
+    fn item_name(item: &syn::Item) -> Option<String> {
+        match *item {
+            syn::Item::Struct(ref struct_inner) => Some(struct_inner.ident.to_string()),
+            syn::Item::Trait(ref trait_inner) => Some(trait_inner.ident.to_string()),
+            _ => None,
+        }
+    }
+
+    fn module(path: &str, items: &[&str], file: &str) -> (String, Vec<String>, String) {
+        (
+            path.to_owned(),
+            items.iter().map(|item| item.to_string()).collect(),
+            file.to_owned(),
+        )
+    }
+
+    #[test]
+    fn walks_all_files_without_mod_rs() {
+        let src = TempDir::with_files(
+            "modules_files",
+            &[
+                ("contract/user.rs", "pub struct User {}"),
+                ("contract/group.rs", "pub struct Group {}"),
+                ("contract/readme.md", "not rust"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "contract"),
+            vec![
+                module("group", &["Group"], "contract/group.rs"),
+                module("user", &["User"], "contract/user.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn walks_nested_directories() {
+        let src = TempDir::with_files(
+            "modules_nested",
+            &[
+                ("contract/mod.rs", "pub mod net; pub mod user;"),
+                ("contract/user.rs", "pub struct User {}"),
+                ("contract/net/mod.rs", "pub mod http; pub struct Net {}"),
+                ("contract/net/http.rs", "pub mod cookie; pub struct Http {}"),
+                ("contract/net/http/cookie.rs", "pub struct Cookie {}"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "contract"),
+            vec![
+                module("net", &["Net"], "contract/net/mod.rs"),
+                module("net::http", &["Http"], "contract/net/http.rs"),
+                module(
+                    "net::http::cookie",
+                    &["Cookie"],
+                    "contract/net/http/cookie.rs"
+                ),
+                module("user", &["User"], "contract/user.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn walks_inline_modules() {
+        let src = TempDir::with_files(
+            "modules_inline",
+            &[
+                (
+                    "contract/mod.rs",
+                    "pub mod user { pub struct User {} pub mod group { pub struct Group {} } }",
+                ),
+                ("contract/net.rs", "pub mod http { pub mod cookie; }"),
+                ("contract/net/http/cookie.rs", "pub struct Cookie {}"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "contract"),
+            vec![
+                module("user", &["User"], "contract/mod.rs"),
+                module("user::group", &["Group"], "contract/mod.rs"),
+            ]
+        );
+
+        let src = TempDir::with_files(
+            "modules_inline_file",
+            &[
+                ("contract/mod.rs", "pub mod net;"),
+                ("contract/net.rs", "pub mod http { pub mod cookie; }"),
+                ("contract/net/http/cookie.rs", "pub struct Cookie {}"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "contract"),
+            vec![
+                module("net", &[], "contract/net.rs"),
+                module("net::http", &[], "contract/net.rs"),
+                module(
+                    "net::http::cookie",
+                    &["Cookie"],
+                    "contract/net/http/cookie.rs"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn walks_path_attributes() {
+        let src = TempDir::with_files(
+            "modules_path",
+            &[
+                (
+                    "contract/mod.rs",
+                    r#"#[path = "../shared/user_api.rs"] pub mod user;"#,
+                ),
+                ("shared/user_api.rs", "pub mod group; pub struct User {}"),
+                ("shared/group.rs", "pub struct Group {}"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "contract"),
+            vec![
+                module("user", &["User"], "contract/../shared/user_api.rs"),
+                module("user::group", &["Group"], "contract/../shared/group.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn walks_nested_mod_paths() {
+        let src = TempDir::with_files(
+            "modules_mod_path",
+            &[
+                ("api.rs", "pub mod v1;"),
+                ("api/v1.rs", "pub mod user;"),
+                ("api/v1/user.rs", "pub struct User {}"),
+            ],
+        );
+        assert_eq!(
+            walked(&src, "api::v1"),
+            vec![module("user", &["User"], "api/v1/user.rs")]
+        );
+    }
+
+    #[test]
+    fn reports_missing_files() {
+        let src = TempDir::with_files("modules_missing", &[("contract/mod.rs", "pub mod user;")]);
+        match walk_mod(src.path(), "contract") {
+            Err(err) => assert!(err.to_string().starts_with("parse error: can't read")),
+            Ok(_) => panic!("missing files are not reported"),
+        }
+    }
+}
@@ -15,6 +15,8 @@ pub(crate) enum AstBaseType {
     String,
     Callback,
     Struct,
+    // Only C-like enums, data-carrying enums are transferred like structs.
+    Enum,
 }
 
 impl<'a> From<&'a str> for AstBaseType {
@@ -30,7 +32,7 @@ impl<'a> From<&'a str> for AstBaseType {
             // Right now, all callbacks are wrapped with Box
             "Box" => AstBaseType::Callback,
             // If the ident can't recognized, we assume it is a struct,
-            // but if we add enum support, it should be changed.
+            // C-like enums are resolved later by AstHandler, after all enums are collected.
             _ => AstBaseType::Struct,
         }
     }
@@ -42,6 +44,39 @@ impl From<String> for AstBaseType {
     }
 }
 
+impl AstBaseType {
+    ///
+    /// numbers and booleans, `Option<T>` of them is passed by a presence flag and the value
+    /// through C, and by boxed objects through jni.
+    ///
+    pub(crate) fn is_primitive(&self) -> bool {
+        match *self {
+            AstBaseType::Byte
+            | AstBaseType::Int
+            | AstBaseType::Long
+            | AstBaseType::Float
+            | AstBaseType::Double
+            | AstBaseType::Boolean => true,
+            _ => false,
+        }
+    }
+
+    ///
+    /// the boxed java class of primitives, like `java/lang/Integer`.
+    ///
+    pub(crate) fn to_java_boxed_class(&self) -> Option<&'static str> {
+        match *self {
+            AstBaseType::Byte => Some("java/lang/Byte"),
+            AstBaseType::Int => Some("java/lang/Integer"),
+            AstBaseType::Long => Some("java/lang/Long"),
+            AstBaseType::Float => Some("java/lang/Float"),
+            AstBaseType::Double => Some("java/lang/Double"),
+            AstBaseType::Boolean => Some("java/lang/Boolean"),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq)]
 pub(crate) enum AstType {
     Void,
@@ -53,8 +88,14 @@ pub(crate) enum AstType {
     Boolean,
     String,
     Vec(AstBaseType),
+    // Option<T>, None is transferred as null, or by a presence flag for primitives through C.
+    Optional(AstBaseType),
+    // HashMap<K, V> or BTreeMap<K, V>, keys are strings or integers, transferred by json.
+    Map(AstBaseType, AstBaseType),
     Callback,
     Struct,
+    // Only C-like enums, data-carrying enums are transferred like structs.
+    Enum,
 }
 
 ///
@@ -73,7 +114,7 @@ impl<'a> From<&'a str> for AstType {
             // Right now, all callbacks are wrapped with Box
             "Box" => AstType::Callback,
             // If the ident can't recognized, we assume it is a struct,
-            // but if we add enum support, it should be changed.
+            // C-like enums are resolved later by AstHandler, after all enums are collected.
             _ => AstType::Struct,
         }
     }
@@ -92,6 +133,7 @@ impl From<AstBaseType> for AstType {
             AstBaseType::String => AstType::String,
             AstBaseType::Callback => AstType::Callback,
             AstBaseType::Struct => AstType::Struct,
+            AstBaseType::Enum => AstType::Enum,
         }
     }
 }
@@ -109,7 +151,15 @@ impl AstType {
             AstType::String => "Ljava/lang/String;".to_owned(),
             AstType::Callback => "Ljava/lang/String;".to_owned(),
             AstType::Struct => "Ljava/lang/String;".to_owned(),
+            // bytes are transferred as byte arrays, other vectors as json.
+            AstType::Vec(AstBaseType::Byte) => "[B".to_owned(),
             AstType::Vec(_) => "Ljava/lang/String;".to_owned(),
+            AstType::Optional(base) => match base.to_java_boxed_class() {
+                Some(class) => format!("L{};", class),
+                None => "Ljava/lang/String;".to_owned(),
+            },
+            AstType::Map(_, _) => "Ljava/lang/String;".to_owned(),
+            AstType::Enum => "I".to_owned(),
         }
     }
 }
@@ -122,3 +172,22 @@ impl From<String> for AstType {
         return AstType::from(ident.as_ref());
     }
 }
+
+///
+/// Split the origin type of a map, like `HashMap<String, StructSimple>`, into the names of the
+/// map, the key and the value.
+///
+pub(crate) fn split_map_origin_ty(origin_ty: &str) -> (String, String, String) {
+    let open = origin_ty.find('<').unwrap_or(origin_ty.len());
+    let map_name = origin_ty[..open].to_owned();
+    let generics = origin_ty[open..]
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .split(',')
+        .map(|each| each.trim().to_owned())
+        .collect::<Vec<String>>();
+    match generics.len() {
+        2 => (map_name, generics[0].clone(), generics[1].clone()),
+        _ => (map_name, "".to_owned(), "".to_owned()),
+    }
+}
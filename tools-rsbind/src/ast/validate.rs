@@ -0,0 +1,705 @@
+//!
+//! Check the contracts against what the bridges support before anything is generated, so all
+//! the problems are reported at once with their locations, instead of failing in the generators.
+//!
+//! The problems found by the parser are reported with the ones found here. The rules checked
+//! here hold for all the bridges, the restrictions of single targets, like maps of data-carrying
+//! enums in java and kotlin, or maps returned from python callbacks, are reported by their
+//! generators.
+//!
+use super::contract::desc::*;
+use super::types::*;
+use super::AstResult;
+use errors::ErrorKind::*;
+use errors::*;
+use std::fs;
+
+///
+/// Validate all the contracts, the problems are printed like rustc does.
+///
+pub(crate) fn validate(ast_result: &AstResult) -> Result<()> {
+    let mut validator = Validator {
+        ast_result,
+        problems: vec![],
+    };
+    validator.check_all();
+
+    let mut problems = validator.problems;
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    problems.sort_by(|a, b| {
+        (&a.location.file, a.location.line, a.location.column).cmp(&(
+            &b.location.file,
+            b.location.line,
+            b.location.column,
+        ))
+    });
+    for problem in problems.iter() {
+        eprintln!("{}", format_problem(problem));
+    }
+    Err(ValidateError(format!(
+        "aborting due to {} unsupported constructs in contracts",
+        problems.len()
+    ))
+    .into())
+}
+
+struct Validator<'a> {
+    ast_result: &'a AstResult,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Validator<'a> {
+    fn check_all(&mut self) {
+        let ast_result = self.ast_result;
+        self.problems.extend(ast_result.problems.iter().cloned());
+        for (mod_name, descs) in ast_result.trait_descs.iter() {
+            for desc in descs.iter() {
+                // wrappers of returned callbacks are generated from the callbacks.
+                match desc.wraps_callback {
+                    Some(ref callback) => self.check_wrapper(callback, desc),
+                    None => {
+                        self.check_public("trait", &desc.name, desc.is_public, &desc.location);
+                        self.check_trait(mod_name, desc);
+                    }
+                }
+            }
+        }
+
+        for desc in ast_result
+            .struct_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+        {
+            self.check_public("struct", &desc.name, desc.is_public, &desc.location);
+            for field in desc.fields.iter() {
+                self.check_field(&desc.name, field);
+            }
+        }
+
+        for desc in ast_result
+            .enum_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+        {
+            self.check_public("enum", &desc.name, desc.is_public, &desc.location);
+            for variant in desc.variants.iter() {
+                let owner = format!("{}::{}", &desc.name, &variant.name);
+                for field in variant.fields.iter() {
+                    self.check_field(&owner, field);
+                }
+            }
+        }
+    }
+
+    fn check_public(&mut self, kind: &str, name: &str, is_public: bool, location: &Location) {
+        if !is_public {
+            self.report(
+                location,
+                format!(
+                    "{} `{}` is not public, make it `pub` or skip it by #[rsbind::skip]",
+                    kind, name
+                ),
+            );
+        }
+    }
+
+    ///
+    /// the generated wrapper of a returned callback, named `Rust{Callback}`, can't share its name
+    /// with the types in contract, which are all generated into the same package.
+    ///
+    fn check_wrapper(&mut self, callback: &str, wrapper: &TraitDesc) {
+        let ast_result = self.ast_result;
+        let conflicts = ast_result
+            .trait_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .filter(|desc| desc.wraps_callback.is_none())
+            .map(|desc| &desc.name)
+            .chain(
+                ast_result
+                    .struct_descs
+                    .values()
+                    .flat_map(|descs| descs.iter())
+                    .map(|desc| &desc.name),
+            )
+            .chain(
+                ast_result
+                    .enum_descs
+                    .values()
+                    .flat_map(|descs| descs.iter())
+                    .map(|desc| &desc.name),
+            )
+            .any(|name| name == &wrapper.name);
+        if conflicts {
+            self.report(
+                &wrapper.location,
+                format!(
+                    "callback `{}` is returned from rust, so it is wrapped by a generated `{}`, \
+                     which conflicts with the type of the same name in contract, rename one of \
+                     them by #[rsbind(rename = \"...\")]",
+                    callback, &wrapper.name
+                ),
+            );
+        }
+    }
+
+    fn check_trait(&mut self, mod_name: &str, desc: &TraitDesc) {
+        let imp = self
+            .ast_result
+            .imp_desc
+            .iter()
+            .find(|imp| imp.contract == desc.name);
+        match imp {
+            // callbacks returned from rust are implemented in rust too.
+            Some(imp) if desc.is_callback && !self.is_returned(&desc.name) => self.report(
+                &desc.location,
+                format!(
+                    "trait `{}` is a callback implemented by other languages, but it is \
+                     implemented by `{}` in rust",
+                    &desc.name, &imp.name
+                ),
+            ),
+            None if desc.exported => self.report(
+                &desc.location,
+                format!(
+                    "trait `{}` is exported by #[rsbind::export] but not implemented",
+                    &desc.name
+                ),
+            ),
+            None if !desc.is_callback => self.report(
+                &desc.location,
+                format!(
+                    "trait `{}` is not a callback but not implemented, mark it by \
+                     #[rsbind::callback] if it is implemented by other languages",
+                    &desc.name
+                ),
+            ),
+            _ => (),
+        }
+
+        for method in desc.methods.iter() {
+            let owner = format!("{}::{}", &desc.name, &method.name);
+            if desc.is_callback {
+                self.check_callback_method(&owner, method);
+            } else {
+                self.check_method(mod_name, &owner, method);
+            }
+        }
+    }
+
+    fn check_method(&mut self, mod_name: &str, owner: &str, method: &MethodDesc) {
+        for arg in method.args.iter() {
+            let what = format!("argument `{}` of `{}`", &arg.name, owner);
+            match arg.ty {
+                AstType::Callback => self.check_callback_arg(mod_name, &what, arg),
+                // the types the parser can't recognize are reported by it.
+                AstType::Void => (),
+                _ => self.check_ty(&what, &arg.ty, &arg.origin_ty, &arg.ty_path, &arg.location),
+            }
+        }
+
+        let what = format!("the return type of `{}`", owner);
+        match method.return_type {
+            AstType::Callback => {
+                if self.find_callback(None, &method.origin_return_ty).is_none() {
+                    self.report(
+                        &method.location,
+                        format!(
+                            "{} is `Box<{}>`, but `{}` is not a callback trait in contract",
+                            what, &method.origin_return_ty, &method.origin_return_ty
+                        ),
+                    );
+                }
+            }
+            // constructors return `Self`.
+            AstType::Struct if method.kind == MethodKind::Constructor => (),
+            _ => self.check_ty(
+                &what,
+                &method.return_type,
+                &method.origin_return_ty,
+                &method.return_ty_path,
+                &method.location,
+            ),
+        }
+
+        // the parser only takes structs, or C-like enums resolved from them, as error types.
+        if method.error_type == AstType::Struct {
+            let what = format!("the error type of `{}`", owner);
+            self.check_ty(
+                &what,
+                &method.error_type,
+                &method.origin_error_ty,
+                &method.error_ty_path,
+                &method.location,
+            );
+        }
+    }
+
+    fn check_callback_method(&mut self, owner: &str, method: &MethodDesc) {
+        for arg in method.args.iter() {
+            let what = format!("argument `{}` of callback `{}`", &arg.name, owner);
+            match arg.ty {
+                AstType::Callback => self.report(
+                    &arg.location,
+                    format!("{} is a callback, which can't be passed to callbacks", what),
+                ),
+                AstType::Void => (),
+                _ => self.check_ty(&what, &arg.ty, &arg.origin_ty, &arg.ty_path, &arg.location),
+            }
+        }
+
+        let what = format!("the return type of callback `{}`", owner);
+        match method.return_type {
+            AstType::Callback | AstType::Map(_, _) => self.report(
+                &method.location,
+                format!(
+                    "{} is `{}`, which can't be returned from callbacks",
+                    what, &method.origin_return_ty
+                ),
+            ),
+            _ => self.check_ty(
+                &what,
+                &method.return_type,
+                &method.origin_return_ty,
+                &method.return_ty_path,
+                &method.location,
+            ),
+        }
+
+        if method.error_type != AstType::Void {
+            self.report(
+                &method.location,
+                format!("callback `{}` can't return a `Result`", owner),
+            );
+        }
+    }
+
+    fn check_field(&mut self, owner: &str, field: &ArgDesc) {
+        let what = format!("field `{}` of `{}`", &field.name, owner);
+        match field.ty {
+            AstType::Callback => self.report(
+                &field.location,
+                format!("{} is a callback, which can't be a field", what),
+            ),
+            AstType::Void => (),
+            _ => self.check_ty(
+                &what,
+                &field.ty,
+                &field.origin_ty,
+                &field.ty_path,
+                &field.location,
+            ),
+        }
+    }
+
+    ///
+    /// callbacks passed as arguments must be defined in the same contract mod.
+    ///
+    fn check_callback_arg(&mut self, mod_name: &str, what: &str, arg: &ArgDesc) {
+        if self.find_callback(Some(mod_name), &arg.origin_ty).is_none() {
+            self.report(
+                &arg.location,
+                format!(
+                    "{} is `Box<{}>`, but `{}` is not a callback trait in the same contract mod",
+                    what, &arg.origin_ty, &arg.origin_ty
+                ),
+            );
+        }
+    }
+
+    ///
+    /// check the keys of maps, and the structs and enums not defined in contracts.
+    ///
+    fn check_ty(
+        &mut self,
+        what: &str,
+        ty: &AstType,
+        origin_ty: &str,
+        ty_path: &str,
+        location: &Location,
+    ) {
+        let inner = match *ty {
+            AstType::Struct => origin_ty.to_owned(),
+            AstType::Vec(base) | AstType::Optional(base) => match base {
+                AstBaseType::Struct => origin_ty
+                    .replace("Vec<", "")
+                    .replace("Option<", "")
+                    .replace(">", ""),
+                _ => return,
+            },
+            AstType::Map(key, value) => {
+                let (_, key_ty, value_ty) = split_map_origin_ty(origin_ty);
+                match key {
+                    AstBaseType::String
+                    | AstBaseType::Byte
+                    | AstBaseType::Int
+                    | AstBaseType::Long => (),
+                    _ => self.report(
+                        location,
+                        format!(
+                            "{} has keys of `{}`, but keys of maps must be strings or integers",
+                            what, key_ty
+                        ),
+                    ),
+                }
+                match value {
+                    AstBaseType::Struct => value_ty,
+                    _ => return,
+                }
+            }
+            _ => return,
+        };
+
+        if !self.is_defined(ty_path) {
+            self.report(
+                location,
+                format!(
+                    "{} refers to `{}`, which is neither a supported type nor defined in contract",
+                    what, inner
+                ),
+            );
+        }
+    }
+
+    ///
+    /// whether the callback is returned from rust, so it is wrapped by a generated object.
+    ///
+    fn is_returned(&self, callback: &str) -> bool {
+        self.ast_result
+            .trait_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .filter_map(|desc| desc.wraps_callback.as_ref())
+            .any(|each| each == callback)
+    }
+
+    ///
+    /// whether the struct or enum the path refers to is defined in contract.
+    ///
+    fn is_defined(&self, ty_path: &str) -> bool {
+        let ast_result = self.ast_result;
+        ast_result
+            .struct_descs
+            .values()
+            .flat_map(|descs| descs.iter())
+            .any(|desc| desc.is_referred_by(ty_path))
+            || ast_result
+                .enum_descs
+                .values()
+                .flat_map(|descs| descs.iter())
+                .any(|desc| desc.is_referred_by(ty_path))
+    }
+
+    ///
+    /// find the callback named `name`, in the mod `mod_name` if it is given.
+    ///
+    fn find_callback(&self, mod_name: Option<&str>, name: &str) -> Option<&'a TraitDesc> {
+        let ast_result = self.ast_result;
+        ast_result
+            .trait_descs
+            .iter()
+            .filter(|&(each, _)| mod_name.map_or(true, |mod_name| mod_name == each))
+            .flat_map(|(_, descs)| descs.iter())
+            .find(|desc| desc.name == name && desc.is_callback)
+    }
+
+    fn report(&mut self, location: &Location, message: String) {
+        self.problems.push(Problem {
+            location: location.clone(),
+            message,
+        });
+    }
+}
+
+///
+/// format a problem like rustc, with the line of source where it is.
+///
+fn format_problem(problem: &Problem) -> String {
+    let location = &problem.location;
+    let mut result = format!("error: {}\n", &problem.message);
+    // asts flushed by older versions have no locations.
+    if location.file.is_empty() || location.line == 0 {
+        return result;
+    }
+
+    let line_no = location.line.to_string();
+    let pad = " ".repeat(line_no.len());
+    result.push_str(&format!(
+        "{}--> {}:{}:{}\n",
+        pad, &location.file, location.line, location.column
+    ));
+
+    let source = fs::read_to_string(&location.file).ok().and_then(|src| {
+        src.lines()
+            .nth(location.line - 1)
+            .map(|line| line.to_owned())
+    });
+    if let Some(source) = source {
+        result.push_str(&format!("{} |\n", pad));
+        result.push_str(&format!("{} | {}\n", line_no, source));
+        result.push_str(&format!(
+            "{} | {}^\n",
+            pad,
+            " ".repeat(location.column.saturating_sub(1))
+        ));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::AstHandler;
+    use super::*;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    ///
+    /// A crate with one contract mod and one imp mod.
+    ///
+    struct Project {
+        dir: TempDir,
+    }
+
+    impl Project {
+        fn new(name: &str, contract: &str, imp: &str) -> Project {
+            let dir = TempDir::with_files(
+                &format!("validate_{}", name),
+                &[("src/contract/demo.rs", contract), ("src/imp/demo.rs", imp)],
+            );
+            Project { dir }
+        }
+
+        fn ast_result(&self) -> AstResult {
+            AstHandler::new("demo".to_owned(), &Common::default())
+                .parse(self.dir.path())
+                .unwrap()
+        }
+
+        fn problems(&self) -> Vec<String> {
+            let ast_result = self.ast_result();
+            let mut validator = Validator {
+                ast_result: &ast_result,
+                problems: vec![],
+            };
+            validator.check_all();
+            validator
+                .problems
+                .into_iter()
+                .map(|problem| problem.message)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn accepts_supported_contracts() {
+        let project = Project::new(
+            "supported",
+            r#"
+            use std::collections::HashMap;
+            pub struct User { pub name: String, pub tags: Vec<String> }
+            pub enum Level { Low, High }
+            pub enum Event { Moved(User), Closed }
+            pub struct Error { pub code: i32 }
+            pub trait Listener {
+                fn on_event(&self, event: Event, level: Level) -> Option<i32>;
+            }
+            pub trait Users {
+                fn save(user: User, users: Vec<User>, level: Option<Level>) -> Result<bool, Error>;
+                fn index(users: HashMap<String, User>) -> HashMap<i64, String>;
+                fn listen(listener: Box<Listener>) -> Box<Listener>;
+            }
+            "#,
+            "pub struct UsersImp; impl Users for UsersImp {}",
+        );
+        assert_eq!(project.problems(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_undefined_and_unsupported_types() {
+        let project = Project::new(
+            "unsupported",
+            r#"
+            pub struct User { pub friend: Friend, pub pair: (i32, i32) }
+            pub trait Users {
+                fn find(scores: HashMap<f32, Group>) -> Vec<Group>;
+            }
+            "#,
+            "pub struct UsersImp; impl Users for UsersImp {}",
+        );
+        assert_eq!(
+            project.problems(),
+            vec![
+                "field `pair` of `User` has a type which is not supported",
+                "argument `scores` of `Users::find` has keys of `f32`, but keys of maps must be \
+                 strings or integers",
+                "argument `scores` of `Users::find` refers to `Group`, which is neither a \
+                 supported type nor defined in contract",
+                "the return type of `Users::find` refers to `Group`, which is neither a \
+                 supported type nor defined in contract",
+                "field `friend` of `User` refers to `Friend`, which is neither a supported type \
+                 nor defined in contract",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_misused_callbacks() {
+        let project = Project::new(
+            "callbacks",
+            r#"
+            use std::collections::HashMap;
+            pub struct Error { pub code: i32 }
+            pub trait Listener {
+                fn on_names(&self, other: Box<Listener>) -> HashMap<String, i32>;
+                fn on_error(&self) -> Result<i32, Error>;
+            }
+            pub trait Users {
+                fn listen(listener: Box<Missing>) -> Box<Missing>;
+            }
+            #[rsbind::export]
+            pub trait Groups {
+                fn count() -> i32;
+            }
+            "#,
+            "pub struct UsersImp; impl Users for UsersImp {}",
+        );
+        assert_eq!(
+            project.problems(),
+            vec![
+                "argument `other` of callback `Listener::on_names` is a callback, which can't be \
+                 passed to callbacks",
+                "the return type of callback `Listener::on_names` is `HashMap<String, i32>`, \
+                 which can't be returned from callbacks",
+                "callback `Listener::on_error` can't return a `Result`",
+                "argument `listener` of `Users::listen` is `Box<Missing>`, but `Missing` is not a \
+                 callback trait in the same contract mod",
+                "the return type of `Users::listen` is `Box<Missing>`, but `Missing` is not a \
+                 callback trait in contract",
+                "trait `Groups` is exported by #[rsbind::export] but not implemented",
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_callbacks_by_their_markers_instead_of_imps() {
+        let project = Project::new(
+            "markers",
+            r#"
+            #[rsbind::callback]
+            pub trait Listener {
+                fn on_event(&self, name: String);
+            }
+            #[rsbind::callback]
+            pub trait Notifier {
+                fn notify(&self, name: String);
+            }
+            pub trait Users {
+                fn notifier() -> Box<Notifier>;
+            }
+            pub trait Groups {
+                fn count() -> i32;
+            }
+            "#,
+            r#"
+            pub struct UsersImp; impl Users for UsersImp {}
+            pub struct ListenerImp; impl Listener for ListenerImp {}
+            pub struct NotifierImp; impl Notifier for NotifierImp {}
+            "#,
+        );
+        assert_eq!(
+            project.problems(),
+            vec![
+                "trait `Listener` is a callback implemented by other languages, but it is \
+                 implemented by `ListenerImp` in rust",
+                "trait `Groups` is not a callback but not implemented, mark it by \
+                 #[rsbind::callback] if it is implemented by other languages",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_wrappers_conflicting_with_contract_types() {
+        let project = Project::new(
+            "wrappers",
+            r#"
+            pub struct RustListener { pub name: String }
+            pub trait Listener {
+                fn on_event(&self, name: String);
+            }
+            pub trait Users {
+                fn listener() -> Box<Listener>;
+            }
+            "#,
+            "pub struct UsersImp; impl Users for UsersImp {}",
+        );
+        assert_eq!(
+            project.problems(),
+            vec![
+                "callback `Listener` is returned from rust, so it is wrapped by a generated \
+                 `RustListener`, which conflicts with the type of the same name in contract, \
+                 rename one of them by #[rsbind(rename = \"...\")]",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_items_not_public() {
+        let project = Project::new(
+            "public",
+            r#"
+            struct User { pub name: String }
+            pub(crate) enum Level { Low, High }
+            trait Users {
+                fn find(level: Level) -> User;
+            }
+            #[rsbind::skip]
+            struct Cache { pub size: i32 }
+            "#,
+            "pub struct UsersImp; impl Users for UsersImp {}",
+        );
+        let ast_result = project.ast_result();
+        assert!(ast_result.struct_descs["demo"]
+            .iter()
+            .all(|desc| desc.name != "Cache"));
+        assert_eq!(
+            project.problems(),
+            vec![
+                "trait `Users` is not public, make it `pub` or skip it by #[rsbind::skip]",
+                "struct `User` is not public, make it `pub` or skip it by #[rsbind::skip]",
+                "enum `Level` is not public, make it `pub` or skip it by #[rsbind::skip]",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_problems_with_locations() {
+        let project = Project::new(
+            "locations",
+            "pub struct User {\n    pub friend: Friend,\n}\n",
+            "",
+        );
+        let ast_result = project.ast_result();
+        assert!(validate(&ast_result)
+            .unwrap_err()
+            .to_string()
+            .contains("aborting due to 1 unsupported constructs in contracts"));
+
+        let location = ast_result.struct_descs["demo"][0].fields[0]
+            .location
+            .clone();
+        let problem = Problem {
+            location: location.clone(),
+            message: "unsupported".to_owned(),
+        };
+        assert_eq!(
+            format_problem(&problem),
+            format!(
+                "error: unsupported\n --> {}:2:9\n  |\n2 |     pub friend: Friend,\n  |         ^\n",
+                &location.file
+            )
+        );
+    }
+}
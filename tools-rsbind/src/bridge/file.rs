@@ -3,9 +3,8 @@ use ast::imp::desc::*;
 use ast::types::*;
 use errors::ErrorKind::*;
 use errors::*;
-use proc_macro2::{Ident, Span, TokenStream};
-use std::fs::File;
-use std::io::Write;
+use incremental::write_if_changed;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
 use std::path::PathBuf;
 
 pub(crate) const TMP_ARG_PREFIX: &str = "r";
@@ -26,10 +25,25 @@ pub(crate) struct BridgeFileGen<'a, T: FileGenStrategy> {
     pub out_dir: &'a PathBuf,
     pub trait_descs: &'a Vec<TraitDesc>,
     pub struct_descs: &'a Vec<StructDesc>,
+    pub enum_descs: &'a Vec<EnumDesc>,
     pub imp_desc: &'a Vec<ImpDesc>,
+    pub foreign_types: &'a Vec<ForeignType>,
     pub strategy: T,
 }
 
+///
+/// A contract type defined in another mod, imported with its json mirror into the bridge file.
+///
+pub(crate) struct ForeignType {
+    pub name: String,
+    /// the rust name of the type, which differs from `name` if it is renamed by `#[rsbind]`.
+    pub origin: String,
+    pub mirror: String,
+    /// path of the contract mod, like `net::http`.
+    pub contract_mod: String,
+    pub bridge_mod: String,
+}
+
 ///
 /// Strategy for generating core files in bridge mod.
 ///
@@ -39,6 +53,7 @@ pub(crate) trait FileGenStrategy {
     fn quote_common_part(&self, trait_desc: &Vec<TraitDesc>) -> Result<TokenStream>;
     fn quote_callback_structures(&self, callback: &TraitDesc) -> Result<TokenStream>;
     fn quote_for_structures(&self, struct_desc: &StructDesc) -> Result<TokenStream>;
+    fn quote_for_enums(&self, enum_desc: &EnumDesc) -> Result<TokenStream>;
     fn quote_method_sig(
         &self,
         trait_desc: &TraitDesc,
@@ -50,15 +65,34 @@ pub(crate) trait FileGenStrategy {
     fn quote_arg_convert(
         &self,
         trait_desc: &TraitDesc,
+        method: &MethodDesc,
         args: &ArgDesc,
         callbacks: &Vec<&TraitDesc>,
     ) -> Result<TokenStream>;
-    fn quote_return_convert(
+    ///
+    /// quote how the value returned by the implementation, named `ret_name`, is passed to
+    /// the caller.
+    ///
+    fn quote_return_convert(&self, method: &MethodDesc, ret_name: &str) -> Result<TokenStream>;
+    ///
+    /// quote how the `err` of a method returning `Result<T, E>` is passed to the caller,
+    /// it must return from the bridge function.
+    ///
+    fn quote_error_return(&self, method: &MethodDesc) -> Result<TokenStream>;
+    ///
+    /// quote how a panic caught in a bridge function is reported to the caller, `err` is the
+    /// payload of the panic, and the quoted block is the value returned to the caller.
+    ///
+    fn quote_panic_return(
         &self,
-        return_ty: &AstType,
-        ret_name: &str,
-        origin_ty: &str,
+        trait_desc: &TraitDesc,
+        method: &MethodDesc,
     ) -> Result<TokenStream>;
+    ///
+    /// quote the signature of the function freeing objects of a trait with constructors,
+    /// the object is passed as `handle: i64`.
+    ///
+    fn quote_free_object_sig(&self, trait_desc: &TraitDesc) -> Result<TokenStream>;
     fn ty_to_tokens(&self, ast_type: &AstType, direction: TypeDirection) -> Result<TokenStream>;
 }
 
@@ -68,7 +102,7 @@ pub(crate) trait CallbackGenStrategy {
         arg: &ArgDesc,
         trait_desc: &TraitDesc,
         callbacks: &Vec<&TraitDesc>,
-    ) -> TokenStream;
+    ) -> Result<TokenStream>;
 }
 
 impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
@@ -76,22 +110,19 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
     /// generate sdk.rs files
     ///
     pub(crate) fn gen_sdk_file(&self, file_name: &str, mod_names: &Vec<String>) -> Result<()> {
-        let result = self.strategy.gen_sdk_file(mod_names).unwrap();
+        let result = self.strategy.gen_sdk_file(mod_names)?;
 
         let out_file_path = self.out_dir.join(file_name);
-        let mut f = File::create(&out_file_path).unwrap();
-        f.write_all(&result.to_string().into_bytes()).unwrap();
-
-        Ok(())
+        write_tokens(&out_file_path, &result)
     }
 
     ///
     /// generate one bridge file for one contract mod.
     ///
     pub(crate) fn gen_one_bridge_file(&self, file_name: &str) -> Result<()> {
-        let use_part = self.quote_use_part().unwrap();
-        let common_part = self.strategy.quote_common_part(self.trait_descs).unwrap();
-        let bridge_codes = self.gen_for_one_mod().unwrap();
+        let use_part = self.quote_use_part()?;
+        let common_part = self.strategy.quote_common_part(self.trait_descs)?;
+        let bridge_codes = self.gen_for_one_mod()?;
 
         let mut merge_tokens = quote! {
             #use_part
@@ -99,22 +130,18 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
         };
 
         for bridge_code in bridge_codes {
-            match bridge_code.result {
-                Ok(code) => {
-                    merge_tokens = quote! {
-                        #merge_tokens
-                        #code
-                    };
-                }
-                _ => (),
-            }
+            let name = bridge_code.name;
+            let code = bridge_code
+                .result
+                .chain_err(|| GenerateError(format!("can't generate bridge codes for {}", name)))?;
+            merge_tokens = quote! {
+                #merge_tokens
+                #code
+            };
         }
 
         let out_file_path = self.out_dir.join(file_name);
-        let mut f = File::create(&out_file_path).unwrap();
-        f.write_all(&merge_tokens.to_string().into_bytes()).unwrap();
-
-        Ok(())
+        write_tokens(&out_file_path, &merge_tokens)
     }
 
     ///
@@ -126,53 +153,80 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
         let callbacks = self
             .trait_descs
             .iter()
-            .filter(|desc| {
-                for imp in self.imp_desc.iter() {
-                    if imp.contract == desc.name {
-                        return false;
-                    }
-                }
-                return true;
-            })
+            .filter(|desc| desc.is_callback)
             .collect::<Vec<&TraitDesc>>();
 
-        println!("callbacks is {:?}", &callbacks);
+        debug!("callbacks is {:?}", &callbacks);
 
         for struct_desc in self.struct_descs.iter() {
-            let tokens = self.strategy.quote_for_structures(&struct_desc);
+            let mut tokens = self.strategy.quote_for_structures(&struct_desc);
+            if struct_desc.binary {
+                let binary = quote_struct_binary(struct_desc);
+                tokens = tokens.map(|tokens| quote!(#tokens #binary));
+            }
             results.push(GenResult {
                 name: struct_desc.name.to_owned(),
                 result: tokens,
             });
         }
 
+        for enum_desc in self.enum_descs.iter() {
+            let mut tokens = self.strategy.quote_for_enums(&enum_desc);
+            if enum_desc.binary {
+                let binary = quote_enum_binary(enum_desc);
+                tokens = tokens.map(|tokens| quote!(#tokens #binary));
+            }
+            results.push(GenResult {
+                name: enum_desc.name.to_owned(),
+                result: tokens,
+            });
+        }
+
         for desc in self.trait_descs.iter() {
+            if let Some(ref callback) = desc.wraps_callback {
+                results.push(GenResult {
+                    name: desc.name.clone(),
+                    result: self.generate_for_returned_callback(
+                        desc,
+                        callback,
+                        &callbacks,
+                        self.struct_descs,
+                    ),
+                });
+                continue;
+            }
+
+            if desc.is_callback {
+                results.push(GenResult {
+                    name: desc.name.clone(),
+                    result: self.strategy.quote_callback_structures(&desc),
+                });
+                continue;
+            }
+
             let imps = self
                 .imp_desc
                 .iter()
                 .filter(|info| info.contract == desc.name)
                 .collect::<Vec<&ImpDesc>>();
 
-            println!("desc => {:?}", desc);
-            println!("imps => {:?}", imps);
-            println!("all imps => {:?}", &self.imp_desc);
+            debug!("desc => {:?}", desc);
+            debug!("imps => {:?}", imps);
+            debug!("all imps => {:?}", &self.imp_desc);
 
             if imps.len() > 1 {
-                println!("You have more than one impl for trait {}", desc.name);
+                debug!("You have more than one impl for trait {}", desc.name);
                 return Err(GenerateError(format!(
                     "You have more than one impl for trait {}",
                     desc.name
                 ))
                 .into());
-            } else if imps.len() <= 0 {
-                println!(
-                    "You haven't impl the trait {}, so I guess it is a callback",
+            } else if imps.is_empty() {
+                return Err(GenerateError(format!(
+                    "trait {} is not a callback but not implemented",
                     desc.name
-                );
-                results.push(GenResult {
-                    name: desc.name.clone(),
-                    result: self.strategy.quote_callback_structures(&desc),
-                });
+                ))
+                .into());
             } else {
                 results.push(GenResult {
                     name: desc.name.clone(),
@@ -196,13 +250,18 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
         callbacks: &Vec<&TraitDesc>,
         structs: &Vec<StructDesc>,
     ) -> Result<TokenStream> {
-        println!("begin generate on trait => {}", &trait_desc.name);
+        debug!("begin generate on trait => {}", &trait_desc.name);
         let mut merge: TokenStream = TokenStream::new();
 
         for method in trait_desc.methods.iter() {
             let one_method = self
                 .quote_one_method(trait_desc, imp, method, callbacks, structs)
-                .unwrap();
+                .chain_err(|| {
+                    GenerateError(format!(
+                        "can't generate method {}::{}",
+                        &trait_desc.name, &method.name
+                    ))
+                })?;
 
             merge = quote! {
                 #merge
@@ -210,16 +269,130 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
             };
         }
 
+        if trait_desc.is_object {
+            let free_object = self.quote_free_object(trait_desc, imp)?;
+            merge = quote! {
+                #merge
+                #free_object
+            };
+        }
+
         Ok(merge)
     }
 
+    ///
+    /// callbacks returned from rust are boxed trait objects, so the wrapper object is generated
+    /// like a normal object whose implementation is the boxed trait object.
+    ///
+    fn generate_for_returned_callback(
+        &self,
+        trait_desc: &TraitDesc,
+        callback: &str,
+        callbacks: &Vec<&TraitDesc>,
+        structs: &Vec<StructDesc>,
+    ) -> Result<TokenStream> {
+        let imp = ImpDesc {
+            name: trait_desc.name.clone(),
+            contract: trait_desc.name.clone(),
+            mod_name: trait_desc.mod_name.clone(),
+            mod_path: trait_desc.mod_path.clone(),
+        };
+        let wrapper_ident = Ident::new(&trait_desc.name, Span::call_site());
+        let callback_ident = Ident::new(callback, Span::call_site());
+        let methods = self.generate_for_one_trait(trait_desc, &imp, callbacks, structs)?;
+
+        Ok(quote! {
+            pub type #wrapper_ident = Box<#callback_ident>;
+            #methods
+        })
+    }
+
+    ///
+    /// quote the function dropping an object created by the constructors.
+    ///
+    fn quote_free_object(&self, trait_desc: &TraitDesc, imp: &ImpDesc) -> Result<TokenStream> {
+        let sig_define = self.strategy.quote_free_object_sig(trait_desc)?;
+        let imp_ident = Ident::new(&imp.name, Span::call_site());
+        let panic_prefix = format!("rsbind panic in {}.free_object", &trait_desc.name);
+
+        Ok(quote! {
+            #sig_define {
+                let catch_result = catch_unwind(AssertUnwindSafe(|| {
+                    if handle != 0 {
+                        unsafe {
+                            drop(Box::from_raw(handle as *mut #imp_ident));
+                        }
+                    }
+                }));
+
+                if let Err(err) = catch_result {
+                    println!("{}: {}", #panic_prefix, panic_message(&err));
+                }
+            }
+        })
+    }
+
     ///
     /// quote use part
     ///
     fn quote_use_part(&self) -> Result<TokenStream> {
-        let mut merge = self.strategy.quote_common_use_part().unwrap();
+        let mut merge = self.strategy.quote_common_use_part()?;
+
+        for foreign_type in self.foreign_types.iter() {
+            let contract_mod = quote_mod_path("contract", &foreign_type.contract_mod);
+            let bridge_mod = Ident::new(&foreign_type.bridge_mod, Span::call_site());
+            let origin = Ident::new(&foreign_type.origin, Span::call_site());
+            let name = Ident::new(&foreign_type.name, Span::call_site());
+            let mirror = Ident::new(&foreign_type.mirror, Span::call_site());
+            merge = quote! {
+                use #contract_mod::#origin as #name;
+                use super::#bridge_mod::#mirror;
+                #merge
+            };
+        }
+
+        // renamed items are imported by their foreign names as well.
+        let renames = self
+            .trait_descs
+            .iter()
+            .map(|desc| (&desc.mod_path, &desc.name, &desc.origin_name))
+            .chain(
+                self.struct_descs
+                    .iter()
+                    .map(|desc| (&desc.mod_path, &desc.name, &desc.origin_name)),
+            )
+            .chain(
+                self.enum_descs
+                    .iter()
+                    .map(|desc| (&desc.mod_path, &desc.name, &desc.origin_name)),
+            );
+        for (mod_path, name, origin_name) in renames {
+            if let Some(ref origin_name) = *origin_name {
+                let contract_mod = quote_mod_path("contract", mod_path);
+                let origin = Ident::new(origin_name, Span::call_site());
+                let name = Ident::new(name, Span::call_site());
+                merge = quote! {
+                    use #contract_mod::#origin as #name;
+                    #merge
+                };
+            }
+        }
 
         for trait_desc in self.trait_descs.iter() {
+            if trait_desc.wraps_callback.is_some() {
+                let contract_mod = quote_mod_path("contract", &trait_desc.mod_path);
+                merge = quote! {
+                    use #contract_mod::*;
+                    #merge
+                };
+                continue;
+            }
+
+            // callbacks are implemented by other languages.
+            if trait_desc.is_callback {
+                continue;
+            }
+
             let imps = self
                 .imp_desc
                 .iter()
@@ -227,21 +400,20 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
                 .collect::<Vec<&ImpDesc>>();
 
             if imps.len() > 1 {
-                println!("You have more than one impl for trait {}", trait_desc.name);
+                debug!("You have more than one impl for trait {}", trait_desc.name);
                 return Err(GenerateError(format!(
                     "You have more than one impl for trait {}",
                     trait_desc.name
                 ))
                 .into());
-            } else if imps.len() <= 0 {
-                println!(
-                    "You haven't impl the trait {}, I guess it is a callback",
+            } else if imps.is_empty() {
+                return Err(GenerateError(format!(
+                    "trait {} is not a callback but not implemented",
                     trait_desc.name
-                );
+                ))
+                .into());
             } else {
-                let use_part = self
-                    .quote_one_use_part(&trait_desc.mod_name, &imps[0].mod_name)
-                    .unwrap();
+                let use_part = self.quote_one_use_part(&trait_desc.mod_path, &imps[0].mod_path)?;
                 merge = quote! {
                    #use_part
                    #merge
@@ -252,12 +424,12 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
         Ok(merge)
     }
 
-    fn quote_one_use_part(&self, trait_mod_name: &str, imp_mod_name: &str) -> Result<TokenStream> {
-        let trait_ident = Ident::new(trait_mod_name, Span::call_site());
-        let mod_ident = Ident::new(imp_mod_name, Span::call_site());
+    fn quote_one_use_part(&self, trait_mod_path: &str, imp_mod_path: &str) -> Result<TokenStream> {
+        let contract_mod = quote_mod_path("contract", trait_mod_path);
+        let imp_mod = quote_mod_path("imp", imp_mod_path);
         let use_part = quote! {
-            use ::imp::#mod_ident::*;
-            use ::contract::#trait_ident::*;
+            use #imp_mod::*;
+            use #contract_mod::*;
         };
 
         Ok(use_part)
@@ -276,15 +448,14 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
     ) -> Result<TokenStream> {
         let sig_define = self
             .strategy
-            .quote_method_sig(trait_desc, imp, method, callbacks, structs)
-            .unwrap();
+            .quote_method_sig(trait_desc, imp, method, callbacks, structs)?;
 
         let mut arg_convert = TokenStream::new();
         for arg in method.args.iter() {
             let arg_tokens = self
                 .strategy
-                .quote_arg_convert(trait_desc, arg, callbacks)
-                .unwrap();
+                .quote_arg_convert(trait_desc, method, arg, callbacks)
+                .chain_err(|| GenerateError(format!("can't convert the argument {}", &arg.name)))?;
             arg_convert = quote! {
                 #arg_convert
                 #arg_tokens
@@ -293,18 +464,25 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
 
         let call_imp = self.quote_imp_call(&imp.name, method)?;
 
-        let return_handle = self.strategy.quote_return_convert(
-            &method.return_type,
-            "ret_value",
-            &method.origin_return_ty,
-        )?;
+        let return_handle = self.strategy.quote_return_convert(method, "ret_value")?;
 
-        // combine all the parts
+        let panic_return = self.strategy.quote_panic_return(trait_desc, method)?;
+
+        // combine all the parts, panics mustn't unwind across the ffi boundary.
         let result = quote! {
             #sig_define {
-                #arg_convert
-                #call_imp
-                #return_handle
+                let catch_result = catch_unwind(AssertUnwindSafe(|| {
+                    #arg_convert
+                    #call_imp
+                    #return_handle
+                }));
+
+                match catch_result {
+                    Ok(ret) => ret,
+                    Err(err) => {
+                        #panic_return
+                    }
+                }
             }
         };
 
@@ -313,7 +491,7 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
 
     fn quote_imp_call(&self, impl_name: &str, method: &MethodDesc) -> Result<TokenStream> {
         let ret_name_str = "ret_value";
-        let imp_fun_name = Ident::new(&method.name, Span::call_site());
+        let imp_fun_name = Ident::new(method.rust_name(), Span::call_site());
         let ret_name_ident = Ident::new(ret_name_str, Span::call_site());
 
         let tmp_arg_names = method
@@ -333,9 +511,37 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
         };
 
         let imp_ident = Ident::new(impl_name, Span::call_site());
+        let mut call = match method.kind {
+            MethodKind::Static | MethodKind::Constructor => {
+                quote!(#imp_ident::#imp_fun_name(#rust_args_repeat))
+            }
+            MethodKind::Ref => quote! {
+                unsafe { &*(handle as *const #imp_ident) }.#imp_fun_name(#rust_args_repeat)
+            },
+            MethodKind::RefMut => quote! {
+                unsafe { &mut *(handle as *mut #imp_ident) }.#imp_fun_name(#rust_args_repeat)
+            },
+        };
+        if method.error_type != AstType::Void {
+            let error_return = self.strategy.quote_error_return(method)?;
+            call = quote! {
+                match #call {
+                    Ok(ret_value) => ret_value,
+                    Err(err) => {
+                        #error_return
+                    }
+                }
+            };
+        }
+
+        // the object is boxed, and its address is handed out as the handle.
+        if method.kind == MethodKind::Constructor {
+            call = quote!(Box::into_raw(Box::new(#call)) as i64);
+        }
+
         let imp_call = match method.return_type {
             AstType::Void => quote! {
-                #imp_ident::#imp_fun_name(#rust_args_repeat);
+                #call;
             },
             AstType::Vec(base) => {
                 let is_vec_i8 = match base {
@@ -345,19 +551,676 @@ impl<'a, T: FileGenStrategy + 'a> BridgeFileGen<'a, T> {
 
                 if is_vec_i8 {
                     quote! {
-                        let mut #ret_name_ident = #imp_ident::#imp_fun_name(#rust_args_repeat);
+                        let mut #ret_name_ident = #call;
                     }
                 } else {
                     quote! {
-                        let #ret_name_ident = #imp_ident::#imp_fun_name(#rust_args_repeat);
+                        let #ret_name_ident = #call;
                     }
                 }
             }
             _ => quote! {
-                let #ret_name_ident = #imp_ident::#imp_fun_name(#rust_args_repeat);
+                let #ret_name_ident = #call;
             },
         };
 
         Ok(imp_call)
     }
 }
+
+///
+/// Quote the absolute path of a mod in the crate, like `::contract::net::http` for the
+/// `mod_path` `net::http` under `root`.
+///
+pub(crate) fn quote_mod_path(root: &str, mod_path: &str) -> TokenStream {
+    let root = Ident::new(root, Span::call_site());
+    let segments = mod_path
+        .split("::")
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| Ident::new(segment, Span::call_site()))
+        .collect::<Vec<Ident>>();
+    quote!(::#root #(::#segments)*)
+}
+
+///
+/// The type of a field in a json mirror(Struct_xxx or Enum_xxx) of contract types.
+///
+/// Nested structs and data-carrying enums are mirrored by `Struct_xxx`, C-like enums by `Enum_xxx`.
+///
+pub(crate) fn quote_mirror_field_ty(field: &ArgDesc) -> TokenStream {
+    match field.ty {
+        AstType::Enum => {
+            let mirror_ident = Ident::new(&format!("Enum_{}", &field.origin_ty), Span::call_site());
+            quote!(#mirror_ident)
+        }
+        AstType::Struct => {
+            let mirror_ident =
+                Ident::new(&format!("Struct_{}", &field.origin_ty), Span::call_site());
+            quote!(#mirror_ident)
+        }
+        AstType::Vec(base) => {
+            let inner = field.origin_ty.replace("Vec<", "").replace(">", "");
+            let inner_ident = quote_mirror_base_ident(base, &inner);
+            quote!(Vec<#inner_ident>)
+        }
+        AstType::Optional(base) => {
+            let inner = field.origin_ty.replace("Option<", "").replace(">", "");
+            let inner_ident = quote_mirror_base_ident(base, &inner);
+            quote!(Option<#inner_ident>)
+        }
+        AstType::Map(_, value) => quote_map_ty(&field.origin_ty, Some(value)),
+        _ => {
+            let ident = Ident::new(&field.origin_ty, Span::call_site());
+            quote!(#ident)
+        }
+    }
+}
+
+fn quote_mirror_base_ident(base: AstBaseType, inner: &str) -> Ident {
+    match base {
+        AstBaseType::Enum => Ident::new(&format!("Enum_{}", inner), Span::call_site()),
+        AstBaseType::Struct => Ident::new(&format!("Struct_{}", inner), Span::call_site()),
+        _ => Ident::new(inner, Span::call_site()),
+    }
+}
+
+///
+/// Convert a field value of contract types to the type of its mirror, or from the mirror back.
+///
+pub(crate) fn quote_mirror_field_value(field: &ArgDesc, value: TokenStream) -> TokenStream {
+    match field.ty {
+        AstType::Optional(_) => quote!(#value.map(|each| each.into())),
+        AstType::Vec(_) => quote!(#value.into_iter().map(|each| each.into()).collect()),
+        AstType::Map(_, _) => quote! {
+            #value.into_iter().map(|(key, value)| (key, value.into())).collect()
+        },
+        _ => quote!(#value.into()),
+    }
+}
+
+///
+/// The rust type of a map, like `::std::collections::HashMap<String, StructSimple>`.
+///
+/// With the value type given, values are replaced by their json mirrors.
+///
+pub(crate) fn quote_map_ty(origin_ty: &str, mirror_value: Option<AstBaseType>) -> TokenStream {
+    let (map_name, key, value) = split_map_origin_ty(origin_ty);
+    let map_ident = Ident::new(&map_name, Span::call_site());
+    let key_ident = Ident::new(&key, Span::call_site());
+    let value_ident = match mirror_value {
+        Some(base) => quote_mirror_base_ident(base, &value),
+        None => Ident::new(&value, Span::call_site()),
+    };
+    quote!(::std::collections::#map_ident<#key_ident, #value_ident>)
+}
+
+///
+/// Maps are transferred by json objects, so the keys are limited to strings and integers.
+///
+fn check_map_key(key: &AstBaseType, origin_ty: &str) -> Result<()> {
+    match *key {
+        AstBaseType::String | AstBaseType::Byte | AstBaseType::Int | AstBaseType::Long => Ok(()),
+        _ => Err(GenerateError(format!(
+            "keys of map must be strings or integers, found {}",
+            origin_ty
+        ))
+        .into()),
+    }
+}
+
+///
+/// Parse a map from the json transferred through ffi.
+///
+pub(crate) fn quote_map_from_json(
+    key: &AstBaseType,
+    value: &AstBaseType,
+    origin_ty: &str,
+    json: TokenStream,
+) -> Result<TokenStream> {
+    check_map_key(key, origin_ty)?;
+    let map_ty = quote_map_ty(origin_ty, None);
+    let mirror_ty = quote_map_ty(origin_ty, Some(*value));
+    Ok(quote! {
+        serde_json::from_str::<#mirror_ty>(&#json)
+            .unwrap()
+            .into_iter()
+            .map(|(key, value)| (key, value.into()))
+            .collect::<#map_ty>()
+    })
+}
+
+///
+/// Convert a map to the json transferred through ffi.
+///
+pub(crate) fn quote_map_to_json(
+    key: &AstBaseType,
+    value: &AstBaseType,
+    origin_ty: &str,
+    map: TokenStream,
+) -> Result<TokenStream> {
+    check_map_key(key, origin_ty)?;
+    let mirror_ty = quote_map_ty(origin_ty, Some(*value));
+    Ok(quote! {
+        serde_json::to_string(
+            &#map
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect::<#mirror_ty>(),
+        )
+        .unwrap()
+    })
+}
+
+///
+/// Parse the inner value of an `Option<T>` from the string transferred through ffi.
+///
+/// Strings are transferred as they are, C-like enums by their discriminants, and others
+/// by json, which is also the literal format of numbers and booleans.
+///
+pub(crate) fn quote_optional_from_str(
+    base: &AstBaseType,
+    origin_ty: &str,
+    str_value: TokenStream,
+) -> Result<TokenStream> {
+    let inner = origin_ty.replace("Option<", "").replace(">", "");
+    let inner_ident = Ident::new(&inner, Span::call_site());
+    Ok(match *base {
+        AstBaseType::String => quote!(#str_value),
+        AstBaseType::Enum => {
+            let mirror_ident = Ident::new(&format!("Enum_{}", &inner), Span::call_site());
+            quote! {
+                #mirror_ident::from_i32(#str_value.parse::<i32>().unwrap()).into()
+            }
+        }
+        AstBaseType::Byte
+        | AstBaseType::Int
+        | AstBaseType::Long
+        | AstBaseType::Float
+        | AstBaseType::Double
+        | AstBaseType::Boolean => quote! {
+            serde_json::from_str::<#inner_ident>(&#str_value).unwrap()
+        },
+        AstBaseType::Struct => {
+            let struct_ident = Ident::new(&format!("Struct_{}", &inner), Span::call_site());
+            quote! {
+                serde_json::from_str::<#struct_ident>(&#str_value).unwrap().into()
+            }
+        }
+        _ => {
+            return Err(
+                GenerateError(format!("find unsupported type in Option, {}", origin_ty)).into(),
+            );
+        }
+    })
+}
+
+///
+/// Parse a struct, or a Vec of structs, from the json transferred through ffi.
+///
+/// The json is parsed to the mirrors(Struct_xxx) first, and then converted to the contract
+/// structs, which are not deserializable themselves.
+///
+pub(crate) fn quote_struct_from_json(
+    ty: &AstType,
+    origin_ty: &str,
+    json_value: TokenStream,
+) -> Result<TokenStream> {
+    Ok(match *ty {
+        AstType::Struct => {
+            let origin_ident = Ident::new(origin_ty, Span::call_site());
+            let mirror_ident = Ident::new(&format!("Struct_{}", origin_ty), Span::call_site());
+            quote! {
+                #origin_ident::from(serde_json::from_str::<#mirror_ident>(&#json_value).unwrap())
+            }
+        }
+        AstType::Vec(AstBaseType::Struct) => {
+            let inner = origin_ty.replace("Vec<", "").replace(">", "");
+            let inner_ident = Ident::new(&inner, Span::call_site());
+            let mirror_ident = Ident::new(&format!("Struct_{}", &inner), Span::call_site());
+            quote! {
+                serde_json::from_str::<Vec<#mirror_ident>>(&#json_value)
+                    .unwrap()
+                    .into_iter()
+                    .map(|each| #inner_ident::from(each))
+                    .collect::<Vec<#inner_ident>>()
+            }
+        }
+        _ => {
+            return Err(GenerateError(format!(
+                "find unsupported struct type in arg, {}",
+                origin_ty
+            ))
+            .into());
+        }
+    })
+}
+
+///
+/// Parse the value returned from a callback, which is transferred as a string.
+///
+/// Strings are transferred as they are, structs and vectors by json. `Option<T>` is parsed
+/// by `quote_optional_from_str`, as None is transferred as null.
+///
+pub(crate) fn quote_callback_return_from_str(
+    ty: &AstType,
+    origin_ty: &str,
+    str_value: TokenStream,
+) -> Result<TokenStream> {
+    Ok(match *ty {
+        AstType::String => quote!(#str_value),
+        AstType::Struct => {
+            let struct_ident = Ident::new(&format!("Struct_{}", origin_ty), Span::call_site());
+            quote! {
+                serde_json::from_str::<#struct_ident>(&#str_value).unwrap().into()
+            }
+        }
+        AstType::Vec(ref base) => {
+            let inner = origin_ty.replace("Vec<", "").replace(">", "");
+            let mirror_ident = match *base {
+                AstBaseType::Struct => Ident::new(&format!("Struct_{}", &inner), Span::call_site()),
+                AstBaseType::Enum => Ident::new(&format!("Enum_{}", &inner), Span::call_site()),
+                _ => Ident::new(&inner, Span::call_site()),
+            };
+            quote! {
+                serde_json::from_str::<Vec<#mirror_ident>>(&#str_value)
+                    .unwrap()
+                    .into_iter()
+                    .map(|each| each.into())
+                    .collect()
+            }
+        }
+        _ => {
+            return Err(GenerateError(format!(
+                "find unsupported return type in callback, {}",
+                origin_ty
+            ))
+            .into());
+        }
+    })
+}
+
+///
+/// The rust type of a callback argument or return value, like `Vec<StructSimple>`.
+///
+pub(crate) fn quote_callback_ty(ty: &AstType, origin_ty: &str) -> TokenStream {
+    match *ty {
+        AstType::Void => quote!(()),
+        AstType::Vec(_) => {
+            let inner = origin_ty.replace("Vec<", "").replace(">", "");
+            let inner_ident = Ident::new(&inner, Span::call_site());
+            quote!(Vec<#inner_ident>)
+        }
+        AstType::Optional(_) => {
+            let inner = origin_ty.replace("Option<", "").replace(">", "");
+            let inner_ident = Ident::new(&inner, Span::call_site());
+            quote!(Option<#inner_ident>)
+        }
+        AstType::Map(_, _) => quote_map_ty(origin_ty, None),
+        _ => {
+            let ident = Ident::new(origin_ty, Span::call_site());
+            quote!(#ident)
+        }
+    }
+}
+
+///
+/// Convert the inner value of an `Option<T>` to the string transferred through ffi.
+///
+pub(crate) fn quote_optional_to_str(
+    base: &AstBaseType,
+    origin_ty: &str,
+    value: TokenStream,
+) -> Result<TokenStream> {
+    let inner = origin_ty.replace("Option<", "").replace(">", "");
+    Ok(match *base {
+        AstBaseType::String => quote!(#value),
+        AstBaseType::Enum => quote!((#value as i32).to_string()),
+        AstBaseType::Struct => {
+            let struct_ident = Ident::new(&format!("Struct_{}", &inner), Span::call_site());
+            quote! {
+                serde_json::to_string(&#struct_ident::from(#value)).unwrap()
+            }
+        }
+        AstBaseType::Byte
+        | AstBaseType::Int
+        | AstBaseType::Long
+        | AstBaseType::Float
+        | AstBaseType::Double
+        | AstBaseType::Boolean => quote! {
+            serde_json::to_string(&#value).unwrap()
+        },
+        _ => {
+            return Err(
+                GenerateError(format!("find unsupported type in Option, {}", origin_ty)).into(),
+            );
+        }
+    })
+}
+
+///
+/// Convert the error of a `Result<T, E>` to the string transferred through ffi.
+///
+/// C-like enums are transferred by their discriminants, structs and data-carrying enums by json.
+///
+pub(crate) fn quote_error_to_str(method: &MethodDesc, value: TokenStream) -> Result<TokenStream> {
+    Ok(match method.error_type {
+        AstType::Enum => quote!((#value as i32).to_string()),
+        AstType::Struct => {
+            let struct_ident = Ident::new(
+                &format!("Struct_{}", &method.origin_error_ty),
+                Span::call_site(),
+            );
+            quote! {
+                serde_json::to_string(&#struct_ident::from(#value)).unwrap()
+            }
+        }
+        _ => {
+            return Err(GenerateError(format!(
+                "find unsupported error type in Result, {}",
+                &method.origin_error_ty
+            ))
+            .into());
+        }
+    })
+}
+
+///
+/// Quote the json mirror of a contract enum.
+///
+/// C-like enums are transferred by discriminant, and their mirror is `Enum_xxx`, which is
+/// used when they are inside a json, like fields of a struct or items of a Vec.
+/// Data-carrying enums are transferred as json like structs, so their mirror is `Struct_xxx`,
+/// tuple variants are mirrored as struct variants with fields named `_0`, `_1`...
+///
+pub(crate) fn quote_enum_mirror(enum_desc: &EnumDesc) -> Result<TokenStream> {
+    let origin_ident = Ident::new(&enum_desc.name, Span::call_site());
+    let variant_idents = enum_desc
+        .variants
+        .iter()
+        .map(|variant| Ident::new(&variant.name, Span::call_site()))
+        .collect::<Vec<Ident>>();
+
+    if enum_desc.is_c_like {
+        let mirror_ident = Ident::new(&format!("Enum_{}", &enum_desc.name), Span::call_site());
+        let values = enum_desc
+            .variants
+            .iter()
+            .map(|variant| Literal::i32_unsuffixed(variant.value))
+            .collect::<Vec<Literal>>();
+        let panic_msg = format!("unknown value {{}} for enum {}", &enum_desc.name);
+
+        let mirror_idents = vec![mirror_ident.clone(); variant_idents.len()];
+        let origin_idents = vec![origin_ident.clone(); variant_idents.len()];
+        let variant_idents2 = variant_idents.clone();
+        let variant_idents3 = variant_idents.clone();
+        let variant_idents4 = variant_idents.clone();
+        let variant_idents5 = variant_idents.clone();
+        let variant_idents6 = variant_idents.clone();
+        let mirror_idents2 = mirror_idents.clone();
+        let mirror_idents3 = mirror_idents.clone();
+        let origin_idents2 = origin_idents.clone();
+        return Ok(quote! {
+            #[derive(Serialize, Deserialize, Clone, Copy)]
+            pub enum #mirror_ident {
+                #(#variant_idents),*
+            }
+
+            impl #mirror_ident {
+                pub fn from_i32(value: i32) -> #mirror_ident {
+                    match value {
+                        #(#values => #mirror_idents::#variant_idents2,)*
+                        _ => panic!(#panic_msg, value),
+                    }
+                }
+            }
+
+            impl From<#origin_ident> for #mirror_ident {
+                fn from(origin: #origin_ident) -> Self {
+                    match origin {
+                        #(#origin_idents::#variant_idents3 => #mirror_idents2::#variant_idents4,)*
+                    }
+                }
+            }
+
+            impl From<#mirror_ident> for #origin_ident {
+                fn from(mirror: #mirror_ident) -> Self {
+                    match mirror {
+                        #(#mirror_idents3::#variant_idents5 => #origin_idents2::#variant_idents6,)*
+                    }
+                }
+            }
+        });
+    }
+
+    let mirror_ident = Ident::new(&format!("Struct_{}", &enum_desc.name), Span::call_site());
+    let mut mirror_variants = TokenStream::new();
+    let mut from_arms = TokenStream::new();
+    let mut into_arms = TokenStream::new();
+    for variant in enum_desc.variants.iter() {
+        let variant_ident = Ident::new(&variant.name, Span::call_site());
+        let field_names = variant
+            .fields
+            .iter()
+            .map(|field| Ident::new(&field.name, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let field_names2 = field_names.clone();
+        let field_names3 = field_names.clone();
+        let field_names4 = field_names.clone();
+        let field_names5 = field_names.clone();
+        let field_tys = variant
+            .fields
+            .iter()
+            .map(|field| quote_mirror_field_ty(field))
+            .collect::<Vec<TokenStream>>();
+        let is_tuple = variant
+            .fields
+            .iter()
+            .any(|field| field.name.trim_start_matches("_").parse::<usize>().is_ok());
+
+        if variant.fields.len() == 0 {
+            mirror_variants = quote! {
+                #mirror_variants
+                #variant_ident,
+            };
+            from_arms = quote! {
+                #from_arms
+                #origin_ident::#variant_ident => #mirror_ident::#variant_ident,
+            };
+            into_arms = quote! {
+                #into_arms
+                #mirror_ident::#variant_ident => #origin_ident::#variant_ident,
+            };
+            continue;
+        }
+
+        mirror_variants = quote! {
+            #mirror_variants
+            #variant_ident { #(#field_names: #field_tys),* },
+        };
+        let origin_pattern = if is_tuple {
+            quote!(#origin_ident::#variant_ident(#(#field_names2),*))
+        } else {
+            quote!(#origin_ident::#variant_ident{#(#field_names2),*})
+        };
+        let field_values = variant
+            .fields
+            .iter()
+            .map(|field| {
+                let field_name = Ident::new(&field.name, Span::call_site());
+                quote_mirror_field_value(field, quote!(#field_name))
+            })
+            .collect::<Vec<TokenStream>>();
+        from_arms = quote! {
+            #from_arms
+            #origin_pattern => #mirror_ident::#variant_ident{#(#field_names3: #field_values),*},
+        };
+
+        let mirror_values = variant
+            .fields
+            .iter()
+            .map(|field| {
+                let field_name = Ident::new(&field.name, Span::call_site());
+                quote_mirror_field_value(field, quote!(#field_name))
+            })
+            .collect::<Vec<TokenStream>>();
+        let origin_value = if is_tuple {
+            quote!(#origin_ident::#variant_ident(#(#mirror_values),*))
+        } else {
+            quote!(#origin_ident::#variant_ident{#(#field_names5: #mirror_values),*})
+        };
+        into_arms = quote! {
+            #into_arms
+            #mirror_ident::#variant_ident{#(#field_names4),*} => #origin_value,
+        };
+    }
+
+    Ok(quote! {
+        #[derive(Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        pub enum #mirror_ident {
+            #mirror_variants
+        }
+
+        impl From<#origin_ident> for #mirror_ident {
+            fn from(origin: #origin_ident) -> Self {
+                match origin {
+                    #from_arms
+                }
+            }
+        }
+
+        impl From<#mirror_ident> for #origin_ident {
+            fn from(mirror: #mirror_ident) -> Self {
+                match mirror {
+                    #into_arms
+                }
+            }
+        }
+    })
+}
+
+///
+/// Implement the binary wire format for the json mirror of a struct, fields are written in the
+/// order they are declared.
+///
+pub(crate) fn quote_struct_binary(struct_desc: &StructDesc) -> TokenStream {
+    let mirror_ident = Ident::new(&format!("Struct_{}", &struct_desc.name), Span::call_site());
+    let names = struct_desc
+        .fields
+        .iter()
+        .map(|field| Ident::new(&field.name, Span::call_site()))
+        .collect::<Vec<Ident>>();
+    let names2 = names.clone();
+
+    quote! {
+        impl RsbindBinary for #mirror_ident {
+            fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+                #(self.#names.encode_to(buf)?;)*
+                Ok(())
+            }
+
+            fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+                Ok(#mirror_ident {
+                    #(#names2: RsbindBinary::decode_from(buf)?),*
+                })
+            }
+        }
+    }
+}
+
+///
+/// Implement the binary wire format for the mirror of a C-like enum, which is written as
+/// its discriminant.
+///
+pub(crate) fn quote_enum_binary(enum_desc: &EnumDesc) -> TokenStream {
+    let mirror_ident = Ident::new(&format!("Enum_{}", &enum_desc.name), Span::call_site());
+    let variant_idents = enum_desc
+        .variants
+        .iter()
+        .map(|variant| Ident::new(&variant.name, Span::call_site()))
+        .collect::<Vec<Ident>>();
+    let values = enum_desc
+        .variants
+        .iter()
+        .map(|variant| Literal::i32_unsuffixed(variant.value))
+        .collect::<Vec<Literal>>();
+    let mirror_idents = vec![mirror_ident.clone(); variant_idents.len()];
+    let mirror_idents2 = mirror_idents.clone();
+    let variant_idents2 = variant_idents.clone();
+    let values2 = values.clone();
+    let unknown_msg = format!("unknown value {{}} of enum {}", &enum_desc.name);
+
+    quote! {
+        impl RsbindBinary for #mirror_ident {
+            fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+                let value: i32 = match *self {
+                    #(#mirror_idents::#variant_idents => #values,)*
+                };
+                value.encode_to(buf)
+            }
+
+            fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+                let value = i32::decode_from(buf)?;
+                match value {
+                    #(#values2 => Ok(#mirror_idents2::#variant_idents2),)*
+                    _ => Err(format!(#unknown_msg, value)),
+                }
+            }
+        }
+    }
+}
+
+///
+/// Decode an argument transferred by the binary wire format, `bytes` is a `&[u8]`.
+///
+pub(crate) fn quote_binary_arg(arg: &ArgDesc, bytes: TokenStream) -> TokenStream {
+    let rust_arg_name = Ident::new(
+        &format!("{}_{}", TMP_ARG_PREFIX, &arg.name),
+        Span::call_site(),
+    );
+    let mirror_ty = quote_mirror_field_ty(arg);
+    let origin_ty = quote_callback_ty(&arg.ty, &arg.origin_ty);
+    let decode_error = format!("can't decode the argument `{}`: {{}}", &arg.name);
+    let decoded = quote! {
+        from_rsbind_bytes::<#mirror_ty>(#bytes).unwrap_or_else(|err| panic!(#decode_error, err))
+    };
+    let value = quote_mirror_field_value(arg, decoded);
+
+    quote! {
+        let #rust_arg_name: #origin_ty = #value;
+    }
+}
+
+///
+/// Encode the return value of a method by the binary wire format, the quoted block is
+/// the `Vec<u8>` of the encoded value.
+///
+pub(crate) fn quote_binary_return(method: &MethodDesc, ret_name: &str) -> TokenStream {
+    let ret_name_ident = Ident::new(ret_name, Span::call_site());
+    let ret_desc = ArgDesc {
+        name: ret_name.to_owned(),
+        ty: method.return_type,
+        origin_ty: method.origin_return_ty.clone(),
+        ty_path: method.return_ty_path.clone(),
+        binary: true,
+        origin_name: None,
+        location: method.location.clone(),
+    };
+    let mirror_ty = quote_mirror_field_ty(&ret_desc);
+    let mirror_value = quote_mirror_field_value(&ret_desc, quote!(#ret_name_ident));
+    let encode_error = format!("can't encode the return value of `{}`: {{}}", &method.name);
+
+    quote! {
+        {
+            let mirror_ret: #mirror_ty = #mirror_value;
+            to_rsbind_bytes(&mirror_ret).unwrap_or_else(|err| panic!(#encode_error, err))
+        }
+    }
+}
+
+///
+/// Write the generated tokens to `path`, the path is kept in the error for where it failed.
+///
+pub(crate) fn write_tokens(path: &PathBuf, tokens: &TokenStream) -> Result<()> {
+    write_if_changed(path, tokens.to_string().as_bytes()).map(|_| ())
+}
@@ -1,12 +1,26 @@
-use ast::contract::desc::{StructDesc, TraitDesc};
+use super::file::{write_tokens, ForeignType};
+use ast::contract::desc::{ArgDesc, EnumDesc, StructDesc, TraitDesc};
 use ast::imp::desc::ImpDesc;
 use ast::AstResult;
+use config::WireFormat;
+use errors::ErrorKind::*;
 use errors::*;
-use proc_macro2::{Ident, Span};
-use std::fs;
-use std::io::Write;
+use incremental::remove_stale;
+use proc_macro2::{Ident, Span, TokenStream};
 use std::path::PathBuf;
 
+///
+/// The structs of `Option<T>` of primitives passed through C, and the types of their values,
+/// booleans are passed as i32.
+///
+pub(crate) const C_OPTIONAL_TYS: [(&str, &str); 5] = [
+    ("RsbindOptionalI8", "i8"),
+    ("RsbindOptionalI32", "i32"),
+    ("RsbindOptionalI64", "i64"),
+    ("RsbindOptionalF32", "f32"),
+    ("RsbindOptionalF64", "f64"),
+];
+
 ///
 /// Different strategy on generating a bridge mod.
 ///
@@ -19,7 +33,9 @@ pub(crate) trait ModGenStrategy {
         file_name: &str,
         trait_descs: &Vec<TraitDesc>,
         struct_descs: &Vec<StructDesc>,
+        enum_descs: &Vec<EnumDesc>,
         imp_desc: &Vec<ImpDesc>,
+        foreign_types: &Vec<ForeignType>,
     ) -> Result<()>;
 }
 
@@ -39,13 +55,19 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
     ///
     pub(crate) fn gen_bridges(&self) -> Result<()> {
         let emtpy_vec = vec![];
+        let empty_enums = vec![];
 
         let traits = &self.ast_result.trait_descs;
         let structs = &self.ast_result.struct_descs;
+        let enums = &self.ast_result.enum_descs;
         let imp_info = &self.ast_result.imp_desc;
 
+        // mods are sorted, so the files are the same between runs for the same contracts.
+        let mut mods = traits.iter().collect::<Vec<_>>();
+        mods.sort_by(|a, b| a.0.cmp(b.0));
+
         let mut bridges: Vec<String> = vec![];
-        for each_mod in traits {
+        for each_mod in mods {
             let mod_name = each_mod.0;
             let trait_descs = each_mod.1;
             let struct_descs = if let Some(vec) = structs.get(mod_name) {
@@ -53,9 +75,15 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
             } else {
                 &emtpy_vec
             };
+            let enum_descs = if let Some(vec) = enums.get(mod_name) {
+                vec
+            } else {
+                &empty_enums
+            };
 
             let out_mod_name = self.mod_gen_strategy.mod_name(&mod_name);
             let out_file_name = format!("{}.rs", &out_mod_name);
+            let foreign_types = self.foreign_types(mod_name, trait_descs, struct_descs, enum_descs);
 
             self.mod_gen_strategy
                 .file_gen(
@@ -63,31 +91,117 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
                     &out_file_name,
                     trait_descs,
                     struct_descs,
+                    enum_descs,
                     imp_info,
+                    &foreign_types,
                 )
-                .unwrap();
+                .chain_err(|| GenerateError(format!("can't generate bridge mod {}", mod_name)))?;
 
             bridges.push(out_mod_name)
         }
 
         self.mod_gen_strategy
             .sdk_gen(self.bridge_dir, "sdk.rs", &bridges)
-            .unwrap();
+            .chain_err(|| GenerateError("can't generate sdk.rs".to_owned()))?;
         bridges.push("sdk".to_owned());
 
         // generate common.rs
-        self.gen_common_code(&self.bridge_dir).unwrap();
+        self.gen_common_code(&self.bridge_dir)
+            .chain_err(|| GenerateError("can't generate common.rs".to_owned()))?;
 
         // generate bridge/mod.rs
-        self.gen_bridge_mod_code(&self.bridge_dir, &bridges)
-            .unwrap();
+        self.gen_bridge_mod_code(&self.bridge_dir, &bridges)?;
+
+        // files of the contract mods removed since the last run.
+        let files = bridges
+            .iter()
+            .map(|bridge| format!("{}.rs", bridge))
+            .chain(vec!["common.rs".to_owned(), "mod.rs".to_owned()])
+            .map(|file_name| self.bridge_dir.join(file_name))
+            .collect::<Vec<PathBuf>>();
+        remove_stale(self.bridge_dir, "rs", &files)?;
 
         // generate _gen/mod.rs
-        self.gen_mode_code(&self.bridge_dir).unwrap();
+        self.gen_mode_code(&self.bridge_dir)?;
 
         Ok(())
     }
 
+    ///
+    /// find the contract types defined in other mods, which are used by the structs, enums and
+    /// traits of this mod, so they can be imported with their mirrors.
+    ///
+    fn foreign_types(
+        &self,
+        mod_name: &str,
+        trait_descs: &Vec<TraitDesc>,
+        struct_descs: &Vec<StructDesc>,
+        enum_descs: &Vec<EnumDesc>,
+    ) -> Vec<ForeignType> {
+        let mut fields: Vec<&ArgDesc> = vec![];
+        let mut ty_paths: Vec<&String> = vec![];
+        for struct_desc in struct_descs.iter() {
+            fields.extend(struct_desc.fields.iter());
+        }
+        for enum_desc in enum_descs.iter() {
+            for variant in enum_desc.variants.iter() {
+                fields.extend(variant.fields.iter());
+            }
+        }
+        for trait_desc in trait_descs.iter() {
+            for method in trait_desc.methods.iter() {
+                ty_paths.push(&method.return_ty_path);
+                ty_paths.push(&method.error_ty_path);
+                fields.extend(method.args.iter());
+            }
+        }
+        // types of the same name in other mods are told apart by their paths.
+        ty_paths.extend(fields.iter().map(|field| &field.ty_path));
+
+        let mut foreign_types = vec![];
+        for (other_mod, descs) in self.ast_result.struct_descs.iter() {
+            if other_mod == mod_name {
+                continue;
+            }
+            for desc in descs
+                .iter()
+                .filter(|desc| ty_paths.iter().any(|ty_path| desc.is_referred_by(ty_path)))
+            {
+                foreign_types.push(ForeignType {
+                    name: desc.name.clone(),
+                    origin: desc.origin_name.clone().unwrap_or(desc.name.clone()),
+                    mirror: format!("Struct_{}", &desc.name),
+                    contract_mod: desc.mod_path.clone(),
+                    bridge_mod: self.mod_gen_strategy.mod_name(other_mod),
+                });
+            }
+        }
+        for (other_mod, descs) in self.ast_result.enum_descs.iter() {
+            if other_mod == mod_name {
+                continue;
+            }
+            for desc in descs
+                .iter()
+                .filter(|desc| ty_paths.iter().any(|ty_path| desc.is_referred_by(ty_path)))
+            {
+                let mirror = if desc.is_c_like {
+                    format!("Enum_{}", &desc.name)
+                } else {
+                    format!("Struct_{}", &desc.name)
+                };
+                foreign_types.push(ForeignType {
+                    name: desc.name.clone(),
+                    origin: desc.origin_name.clone().unwrap_or(desc.name.clone()),
+                    mirror,
+                    contract_mod: desc.mod_path.clone(),
+                    bridge_mod: self.mod_gen_strategy.mod_name(other_mod),
+                });
+            }
+        }
+
+        foreign_types
+    }
+
     ///
     /// generate common.rs
     ///
@@ -96,11 +210,48 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
         let free_fun_ident = Ident::new(&format!("{}_free_rust", crate_name), Span::call_site());
         let free_str_fun_ident = Ident::new(&format!("{}_free_str", crate_name), Span::call_site());
 
-        let tokens = quote! {
+        let take_panic_fun_ident =
+            Ident::new(&format!("{}_take_panic", crate_name), Span::call_site());
+
+        let mut tokens = quote! {
             use std::panic::*;
+            use std::any::Any;
+            use std::cell::RefCell;
             use std::ffi::CString;
             use std::os::raw::c_char;
 
+            thread_local! {
+                static LAST_PANIC: RefCell<Option<String>> = RefCell::new(None);
+            }
+
+            ///
+            /// get the message of a panic caught by catch_unwind.
+            ///
+            pub fn panic_message(err: &Box<Any + Send>) -> String {
+                if let Some(msg) = err.downcast_ref::<&str>() {
+                    msg.to_string()
+                } else if let Some(msg) = err.downcast_ref::<String>() {
+                    msg.clone()
+                } else {
+                    "unknown panic".to_owned()
+                }
+            }
+
+            ///
+            /// keep the message of a panic caught in current thread, until it is taken by the caller.
+            ///
+            pub fn set_last_panic(msg: String) {
+                LAST_PANIC.with(|last| *last.borrow_mut() = Some(msg));
+            }
+
+            #[no_mangle]
+            pub extern "C" fn #take_panic_fun_ident() -> *mut c_char {
+                match LAST_PANIC.with(|last| last.borrow_mut().take()) {
+                    Some(msg) => CString::new(msg.replace("\0", "")).unwrap().into_raw(),
+                    None => std::ptr::null_mut(),
+                }
+            }
+
             #[no_mangle]
             pub extern "C" fn #free_fun_ident(ptr: *mut u8, length: u32) {
                 let catch_result = catch_unwind(AssertUnwindSafe(|| {
@@ -135,11 +286,112 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
             }
         };
 
+        // options of primitives are passed through C by a presence flag and the value.
+        let optional_idents = C_OPTIONAL_TYS
+            .iter()
+            .map(|&(name, _)| Ident::new(name, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let optional_value_tys = C_OPTIONAL_TYS
+            .iter()
+            .map(|&(_, ty)| Ident::new(ty, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        tokens = quote! {
+            #tokens
+            #(
+                #[repr(C)]
+                #[derive(Clone, Copy)]
+                pub struct #optional_idents {
+                    pub is_some: i32,
+                    pub value: #optional_value_tys,
+                }
+            )*
+        };
+
+        if self.ast_result.wire_format == WireFormat::Binary {
+            let codec = self.quote_binary_codec(crate_name)?;
+            tokens = quote! {
+                #tokens
+                #codec
+            };
+        }
+
         let file_path = bridge_dir.join("common.rs");
-        let mut file = fs::File::create(&file_path).unwrap();
-        file.write_all(&tokens.to_string().into_bytes()).unwrap();
+        write_tokens(&file_path, &tokens)
+    }
 
-        Ok(())
+    ///
+    /// quote the codec of the binary wire format, values are written in big-endian, integers
+    /// narrower than i32 are written as i32, strings and vectors are prefixed by their i32
+    /// lengths, and options by a byte telling whether there is a value.
+    ///
+    fn quote_binary_codec(&self, crate_name: &str) -> Result<TokenStream> {
+        let free_buffer_fun_ident =
+            Ident::new(&format!("{}_free_buffer", crate_name), Span::call_site());
+
+        // the codec is a rust file, so it is compiled and tested with the generator itself.
+        let codec = include_str!("res/binary_codec.rs")
+            .parse::<TokenStream>()
+            .map_err(|err| GenerateError(format!("can't parse the binary codec, {:?}", err)))?;
+
+        Ok(quote! {
+            #codec
+
+            ///
+            /// bytes of the binary wire format passed through C, buffers returned from rust are
+            /// freed by `<crate>_free_buffer`.
+            ///
+            #[repr(C)]
+            pub struct RsbindBuffer {
+                pub ptr: *mut u8,
+                pub len: i32,
+            }
+
+            impl RsbindBuffer {
+                pub fn from_vec(bytes: Vec<u8>) -> RsbindBuffer {
+                    let mut bytes = bytes.into_boxed_slice();
+                    let buffer = RsbindBuffer {
+                        ptr: bytes.as_mut_ptr(),
+                        len: bytes.len() as i32,
+                    };
+                    std::mem::forget(bytes);
+                    buffer
+                }
+
+                pub fn empty() -> RsbindBuffer {
+                    RsbindBuffer {
+                        ptr: std::ptr::null_mut(),
+                        len: 0,
+                    }
+                }
+
+                pub fn as_slice(&self) -> &[u8] {
+                    if self.ptr.is_null() || self.len <= 0 {
+                        &[]
+                    } else {
+                        unsafe { std::slice::from_raw_parts(self.ptr, self.len as usize) }
+                    }
+                }
+            }
+
+            #[no_mangle]
+            pub extern "C" fn #free_buffer_fun_ident(buffer: RsbindBuffer) {
+                let catch_result = catch_unwind(AssertUnwindSafe(|| {
+                    if !buffer.ptr.is_null() {
+                        unsafe {
+                            let slice = std::slice::from_raw_parts_mut(buffer.ptr, buffer.len as usize);
+                            drop(Box::from_raw(slice as *mut [u8]));
+                        }
+                    }
+                }));
+
+                match catch_result {
+                    Ok(_) => {}
+                    Err(e) => {
+                        println!("catch_unwind of `rsbind free_buffer` error: {:?}", e);
+                    }
+                };
+            }
+        })
     }
 
     ///
@@ -157,12 +409,7 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
         };
 
         let bridget_mod_file_path = out_dir.join("mod.rs");
-        let mut bridge_mod_file = fs::File::create(&bridget_mod_file_path).unwrap();
-        bridge_mod_file
-            .write_all(&bridge_mod_tokens.to_string().into_bytes())
-            .unwrap();
-
-        Ok(())
+        write_tokens(&bridget_mod_file_path, &bridge_mod_tokens)
     }
 
     ///
@@ -173,11 +420,92 @@ impl<'a, T: ModGenStrategy> BridgeModGen<'a, T> {
             pub mod bridge;
         };
 
-        let gen_mod_path = out_dir.parent().unwrap().join("mod.rs");
-        let mut gen_mod_file = fs::File::create(&gen_mod_path).unwrap();
-        gen_mod_file
-            .write_all(&gen_mod_tokens.to_string().into_bytes())
-            .unwrap();
-        Ok(())
+        let gen_mod_path = out_dir
+            .parent()
+            .ok_or_else(|| FileError(format!("{:?} has no parent directory", out_dir)))?
+            .join("mod.rs");
+        write_tokens(&gen_mod_path, &gen_mod_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod codec {
+        include!("res/binary_codec.rs");
+    }
+
+    use self::codec::*;
+
+    #[test]
+    fn binary_codec_round_trip() {
+        let value: (Vec<Option<String>>, i64, f64, u16, bool) = (
+            vec![Some("rsbind".to_owned()), None, Some("".to_owned())],
+            -42,
+            1.5,
+            65535,
+            true,
+        );
+        let mut buf = vec![];
+        value.0.encode_to(&mut buf).unwrap();
+        value.1.encode_to(&mut buf).unwrap();
+        value.2.encode_to(&mut buf).unwrap();
+        value.3.encode_to(&mut buf).unwrap();
+        value.4.encode_to(&mut buf).unwrap();
+
+        let mut bytes = &buf[..];
+        assert_eq!(
+            Vec::<Option<String>>::decode_from(&mut bytes).unwrap(),
+            value.0
+        );
+        assert_eq!(i64::decode_from(&mut bytes).unwrap(), value.1);
+        assert_eq!(f64::decode_from(&mut bytes).unwrap(), value.2);
+        assert_eq!(u16::decode_from(&mut bytes).unwrap(), value.3);
+        assert_eq!(bool::decode_from(&mut bytes).unwrap(), value.4);
+        assert!(bytes.is_empty());
+    }
+
+    #[test]
+    fn binary_codec_writes_big_endian_lengths() {
+        let bytes = to_rsbind_bytes(&"ab".to_owned()).unwrap();
+        assert_eq!(bytes, vec![0, 0, 0, 2, b'a', b'b']);
+    }
+
+    #[test]
+    fn binary_codec_rejects_truncated_input() {
+        let bytes = to_rsbind_bytes(&vec![1i32, 2, 3]).unwrap();
+        let result = from_rsbind_bytes::<Vec<i32>>(&bytes[..bytes.len() - 1]);
+        assert!(result.unwrap_err().contains("truncated input"));
+
+        assert!(from_rsbind_bytes::<i64>(&[0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn binary_codec_rejects_negative_lengths() {
+        let bytes = to_rsbind_bytes(&-1i32).unwrap();
+        let result = from_rsbind_bytes::<String>(&bytes);
+        assert_eq!(result.unwrap_err(), "negative length -1");
+    }
+
+    #[test]
+    fn binary_codec_rejects_trailing_bytes() {
+        let bytes = to_rsbind_bytes(&vec![1i32, 2]).unwrap();
+        let result = from_rsbind_bytes::<Vec<i32>>(&[&bytes[..], &[0]].concat());
+        assert_eq!(result.unwrap_err(), "1 trailing bytes after the value");
+    }
+
+    #[test]
+    fn binary_codec_rejects_integers_out_of_their_wire_types() {
+        let result = to_rsbind_bytes(&(i32::max_value() as usize + 1));
+        assert_eq!(result.unwrap_err(), "2147483648 is out of the range of i32");
+        let result = to_rsbind_bytes(&u64::max_value());
+        assert_eq!(
+            result.unwrap_err(),
+            "18446744073709551615 is out of the range of i64"
+        );
+
+        let bytes = to_rsbind_bytes(&-1i32).unwrap();
+        let result = from_rsbind_bytes::<u32>(&bytes);
+        assert_eq!(result.unwrap_err(), "-1 is out of the range of u32");
+        assert!(from_rsbind_bytes::<usize>(&bytes).is_err());
     }
 }
@@ -12,21 +12,32 @@ const MAGIC_NUM: &'static str = "*521%";
 pub(crate) struct Unpack<'a> {
     pub path: &'a PathBuf,
     pub host_crate: &'a str,
+    /// the directory of the host crate, the bridge project depends on it by path.
+    pub host_path: &'a PathBuf,
+    /// the contract and imp modules of the host crate, imported as `contract` and `imp`.
+    pub contract_mod: &'a str,
+    pub imp_mod: &'a str,
     pub buf: &'a [u8],
     pub features: &'a Vec<String>,
 }
 
 impl<'a> Unpack<'a> {
+    ///
+    /// The project is kept between runs, so cargo can reuse its target directory, and its files
+    /// are only rewritten if they change.
+    ///
     pub(crate) fn unpack(&self) -> Result<()> {
-        if self.path.exists() {
-            ::fs::remove_dir_all(&self.path)?;
-        }
         fs::create_dir_all(&self.path)?;
 
-        unzip::unzip_to(self.buf, &self.path)?;
+        unzip::unzip_with(self.buf, &self.path, |name, content| match name {
+            "Cargo.toml" => self.replace_manifest(content),
+            "src/lib.rs" => self.replace_lib(content),
+            _ => Ok(content),
+        })
+    }
 
-        let manifest_path = self.path.join("Cargo.toml");
-        let manifest_text = fs::read_to_string(&manifest_path)
+    fn replace_manifest(&self, content: Vec<u8>) -> Result<Vec<u8>> {
+        let manifest_text = String::from_utf8(content)
             .map_err(|e| FileError(format!("read rust project Cargo.toml error: {:?}", e)))?;
 
         // replace the crate name in manifest.
@@ -37,6 +48,15 @@ impl<'a> Unpack<'a> {
             &self.host_crate.replace("-", "_"),
         );
 
+        // the templates expect the bridge project in `_gen`, which may be moved by `--out-dir`.
+        let host_path = self.host_path.canonicalize().chain_err(|| {
+            FileError(format!("can't find the host crate in {:?}", self.host_path))
+        })?;
+        let replaced = replaced.replace(
+            "path = \"../../\"",
+            &format!("path = {:?}", host_path.display().to_string()),
+        );
+
         // add some features defination.
         let mut feature_defs = String::new();
         for feature in self.features.iter() {
@@ -44,24 +64,23 @@ impl<'a> Unpack<'a> {
         }
 
         let replaced = replaced.replace(&format!("$({}-features)", MAGIC_NUM), &feature_defs);
-        fs::write(manifest_path, replaced)
-            .map_err(|e| FileError(format!("write rust project Cargo.toml error {:?}", e)))?;
+        Ok(replaced.into_bytes())
+    }
 
-        // replace the crate name in lib.rs.
-        let lib_file = self.path.join("src").join("lib.rs");
-        let lib_text = fs::read_to_string(&lib_file)
+    fn replace_lib(&self, content: Vec<u8>) -> Result<Vec<u8>> {
+        let lib_text = String::from_utf8(content)
             .map_err(|e| FileError(format!("read lib.rs error, {:?}", e)))?;
 
+        // replace the crate name in lib.rs.
         let lib_replaced =
             lib_text.replace(&format!("$({}-host_crate)", MAGIC_NUM), &self.host_crate);
         let lib_replaced = lib_replaced.replace(
             &format!("$({}-host_crate_underscore)", MAGIC_NUM),
             &self.host_crate.replace("-", "_"),
         );
-
-        fs::write(lib_file, lib_replaced)
-            .map_err(|e| FileError(format!("write lib.rs error, {}", e)))?;
-
-        Ok(())
+        let lib_replaced =
+            lib_replaced.replace(&format!("$({}-contract_mod)", MAGIC_NUM), self.contract_mod);
+        let lib_replaced = lib_replaced.replace(&format!("$({}-imp_mod)", MAGIC_NUM), self.imp_mod);
+        Ok(lib_replaced.into_bytes())
     }
 }
@@ -0,0 +1,216 @@
+use std::convert::TryFrom;
+
+///
+/// values transferred by the binary wire format.
+///
+pub trait RsbindBinary: Sized {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String>;
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String>;
+}
+
+pub fn to_rsbind_bytes<T: RsbindBinary>(value: &T) -> Result<Vec<u8>, String> {
+    let mut buf = vec![];
+    value.encode_to(&mut buf)?;
+    Ok(buf)
+}
+
+///
+/// decode a whole value, the bytes left after it are rejected.
+///
+pub fn from_rsbind_bytes<T: RsbindBinary>(bytes: &[u8]) -> Result<T, String> {
+    let mut buf = bytes;
+    let value = T::decode_from(&mut buf)?;
+    if !buf.is_empty() {
+        return Err(format!("{} trailing bytes after the value", buf.len()));
+    }
+    Ok(value)
+}
+
+fn take_bytes<'a>(buf: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if buf.len() < len {
+        return Err(format!(
+            "truncated input, {} bytes expected but {} left",
+            len,
+            buf.len()
+        ));
+    }
+    let (head, tail) = buf.split_at(len);
+    *buf = tail;
+    Ok(head)
+}
+
+///
+/// lengths of strings and vectors are written as i32, like the other languages read them.
+///
+fn encode_len(len: usize, buf: &mut Vec<u8>) -> Result<(), String> {
+    let len =
+        i32::try_from(len).map_err(|_| format!("length {} is out of the range of i32", len))?;
+    len.encode_to(buf)
+}
+
+fn decode_len(buf: &mut &[u8]) -> Result<usize, String> {
+    let len = i32::decode_from(buf)?;
+    if len < 0 {
+        return Err(format!("negative length {}", len));
+    }
+    Ok(len as usize)
+}
+
+impl RsbindBinary for bool {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        buf.push(if *self { 1 } else { 0 });
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        Ok(take_bytes(buf, 1)?[0] != 0)
+    }
+}
+
+impl RsbindBinary for u8 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        buf.push(*self);
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        Ok(take_bytes(buf, 1)?[0])
+    }
+}
+
+impl RsbindBinary for i8 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        buf.push(*self as u8);
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        Ok(take_bytes(buf, 1)?[0] as i8)
+    }
+}
+
+impl RsbindBinary for i32 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        buf.extend_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(take_bytes(buf, 4)?);
+        Ok(i32::from_be_bytes(bytes))
+    }
+}
+
+impl RsbindBinary for i64 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        buf.extend_from_slice(&self.to_be_bytes());
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(take_bytes(buf, 8)?);
+        Ok(i64::from_be_bytes(bytes))
+    }
+}
+
+impl RsbindBinary for f32 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        (self.to_bits() as i32).encode_to(buf)
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        Ok(f32::from_bits(i32::decode_from(buf)? as u32))
+    }
+}
+
+impl RsbindBinary for f64 {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        (self.to_bits() as i64).encode_to(buf)
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        Ok(f64::from_bits(i64::decode_from(buf)? as u64))
+    }
+}
+
+///
+/// integers without their own wire types are written as the ones other languages read them
+/// as, values out of the range of either are rejected instead of truncated.
+///
+macro_rules! rsbind_wire_int {
+    ($ty:ident, $wire_ty:ident) => {
+        impl RsbindBinary for $ty {
+            fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+                $wire_ty::try_from(*self)
+                    .map_err(|_| {
+                        format!("{} is out of the range of {}", self, stringify!($wire_ty))
+                    })?
+                    .encode_to(buf)
+            }
+
+            fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+                let value = $wire_ty::decode_from(buf)?;
+                $ty::try_from(value)
+                    .map_err(|_| format!("{} is out of the range of {}", value, stringify!($ty)))
+            }
+        }
+    };
+}
+
+rsbind_wire_int!(i16, i32);
+rsbind_wire_int!(u16, i32);
+rsbind_wire_int!(u32, i32);
+rsbind_wire_int!(isize, i32);
+rsbind_wire_int!(usize, i32);
+rsbind_wire_int!(u64, i64);
+
+impl RsbindBinary for String {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        encode_len(self.len(), buf)?;
+        buf.extend_from_slice(self.as_bytes());
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        let len = decode_len(buf)?;
+        String::from_utf8(take_bytes(buf, len)?.to_vec())
+            .map_err(|err| format!("invalid utf-8 string, {}", err))
+    }
+}
+
+impl<T: RsbindBinary> RsbindBinary for Vec<T> {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        encode_len(self.len(), buf)?;
+        for each in self.iter() {
+            each.encode_to(buf)?;
+        }
+        Ok(())
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        let len = decode_len(buf)?;
+        (0..len).map(|_| T::decode_from(buf)).collect()
+    }
+}
+
+impl<T: RsbindBinary> RsbindBinary for Option<T> {
+    fn encode_to(&self, buf: &mut Vec<u8>) -> Result<(), String> {
+        match *self {
+            Some(ref value) => {
+                true.encode_to(buf)?;
+                value.encode_to(buf)
+            }
+            None => false.encode_to(buf),
+        }
+    }
+
+    fn decode_from(buf: &mut &[u8]) -> Result<Self, String> {
+        if bool::decode_from(buf)? {
+            Ok(Some(T::decode_from(buf)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
@@ -6,9 +6,11 @@ use android::bridge as android_bridge;
 use ast::contract::desc::*;
 use ast::imp::desc::*;
 use ast::AstResult;
+use bridge::file::ForeignType;
 use bridge::gen;
 use errors::*;
 use ios::bridge as ios_bridge;
+use node::bridge as node_bridge;
 use std::path::PathBuf;
 
 pub(crate) enum BridgeGen<'a> {
@@ -16,6 +18,8 @@ pub(crate) enum BridgeGen<'a> {
     CGen(String, &'a AstResult, &'a PathBuf),
     // create_name, ast, bridge_dir, namespace
     JavaGen(String, &'a AstResult, &'a PathBuf, String),
+    // create_name, ast, bridge_dir
+    NodeGen(String, &'a AstResult, &'a PathBuf),
 }
 
 impl<'a> BridgeGen<'a> {
@@ -46,6 +50,16 @@ impl<'a> BridgeGen<'a> {
                 };
                 mod_gen.gen_bridges()?;
             }
+            BridgeGen::NodeGen(ref crate_name, ast_result, bridge_dir) => {
+                let mod_gen_strategy = NodeGenStrategyImp {};
+                let mod_gen = gen::BridgeModGen {
+                    crate_name: crate_name.to_owned(),
+                    ast_result,
+                    bridge_dir,
+                    mod_gen_strategy,
+                };
+                mod_gen.gen_bridges()?;
+            }
         };
 
         Ok(())
@@ -60,7 +74,8 @@ impl gen::ModGenStrategy for CGenStrategyImp {
     }
 
     fn sdk_gen(&self, out_dir: &PathBuf, file_name: &str, mod_names: &Vec<String>) -> Result<()> {
-        ios_bridge::new_gen(out_dir, &vec![], &vec![], &vec![]).gen_sdk_file(file_name, mod_names)
+        ios_bridge::new_gen(out_dir, &vec![], &vec![], &vec![], &vec![], &vec![])
+            .gen_sdk_file(file_name, mod_names)
     }
 
     fn file_gen(
@@ -69,10 +84,19 @@ impl gen::ModGenStrategy for CGenStrategyImp {
         file_name: &str,
         trait_descs: &Vec<TraitDesc>,
         struct_descs: &Vec<StructDesc>,
+        enum_descs: &Vec<EnumDesc>,
         imp_desc: &Vec<ImpDesc>,
+        foreign_types: &Vec<ForeignType>,
     ) -> Result<()> {
-        ios_bridge::new_gen(out_dir, trait_descs, struct_descs, imp_desc)
-            .gen_one_bridge_file(file_name)
+        ios_bridge::new_gen(
+            out_dir,
+            trait_descs,
+            struct_descs,
+            enum_descs,
+            imp_desc,
+            foreign_types,
+        )
+        .gen_one_bridge_file(file_name)
     }
 }
 
@@ -86,8 +110,16 @@ impl gen::ModGenStrategy for JavaGenStrategyImp {
     }
 
     fn sdk_gen(&self, out_dir: &PathBuf, file_name: &str, mod_names: &Vec<String>) -> Result<()> {
-        android_bridge::new_gen(out_dir, &vec![], &vec![], &vec![], &self.namespace)
-            .gen_sdk_file(file_name, mod_names)
+        android_bridge::new_gen(
+            out_dir,
+            &vec![],
+            &vec![],
+            &vec![],
+            &vec![],
+            &vec![],
+            &self.namespace,
+        )
+        .gen_sdk_file(file_name, mod_names)
     }
 
     fn file_gen(
@@ -96,15 +128,53 @@ impl gen::ModGenStrategy for JavaGenStrategyImp {
         file_name: &str,
         trait_descs: &Vec<TraitDesc>,
         struct_descs: &Vec<StructDesc>,
+        enum_descs: &Vec<EnumDesc>,
         imp_desc: &Vec<ImpDesc>,
+        foreign_types: &Vec<ForeignType>,
     ) -> Result<()> {
         android_bridge::new_gen(
             out_dir,
             trait_descs,
             struct_descs,
+            enum_descs,
             imp_desc,
+            foreign_types,
             &self.namespace,
         )
         .gen_one_bridge_file(file_name)
     }
 }
+
+struct NodeGenStrategyImp {}
+
+impl gen::ModGenStrategy for NodeGenStrategyImp {
+    fn mod_name(&self, mod_name: &str) -> String {
+        format!("node_{}", mod_name)
+    }
+
+    fn sdk_gen(&self, out_dir: &PathBuf, file_name: &str, mod_names: &Vec<String>) -> Result<()> {
+        node_bridge::new_gen(out_dir, &vec![], &vec![], &vec![], &vec![], &vec![])
+            .gen_sdk_file(file_name, mod_names)
+    }
+
+    fn file_gen(
+        &self,
+        out_dir: &PathBuf,
+        file_name: &str,
+        trait_descs: &Vec<TraitDesc>,
+        struct_descs: &Vec<StructDesc>,
+        enum_descs: &Vec<EnumDesc>,
+        imp_desc: &Vec<ImpDesc>,
+        foreign_types: &Vec<ForeignType>,
+    ) -> Result<()> {
+        node_bridge::new_gen(
+            out_dir,
+            trait_descs,
+            struct_descs,
+            enum_descs,
+            imp_desc,
+            foreign_types,
+        )
+        .gen_one_bridge_file(file_name)
+    }
+}
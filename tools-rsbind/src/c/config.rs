@@ -0,0 +1,51 @@
+///
+/// C Configuration struct, for the libraries and the header used by c programs.
+///
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct C {
+    pub rustc_param: Option<String>,
+    /// the target triple to build for, the host by default.
+    pub target: Option<String>,
+    pub release: Option<bool>,
+    /// the name of the libraries, the header and the pkg-config file, the crate name by default.
+    pub lib_name: Option<String>,
+    pub features_def: Option<Vec<String>>,
+}
+
+impl C {
+    pub fn rustc_param(&self) -> String {
+        match self.rustc_param {
+            Some(ref rustc) => rustc.clone(),
+            None => "".to_owned(),
+        }
+    }
+
+    pub fn release_str(&self) -> String {
+        if self.is_release() {
+            "--release".to_owned()
+        } else {
+            "".to_owned()
+        }
+    }
+
+    pub fn is_release(&self) -> bool {
+        match self.release {
+            Some(is_release) => is_release,
+            None => true,
+        }
+    }
+
+    pub fn lib_name(&self, crate_name: &str) -> String {
+        match self.lib_name {
+            Some(ref lib_name) => lib_name.clone(),
+            None => crate_name.replace("-", "_"),
+        }
+    }
+
+    pub fn features(&self) -> Vec<String> {
+        match self.features_def {
+            Some(ref features) => features.clone(),
+            None => vec![],
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use ast::AstResult;
+use bridge::prj::Unpack;
+use bridges::BridgeGen::CGen;
+use cargo;
+use cbindgen;
+use cbindgen::{Config, Language};
+use errors::ErrorKind::*;
+use errors::*;
+use incremental::write_if_changed;
+use process::{print_output, BuildProcess};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use super::config::C;
+
+/// the native libraries needed to link rust static libraries on linux.
+const LINUX_NATIVE_LIBS: &str = "-lgcc_s -lutil -lrt -lpthread -lm -ldl -lc";
+
+///
+/// Build the c bridge for the host or a configured target, and package the libraries with the
+/// header and a pkg-config file, for c programs.
+///
+pub(crate) struct CProcess<'a> {
+    origin_prj_path: &'a PathBuf,
+    dest_prj_path: &'a PathBuf,
+    bridge_prj_path: &'a PathBuf,
+    host_crate_name: &'a str,
+    ast_result: &'a AstResult,
+    config: Option<C>,
+}
+
+impl<'a> CProcess<'a> {
+    pub fn new(
+        origin_prj_path: &'a PathBuf,
+        dest_prj_path: &'a PathBuf,
+        bridge_prj_path: &'a PathBuf,
+        host_crate_name: &'a str,
+        ast_result: &'a AstResult,
+        config: Option<C>,
+    ) -> Self {
+        CProcess {
+            origin_prj_path,
+            dest_prj_path,
+            bridge_prj_path,
+            host_crate_name,
+            ast_result,
+            config,
+        }
+    }
+}
+
+impl<'a> CProcess<'a> {
+    fn config(&self) -> C {
+        match self.config {
+            Some(ref config) => config.to_owned(),
+            None => C::default(),
+        }
+    }
+
+    fn lib_name(&self) -> String {
+        self.config().lib_name(self.host_crate_name)
+    }
+
+    fn bridge_lib_name(&self, ext: &str) -> String {
+        format!(
+            "lib{}_c_bridge_prj.{}",
+            &self.host_crate_name.replace("-", "_"),
+            ext
+        )
+    }
+
+    ///
+    /// the extension of dynamic libraries, only linux and macOS are supported.
+    ///
+    fn dylib_ext(&self) -> &'static str {
+        let is_apple = match self.config().target {
+            Some(ref target) => target.contains("apple"),
+            None => cfg!(target_os = "macos"),
+        };
+        if is_apple {
+            "dylib"
+        } else {
+            "so"
+        }
+    }
+
+    fn is_linux(&self) -> bool {
+        match self.config().target {
+            Some(ref target) => target.contains("linux"),
+            None => cfg!(target_os = "linux"),
+        }
+    }
+
+    ///
+    /// the directory of building outputs in the bridge project.
+    ///
+    fn output_dir(&self) -> PathBuf {
+        let debug_release = if self.config().is_release() {
+            "release"
+        } else {
+            "debug"
+        };
+
+        let target_dir = self.bridge_prj_path.join("target");
+        match self.config().target {
+            Some(ref target) => target_dir.join(target).join(debug_release),
+            None => target_dir.join(debug_release),
+        }
+    }
+
+    pub fn gen_c_header(&self) -> Result<()> {
+        fs::create_dir_all(&self.dest_prj_path)?;
+        let lib_name = self.lib_name();
+        let output_file = self
+            .dest_prj_path
+            .join(format!("{}.h", &lib_name))
+            .display()
+            .to_string();
+
+        let config = Config {
+            language: Language::C,
+            include_guard: Some(format!("{}_H", lib_name.to_uppercase())),
+            ..Default::default()
+        };
+
+        let root_path = self.bridge_prj_path.to_str().ok_or(FileError(
+            "get bridge project path string error".to_string(),
+        ))?;
+        cbindgen::generate_with_config(root_path, config)?.write_to_file(&output_file);
+        Ok(())
+    }
+
+    ///
+    /// the files are in the same directory, which is found by `${pcfiledir}` wherever it's put.
+    ///
+    fn gen_pkg_config(&self) -> Result<()> {
+        let toml_path = self.origin_prj_path.join("Cargo.toml");
+        let manifest = cargo::manifest(toml_path.as_path())?;
+        let version = manifest
+            .package
+            .version
+            .unwrap_or_else(|| "0.0.0".to_owned());
+
+        let lib_name = self.lib_name();
+        let libs_private = if self.is_linux() {
+            format!("Libs.private: {}\n", LINUX_NATIVE_LIBS)
+        } else {
+            "".to_owned()
+        };
+        let content = format!(
+            "prefix=${{pcfiledir}}\n\
+             libdir=${{prefix}}\n\
+             includedir=${{prefix}}\n\
+             \n\
+             Name: {name}\n\
+             Description: c bindings of the rust crate {crate_name}\n\
+             Version: {version}\n\
+             Libs: -L${{libdir}} -l{name}\n\
+             {libs_private}\
+             Cflags: -I${{includedir}}\n",
+            name = &lib_name,
+            crate_name = &self.host_crate_name,
+            version = &version,
+            libs_private = &libs_private
+        );
+
+        let pc_path = self.dest_prj_path.join(format!("{}.pc", &lib_name));
+        write_if_changed(&pc_path, content.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'a> BuildProcess for CProcess<'a> {
+    fn unpack(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn gen_bridge_src(&self) -> Result<()> {
+        info!("begin unzip rust template for c");
+        let buf: &[u8] = include_bytes!("res/template_bridge_c.zip");
+        let unpack = Unpack {
+            path: self.bridge_prj_path,
+            host_crate: self.host_crate_name,
+            host_path: self.origin_prj_path,
+            contract_mod: &self.ast_result.contract_mod,
+            imp_mod: &self.ast_result.imp_mod,
+            buf,
+            features: &self.config().features(),
+        };
+
+        unpack.unpack()?;
+
+        let bridge_c_src_path = self.bridge_prj_path.join("src").join("c").join("bridge");
+        fs::create_dir_all(&bridge_c_src_path)?;
+        CGen(
+            self.host_crate_name.to_owned(),
+            self.ast_result,
+            &bridge_c_src_path,
+        )
+        .gen_bridges()
+        .chain_err(|| GenerateError("can't generate c bridges".to_string()))?;
+
+        let _ = Command::new("cargo")
+            .arg("fmt")
+            .current_dir(&self.bridge_prj_path)
+            .output();
+
+        Ok(())
+    }
+
+    fn build_bridge_prj(&self) -> Result<()> {
+        info!("building c bridge project");
+
+        let target = match self.config().target {
+            Some(ref target) => format!("--target {}", target),
+            None => "".to_owned(),
+        };
+        let build_cmd = format!(
+            "cargo rustc {} --lib {} --target-dir {} {}",
+            &target,
+            self.config().release_str(),
+            "target",
+            &self.config().rustc_param()
+        );
+
+        debug!("run building => {}", &build_cmd);
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(build_cmd)
+            .current_dir(self.bridge_prj_path)
+            .output()?;
+
+        print_output(&output)?;
+
+        if !output.status.success() {
+            return Err(CommandError(format!("run build c rust project build failed.")).into());
+        }
+
+        Ok(())
+    }
+
+    fn copy_bridge_outputs(&self) -> Result<()> {
+        info!("copy output files to c project.");
+
+        fs::create_dir_all(&self.dest_prj_path)?;
+        let lib_name = self.lib_name();
+        for ext in [self.dylib_ext(), "a"].iter() {
+            let lib_src = self.output_dir().join(self.bridge_lib_name(ext));
+            let lib_dest = self.dest_prj_path.join(format!("lib{}.{}", &lib_name, ext));
+            fs::copy(&lib_src, &lib_dest)
+                .chain_err(|| FileError(format!("copy c bridge output {:?} failed.", &lib_src)))?;
+        }
+
+        Ok(())
+    }
+
+    fn gen_bind_code(&self) -> Result<()> {
+        info!("generate c header and pkg-config file.");
+        self.gen_c_header()?;
+        self.gen_pkg_config()
+    }
+
+    fn build_dest_prj(&self) -> Result<()> {
+        // nothing to build for c, the package is copied to the origin project.
+        let target = self.origin_prj_path.join("target").join("c");
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::create_dir_all(&target)?;
+
+        let lib_name = self.lib_name();
+        let files = vec![
+            format!("lib{}.{}", &lib_name, self.dylib_ext()),
+            format!("lib{}.a", &lib_name),
+            format!("{}.h", &lib_name),
+            format!("{}.pc", &lib_name),
+        ];
+        for file in files.iter() {
+            let from = self.dest_prj_path.join(file);
+            fs::copy(&from, &target.join(file))
+                .chain_err(|| FileError(format!("copy c output {:?} failed.", &from)))?;
+        }
+
+        info!("c libraries and header are in {:?}", &target);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::AstHandler;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn generates_the_bridge_and_pkg_config_of_a_crate() {
+        let prj = TempDir::with_files(
+            "c_process",
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"demo-sdk\"\nversion = \"1.2.3\"\n",
+                ),
+                ("src/contract/mod.rs", "pub mod demo;"),
+                (
+                    "src/contract/demo.rs",
+                    "pub trait Greeter { fn greet(name: String) -> String; }",
+                ),
+                ("src/imp/mod.rs", "pub mod demo;"),
+                (
+                    "src/imp/demo.rs",
+                    r#"
+                    use contract::demo::Greeter;
+                    pub struct GreeterImp {}
+                    impl Greeter for GreeterImp {
+                        fn greet(name: String) -> String { format!("hello {}", name) }
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let ast_result = AstHandler::new("demo-sdk".to_owned(), &Common::default())
+            .parse(prj.path())
+            .unwrap();
+        let dest = prj.path().join("c_dest");
+        let bridge = prj.path().join("c_bridge");
+        let config = C {
+            target: Some("x86_64-unknown-linux-gnu".to_owned()),
+            ..Default::default()
+        };
+        let process = CProcess::new(
+            prj.path(),
+            &dest,
+            &bridge,
+            "demo-sdk",
+            &ast_result,
+            Some(config),
+        );
+
+        process.gen_bridge_src().unwrap();
+        let code = fs::read_to_string(bridge.join("src/c/bridge/c_demo.rs"))
+            .unwrap()
+            .replace(char::is_whitespace, "");
+        assert!(code.contains("pubextern\"C\"fndemo_greet(name:*constc_char)->*mutc_char{"));
+        assert!(code.contains("GreeterImp::greet(r_name)"));
+
+        process.gen_pkg_config().unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.join("demo_sdk.pc")).unwrap(),
+            "prefix=${pcfiledir}\n\
+             libdir=${prefix}\n\
+             includedir=${prefix}\n\
+             \n\
+             Name: demo_sdk\n\
+             Description: c bindings of the rust crate demo-sdk\n\
+             Version: 1.2.3\n\
+             Libs: -L${libdir} -ldemo_sdk\n\
+             Libs.private: -lgcc_s -lutil -lrt -lpthread -lm -ldl -lc\n\
+             Cflags: -I${includedir}\n"
+        );
+    }
+}
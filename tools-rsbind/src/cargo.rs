@@ -13,6 +13,7 @@ pub struct Manifest {
 #[derive(Clone, Deserialize, Debug)]
 pub struct Package {
     pub name: String,
+    pub version: Option<String>,
 }
 
 /// Parse the Cargo.toml for a given path
@@ -1,31 +1,202 @@
+use errors::ErrorKind::*;
+use errors::*;
 use std::fs::File;
 use std::io::Read;
 use std::path::PathBuf;
 use toml;
-use android::config::Android;
+use android::config::{Android, AndroidLanguage};
+use c::config::C;
 use ios::config::Ios;
+use jvm::config::Jvm;
+use node::config::Node;
+use python::config::Python;
+use Target;
+
 
 ///
 /// Configuration struct mapping from Rsbind.toml
 /// 
 #[derive(Clone, Deserialize, Debug)]
 pub struct Config {
+    pub common: Option<Common>,
     pub android: Option<Android>,
     pub ios: Option<Ios>,
+    pub jvm: Option<Jvm>,
+    pub c: Option<C>,
+    pub python: Option<Python>,
+    pub node: Option<Node>,
+}
+
+///
+/// Configurations shared by all the targets.
+/// 
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct Common {
+    pub wire_format: Option<WireFormat>,
+    /// path of the module holding contracts in the host crate, like `api` or `api::v1`.
+    pub contract: Option<String>,
+    /// path of the module holding implementations in the host crate.
+    pub imp: Option<String>,
+}
+
+impl Common {
+    pub fn contract_mod(&self) -> String {
+        match self.contract {
+            Some(ref contract) => contract.to_owned(),
+            None => "contract".to_owned(),
+        }
+    }
+
+    pub fn imp_mod(&self) -> String {
+        match self.imp {
+            Some(ref imp) => imp.to_owned(),
+            None => "imp".to_owned(),
+        }
+    }
+
+    pub fn wire_format(&self) -> WireFormat {
+        match self.wire_format {
+            Some(wire_format) => wire_format,
+            None => WireFormat::Json,
+        }
+    }
+}
+
+///
+/// How structs and vectors are transferred between rust and other languages.
+/// 
+#[derive(Clone, Copy, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    Json,
+    /// a generated length-prefixed big-endian layout, decoded by generated java and swift codes.
+    Binary,
+}
+
+impl Config {
+    pub fn common(&self) -> Common {
+        match self.common {
+            Some(ref common) => common.to_owned(),
+            None => Common::default(),
+        }
+    }
+
+    ///
+    /// reject the combinations of configurations which the generators don't support.
+    ///
+    pub fn validate(&self) -> Result<()> {
+        let kotlin = self.android.as_ref().map_or(false, |android| {
+            android.language() == AndroidLanguage::Kotlin
+        });
+        if kotlin && self.common().wire_format() == WireFormat::Binary {
+            return Err(ConfigError(
+                "wire_format = \"binary\" is not supported by the kotlin bindings yet, \
+                 use language = \"java\" in [android] instead"
+                    .to_owned(),
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    ///
+    /// reject the configurations which the generators of `target` don't support.
+    ///
+    pub fn validate_for(&self, target: &Target) -> Result<()> {
+        let binary = self.common().wire_format() == WireFormat::Binary;
+        match *target {
+            Target::Python if binary => Err(ConfigError(
+                "wire_format = \"binary\" is not supported by the python bindings yet, \
+                 use wire_format = \"json\" in [common] instead"
+                    .to_owned(),
+            )
+            .into()),
+            Target::Node if binary => Err(ConfigError(
+                "wire_format = \"binary\" is not supported by the node bindings yet, \
+                 use wire_format = \"json\" in [common] instead"
+                    .to_owned(),
+            )
+            .into()),
+            _ => Ok(()),
+        }
+    }
 }
 
 ///
 /// Parsing Rsbind.toml to Config struct.
 /// 
-pub fn parse(prj_path: &PathBuf) -> Option<Config> {
-    let mut s = String::new();
+pub fn parse(prj_path: &PathBuf) -> Result<Option<Config>> {
     let path = prj_path.join("Rsbind.toml");
     if !path.exists() {
-        println!("Rsbind.toml didn't found, skip parsing.");
-        return None;
+        info!("Rsbind.toml didn't found, skip parsing.");
+        return Ok(None);
+    }
+
+    parse_file(&path).map(Some)
+}
+
+///
+/// Parsing the config file given by `--config`, which must exist.
+///
+pub fn parse_file(path: &PathBuf) -> Result<Config> {
+    let mut s = String::new();
+    let mut f = File::open(path).chain_err(|| ConfigError(format!("can't open {:?}", path)))?;
+    f.read_to_string(&mut s)
+        .chain_err(|| ConfigError(format!("can't read {:?}", path)))?;
+    let config = toml::from_str::<Config>(&s)
+        .chain_err(|| ConfigError(format!("{:?} is not a valid config", path)))?;
+    config
+        .validate()
+        .chain_err(|| ConfigError(format!("{:?} is not a valid config", path)))?;
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(toml: &str) -> Result<()> {
+        toml::from_str::<Config>(toml).unwrap().validate()
+    }
+
+    #[test]
+    fn rejects_kotlin_with_binary_wire_format() {
+        let err = validate(
+            r#"
+            [common]
+            wire_format = "binary"
+            [android]
+            language = "kotlin"
+            "#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("wire_format = \"binary\" is not supported by the kotlin bindings yet"));
+    }
+
+    #[test]
+    fn accepts_supported_wire_formats() {
+        validate("[common]\nwire_format = \"binary\"\n[android]\nlanguage = \"java\"").unwrap();
+        validate("[common]\nwire_format = \"binary\"").unwrap();
+        validate("[common]\nwire_format = \"json\"\n[android]\nlanguage = \"kotlin\"").unwrap();
+        validate("[android]\nlanguage = \"kotlin\"").unwrap();
     }
 
-    let mut f = File::open(&path).expect("open Rsbind.toml failed.");
-    f.read_to_string(&mut s).expect("read Rsbind.toml failed.");
-    toml::from_str::<Config>(&s).ok()
+    #[test]
+    fn rejects_python_and_node_with_binary_wire_format() {
+        let config = toml::from_str::<Config>("[common]\nwire_format = \"binary\"").unwrap();
+        let err = config.validate_for(&Target::Python).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("wire_format = \"binary\" is not supported by the python bindings yet"));
+        config.validate_for(&Target::C).unwrap();
+        let err = config.validate_for(&Target::Node).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("wire_format = \"binary\" is not supported by the node bindings yet"));
+
+        let config = toml::from_str::<Config>("[common]\nwire_format = \"json\"").unwrap();
+        config.validate_for(&Target::Python).unwrap();
+    }
 }
@@ -1,5 +1,9 @@
 error_chain! {
     errors {
+        ConfigError(msg: String) {
+            description("config error"),
+            display("config error: {}", msg),
+        }
         FileError(msg: String) {
             description("file error"),
             display("file error: {}", msg),
@@ -10,7 +14,11 @@ error_chain! {
         }
         GenerateError(msg: String) {
             description("generate error"),
-            display("parse error: {}", msg),
+            display("generate error: {}", msg),
+        }
+        ValidateError(msg: String) {
+            description("validate error"),
+            display("validate error: {}", msg),
         }
 
         ZipError(msg: String) {
@@ -31,3 +39,78 @@ error_chain! {
         Cbind(cbindgen::Error);
     }
 }
+
+impl Error {
+    ///
+    /// The exit code of the cli for this error, decided by the stage where it fails.
+    ///
+    pub fn exit_code(&self) -> i32 {
+        match *self.kind() {
+            ErrorKind::ConfigError(_) | ErrorKind::Toml(_) => 3,
+            ErrorKind::ParseError(_) => 4,
+            ErrorKind::ValidateError(_) => 5,
+            ErrorKind::GenerateError(_) | ErrorKind::Cbind(_) | ErrorKind::Fmt(_) => 6,
+            ErrorKind::FileError(_)
+            | ErrorKind::ZipError(_)
+            | ErrorKind::Io(_)
+            | ErrorKind::FsExt(_) => 7,
+            ErrorKind::CommandError(_) => 8,
+            _ => 1,
+        }
+    }
+
+    ///
+    /// The error and its causes, one per line, as the cli prints them.
+    ///
+    pub fn report(&self) -> String {
+        let mut report = format!("error: {}\n", self);
+        for cause in self.iter().skip(1) {
+            report.push_str(&format!("caused by: {}\n", cause));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exits_with_the_code_of_the_failed_stage() {
+        let code = |kind: ErrorKind| Error::from(kind).exit_code();
+        assert_eq!(code(ErrorKind::ConfigError("".to_owned())), 3);
+        assert_eq!(code(ErrorKind::ParseError("".to_owned())), 4);
+        assert_eq!(code(ErrorKind::ValidateError("".to_owned())), 5);
+        assert_eq!(code(ErrorKind::GenerateError("".to_owned())), 6);
+        assert_eq!(code(ErrorKind::FileError("".to_owned())), 7);
+        assert_eq!(code(ErrorKind::ZipError("".to_owned())), 7);
+        assert_eq!(code(ErrorKind::CommandError("".to_owned())), 8);
+        assert_eq!(code(ErrorKind::Msg("".to_owned())), 1);
+
+        let io = ::std::io::Error::new(::std::io::ErrorKind::NotFound, "no such file");
+        assert_eq!(Error::from(io).exit_code(), 7);
+        assert_eq!(Error::from(::std::fmt::Error).exit_code(), 6);
+    }
+
+    #[test]
+    fn exits_with_the_code_of_the_outermost_error() {
+        let err: Result<()> = Err(ErrorKind::ParseError("can't read user.rs".to_owned()).into());
+        let err = err
+            .chain_err(|| ErrorKind::GenerateError("can't generate c bridges".to_owned()))
+            .unwrap_err();
+        assert_eq!(err.exit_code(), 6);
+    }
+
+    #[test]
+    fn reports_errors_with_their_causes() {
+        let err: Result<()> = Err(ErrorKind::ParseError("can't read user.rs".to_owned()).into());
+        let err = err
+            .chain_err(|| ErrorKind::GenerateError("can't generate c bridges".to_owned()))
+            .unwrap_err();
+        assert_eq!(
+            err.report(),
+            "error: generate error: can't generate c bridges\n\
+             caused by: parse error: can't read user.rs\n"
+        );
+    }
+}
@@ -0,0 +1,209 @@
+//!
+//! Avoid regenerating and rebuilding from scratch on every run.
+//!
+//! The sources of contract and imp mods, and the configurations are hashed into a fingerprint
+//! for each target, generating is skipped if it's the same as the one saved by the last run.
+//! Generated files are only written when their contents change, so their modification times
+//! are kept, and cargo and gradle can reuse what they built before.
+//!
+use errors::ErrorKind::*;
+use errors::*;
+use std::fs;
+use std::path::PathBuf;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+///
+/// A FNV-1a hash of the inputs of generating, which is stable between runs and rust versions.
+///
+pub(crate) struct Fingerprint {
+    hash: u64,
+}
+
+impl Fingerprint {
+    ///
+    /// the version of rsbind is always hashed, as its outputs may change between versions.
+    ///
+    pub(crate) fn new() -> Fingerprint {
+        let mut fingerprint = Fingerprint { hash: FNV_OFFSET };
+        fingerprint.add(env!("CARGO_PKG_VERSION").as_bytes());
+        fingerprint
+    }
+
+    pub(crate) fn add(&mut self, bytes: &[u8]) {
+        // the length is hashed first, so ["ab", "c"] and ["a", "bc"] are different.
+        let len = bytes.len() as u64;
+        for byte in len.to_be_bytes().iter().chain(bytes.iter()) {
+            self.hash ^= *byte as u64;
+            self.hash = self.hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    ///
+    /// hash the path and the content of a file.
+    ///
+    pub(crate) fn add_file(&mut self, path: &PathBuf) -> Result<()> {
+        let content = fs::read(path).chain_err(|| FileError(format!("can't read {:?}", path)))?;
+        self.add(path.display().to_string().as_bytes());
+        self.add(&content);
+        Ok(())
+    }
+
+    pub(crate) fn to_hex(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+
+    ///
+    /// whether it's the same as the one saved in `path`.
+    ///
+    pub(crate) fn matches(&self, path: &PathBuf) -> bool {
+        match fs::read_to_string(path) {
+            Ok(saved) => saved.trim() == self.to_hex(),
+            Err(_) => false,
+        }
+    }
+
+    pub(crate) fn save(&self, path: &PathBuf) -> Result<()> {
+        write_if_changed(path, self.to_hex().as_bytes()).map(|_| ())
+    }
+}
+
+///
+/// Write `content` to `path` only if it differs from the content of the existing file, the
+/// parent directories are created if needed. Returns whether the file is written.
+///
+pub(crate) fn write_if_changed(path: &PathBuf, content: &[u8]) -> Result<bool> {
+    if let Ok(existing) = fs::read(path) {
+        if existing.as_slice() == content {
+            debug!("{:?} is unchanged.", path);
+            return Ok(false);
+        }
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .chain_err(|| FileError(format!("can't create directory {:?}", parent)))?;
+    }
+    fs::write(path, content).chain_err(|| FileError(format!("can't write {:?}", path)))?;
+    Ok(true)
+}
+
+///
+/// Remove the files in `dir` with the extension `ext`, which are not in `keep`. They are the
+/// outputs of the last run for contracts that don't exist anymore.
+///
+pub(crate) fn remove_stale(dir: &PathBuf, ext: &str, keep: &Vec<PathBuf>) -> Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(()),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if !path.is_file() || path.extension().map_or(true, |each| each != ext) {
+            continue;
+        }
+        if !keep.contains(&path) {
+            debug!("remove stale file {:?}", &path);
+            fs::remove_file(&path)
+                .chain_err(|| FileError(format!("can't remove stale file {:?}", &path)))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    fn fingerprint(inputs: &[&str]) -> String {
+        let mut fingerprint = Fingerprint::new();
+        for input in inputs.iter() {
+            fingerprint.add(input.as_bytes());
+        }
+        fingerprint.to_hex()
+    }
+
+    #[test]
+    fn hashes_inputs_with_lengths() {
+        assert_eq!(fingerprint(&["ab", "c"]), fingerprint(&["ab", "c"]));
+        assert_ne!(fingerprint(&["ab", "c"]), fingerprint(&["a", "bc"]));
+        assert_ne!(fingerprint(&["ab"]), fingerprint(&["ab", ""]));
+        assert_eq!(fingerprint(&[]).len(), 16);
+    }
+
+    #[test]
+    fn hashes_paths_and_contents_of_files() {
+        let temp = TempDir::new("incremental_files");
+        let dir = temp.path();
+        let first = dir.join("first.rs");
+        let second = dir.join("second.rs");
+        write_if_changed(&first, b"pub struct User {}").unwrap();
+        write_if_changed(&second, b"pub struct User {}").unwrap();
+
+        let hash = |path: &PathBuf| {
+            let mut fingerprint = Fingerprint::new();
+            fingerprint.add_file(path).unwrap();
+            fingerprint.to_hex()
+        };
+        let origin = hash(&first);
+        assert_eq!(hash(&first), origin);
+        assert_ne!(hash(&second), origin);
+
+        write_if_changed(&first, b"pub struct Group {}").unwrap();
+        assert_ne!(hash(&first), origin);
+
+        assert!(Fingerprint::new()
+            .add_file(&dir.join("missing.rs"))
+            .is_err());
+    }
+
+    #[test]
+    fn matches_saved_fingerprints() {
+        let temp = TempDir::new("incremental_saved");
+        let dir = temp.path();
+        let path = dir.join("c.fingerprint");
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add(b"contract");
+        assert!(!fingerprint.matches(&path));
+
+        fingerprint.save(&path).unwrap();
+        assert!(fingerprint.matches(&path));
+
+        fingerprint.add(b"imp");
+        assert!(!fingerprint.matches(&path));
+    }
+
+    #[test]
+    fn writes_only_changed_files() {
+        let temp = TempDir::new("incremental_write");
+        let dir = temp.path();
+        let path = dir.join("nested/bridge.rs");
+        assert!(write_if_changed(&path, b"fn a() {}").unwrap());
+        assert!(!write_if_changed(&path, b"fn a() {}").unwrap());
+        assert!(write_if_changed(&path, b"fn b() {}").unwrap());
+        assert_eq!(fs::read(&path).unwrap(), b"fn b() {}");
+    }
+
+    #[test]
+    fn removes_stale_files() {
+        let temp = TempDir::new("incremental_stale");
+        let dir = temp.path();
+        let kept = dir.join("user.rs");
+        let stale = dir.join("group.rs");
+        let other = dir.join("group.java");
+        for path in [&kept, &stale, &other].iter() {
+            write_if_changed(path, b"").unwrap();
+        }
+        fs::create_dir_all(dir.join("net.rs")).unwrap();
+
+        remove_stale(dir, "rs", &vec![kept.clone()]).unwrap();
+        assert!(kept.exists());
+        assert!(!stale.exists());
+        assert!(other.exists());
+        assert!(dir.join("net.rs").is_dir());
+
+        remove_stale(&dir.join("missing"), "rs", &vec![]).unwrap();
+    }
+}
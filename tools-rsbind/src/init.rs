@@ -0,0 +1,320 @@
+//!
+//! Scaffold the contract and imp modules, and a Rsbind.toml for a new or an existing crate, so
+//! it can be bound right away.
+//!
+//! Existing files are never overwritten, the mods are declared by appending to their parents.
+//!
+use config::{self, Common};
+use errors::ErrorKind::*;
+use errors::*;
+use std::fs;
+use std::path::PathBuf;
+
+const SAMPLE_MOD: &str = "sample";
+
+const CONTRACT_TEMPLATE: &str = r#"//!
+//! A sample contract created by `rsbind init`, the traits here are exported to java and swift.
+//!
+
+///
+/// Implemented in the imp mod, and called from java and swift.
+///
+pub trait Sample {
+    fn hello(name: String) -> String;
+    fn sum(values: Vec<i32>) -> i64;
+    fn find_user(id: i64) -> Option<User>;
+    fn watch_user(id: i64, callback: Box<dyn UserCallback>);
+}
+
+///
+/// Traits whose methods take `&self` are callbacks, implemented in java and swift, and called
+/// from rust.
+///
+pub trait UserCallback: Sync {
+    fn on_changed(&self, user: User) -> bool;
+}
+
+pub struct User {
+    pub id: i64,
+    pub name: String,
+    pub tags: Vec<String>,
+}
+"#;
+
+const IMP_TEMPLATE: &str = r#"use crate::$(contract)::sample::{Sample, User, UserCallback};
+
+pub struct SampleImp {}
+
+impl Sample for SampleImp {
+    fn hello(name: String) -> String {
+        format!("hello {}", name)
+    }
+
+    fn sum(values: Vec<i32>) -> i64 {
+        values.iter().map(|value| *value as i64).sum()
+    }
+
+    fn find_user(id: i64) -> Option<User> {
+        if id < 0 {
+            return None;
+        }
+        Some(User {
+            id,
+            name: format!("user {}", id),
+            tags: vec!["sample".to_owned()],
+        })
+    }
+
+    fn watch_user(id: i64, callback: Box<dyn UserCallback>) {
+        if let Some(user) = Self::find_user(id) {
+            callback.on_changed(user);
+        }
+    }
+}
+"#;
+
+const CARGO_TEMPLATE: &str = r#"[package]
+name = "$(crate)"
+version = "0.1.0"
+
+[dependencies]
+"#;
+
+const CONFIG_TEMPLATE: &str = r#"# Configurations of rsbind, all the fields are optional and commented with their defaults.
+
+[common]
+# how structs and vectors are transferred, "json" or "binary".
+#wire_format = "json"
+# the module holding contracts, like "api" or "api::v1".
+#contract = "$(contract)"
+# the module holding the implementations of contracts.
+#imp = "$(imp)"
+
+[android]
+# the standalone toolchain of NDK.
+#ndk_stand_alone = "/path/to/ndk/standalone"
+# extra parameters of building the bridge project, `--features rsbind` is always given.
+#rustc_param = ""
+# the 32 bits arm targets.
+#arch = ["armv7-linux-androideabi", "arm-linux-androideabi"]
+# the 64 bits arm targets.
+#arch_64 = ["aarch64-linux-android"]
+# the x86 targets.
+#arch_x86 = ["i686-linux-android"]
+#release = true
+# the java package of the generated classes.
+#namespace = "com.afoxer.xxx.ffi"
+# the name of the so library loaded by java.
+#so_name = "ffi"
+# other so libraries to load before it.
+#ext_lib = []
+# features defined in the bridge project, like ["xxxx=[]"].
+#features_def = []
+# the language of the generated bindings, "java" or "kotlin".
+#language = "java"
+
+[ios]
+# extra parameters of building the bridge project.
+#rustc_param = ""
+# the targets of iPhones.
+#arch_phone = ["aarch64-apple-ios", "armv7-apple-ios"]
+# the targets of simulators.
+#arch_simu = ["i386-apple-ios", "x86_64-apple-ios"]
+#release = true
+# features defined in the bridge project.
+#features_def = []
+
+[jvm]
+# extra parameters of building the bridge project for the host, `--features rsbind` is always given.
+#rustc_param = ""
+#release = true
+# the java package of the generated classes.
+#namespace = "com.afoxer.xxx.ffi"
+# the name of the library loaded by java.
+#so_name = "ffi"
+# other libraries to load before it.
+#ext_lib = []
+# features defined in the bridge project.
+#features_def = []
+# the jars to compile the generated java sources with, relative to the crate, like fastjson's.
+#classpath = []
+
+[c]
+# extra parameters of building the bridge project.
+#rustc_param = ""
+# the target triple to build for, the host by default.
+#target = "x86_64-unknown-linux-gnu"
+#release = true
+# the name of the libraries, the header and the pkg-config file, the crate name by default.
+#lib_name = "my_crate"
+# features defined in the bridge project.
+#features_def = []
+
+[python]
+# extra parameters of building the bridge project.
+#rustc_param = ""
+#release = true
+# the name of the package, the crate name by default.
+#package_name = "my_crate"
+# features defined in the bridge project.
+#features_def = []
+
+[node]
+# extra parameters of building the bridge project.
+#rustc_param = ""
+#release = true
+# the name of the npm package, the crate name by default.
+#package_name = "my-crate"
+# features defined in the bridge project.
+#features_def = []
+"#;
+
+///
+/// Scaffold the crate in `prj_path`, which is created with `crate_name` if it doesn't exist,
+/// or named by its directory if `crate_name` is not given.
+///
+pub(crate) fn init(prj_path: &PathBuf, crate_name: Option<&str>) -> Result<()> {
+    let manifest_path = prj_path.join("Cargo.toml");
+    if !manifest_path.exists() {
+        let crate_name = match crate_name {
+            Some(crate_name) => crate_name.to_owned(),
+            None => prj_path
+                .canonicalize()
+                .unwrap_or(prj_path.clone())
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .ok_or_else(|| {
+                    FileError(format!(
+                        "can't name the crate in {:?}, try --name",
+                        prj_path
+                    ))
+                })?,
+        };
+        fs::create_dir_all(prj_path)?;
+        write_new(
+            &manifest_path,
+            &CARGO_TEMPLATE.replace("$(crate)", &crate_name),
+        )?;
+    }
+
+    // the modules configured in an existing Rsbind.toml are scaffolded.
+    let common = match config::parse(prj_path)? {
+        Some(config) => config.common(),
+        None => Common::default(),
+    };
+    let contract_mod = common.contract_mod();
+    let imp_mod = common.imp_mod();
+
+    let src_path = prj_path.join("src");
+    fs::create_dir_all(&src_path)?;
+    let lib_path = src_path.join("lib.rs");
+
+    let contract_file = mod_file(&src_path, &lib_path, &contract_mod)?;
+    let contract_dir = contract_file
+        .parent()
+        .map(|parent| parent.join(file_stem(&contract_file)))
+        .unwrap_or(src_path.clone());
+    write_sample(&contract_file, &contract_dir, CONTRACT_TEMPLATE)?;
+
+    let imp_file = mod_file(&src_path, &lib_path, &imp_mod)?;
+    let imp_dir = imp_file
+        .parent()
+        .map(|parent| parent.join(file_stem(&imp_file)))
+        .unwrap_or(src_path.clone());
+    write_sample(
+        &imp_file,
+        &imp_dir,
+        &IMP_TEMPLATE.replace("$(contract)", &contract_mod),
+    )?;
+
+    write_new(
+        &prj_path.join("Rsbind.toml"),
+        &CONFIG_TEMPLATE
+            .replace("$(contract)", &contract_mod)
+            .replace("$(imp)", &imp_mod),
+    )?;
+
+    info!(
+        "rsbind is ready in {:?}, try `rsbind check` and `rsbind build`.",
+        prj_path
+    );
+    Ok(())
+}
+
+///
+/// declare the mods of `mod_path` like `api::v1` from lib.rs, and return the file of the last mod.
+///
+fn mod_file(src_path: &PathBuf, lib_path: &PathBuf, mod_path: &str) -> Result<PathBuf> {
+    let mut parent_file = lib_path.clone();
+    let mut dir = src_path.clone();
+    for name in mod_path.split("::") {
+        declare_mod(&parent_file, name)?;
+
+        // `api.rs` is used if it exists, otherwise `api/mod.rs`.
+        let file = dir.join(format!("{}.rs", name));
+        dir = dir.join(name);
+        parent_file = if file.exists() {
+            file
+        } else {
+            fs::create_dir_all(&dir)?;
+            dir.join("mod.rs")
+        };
+    }
+    Ok(parent_file)
+}
+
+///
+/// put the sample into the mod defined by `mod_file`, whose submodules are in `mod_dir`.
+///
+fn write_sample(mod_file: &PathBuf, mod_dir: &PathBuf, content: &str) -> Result<()> {
+    fs::create_dir_all(mod_dir)?;
+    declare_mod(mod_file, SAMPLE_MOD)?;
+    write_new(&mod_dir.join(format!("{}.rs", SAMPLE_MOD)), content)
+}
+
+///
+/// append `pub mod name;` to `file` if it is not declared, the file is created if needed.
+///
+fn declare_mod(file: &PathBuf, name: &str) -> Result<()> {
+    let content = if file.exists() {
+        fs::read_to_string(file).chain_err(|| FileError(format!("can't read {:?}", file)))?
+    } else {
+        String::new()
+    };
+
+    let declared = content.lines().any(|line| {
+        let line = line.trim();
+        line == format!("mod {};", name) || line == format!("pub mod {};", name)
+    });
+    if declared {
+        return Ok(());
+    }
+
+    let mut content = content;
+    if !content.is_empty() && !content.ends_with("\n") {
+        content.push('\n');
+    }
+    content.push_str(&format!("pub mod {};\n", name));
+    info!("declare mod {} in {:?}", name, file);
+    fs::write(file, content).chain_err(|| FileError(format!("can't write {:?}", file)))
+}
+
+fn write_new(path: &PathBuf, content: &str) -> Result<()> {
+    if path.exists() {
+        info!("skip {:?}, it already exists.", path);
+        return Ok(());
+    }
+    info!("create {:?}", path);
+    fs::write(path, content).chain_err(|| FileError(format!("can't write {:?}", path)))
+}
+
+fn file_stem(file: &PathBuf) -> String {
+    match file.file_name().and_then(|name| name.to_str()) {
+        // the submodules of `api/mod.rs` are in `api`, which is the parent already.
+        Some("mod.rs") => String::new(),
+        _ => file
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    }
+}
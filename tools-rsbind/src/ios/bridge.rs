@@ -16,13 +16,17 @@ pub(crate) fn new_gen<'a>(
     out_dir: &'a PathBuf,
     trait_descs: &'a Vec<TraitDesc>,
     struct_descs: &'a Vec<StructDesc>,
+    enum_descs: &'a Vec<EnumDesc>,
     imp_desc: &'a Vec<ImpDesc>,
+    foreign_types: &'a Vec<ForeignType>,
 ) -> BridgeFileGen<'a, CFileGenStrategy> {
     return BridgeFileGen {
         out_dir,
         trait_descs,
         struct_descs,
+        enum_descs,
         imp_desc,
+        foreign_types,
         strategy: CFileGenStrategy {
             callback_strategy: CCallbackStrategy {},
         },
@@ -36,7 +40,32 @@ pub(crate) struct CFileGenStrategy {
     pub(crate) callback_strategy: CCallbackStrategy,
 }
 
-impl CFileGenStrategy {}
+impl CFileGenStrategy {
+    ///
+    /// the value returned to swift when an error or a panic happens, which is ignored by swift.
+    ///
+    fn quote_placeholder(&self, method: &MethodDesc) -> TokenStream {
+        if method.binary_return {
+            return quote!(RsbindBuffer::empty());
+        }
+
+        match method.return_type {
+            AstType::Void => quote!(),
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let optional_ident = c_optional_ident(base);
+                quote!(#optional_ident { is_some: 0, value: Default::default() })
+            }
+            AstType::Float | AstType::Double => quote!(0.0),
+            AstType::Byte
+            | AstType::Int
+            | AstType::Long
+            | AstType::Boolean
+            | AstType::Enum
+            | AstType::Callback => quote!(0),
+            _ => quote!(std::ptr::null_mut()),
+        }
+    }
+}
 
 impl FileGenStrategy for CFileGenStrategy {
     fn gen_sdk_file(&self, _mod_names: &Vec<String>) -> Result<TokenStream> {
@@ -45,6 +74,8 @@ impl FileGenStrategy for CFileGenStrategy {
 
     fn quote_common_use_part(&self) -> Result<TokenStream> {
         Ok(quote! {
+            use super::common::*;
+            use std::panic::{catch_unwind, AssertUnwindSafe};
             use std::ffi::CStr;
             use std::os::raw::c_char;
             use std::ffi::CString;
@@ -74,13 +105,39 @@ impl FileGenStrategy for CFileGenStrategy {
             .iter()
             .map(|field| Ident::new(&field.name, Span::call_site()))
             .collect::<Vec<Ident>>();
-        let arg_names = names.clone();
+        let values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(field.rust_name(), Span::call_site());
+                quote_mirror_field_value(field, quote!(origin.#name))
+            })
+            .collect::<Vec<TokenStream>>();
+        let mirror_values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(&field.name, Span::call_site());
+                quote_mirror_field_value(field, quote!(mirror.#name))
+            })
+            .collect::<Vec<TokenStream>>();
         let orgin_arg_names = names.clone();
-        let tys = struct_desc
+        // mirrors use the foreign names of renamed fields, origin structs keep the rust names.
+        let mirror_arg_names = struct_desc
             .fields
             .iter()
-            .map(|field| Ident::new(&field.origin_ty, Span::call_site()))
+            .map(|field| Ident::new(field.rust_name(), Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let skipped_names = struct_desc
+            .skipped_fields
+            .iter()
+            .map(|field| Ident::new(field, Span::call_site()))
             .collect::<Vec<Ident>>();
+        let tys = struct_desc
+            .fields
+            .iter()
+            .map(|field| quote_mirror_field_ty(field))
+            .collect::<Vec<TokenStream>>();
         Ok(quote! {
             #[repr(C)]
             #[derive(Serialize, Deserialize)]
@@ -90,12 +147,25 @@ impl FileGenStrategy for CFileGenStrategy {
 
             impl From<#origin_struct_name> for #struct_name {
                 fn from(origin: #origin_struct_name) -> Self {
-                    #struct_name{#(#orgin_arg_names: origin.#arg_names),*}
+                    #struct_name{#(#orgin_arg_names: #values),*}
+                }
+            }
+
+            impl From<#struct_name> for #origin_struct_name {
+                fn from(mirror: #struct_name) -> Self {
+                    #origin_struct_name{
+                        #(#mirror_arg_names: #mirror_values,)*
+                        #(#skipped_names: Default::default(),)*
+                    }
                 }
             }
         })
     }
 
+    fn quote_for_enums(&self, enum_desc: &EnumDesc) -> Result<TokenStream> {
+        quote_enum_mirror(enum_desc)
+    }
+
     fn quote_method_sig(
         &self,
         trait_desc: &TraitDesc,
@@ -104,12 +174,18 @@ impl FileGenStrategy for CFileGenStrategy {
         callbacks: &Vec<&TraitDesc>,
         _structs: &Vec<StructDesc>,
     ) -> Result<TokenStream> {
-        let fun_name = Ident::new(
-            &format!("{}_{}", &trait_desc.mod_name, &method.name),
-            Span::call_site(),
-        );
+        // methods of returned callbacks are named after the wrapper, as the callback methods
+        // are likely to be shared by several callbacks in one mod.
+        let fun_name = match trait_desc.wraps_callback {
+            Some(_) => format!(
+                "{}_{}_{}",
+                &trait_desc.mod_name, &trait_desc.name, &method.name
+            ),
+            None => format!("{}_{}", &trait_desc.mod_name, &method.name),
+        };
+        let fun_name = Ident::new(&fun_name, Span::call_site());
 
-        let arg_names = method
+        let mut arg_names = method
             .args
             .iter()
             .filter(|arg| match arg.ty {
@@ -119,7 +195,7 @@ impl FileGenStrategy for CFileGenStrategy {
             .map(|arg| Ident::new(&arg.name, Span::call_site()))
             .collect::<Vec<Ident>>();
 
-        let arg_types = method
+        let mut arg_types = method
             .args
             .iter()
             .filter(|arg| match arg.ty {
@@ -127,28 +203,50 @@ impl FileGenStrategy for CFileGenStrategy {
                 _ => true,
             })
             .map(|arg| match arg.ty {
+                _ if arg.binary => Ok(quote!(RsbindBuffer)),
                 AstType::Callback => {
-                    let mut callback_trait = None;
-                    for callback in callbacks.iter() {
-                        if callback.name == arg.origin_ty {
-                            callback_trait = Some(callback);
-                            break;
-                        }
-                    }
+                    let callback_trait = callbacks
+                        .iter()
+                        .find(|callback| callback.name == arg.origin_ty)
+                        .ok_or_else(|| {
+                            GenerateError(format!(
+                                "can't find the callback {} of argument {}",
+                                &arg.origin_ty, &arg.name
+                            ))
+                        })?;
                     let callback_str = &format!(
                         "{}_{}_Model",
-                        &callback_trait.unwrap().mod_name,
-                        &callback_trait.unwrap().name
+                        &callback_trait.mod_name, &callback_trait.name
                     );
                     let callback_ident = Ident::new(callback_str, Span::call_site());
-                    quote!(#callback_ident)
+                    Ok(quote!(#callback_ident))
                 }
-                _ => self.ty_to_tokens(&arg.ty, TypeDirection::Argument).unwrap(),
+                _ => self.ty_to_tokens(&arg.ty, TypeDirection::Argument),
             })
-            .collect::<Vec<TokenStream>>();
+            .collect::<Result<Vec<TokenStream>>>()?;
 
-        let ret_ty_tokens = self.ty_to_tokens(&method.return_type, TypeDirection::Return)?;
-        println!(
+        // methods of objects are called with the handle of the object.
+        match method.kind {
+            MethodKind::Ref | MethodKind::RefMut => {
+                arg_names.insert(0, Ident::new("handle", Span::call_site()));
+                arg_types.insert(0, quote!(i64));
+            }
+            _ => (),
+        }
+
+        // methods returning Result<T, E> write the error to an out parameter, which must be
+        // initialized with null by the caller.
+        if method.error_type != AstType::Void {
+            arg_names.push(Ident::new("out_err", Span::call_site()));
+            arg_types.push(quote!(*mut *mut c_char));
+        }
+
+        let ret_ty_tokens = if method.binary_return {
+            quote!(RsbindBuffer)
+        } else {
+            self.ty_to_tokens(&method.return_type, TypeDirection::Return)?
+        };
+        debug!(
             "xxxxxx result ={:?} -> {:?}",
             &method.return_type, ret_ty_tokens
         );
@@ -182,6 +280,7 @@ impl FileGenStrategy for CFileGenStrategy {
     fn quote_arg_convert(
         &self,
         trait_desc: &TraitDesc,
+        _method: &MethodDesc,
         arg: &ArgDesc,
         callbacks: &Vec<&TraitDesc>,
     ) -> Result<TokenStream> {
@@ -191,6 +290,11 @@ impl FileGenStrategy for CFileGenStrategy {
         );
         let arg_name_ident = Ident::new(&arg.name, Span::call_site());
 
+        // the buffer of encoded values is owned by the caller.
+        if arg.binary {
+            return Ok(quote_binary_arg(arg, quote!(#arg_name_ident.as_slice())));
+        }
+
         Ok(match arg.ty {
             AstType::Byte | AstType::Int | AstType::Long | AstType::Float | AstType::Double => {
                 let origin_type_ident = Ident::new(&arg.origin_ty, Span::call_site());
@@ -212,6 +316,40 @@ impl FileGenStrategy for CFileGenStrategy {
                     let #rust_arg_name: String = #c_slice_ident.to_owned();
                 }
             }
+            AstType::Enum => {
+                let mirror_ident =
+                    Ident::new(&format!("Enum_{}", &arg.origin_ty), Span::call_site());
+                let origin_type_ident = Ident::new(&arg.origin_ty, Span::call_site());
+                quote! {
+                    let #rust_arg_name: #origin_type_ident = #mirror_ident::from_i32(#arg_name_ident).into();
+                }
+            }
+            AstType::Vec(AstBaseType::Enum) => {
+                let enum_name = arg.origin_ty.replace("Vec<", "").replace(">", "");
+                let enum_ident = Ident::new(&enum_name, Span::call_site());
+                let mirror_ident = Ident::new(&format!("Enum_{}", &enum_name), Span::call_site());
+                let c_str_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
+                let c_slice_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
+                let mirror_arg_ident =
+                    Ident::new(&format!("mirror_{}", &arg.name), Span::call_site());
+                quote! {
+                    let #c_str_ident: &CStr = unsafe{CStr::from_ptr(#arg_name_ident)};
+                    let #c_slice_ident: &str = #c_str_ident.to_str().unwrap();
+                    let #mirror_arg_ident: Vec<#mirror_ident> = serde_json::from_str(&#c_slice_ident.to_owned()).unwrap();
+                    let #rust_arg_name = #mirror_arg_ident.into_iter().map(|each| each.into()).collect::<Vec<#enum_ident>>();
+                }
+            }
+            AstType::Vec(AstBaseType::Struct) | AstType::Struct => {
+                let c_str_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
+                let json_arg_ident = Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                let value =
+                    quote_struct_from_json(&arg.ty, &arg.origin_ty, quote!(#json_arg_ident))?;
+                quote! {
+                    let #c_str_ident: &CStr = unsafe{CStr::from_ptr(#arg_name_ident)};
+                    let #json_arg_ident: String = #c_str_ident.to_str().unwrap().to_owned();
+                    let #rust_arg_name = #value;
+                }
+            }
             AstType::Vec(_base) => {
                 let c_str_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
                 let c_slice_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
@@ -221,10 +359,44 @@ impl FileGenStrategy for CFileGenStrategy {
                     let #rust_arg_name = serde_json::from_str(&#c_slice_ident.to_owned()).unwrap();
                 }
             }
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let value = quote_c_optional_from(base, &arg.origin_ty, quote!(#arg_name_ident));
+                quote! {
+                    let #rust_arg_name = #value;
+                }
+            }
+            AstType::Optional(ref base) => {
+                let inner = arg.origin_ty.replace("Option<", "").replace(">", "");
+                let inner_ident = Ident::new(&inner, Span::call_site());
+                let c_str_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
+                let tmp_arg_name = Ident::new(&format!("tmp_{}", &arg.name), Span::call_site());
+                let inner_value =
+                    quote_optional_from_str(base, &arg.origin_ty, quote!(#tmp_arg_name))?;
+                quote! {
+                    let #rust_arg_name: Option<#inner_ident> = if #arg_name_ident.is_null() {
+                        None
+                    } else {
+                        let #c_str_ident: &CStr = unsafe{CStr::from_ptr(#arg_name_ident)};
+                        let #tmp_arg_name: String = #c_str_ident.to_str().unwrap().to_owned();
+                        Some(#inner_value)
+                    };
+                }
+            }
+            AstType::Map(ref key, ref value) => {
+                let c_str_ident = Ident::new(&format!("c_str_{}", &arg.name), Span::call_site());
+                let json_arg_ident = Ident::new(&format!("json_{}", &arg.name), Span::call_site());
+                let map_value =
+                    quote_map_from_json(key, value, &arg.origin_ty, quote!(#json_arg_ident))?;
+                quote! {
+                    let #c_str_ident: &CStr = unsafe{CStr::from_ptr(#arg_name_ident)};
+                    let #json_arg_ident: String = #c_str_ident.to_str().unwrap().to_owned();
+                    let #rust_arg_name = #map_value;
+                }
+            }
             AstType::Callback => {
-                println!("callback in argument found, {}", arg.origin_ty);
+                debug!("callback in argument found, {}", arg.origin_ty);
                 self.callback_strategy
-                    .arg_convert(arg, trait_desc, callbacks)
+                    .arg_convert(arg, trait_desc, callbacks)?
             }
             _ => {
                 return Err(
@@ -234,14 +406,19 @@ impl FileGenStrategy for CFileGenStrategy {
         })
     }
 
-    fn quote_return_convert(
-        &self,
-        ty: &AstType,
-        ret_name: &str,
-        origin_ty: &str,
-    ) -> Result<TokenStream> {
+    fn quote_return_convert(&self, method: &MethodDesc, ret_name: &str) -> Result<TokenStream> {
+        let ty = &method.return_type;
+        let origin_ty: &str = &method.origin_return_ty;
         let ret_name_ident = Ident::new(ret_name, Span::call_site());
 
+        // encoded values are returned in a buffer, which is freed by `<crate>_free_buffer`.
+        if method.binary_return {
+            let bytes_ret = quote_binary_return(method, ret_name);
+            return Ok(quote! {
+                RsbindBuffer::from_vec(#bytes_ret)
+            });
+        }
+
         Ok(match *ty {
             AstType::Void => quote!(),
             AstType::Boolean => quote! {
@@ -261,6 +438,16 @@ impl FileGenStrategy for CFileGenStrategy {
                         CString::new(json_ret.unwrap()).unwrap().into_raw()
                     }
                 }
+                AstBaseType::Enum => {
+                    let enum_name = origin_ty.to_owned().replace("Vec<", "").replace(">", "");
+                    let mirror_ident =
+                        Ident::new(&format!("Enum_{}", &enum_name), Span::call_site());
+                    quote! {
+                        let ret_value = ret_value.into_iter().map(|each| #mirror_ident::from(each)).collect::<Vec<#mirror_ident>>();
+                        let json_ret = serde_json::to_string(&ret_value);
+                        CString::new(json_ret.unwrap()).unwrap().into_raw()
+                    }
+                }
                 _ => {
                     quote! {
                         let json_ret = serde_json::to_string(&ret_value);
@@ -276,6 +463,31 @@ impl FileGenStrategy for CFileGenStrategy {
                     CString::new(json_ret.unwrap()).unwrap().into_raw()
                 }
             }
+            AstType::Optional(ref base) if base.is_primitive() => {
+                quote_c_optional_to(base, quote!(#ret_name_ident))
+            }
+            AstType::Optional(ref base) => {
+                let str_ret = quote_optional_to_str(base, origin_ty, quote!(ret_value))?;
+                quote! {
+                    match #ret_name_ident {
+                        Some(ret_value) => {
+                            let str_ret = #str_ret;
+                            CString::new(str_ret).unwrap().into_raw()
+                        }
+                        None => std::ptr::null_mut(),
+                    }
+                }
+            }
+            AstType::Map(ref key, ref value) => {
+                let json_ret = quote_map_to_json(key, value, origin_ty, quote!(#ret_name_ident))?;
+                quote! {
+                    let json_ret = #json_ret;
+                    CString::new(json_ret).unwrap().into_raw()
+                }
+            }
+            AstType::Callback => quote! {
+                Box::into_raw(Box::new(#ret_name_ident)) as i64
+            },
             _ => {
                 let ty_ident = self.ty_to_tokens(&ty, TypeDirection::Return)?;
                 quote! {
@@ -285,6 +497,54 @@ impl FileGenStrategy for CFileGenStrategy {
         })
     }
 
+    ///
+    /// write the error to `out_err` as a C string, which is freed by the caller, and return
+    /// a placeholder value.
+    ///
+    fn quote_error_return(&self, method: &MethodDesc) -> Result<TokenStream> {
+        let error_str = quote_error_to_str(method, quote!(err))?;
+        let placeholder = self.quote_placeholder(method);
+
+        Ok(quote! {
+            let error_str = #error_str;
+            if !out_err.is_null() {
+                unsafe {
+                    *out_err = CString::new(error_str).unwrap().into_raw();
+                }
+            }
+            return #placeholder;
+        })
+    }
+
+    ///
+    /// keep the panic message, which is taken by `<crate>_take_panic` and thrown in swift.
+    ///
+    fn quote_panic_return(
+        &self,
+        trait_desc: &TraitDesc,
+        method: &MethodDesc,
+    ) -> Result<TokenStream> {
+        let panic_prefix = format!("rsbind panic in {}.{}", &trait_desc.name, &method.name);
+        let placeholder = self.quote_placeholder(method);
+
+        Ok(quote! {
+            let panic_str = format!("{}: {}", #panic_prefix, panic_message(&err));
+            set_last_panic(panic_str);
+            #placeholder
+        })
+    }
+
+    fn quote_free_object_sig(&self, trait_desc: &TraitDesc) -> Result<TokenStream> {
+        let fun_name = Ident::new(
+            &format!("{}_{}_free_object", &trait_desc.mod_name, &trait_desc.name),
+            Span::call_site(),
+        );
+        Ok(quote! {
+            #[no_mangle]
+            pub extern "C" fn #fun_name(handle: i64)
+        })
+    }
+
     fn ty_to_tokens(&self, ast_type: &AstType, direction: TypeDirection) -> Result<TokenStream> {
         let mut tokens = TokenStream::new();
         match *ast_type {
@@ -294,6 +554,7 @@ impl FileGenStrategy for CFileGenStrategy {
             AstType::Float => tokens.append(Ident::new("f32", Span::call_site())),
             AstType::Double => tokens.append(Ident::new("f64", Span::call_site())),
             AstType::Boolean => tokens.append(Ident::new("i32", Span::call_site())),
+            AstType::Enum => tokens.append(Ident::new("i32", Span::call_site())),
             AstType::String => match direction {
                 TypeDirection::Return => {
                     tokens.append(Punct::new('*', Spacing::Alone));
@@ -314,9 +575,93 @@ impl FileGenStrategy for CFileGenStrategy {
                 let vec_tokens = self.ty_to_tokens(&AstType::String, direction)?;
                 tokens = quote!(#vec_tokens)
             }
+            // primitives are transferred by a presence flag and the value, others as strings,
+            // in which None is transferred as null pointer.
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let optional_ident = c_optional_ident(base);
+                tokens = quote!(#optional_ident)
+            }
+            AstType::Optional(_) => {
+                let optional_tokens = self.ty_to_tokens(&AstType::String, direction)?;
+                tokens = quote!(#optional_tokens)
+            }
+            AstType::Map(_, _) => {
+                let map_tokens = self.ty_to_tokens(&AstType::String, direction)?;
+                tokens = quote!(#map_tokens)
+            }
+            // callbacks returned from rust are transferred by the handle of the wrapper object.
+            AstType::Callback => match direction {
+                TypeDirection::Return => tokens.append(Ident::new("i64", Span::call_site())),
+                TypeDirection::Argument => (),
+            },
             _ => (),
         };
 
         Ok(tokens)
     }
 }
+
+///
+/// The struct passing an `Option<T>` of primitives through C, see `C_OPTIONAL_TYS`.
+/// Only for the primitives, which are checked by `AstBaseType::is_primitive`.
+///
+pub(crate) fn c_optional_ident(base: &AstBaseType) -> Ident {
+    let name = match *base {
+        AstBaseType::Byte => "RsbindOptionalI8",
+        AstBaseType::Long => "RsbindOptionalI64",
+        AstBaseType::Float => "RsbindOptionalF32",
+        AstBaseType::Double => "RsbindOptionalF64",
+        _ => "RsbindOptionalI32",
+    };
+    Ident::new(name, Span::call_site())
+}
+
+///
+/// Convert the struct of an `Option<T>` of primitives transferred through C to the rust value.
+///
+pub(crate) fn quote_c_optional_from(
+    base: &AstBaseType,
+    origin_ty: &str,
+    value: TokenStream,
+) -> TokenStream {
+    let inner = origin_ty.replace("Option<", "").replace(">", "");
+    let inner_ident = Ident::new(&inner, Span::call_site());
+    let inner_value = match *base {
+        AstBaseType::Boolean => quote!(#value.value > 0),
+        _ => quote!(#value.value as #inner_ident),
+    };
+    quote! {
+        if #value.is_some != 0 { Some(#inner_value) } else { None }
+    }
+}
+
+///
+/// Convert an `Option<T>` of primitives to the struct transferred through C.
+///
+pub(crate) fn quote_c_optional_to(base: &AstBaseType, value: TokenStream) -> TokenStream {
+    let optional_ident = c_optional_ident(base);
+    let c_value = match *base {
+        AstBaseType::Boolean => quote!(if value { 1 } else { 0 }),
+        _ => {
+            let c_ty = c_optional_value_ty(base);
+            quote!(value as #c_ty)
+        }
+    };
+    quote! {
+        match #value {
+            Some(value) => #optional_ident { is_some: 1, value: #c_value },
+            None => #optional_ident { is_some: 0, value: Default::default() },
+        }
+    }
+}
+
+fn c_optional_value_ty(base: &AstBaseType) -> Ident {
+    let name = match *base {
+        AstBaseType::Byte => "i8",
+        AstBaseType::Long => "i64",
+        AstBaseType::Float => "f32",
+        AstBaseType::Double => "f64",
+        _ => "i32",
+    };
+    Ident::new(name, Span::call_site())
+}
@@ -1,3 +1,4 @@
+use super::bridge::*;
 use ast::contract::desc::*;
 use ast::types::*;
 use bridge::file::*;
@@ -13,7 +14,7 @@ impl CallbackGenStrategy for CCallbackStrategy {
         arg: &ArgDesc,
         _trait_desc: &TraitDesc,
         callbacks: &Vec<&TraitDesc>,
-    ) -> TokenStream {
+    ) -> Result<TokenStream> {
         let arg_name_ident = Ident::new(&arg.name, Span::call_site());
 
         let struct_name = &format!("{}_struct", arg.name);
@@ -30,15 +31,15 @@ impl CallbackGenStrategy for CCallbackStrategy {
         let mut method_names = Vec::new();
         let mut callback_methods = TokenStream::new();
         let mut callback_struct = TokenStream::new();
-        if callback_desc.is_some() {
-            let callback_desc = callback_desc.unwrap();
+        if let Some(callback_desc) = callback_desc {
             for method in callback_desc.methods.iter() {
-                println!(
+                debug!(
                     "quote method {} in callback {}",
                     method.name, callback_desc.name
                 );
 
                 let mut strs_to_release: Vec<Ident> = vec![];
+                let mut nullable_strs_to_release: Vec<Ident> = vec![];
                 // arguments converting in callback
                 let mut args_convert = TokenStream::new();
                 for cb_arg in method.args.iter() {
@@ -81,6 +82,26 @@ impl CallbackGenStrategy for CCallbackStrategy {
                                         let #cb_arg_name = CString::new(#cb_tmp_arg_name.unwrap()).unwrap().into_raw();
                                     }
                                 }
+                                AstBaseType::Enum => {
+                                    let enum_name = cb_arg
+                                        .origin_ty
+                                        .to_owned()
+                                        .replace("Vec<", "")
+                                        .replace(">", "");
+                                    let mirror_ident = Ident::new(
+                                        &format!("Enum_{}", &enum_name),
+                                        Span::call_site(),
+                                    );
+                                    let cb_tmp_vec_arg_name = Ident::new(
+                                        &format!("c_tmp_vec_{}", cb_arg.name),
+                                        Span::call_site(),
+                                    );
+                                    quote! {
+                                        let #cb_tmp_vec_arg_name = #cb_origin_arg_name.into_iter().map(|each| #mirror_ident::from(each)).collect::<Vec<#mirror_ident>>();
+                                        let #cb_tmp_arg_name = serde_json::to_string(&#cb_tmp_vec_arg_name);
+                                        let #cb_arg_name = CString::new(#cb_tmp_arg_name.unwrap()).unwrap().into_raw();
+                                    }
+                                }
                                 _ => {
                                     quote! {
                                         let #cb_tmp_arg_name = serde_json::to_string(&#cb_origin_arg_name);
@@ -102,8 +123,40 @@ impl CallbackGenStrategy for CCallbackStrategy {
                                 let #cb_arg_name = CString::new(#cb_tmp_arg_name.unwrap()).unwrap().into_raw();
                             }
                         }
+                        AstType::Map(ref key, ref value) => {
+                            strs_to_release.push(cb_arg_name.clone());
+                            let json_value = quote_map_to_json(
+                                key,
+                                value,
+                                &cb_arg.origin_ty,
+                                quote!(#cb_origin_arg_name),
+                            )?;
+                            quote! {
+                                let #cb_arg_name = CString::new(#json_value).unwrap().into_raw();
+                            }
+                        }
+                        AstType::Optional(ref base) if base.is_primitive() => {
+                            let value = quote_c_optional_to(base, quote!(#cb_origin_arg_name));
+                            quote! {
+                                let #cb_arg_name = #value;
+                            }
+                        }
+                        AstType::Optional(ref base) => {
+                            nullable_strs_to_release.push(cb_arg_name.clone());
+                            let str_value = quote_optional_to_str(
+                                base,
+                                &cb_arg.origin_ty,
+                                quote!(#cb_origin_arg_name),
+                            )?;
+                            quote! {
+                                let #cb_arg_name = match #cb_origin_arg_name {
+                                    Some(#cb_origin_arg_name) => CString::new(#str_value).unwrap().into_raw(),
+                                    None => std::ptr::null_mut(),
+                                };
+                            }
+                        }
                         _ => {
-                            let arg_ty_ident = self.ty_to_tokens(&cb_arg.ty).unwrap();
+                            let arg_ty_ident = self.ty_to_tokens(&cb_arg.ty)?;
                             quote! {
                                 let #cb_arg_name = #cb_origin_arg_name as #arg_ty_ident;
                             }
@@ -142,38 +195,61 @@ impl CallbackGenStrategy for CCallbackStrategy {
                         AstType::Void => false,
                         _ => true,
                     })
-                    .map(|arg| match arg.ty {
-                        AstType::Vec(_base_ty) => {
-                            let vec_inner_name =
-                                arg.origin_ty.clone().replace("Vec<", "").replace(">", "");
-                            let vec_innder_ident = Ident::new(&vec_inner_name, Span::call_site());
-                            quote!(Vec<#vec_innder_ident>)
-                        }
-                        _ => {
-                            let ident = Ident::new(&arg.origin_ty, Span::call_site());
-                            quote!(#ident)
-                        }
-                    })
+                    .map(|arg| quote_callback_ty(&arg.ty, &arg.origin_ty))
                     .collect::<Vec<TokenStream>>();
 
-                let ret_ty_tokens = match method.return_type {
-                    AstType::Void => quote!(()),
-                    _ => {
-                        let ident = Ident::new(&method.origin_return_ty, Span::call_site());
-                        quote!(#ident)
-                    }
-                };
+                let ret_ty_tokens =
+                    quote_callback_ty(&method.return_type, &method.origin_return_ty);
 
                 let return_convert = match method.return_type {
                     AstType::Void => quote!(),
                     AstType::Boolean => quote! {
                         let s_result = if result > 0 {true} else {false};
                     },
-                    AstType::String => quote! {
-                        let s_result_c_str: &CStr = unsafe { CStr::from_ptr(result) };
-                        let s_result_str: &str = s_result_str.to_str().unwrap();
-                        let s_result: String = s_result_str.to_owned();
-                    },
+                    AstType::Enum => {
+                        let mirror_ident = Ident::new(
+                            &format!("Enum_{}", &method.origin_return_ty),
+                            Span::call_site(),
+                        );
+                        quote! {
+                            let s_result = #mirror_ident::from_i32(result).into();
+                        }
+                    }
+                    // strings returned from callbacks are copied, and freed by the caller's `free_ptr`.
+                    AstType::String | AstType::Vec(_) | AstType::Struct => {
+                        let value = quote_callback_return_from_str(
+                            &method.return_type,
+                            &method.origin_return_ty,
+                            quote!(r_str),
+                        )?;
+                        quote! {
+                            let r_str: String = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_owned();
+                            let free_ptr = self.free_ptr;
+                            free_ptr(result);
+                            let s_result = #value;
+                        }
+                    }
+                    AstType::Optional(ref base) if base.is_primitive() => {
+                        let value =
+                            quote_c_optional_from(base, &method.origin_return_ty, quote!(result));
+                        quote! {
+                            let s_result = #value;
+                        }
+                    }
+                    AstType::Optional(ref base) => {
+                        let value =
+                            quote_optional_from_str(base, &method.origin_return_ty, quote!(r_str))?;
+                        quote! {
+                            let s_result = if result.is_null() {
+                                None
+                            } else {
+                                let r_str: String = unsafe { CStr::from_ptr(result) }.to_str().unwrap().to_owned();
+                                let free_ptr = self.free_ptr;
+                                free_ptr(result);
+                                Some(#value)
+                            };
+                        }
+                    }
                     _ => quote! {
                         let s_result = result as #ret_ty_tokens;
                     },
@@ -187,13 +263,33 @@ impl CallbackGenStrategy for CCallbackStrategy {
 
                 // methods calls on impl
                 let method_name = Ident::new(&method.name, Span::call_site());
+                let rust_method_name = Ident::new(method.rust_name(), Span::call_site());
                 let fn_method_name = Ident::new(&format!("fn_{}", method.name), Span::call_site());
+                // a variable can only be used once in a repetition of quote.
+                let nullable_strs_to_check = nullable_strs_to_release.clone();
+                // options of primitives are returned by an out pointer, as some ffi libraries
+                // can't return structs from callbacks.
+                let call_method = match method.return_type {
+                    AstType::Optional(ref base) if base.is_primitive() => {
+                        let optional_ident = c_optional_ident(base);
+                        quote! {
+                            let mut result = #optional_ident { is_some: 0, value: Default::default() };
+                            #fn_method_name(self.index, #(#convert_arg_names,)* &mut result);
+                        }
+                    }
+                    _ => quote! {
+                        let result = #fn_method_name(self.index, #(#convert_arg_names),*);
+                    },
+                };
                 let method_result = quote! {
-                    fn #method_name(&self, #(#arg_names: #arg_types),*) -> #ret_ty_tokens {
+                    fn #rust_method_name(&self, #(#arg_names: #arg_types),*) -> #ret_ty_tokens {
                         #args_convert
                         let #fn_method_name = self.#method_name;
-                        let result = #fn_method_name(self.index, #(#convert_arg_names),*);
+                        #call_method
                         #(unsafe {CString::from_raw(#strs_to_release)};)*
+                        #(if !#nullable_strs_to_check.is_null() {
+                            unsafe {CString::from_raw(#nullable_strs_to_release)};
+                        })*
                         #return_convert
                         #return_var_name
                     }
@@ -207,9 +303,7 @@ impl CallbackGenStrategy for CCallbackStrategy {
                 method_names.push(method_name);
             }
 
-            callback_struct = self
-                .quote_callback_struct(callback_desc, struct_name)
-                .unwrap();
+            callback_struct = self.quote_callback_struct(callback_desc, struct_name)?;
         }
 
         // xxxx : arg.xxxx
@@ -226,7 +320,7 @@ impl CallbackGenStrategy for CCallbackStrategy {
         }
 
         // total converting codes.
-        quote! {
+        Ok(quote! {
             #callback_struct
 
             impl #callback_ty for #struct_ident {
@@ -243,10 +337,11 @@ impl CallbackGenStrategy for CCallbackStrategy {
             let #converted_callback_name = Box::new(#struct_ident {
                 #method_assign_tokens
                 free_callback: #arg_name_ident.free_callback,
+                free_ptr: #arg_name_ident.free_ptr,
                 index: #arg_name_ident.index,
             });
 
-        }
+        })
     }
 }
 
@@ -265,9 +360,18 @@ impl CCallbackStrategy {
         let mut callback_methods = TokenStream::new();
         for method in trait_desc.methods.iter() {
             let callback_method_ident = Ident::new(&method.name, Span::call_site());
+            let mut out_arg = quote!();
             let ret_ty_tokens = match method.return_type {
                 AstType::Void => quote!(()),
-                _ => self.ty_to_tokens(&method.return_type).unwrap(),
+                AstType::Optional(ref base) if base.is_primitive() => {
+                    let optional_ident = c_optional_ident(base);
+                    out_arg = quote!(*mut #optional_ident);
+                    quote!(())
+                }
+                AstType::String | AstType::Vec(_) | AstType::Struct | AstType::Optional(_) => {
+                    quote!(*mut c_char)
+                }
+                _ => self.ty_to_tokens(&method.return_type)?,
             };
 
             let arg_types = method
@@ -277,12 +381,12 @@ impl CCallbackStrategy {
                     AstType::Void => false,
                     _ => true,
                 })
-                .map(|arg| self.ty_to_tokens(&arg.ty).unwrap())
-                .collect::<Vec<TokenStream>>();
+                .map(|arg| self.ty_to_tokens(&arg.ty))
+                .collect::<Result<Vec<TokenStream>>>()?;
 
             callback_methods = quote! {
                 #callback_methods
-                pub #callback_method_ident: extern "C" fn(i64, #(#arg_types),*) -> #ret_ty_tokens,
+                pub #callback_method_ident: extern "C" fn(i64, #(#arg_types,)* #out_arg) -> #ret_ty_tokens,
             }
         }
 
@@ -290,6 +394,7 @@ impl CCallbackStrategy {
             #callback_struct_sig {
                 #callback_methods
                 pub free_callback: extern "C" fn(i64),
+                pub free_ptr: extern "C" fn(*mut c_char),
                 pub index: i64,
 
             }
@@ -307,19 +412,32 @@ impl CCallbackStrategy {
             AstType::Float => tokens.append(Ident::new("f32", Span::call_site())),
             AstType::Double => tokens.append(Ident::new("f64", Span::call_site())),
             AstType::Boolean => tokens.append(Ident::new("i32", Span::call_site())),
+            AstType::Enum => tokens.append(Ident::new("i32", Span::call_site())),
             AstType::String => {
                 tokens.append(Punct::new('*', Spacing::Alone));
                 tokens.append(Ident::new("const", Span::call_site()));
                 tokens.append(Ident::new("c_char", Span::call_site()));
             }
             AstType::Struct => {
-                let struct_tokens = self.ty_to_tokens(&AstType::String).unwrap();
+                let struct_tokens = self.ty_to_tokens(&AstType::String)?;
                 tokens = quote!(#struct_tokens)
             }
             AstType::Vec(_) => {
-                let vec_tokens = self.ty_to_tokens(&AstType::String).unwrap();
+                let vec_tokens = self.ty_to_tokens(&AstType::String)?;
                 tokens = quote!(#vec_tokens)
             }
+            AstType::Optional(ref base) if base.is_primitive() => {
+                let optional_ident = c_optional_ident(base);
+                tokens = quote!(#optional_ident)
+            }
+            AstType::Optional(_) => {
+                let optional_tokens = self.ty_to_tokens(&AstType::String)?;
+                tokens = quote!(#optional_tokens)
+            }
+            AstType::Map(_, _) => {
+                let map_tokens = self.ty_to_tokens(&AstType::String)?;
+                tokens = quote!(#map_tokens)
+            }
             _ => (),
         };
 
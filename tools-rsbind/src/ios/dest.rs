@@ -5,7 +5,7 @@ use std::path::PathBuf;
 use std::process::Command;
 
 pub fn gen_swift_code(prj_dir: &PathBuf, ast_dir: &PathBuf, bin_dir: &PathBuf) -> Result<()> {
-    print!("gen_swift_code");
+    info!("gen_swift_code");
 
     fs::create_dir_all(&bin_dir)
         .or_else(|_e| Err(FileError("create bin dir failed.".to_string())))?;
@@ -24,7 +24,9 @@ pub fn gen_swift_code(prj_dir: &PathBuf, ast_dir: &PathBuf, bin_dir: &PathBuf) -
     })?;
     fs::write(&bin_file_path, swift_gen_bin_buf)?;
 
-    let ast_dir_tmp = ast_dir.canonicalize().unwrap();
+    let ast_dir_tmp = ast_dir
+        .canonicalize()
+        .chain_err(|| FileError(format!("can't find ast dir {:?}", ast_dir)))?;
     let ast_dir_str = ast_dir_tmp
         .to_str()
         .ok_or(FileError("get ast dir path string error".to_string()))?;
@@ -38,17 +40,22 @@ pub fn gen_swift_code(prj_dir: &PathBuf, ast_dir: &PathBuf, bin_dir: &PathBuf) -
     }
     fs::create_dir_all(&swift_gen_path)?;
 
-    let swift_gen_dir_tmp = swift_gen_path.canonicalize().unwrap();
+    let swift_gen_dir_tmp = swift_gen_path
+        .canonicalize()
+        .chain_err(|| FileError(format!("can't find swift gen dir {:?}", &swift_gen_path)))?;
     let swift_gen_dir_str = swift_gen_dir_tmp
         .to_str()
         .ok_or(FileError("get swift gen dir str wrong.".to_string()))?;
 
-    let output_dir_tmp = prj_dir.join("rustlib").canonicalize().unwrap();
+    let output_dir_tmp = prj_dir
+        .join("rustlib")
+        .canonicalize()
+        .chain_err(|| FileError(format!("can't find rustlib dir in {:?}", prj_dir)))?;
     let output_dir_str = output_dir_tmp
         .to_str()
         .ok_or(FileError("get swift dir path string error.".to_string()))?;
 
-    println!(
+    debug!(
         "generating swift code, ast dir = {}, out dir = {}",
         ast_dir_str, swift_gen_dir_str
     );
@@ -64,7 +71,7 @@ pub fn gen_swift_code(prj_dir: &PathBuf, ast_dir: &PathBuf, bin_dir: &PathBuf) -
         .current_dir(bin_dir)
         .output()?;
 
-    print!("gen_swift_code over");
+    info!("gen_swift_code over");
 
     if !output.status.success() {
         return Err(
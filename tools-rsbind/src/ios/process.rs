@@ -8,9 +8,8 @@ use errors::ErrorKind::*;
 use errors::*;
 use fs_extra;
 use fs_extra::dir::CopyOptions;
-use process::BuildProcess;
+use process::{print_output, BuildProcess};
 use std::fs;
-use std::io::{self, Write};
 use std::path::PathBuf;
 use std::process::Command;
 use unzip;
@@ -71,7 +70,9 @@ impl<'a> IosProcess<'a> {
             ..Default::default()
         };
 
-        let root_path = self.bridge_prj_path.to_str().unwrap();
+        let root_path = self.bridge_prj_path.to_str().ok_or(FileError(
+            "get bridge project path string error".to_string(),
+        ))?;
         cbindgen::generate_with_config(root_path, config)?.write_to_file(&output_file);
         Ok(())
     }
@@ -99,11 +100,14 @@ impl<'a> BuildProcess for IosProcess<'a> {
     }
 
     fn gen_bridge_src(&self) -> Result<()> {
-        println!("begin unzip rust template for ios");
+        info!("begin unzip rust template for ios");
         let buf: &[u8] = include_bytes!("res/template_bridge_ios.zip");
         let unpack = Unpack {
             path: self.bridge_prj_path,
             host_crate: self.host_crate_name,
+            host_path: self.origin_prj_path,
+            contract_mod: &self.ast_result.contract_mod,
+            imp_mod: &self.ast_result.imp_mod,
             buf,
             features: &self.config().features(),
         };
@@ -117,7 +121,8 @@ impl<'a> BuildProcess for IosProcess<'a> {
             self.ast_result,
             &bridge_c_src_path,
         )
-        .gen_bridges()?;
+        .gen_bridges()
+        .chain_err(|| GenerateError("can't generate c bridges".to_string()))?;
 
         let _ = Command::new("cargo")
             .arg("fmt")
@@ -128,7 +133,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
     }
 
     fn build_bridge_prj(&self) -> Result<()> {
-        println!("run building rust project for iOS");
+        info!("run building rust project for iOS");
 
         let debug_release = if self.config().is_release() {
             "release"
@@ -187,7 +192,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
 
         build_cmds = format!("{} && {}", &build_cmds, &lipo_cmd);
 
-        println!("run building => {}", &build_cmds);
+        debug!("run building => {}", &build_cmds);
 
         let output = Command::new("sh")
             .arg("-c")
@@ -195,14 +200,13 @@ impl<'a> BuildProcess for IosProcess<'a> {
             .current_dir(self.bridge_prj_path)
             .output()?;
 
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
+        print_output(&output)?;
 
         if !output.status.success() {
             return Err(CommandError(format!("run build rust project build failed.",)).into());
         }
 
-        println!("begin strip lib");
+        info!("begin strip lib");
         let strip_result = Command::new("strip")
             .arg("-S")
             .arg(&format!(
@@ -214,10 +218,9 @@ impl<'a> BuildProcess for IosProcess<'a> {
             .output();
 
         match strip_result {
-            Err(err) => println!("strip error, err = {:?}", err),
+            Err(err) => info!("strip error, err = {:?}", err),
             Ok(output) => {
-                io::stdout().write_all(&output.stdout)?;
-                io::stderr().write_all(&output.stderr)?;
+                print_output(&output)?;
             }
         }
 
@@ -225,7 +228,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
     }
 
     fn copy_bridge_outputs(&self) -> Result<()> {
-        println!("copy output files to swift project.");
+        info!("copy output files to swift project.");
 
         let header_file = self.header_path.join("ffi.h");
         let header_dest = self.dest_prj_path.join("rustlib");
@@ -260,16 +263,14 @@ impl<'a> BuildProcess for IosProcess<'a> {
         fs::rename(&lib_dest.join(&self.lib_name()), &lib_dest.join("ffi.a"))
             .map_err(|e| FileError(format!("rename ffi.a failed. {:?}", e)))?;
 
-        println!("copy output files to swift project over.");
+        debug!("copy output files to swift project over.");
 
         Ok(())
     }
 
     fn gen_bind_code(&self) -> Result<()> {
-        println!("begin unzip ios template");
-        if self.dest_prj_path.exists() {
-            fs::remove_dir_all(&self.dest_prj_path)?;
-        }
+        info!("begin unzip ios template");
+        // the project is kept between runs, files are only rewritten if they change.
         fs::create_dir_all(&self.dest_prj_path)?;
         let ios_template_buf: &[u8] = include_bytes!("res/template_ios.zip");
         unzip::unzip_to(ios_template_buf, &self.dest_prj_path)?;
@@ -280,7 +281,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
     }
 
     fn build_dest_prj(&self) -> Result<()> {
-        println!("run building swift project");
+        info!("run building swift project");
 
         // prj file
         let prj_file = self.dest_prj_path.join("rustlib.xcodeproj");
@@ -297,7 +298,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
             fs::remove_dir_all(&simu_output_dir)?;
         }
         fs::create_dir_all(&simu_output_dir)?;
-        let simu_output_dir_path = simu_output_dir.canonicalize().unwrap();
+        let simu_output_dir_path = simu_output_dir.canonicalize()?;
         let simu_output_dir_str = simu_output_dir_path
             .to_str()
             .ok_or(FileError(format!("can't get ios outupt file string")))?;
@@ -310,7 +311,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
             fs::remove_dir_all(&iphone_output_dir)?;
         }
         fs::create_dir_all(&iphone_output_dir)?;
-        let iphone_output_dir_path = iphone_output_dir.canonicalize().unwrap();
+        let iphone_output_dir_path = iphone_output_dir.canonicalize()?;
         let iphone_output_dir_str = iphone_output_dir_path
             .to_str()
             .ok_or(FileError(format!("can't get ios outupt file string")))?;
@@ -323,14 +324,14 @@ impl<'a> BuildProcess for IosProcess<'a> {
             fs::remove_dir_all(&universal_output_dir)?;
         }
         fs::create_dir_all(&universal_output_dir)?;
-        let universal_output_dir_path = universal_output_dir.canonicalize().unwrap();
+        let universal_output_dir_path = universal_output_dir.canonicalize()?;
         let universal_output_dir_str = universal_output_dir_path
             .to_str()
             .ok_or(FileError(format!("can't get ios outupt file string")))?;
 
-        println!("archive swift path: {}", simu_output_dir_str);
-        println!("archive swift path: {}", iphone_output_dir_str);
-        println!("archive swift path: {}", universal_output_dir_str);
+        debug!("archive swift path: {}", simu_output_dir_str);
+        debug!("archive swift path: {}", iphone_output_dir_str);
+        debug!("archive swift path: {}", universal_output_dir_str);
 
         let build_cmd1 = format!("xcodebuild -scheme rustlib -project {} -sdk iphonesimulator  -configuration Release CONFIGURATION_BUILD_DIR={} clean build", prj_file_str, simu_output_dir_str);
         let build_cmd2 = format!("xcodebuild -scheme rustlib -project {} -sdk iphoneos -configuration Release CONFIGURATION_BUILD_DIR={} clean build", prj_file_str, iphone_output_dir_str);
@@ -351,8 +352,7 @@ impl<'a> BuildProcess for IosProcess<'a> {
             .current_dir(self.dest_prj_path)
             .output()?;
 
-        io::stdout().write_all(&output.stdout)?;
-        io::stderr().write_all(&output.stderr)?;
+        print_output(&output)?;
 
         if !output.status.success() {
             return Err(
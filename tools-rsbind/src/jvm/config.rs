@@ -0,0 +1,77 @@
+const NAMESPACE: &str = "com.afoxer.xxx.ffi";
+const SO_NAME: &str = "ffi";
+
+///
+/// Jvm Configuration struct, for the jar running on the host.
+///
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct Jvm {
+    pub rustc_param: Option<String>,
+    pub release: Option<bool>,
+    pub namespace: Option<String>,
+    pub so_name: Option<String>,
+    pub ext_lib: Option<Vec<String>>,
+    pub features_def: Option<Vec<String>>,
+    /// the jars needed to compile the generated java sources, like fastjson.
+    pub classpath: Option<Vec<String>>,
+}
+
+impl Jvm {
+    pub fn namespace(&self) -> String {
+        match self.namespace {
+            Some(ref namespace) => namespace.to_owned(),
+            None => NAMESPACE.to_owned(),
+        }
+    }
+
+    pub fn rustc_param(&self) -> String {
+        let init = "--features rsbind";
+        match self.rustc_param {
+            Some(ref rustc) => format!("{} {}", rustc, init),
+            None => init.to_owned(),
+        }
+    }
+
+    pub fn is_release(&self) -> bool {
+        match self.release {
+            Some(is_release) => is_release,
+            None => true,
+        }
+    }
+
+    pub fn release_str(&self) -> String {
+        if self.is_release() {
+            "--release".to_owned()
+        } else {
+            "".to_owned()
+        }
+    }
+
+    pub fn ext_libs(&self) -> String {
+        match self.ext_lib {
+            Some(ref ext_lib) => ext_lib.join(","),
+            None => String::new(),
+        }
+    }
+
+    pub fn so_name(&self) -> String {
+        match self.so_name {
+            Some(ref so_name) => so_name.to_owned(),
+            None => SO_NAME.to_owned(),
+        }
+    }
+
+    pub fn features(&self) -> Vec<String> {
+        match self.features_def {
+            Some(ref features) => features.to_owned(),
+            None => vec![],
+        }
+    }
+
+    pub fn classpath(&self) -> Vec<String> {
+        match self.classpath {
+            Some(ref classpath) => classpath.to_owned(),
+            None => vec![],
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use android::dest::JavaCodeGen;
+use ast::AstResult;
+use bridge::prj::Unpack;
+use bridges::BridgeGen::JavaGen;
+use errors::ErrorKind::*;
+use errors::*;
+use process::{print_output, BuildProcess};
+use std::env;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use super::config::Jvm;
+
+const JAR_NAME: &str = "rustlib.jar";
+const CLASSES_DIR: &str = "classes";
+
+///
+/// Build the jni bridge for the host, and a jar of the generated java sources, so the bindings
+/// can be called from java without an android device.
+///
+pub(crate) struct JvmProcess<'a> {
+    origin_prj_path: &'a PathBuf,
+    dest_prj_path: &'a PathBuf,
+    bridge_prj_path: &'a PathBuf,
+    host_crate_name: &'a str,
+    ast_result: &'a AstResult,
+    config: Option<Jvm>,
+}
+
+impl<'a> JvmProcess<'a> {
+    pub fn new(
+        origin_prj_path: &'a PathBuf,
+        dest_prj_path: &'a PathBuf,
+        bridge_prj_path: &'a PathBuf,
+        host_crate_name: &'a str,
+        ast_result: &'a AstResult,
+        config: Option<Jvm>,
+    ) -> Self {
+        JvmProcess {
+            origin_prj_path,
+            dest_prj_path,
+            bridge_prj_path,
+            host_crate_name,
+            ast_result,
+            config,
+        }
+    }
+}
+
+impl<'a> JvmProcess<'a> {
+    fn lib_name(&self) -> String {
+        format!(
+            "{}{}_jvm_bridge_prj{}",
+            DLL_PREFIX,
+            &self.host_crate_name.replace("-", "_"),
+            DLL_SUFFIX
+        )
+    }
+
+    ///
+    /// the name of the library loaded by `System.loadLibrary(so_name)` on the host.
+    ///
+    fn dest_lib_name(&self) -> String {
+        format!("{}{}{}", DLL_PREFIX, &self.config().so_name(), DLL_SUFFIX)
+    }
+
+    fn config(&self) -> Jvm {
+        match self.config {
+            Some(ref config) => config.to_owned(),
+            None => Jvm::default(),
+        }
+    }
+
+    ///
+    /// the jars in the config are relative to the origin project.
+    ///
+    fn classpath(&self) -> Result<Option<String>> {
+        let mut jars = vec![];
+        for jar in self.config().classpath().iter() {
+            let path = self.origin_prj_path.join(jar);
+            let path = path
+                .canonicalize()
+                .chain_err(|| ConfigError(format!("can't find {:?} in jvm classpath", path)))?;
+            jars.push(path);
+        }
+        if jars.is_empty() {
+            return Ok(None);
+        }
+
+        let classpath = env::join_paths(jars)
+            .chain_err(|| ConfigError("jvm classpath contains a separator".to_string()))?;
+        let classpath = classpath
+            .into_string()
+            .map_err(|e| ConfigError(format!("jvm classpath is not utf-8, {:?}", e)))?;
+        Ok(Some(classpath))
+    }
+}
+
+impl<'a> BuildProcess for JvmProcess<'a> {
+    fn unpack(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn gen_bridge_src(&self) -> Result<()> {
+        info!("begin unzip rust template for jvm");
+        let buf: &[u8] = include_bytes!("res/template_bridge_jvm.zip");
+        let unpack = Unpack {
+            path: self.bridge_prj_path,
+            host_crate: self.host_crate_name,
+            host_path: self.origin_prj_path,
+            contract_mod: &self.ast_result.contract_mod,
+            imp_mod: &self.ast_result.imp_mod,
+            buf,
+            features: &self.config().features(),
+        };
+
+        unpack.unpack()?;
+
+        let bridge_src_path = self.bridge_prj_path.join("src").join("java").join("bridge");
+        fs::create_dir_all(&bridge_src_path)?;
+        JavaGen(
+            self.host_crate_name.to_owned(),
+            self.ast_result,
+            &bridge_src_path,
+            self.config().namespace(),
+        )
+        .gen_bridges()
+        .chain_err(|| GenerateError("can't generate java bridges".to_string()))?;
+
+        let _ = Command::new("cargo")
+            .arg("fmt")
+            .current_dir(&self.bridge_prj_path)
+            .output();
+
+        Ok(())
+    }
+
+    fn build_bridge_prj(&self) -> Result<()> {
+        info!("building jvm bridge project");
+
+        // no `--target`, the library is built for the host.
+        let build_cmd = format!(
+            "cargo rustc --lib {} --target-dir {} {}",
+            self.config().release_str(),
+            "target",
+            &self.config().rustc_param()
+        );
+
+        debug!("run building => {}", &build_cmd);
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(build_cmd)
+            .current_dir(self.bridge_prj_path)
+            .output()?;
+
+        print_output(&output)?;
+
+        if !output.status.success() {
+            return Err(CommandError(format!("run build jvm rust project build failed.")).into());
+        }
+
+        Ok(())
+    }
+
+    fn copy_bridge_outputs(&self) -> Result<()> {
+        info!("copy output files to jvm project.");
+
+        let debug_release = if self.config().is_release() {
+            "release"
+        } else {
+            "debug"
+        };
+
+        let lib_src = self
+            .bridge_prj_path
+            .join("target")
+            .join(debug_release)
+            .join(&self.lib_name());
+        let lib_dest = self.dest_prj_path.join("lib");
+        fs::create_dir_all(&lib_dest)?;
+        fs::copy(&lib_src, &lib_dest.join(&self.dest_lib_name()))
+            .chain_err(|| FileError(format!("copy jvm bridge output {:?} failed.", &lib_src)))?;
+
+        Ok(())
+    }
+
+    fn gen_bind_code(&self) -> Result<()> {
+        info!("generate java code for jvm.");
+        let src_path = self.dest_prj_path.join("src");
+        if src_path.exists() {
+            fs::remove_dir_all(&src_path)?;
+        }
+
+        let namespace = self.config().namespace();
+        let java_gen_path = namespace
+            .split(".")
+            .fold(src_path, |path, pkg_part| path.join(pkg_part));
+        fs::create_dir_all(&java_gen_path)?;
+
+        JavaCodeGen {
+            origin_prj: self.origin_prj_path,
+            java_gen_dir: &java_gen_path,
+            ast: &self.ast_result,
+            namespace,
+            so_name: self.config().so_name(),
+            ext_libs: self.config().ext_libs(),
+            nullable: false,
+        }
+        .gen_java_code()
+    }
+
+    fn build_dest_prj(&self) -> Result<()> {
+        info!("build jvm jar.");
+
+        let classes_path = self.dest_prj_path.join(CLASSES_DIR);
+        if classes_path.exists() {
+            fs::remove_dir_all(&classes_path)?;
+        }
+        fs::create_dir_all(&classes_path)?;
+
+        let mut javac = Command::new("javac");
+        javac.args(&["-encoding", "UTF-8", "-d", CLASSES_DIR]);
+        if let Some(classpath) = self.classpath()? {
+            javac.arg("-cp").arg(classpath);
+        }
+        let src_path = self.dest_prj_path.join("src");
+        for java_file in java_files(&src_path)?.iter() {
+            let relative = java_file
+                .strip_prefix(self.dest_prj_path)
+                .unwrap_or(java_file);
+            javac.arg(relative);
+        }
+
+        let output = javac
+            .current_dir(self.dest_prj_path)
+            .output()
+            .chain_err(|| CommandError("can't run javac, is jdk installed?".to_string()))?;
+        print_output(&output)?;
+        if !output.status.success() {
+            return Err(CommandError(format!("compile java sources for jvm failed.")).into());
+        }
+
+        let output = Command::new("jar")
+            .args(&["cf", JAR_NAME, "-C", CLASSES_DIR, "."])
+            .current_dir(self.dest_prj_path)
+            .output()
+            .chain_err(|| CommandError("can't run jar, is jdk installed?".to_string()))?;
+        print_output(&output)?;
+        if !output.status.success() {
+            return Err(CommandError(format!("package the jar for jvm failed.")).into());
+        }
+
+        // the jar and the library are put together, run java with
+        // `-Djava.library.path=target/jvm -cp target/jvm/rustlib.jar`.
+        let target = self.origin_prj_path.join("target").join("jvm");
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::create_dir_all(&target)?;
+
+        let outputs = vec![
+            (self.dest_prj_path.join(JAR_NAME), target.join(JAR_NAME)),
+            (
+                self.dest_prj_path.join("lib").join(&self.dest_lib_name()),
+                target.join(&self.dest_lib_name()),
+            ),
+        ];
+        for (from, to) in outputs.iter() {
+            fs::copy(from, to)
+                .chain_err(|| FileError(format!("copy jvm output {:?} failed.", from)))?;
+        }
+
+        Ok(())
+    }
+}
+
+///
+/// find the java sources in `dir` recursively.
+///
+fn java_files(dir: &PathBuf) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(java_files(&path)?);
+        } else if path.extension().map_or(false, |ext| ext == "java") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::AstHandler;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn generates_the_bridge_and_java_sources_of_a_crate() {
+        let prj = TempDir::with_files(
+            "jvm_process",
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"demo-sdk\"\nversion = \"1.2.3\"\n",
+                ),
+                ("src/contract/mod.rs", "pub mod demo;"),
+                (
+                    "src/contract/demo.rs",
+                    r#"
+                    pub trait Greeter {
+                        fn greet(name: String, person: Person) -> Option<String>;
+                    }
+                    pub struct Person {
+                        pub name: String,
+                        pub age: i32,
+                    }
+                    "#,
+                ),
+                ("src/imp/mod.rs", "pub mod demo;"),
+                (
+                    "src/imp/demo.rs",
+                    r#"
+                    use contract::demo::*;
+                    pub struct GreeterImp {}
+                    impl Greeter for GreeterImp {
+                        fn greet(name: String, person: Person) -> Option<String> {
+                            Some(format!("hello {} {}", name, person.name))
+                        }
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let ast_result = AstHandler::new("demo-sdk".to_owned(), &Common::default())
+            .parse(prj.path())
+            .unwrap();
+        let dest = prj.path().join("jvm_dest");
+        let bridge = prj.path().join("jvm_bridge");
+        let process = JvmProcess::new(prj.path(), &dest, &bridge, "demo-sdk", &ast_result, None);
+
+        process.gen_bridge_src().unwrap();
+        let code = fs::read_to_string(bridge.join("src/java/bridge/java_demo.rs"))
+            .unwrap()
+            .replace(char::is_whitespace, "");
+        assert!(code.contains(
+            "pubextern\"C\"fnJava_com_afoxer_xxx_ffi_Greeter_native_1greet(env:JNIEnv,class:JClass,name:JString,person:JString)->jstring{"
+        ));
+        assert!(code.contains("pubstructStruct_Person{pubname:String,pubage:i32}"));
+
+        // the java sources are in the directories of the namespace.
+        process.gen_bind_code().unwrap();
+        let java_dir = dest.join("src/com/afoxer/xxx/ffi");
+        assert!(java_dir.join("Greeter.java").is_file());
+        assert!(java_dir.join("Person.java").is_file());
+    }
+}
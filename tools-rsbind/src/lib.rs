@@ -1,4 +1,4 @@
-#![recursion_limit = "128"]
+#![recursion_limit = "256"]
 extern crate syn;
 #[macro_use]
 extern crate quote;
@@ -16,26 +16,44 @@ extern crate error_chain;
 #[macro_use]
 extern crate genco;
 
+// the logging macros must be defined before the mods using them.
+#[macro_use]
+mod log;
 mod android;
 mod ast;
 mod bridge;
 mod bridges;
+mod c;
 mod cargo;
 mod config;
-mod errors;
+pub mod errors;
+mod incremental;
+mod init;
 mod ios;
+mod jvm;
+mod node;
 mod process;
+mod python;
+#[cfg(test)]
+mod temp_dir;
 mod unzip;
+mod watch;
 
 use android::process::AndroidProcess;
 use ast::AstResult;
+use c::process::CProcess;
+use config::Common;
 use errors::*;
+use incremental::Fingerprint;
 use ios::process::IosProcess;
+use jvm::process::JvmProcess;
+pub use log::Verbosity;
+use node::process::NodeProcess;
 use process::*;
+use python::process::PythonProcess;
+use std::fmt::Debug;
 use std::fs;
 use std::path::PathBuf;
-use android::config::Android;
-use ios::config::Ios;
 
 const GEN_DIR_NAME: &str = "_gen";
 const HEADER_NAME: &str = "header";
@@ -44,24 +62,60 @@ const IOS_PROJ: &str = "ios_dest";
 const IOS_BRIDGE_PROJ: &str = "ios_bridge";
 const ANDROID_BRIDGE_PROJ: &str = "android_bridge";
 const ANDROID_PROJ: &str = "android_dest";
+const JVM_BRIDGE_PROJ: &str = "jvm_bridge";
+const JVM_PROJ: &str = "jvm_dest";
+const C_BRIDGE_PROJ: &str = "c_bridge";
+const C_PROJ: &str = "c_dest";
+const PYTHON_BRIDGE_PROJ: &str = "python_bridge";
+const PYTHON_PROJ: &str = "python_dest";
+const NODE_BRIDGE_PROJ: &str = "node_bridge";
+const NODE_PROJ: &str = "node_dest";
 const BIN_DIR: &str = "bin";
+const IOS_TARGET: &str = "ios";
+const ANDROID_TARGET: &str = "android";
+const JVM_TARGET: &str = "jvm";
+const C_TARGET: &str = "c";
+const PYTHON_TARGET: &str = "python";
+const NODE_TARGET: &str = "node";
 
 pub struct Bind {
     prj_path: PathBuf,
+    gen_path: PathBuf,
     ios_dest_path: PathBuf,
     ios_bridge_path: PathBuf,
     android_bridge_path: PathBuf,
     android_dest_path: PathBuf,
+    jvm_bridge_path: PathBuf,
+    jvm_dest_path: PathBuf,
+    c_bridge_path: PathBuf,
+    c_dest_path: PathBuf,
+    python_bridge_path: PathBuf,
+    python_dest_path: PathBuf,
+    node_bridge_path: PathBuf,
+    node_dest_path: PathBuf,
     header_path: PathBuf,
     ast_path: PathBuf,
     bin_path: PathBuf,
     target: Target,
     action: Action,
+    config_path: Option<PathBuf>,
+    release: Option<bool>,
+    verbosity: Verbosity,
+    crate_name: Option<String>,
 }
 
 pub enum Target {
     Android,
     Ios,
+    /// the jni bridge and a jar for the host, to call the bindings without an android device.
+    Jvm,
+    /// the shared and static libraries with a header for c programs, on linux or macOS.
+    C,
+    /// a python package calling the c bridge by ctypes, on linux or macOS.
+    Python,
+    /// an npm package loading the N-API bridge, for node or electron on linux or macOS.
+    Node,
+    /// android and iOS.
     All,
 }
 
@@ -70,8 +124,19 @@ pub enum Action {
     GenBridge,
     GenBindSrc,
     GenCHeader,
+    /// generate the bridge and the binding sources, without building them.
+    Gen,
     Build,
     All,
+    /// parse and validate the contracts, nothing is generated.
+    Check,
+    /// remove all the generated files.
+    Clean,
+    /// scaffold the contracts, the implementations and Rsbind.toml in a new or existing crate.
+    Init,
+    /// generate like `Gen`, and again whenever the contracts, the implementations or the
+    /// config change.
+    Watch,
 }
 
 impl Bind {
@@ -82,52 +147,157 @@ impl Bind {
     ///
     pub fn from(prj_path: String, target: Target, action: Action) -> Bind {
         let root = PathBuf::from(&prj_path);
+        let gen_path = root.join(GEN_DIR_NAME);
+
+        let mut bind = Bind {
+            prj_path: root,
+            gen_path: PathBuf::new(),
+            ios_dest_path: PathBuf::new(),
+            ios_bridge_path: PathBuf::new(),
+            android_bridge_path: PathBuf::new(),
+            android_dest_path: PathBuf::new(),
+            jvm_bridge_path: PathBuf::new(),
+            jvm_dest_path: PathBuf::new(),
+            c_bridge_path: PathBuf::new(),
+            c_dest_path: PathBuf::new(),
+            python_bridge_path: PathBuf::new(),
+            python_dest_path: PathBuf::new(),
+            node_bridge_path: PathBuf::new(),
+            node_dest_path: PathBuf::new(),
+            header_path: PathBuf::new(),
+            ast_path: PathBuf::new(),
+            bin_path: PathBuf::new(),
+            target,
+            action,
+            config_path: None,
+            release: None,
+            verbosity: Verbosity::Normal,
+            crate_name: None,
+        };
+        bind.set_gen_path(gen_path);
+        bind
+    }
+
+    ///
+    /// generate the files into `out_dir` instead of `_gen` in the project.
+    ///
+    pub fn out_dir(mut self, out_dir: PathBuf) -> Bind {
+        self.set_gen_path(out_dir);
+        self
+    }
+
+    ///
+    /// read the configurations from `config_path` instead of `Rsbind.toml` in the project.
+    ///
+    pub fn config_path(mut self, config_path: PathBuf) -> Bind {
+        self.config_path = Some(config_path);
+        self
+    }
 
+    ///
+    /// build in release or debug mode, overriding `release` of the targets in Rsbind.toml.
+    ///
+    pub fn release(mut self, release: bool) -> Bind {
+        self.release = Some(release);
+        self
+    }
+
+    pub fn verbosity(mut self, verbosity: Verbosity) -> Bind {
+        self.verbosity = verbosity;
+        self
+    }
+
+    ///
+    /// name the crate created by `Action::Init`, the directory name is used by default.
+    ///
+    pub fn crate_name(mut self, crate_name: String) -> Bind {
+        self.crate_name = Some(crate_name);
+        self
+    }
+
+    fn set_gen_path(&mut self, gen_path: PathBuf) {
         // ./_gen/ast
-        let ast_path = root.join(GEN_DIR_NAME).join(AST_DIR);
+        self.ast_path = gen_path.join(AST_DIR);
 
         // ./_gen/bin/
-        let bin_path = root.join(GEN_DIR_NAME).join(BIN_DIR);
+        self.bin_path = gen_path.join(BIN_DIR);
 
         // ./_gen/header/
-        let header_path = root.join(GEN_DIR_NAME).join(HEADER_NAME);
+        self.header_path = gen_path.join(HEADER_NAME);
 
         // ./_gen/ios_dest/
-        let ios_dest_path = root.join(GEN_DIR_NAME).join(IOS_PROJ);
+        self.ios_dest_path = gen_path.join(IOS_PROJ);
 
         // ./_gen/ios_bridge
-        let ios_bridge_path = root.join(GEN_DIR_NAME).join(IOS_BRIDGE_PROJ);
+        self.ios_bridge_path = gen_path.join(IOS_BRIDGE_PROJ);
 
-        let android_bridge_path = root.join(GEN_DIR_NAME).join(ANDROID_BRIDGE_PROJ);
+        self.android_bridge_path = gen_path.join(ANDROID_BRIDGE_PROJ);
 
-        let android_dest_path = root.join(GEN_DIR_NAME).join(ANDROID_PROJ);
+        self.android_dest_path = gen_path.join(ANDROID_PROJ);
 
-        return Bind {
-            prj_path: root,
-            ios_dest_path,
-            ios_bridge_path,
-            android_bridge_path,
-            android_dest_path,
-            header_path,
-            ast_path,
-            bin_path,
-            target,
-            action,
-        };
+        self.jvm_bridge_path = gen_path.join(JVM_BRIDGE_PROJ);
+
+        self.jvm_dest_path = gen_path.join(JVM_PROJ);
+
+        self.c_bridge_path = gen_path.join(C_BRIDGE_PROJ);
+
+        self.c_dest_path = gen_path.join(C_PROJ);
+
+        self.python_bridge_path = gen_path.join(PYTHON_BRIDGE_PROJ);
+
+        self.python_dest_path = gen_path.join(PYTHON_PROJ);
+
+        self.node_bridge_path = gen_path.join(NODE_BRIDGE_PROJ);
+
+        self.node_dest_path = gen_path.join(NODE_PROJ);
+
+        self.gen_path = gen_path;
     }
 
     ///
     /// generate the ios framework and android aar as per the target config
     ///
     pub fn gen_all(&self) -> Result<()> {
-        let config = config::parse(&self.prj_path);
-        println!("rsbind config in {:?} is {:?}", &self.prj_path, config);
+        log::set_verbosity(self.verbosity);
+        match self.action {
+            Action::Clean => return self.clean(),
+            Action::Init => {
+                return init::init(&self.prj_path, self.crate_name.as_ref().map(String::as_str));
+            }
+            Action::Watch => {
+                return watch::watch(|| self.watched_paths(), &self.gen_path, || self.generate());
+            }
+            _ => (),
+        }
+
+        self.generate()
+    }
+
+    fn generate(&self) -> Result<()> {
+        let config = match self.config_path {
+            Some(ref config_path) => Some(config::parse_file(config_path)?),
+            None => config::parse(&self.prj_path)?,
+        };
+        debug!("rsbind config in {:?} is {:?}", &self.prj_path, config);
+        if let Some(ref config) = config {
+            config.validate_for(&self.target)?;
+        }
 
         let crate_name = self.parse_crate_name()?;
+        let common = match config {
+            Some(ref config) => config.common(),
+            None => Common::default(),
+        };
 
         match self.action {
             Action::GenAst => {
-                self.parse_ast(crate_name.clone())?;
+                self.parse_ast(crate_name.clone(), &common)?;
+                return Ok(());
+            }
+            Action::Check => {
+                let ast_result = ast::AstHandler::new(crate_name, &common).parse(&self.prj_path)?;
+                ast::validate::validate(&ast_result)?;
+                info!("contracts in {:?} are valid.", &self.prj_path);
                 return Ok(());
             }
             _ => (),
@@ -135,47 +305,66 @@ impl Bind {
 
         Ok(match self.target {
             Target::Ios => {
-                let ast = &self.get_ast_if_need(crate_name.clone())?;
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
                 self.gen_for_ios(&crate_name, ast, config.clone())?;
             }
             Target::Android => {
-                let ast = &self.get_ast_if_need(crate_name.clone())?;
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
                 self.gen_for_android(&crate_name, ast, config.clone())?;
             }
+            Target::Jvm => {
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
+                self.gen_for_jvm(&crate_name, ast, config.clone())?;
+            }
+            Target::C => {
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
+                self.gen_for_c(&crate_name, ast, config.clone())?;
+            }
+            Target::Python => {
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
+                self.gen_for_python(&crate_name, ast, config.clone())?;
+            }
+            Target::Node => {
+                let ast = &self.get_ast_if_need(crate_name.clone(), &common)?;
+                self.gen_for_node(&crate_name, ast, config.clone())?;
+            }
             Target::All => {
-                let ast_result = self.get_ast_if_need(crate_name.clone())?;
+                let ast_result = self.get_ast_if_need(crate_name.clone(), &common)?;
                 self.gen_for_ios(&crate_name, &ast_result, config.clone())?;
                 self.gen_for_android(&crate_name, &ast_result, config.clone())?;
             }
         })
     }
 
-    fn get_ast_if_need(&self, crate_name: String) -> Result<AstResult> {
+    fn get_ast_if_need(&self, crate_name: String, common: &Common) -> Result<AstResult> {
         match self.action {
-            Action::GenBridge | Action::GenBindSrc | Action::All => {
-                self.parse_ast(crate_name.clone())
+            Action::GenBridge | Action::GenBindSrc | Action::Gen | Action::All | Action::Watch => {
+                self.parse_ast(crate_name.clone(), common)
             }
             _ => {
                 use std::collections::HashMap;
                 let ast_result = AstResult {
                     trait_descs: HashMap::new(),
                     struct_descs: HashMap::new(),
+                    enum_descs: HashMap::new(),
                     imp_desc: vec![],
+                    wire_format: common.wire_format(),
+                    contract_mod: common.contract_mod(),
+                    imp_mod: common.imp_mod(),
+                    sources: vec![],
+                    problems: vec![],
                 };
                 Ok(ast_result)
             }
         }
     }
 
-    fn parse_ast(&self, crate_name: String) -> Result<AstResult> {
+    fn parse_ast(&self, crate_name: String, common: &Common) -> Result<AstResult> {
         let prj_path = PathBuf::from(&self.prj_path);
-        if self.ast_path.exists() {
-            fs::remove_dir_all(&self.ast_path)?;
-        }
         fs::create_dir_all(&self.ast_path)?;
-        return ast::AstHandler::new(crate_name)
-            .parse(&prj_path)?
-            .flush(&self.ast_path);
+        let ast_result = ast::AstHandler::new(crate_name, common).parse(&prj_path)?;
+        ast::validate::validate(&ast_result)?;
+        return ast_result.flush(&self.ast_path);
     }
 
     ///
@@ -186,10 +375,15 @@ impl Bind {
         ast_result: &AstResult,
         config: Option<config::Config>,
     ) -> Result<()> {
-        let ios = match config {
+        let mut ios = match config {
             Some(ref config) => config.ios.clone(),
-            None => Some(Ios::default())
-        };
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            ios.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &ios)?;
 
         let ios_process = IosProcess::new(
             &self.prj_path,
@@ -200,29 +394,21 @@ impl Bind {
             &self.bin_path,
             crate_name,
             &ast_result,
-            ios,
+            Some(ios),
         );
 
+        let outputs = [
+            &self.ios_bridge_path,
+            &self.ios_dest_path,
+            &self.header_path,
+        ];
         match self.action {
-            Action::GenAst => (),
-            Action::GenBridge => ios_process.gen_bridge_src()?,
-            Action::GenBindSrc => ios_process.gen_bind_code()?,
-            Action::GenCHeader => ios_process.gen_c_header()?,
-            Action::Build => {
-                ios_process.build_bridge_prj()?;
-                ios_process.copy_bridge_outputs()?;
-                ios_process.build_dest_prj()?;
-            }
-            Action::All => {
-                ios_process.gen_bridge_src()?;
-                ios_process.gen_bind_code()?;
-                ios_process.build_bridge_prj()?;
-                ios_process.copy_bridge_outputs()?;
-                ios_process.build_dest_prj()?;
+            Action::GenCHeader => {
+                self.remove_fingerprint(IOS_TARGET)?;
+                ios_process.gen_c_header()
             }
+            _ => self.run_process(IOS_TARGET, &ios_process, &fingerprint, &outputs),
         }
-
-        Ok(())
     }
 
     ///
@@ -234,10 +420,15 @@ impl Bind {
         ast_result: &AstResult,
         config: Option<config::Config>,
     ) -> Result<()> {
-        let android = match config {
+        let mut android = match config {
             Some(ref config) => config.android.clone(),
-            None => Some(Android::default())
-        };
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            android.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &android)?;
 
         let android_process = AndroidProcess::new(
             &self.prj_path,
@@ -247,29 +438,306 @@ impl Bind {
             &self.bin_path,
             crate_name,
             ast_result,
-            android,
+            Some(android),
             ast_result
         );
 
+        let outputs = [&self.android_bridge_path, &self.android_dest_path];
+        self.run_process(ANDROID_TARGET, &android_process, &fingerprint, &outputs)
+    }
+
+    ///
+    /// generate the jar and the jni library for the host
+    ///
+    fn gen_for_jvm(
+        &self,
+        crate_name: &str,
+        ast_result: &AstResult,
+        config: Option<config::Config>,
+    ) -> Result<()> {
+        let mut jvm = match config {
+            Some(ref config) => config.jvm.clone(),
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            jvm.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &jvm)?;
+
+        let jvm_process = JvmProcess::new(
+            &self.prj_path,
+            &self.jvm_dest_path,
+            &self.jvm_bridge_path,
+            crate_name,
+            ast_result,
+            Some(jvm),
+        );
+
+        let outputs = [&self.jvm_bridge_path, &self.jvm_dest_path];
+        self.run_process(JVM_TARGET, &jvm_process, &fingerprint, &outputs)
+    }
+
+    ///
+    /// generate the libraries, the header and the pkg-config file for c
+    ///
+    fn gen_for_c(
+        &self,
+        crate_name: &str,
+        ast_result: &AstResult,
+        config: Option<config::Config>,
+    ) -> Result<()> {
+        let mut c = match config {
+            Some(ref config) => config.c.clone(),
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            c.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &c)?;
+
+        let c_process = CProcess::new(
+            &self.prj_path,
+            &self.c_dest_path,
+            &self.c_bridge_path,
+            crate_name,
+            ast_result,
+            Some(c),
+        );
+
+        let outputs = [&self.c_bridge_path, &self.c_dest_path];
         match self.action {
-            Action::GenAst => (),
-            Action::GenBridge => android_process.gen_bridge_src()?,
-            Action::GenBindSrc => android_process.gen_bind_code()?,
+            Action::GenCHeader => {
+                self.remove_fingerprint(C_TARGET)?;
+                c_process.gen_c_header()
+            }
+            _ => self.run_process(C_TARGET, &c_process, &fingerprint, &outputs),
+        }
+    }
+
+    ///
+    /// generate the python package and the c bridge library for the host
+    ///
+    fn gen_for_python(
+        &self,
+        crate_name: &str,
+        ast_result: &AstResult,
+        config: Option<config::Config>,
+    ) -> Result<()> {
+        let mut python = match config {
+            Some(ref config) => config.python.clone(),
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            python.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &python)?;
+
+        let python_process = PythonProcess::new(
+            &self.prj_path,
+            &self.python_dest_path,
+            &self.python_bridge_path,
+            crate_name,
+            ast_result,
+            Some(python),
+        );
+
+        let outputs = [&self.python_bridge_path, &self.python_dest_path];
+        self.run_process(PYTHON_TARGET, &python_process, &fingerprint, &outputs)
+    }
+
+    ///
+    /// generate the npm package and the N-API bridge library for the host
+    ///
+    fn gen_for_node(
+        &self,
+        crate_name: &str,
+        ast_result: &AstResult,
+        config: Option<config::Config>,
+    ) -> Result<()> {
+        let mut node = match config {
+            Some(ref config) => config.node.clone(),
+            None => None,
+        }
+        .unwrap_or_default();
+        if let Some(release) = self.release {
+            node.release = Some(release);
+        }
+        let fingerprint = self.fingerprint(crate_name, ast_result, &node)?;
+
+        let node_process = NodeProcess::new(
+            &self.prj_path,
+            &self.node_dest_path,
+            &self.node_bridge_path,
+            crate_name,
+            ast_result,
+            Some(node),
+        );
+
+        let outputs = [&self.node_bridge_path, &self.node_dest_path];
+        self.run_process(NODE_TARGET, &node_process, &fingerprint, &outputs)
+    }
+
+    ///
+    /// run the steps of `self.action` by the process of a target, the generated `outputs` are
+    /// skipped if they are generated from the same `fingerprint`. C headers are generated by
+    /// the targets having them.
+    ///
+    fn run_process<P: BuildProcess>(
+        &self,
+        target: &str,
+        process: &P,
+        fingerprint: &Fingerprint,
+        outputs: &[&PathBuf],
+    ) -> Result<()> {
+        let gen = || {
+            process.gen_bridge_src()?;
+            process.gen_bind_code()
+        };
+
+        match self.action {
+            Action::GenAst | Action::Check | Action::Clean | Action::Init => (),
             Action::GenCHeader => (),
+            Action::GenBridge => {
+                self.remove_fingerprint(target)?;
+                process.gen_bridge_src()?;
+            }
+            Action::GenBindSrc => {
+                self.remove_fingerprint(target)?;
+                process.gen_bind_code()?;
+            }
+            Action::Gen | Action::Watch => {
+                self.gen_if_changed(target, fingerprint, outputs, gen)?
+            }
             Action::Build => {
-                android_process.build_bridge_prj()?;
-                android_process.copy_bridge_outputs()?;
-                android_process.build_dest_prj()?;
+                process.build_bridge_prj()?;
+                process.copy_bridge_outputs()?;
+                process.build_dest_prj()?;
             }
             Action::All => {
-                android_process.gen_bridge_src()?;
-                android_process.gen_bind_code()?;
-                android_process.build_bridge_prj()?;
-                android_process.copy_bridge_outputs()?;
-                android_process.build_dest_prj()?;
+                self.gen_if_changed(target, fingerprint, outputs, gen)?;
+                process.build_bridge_prj()?;
+                process.copy_bridge_outputs()?;
+                process.build_dest_prj()?;
             }
+        }
+
+        Ok(())
+    }
+
+    ///
+    /// hash the sources of contract and imp mods, and the configurations of a target, which
+    /// decide what are generated for it.
+    ///
+    fn fingerprint<T: Debug>(
+        &self,
+        crate_name: &str,
+        ast_result: &AstResult,
+        config: &T,
+    ) -> Result<Fingerprint> {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add(crate_name.as_bytes());
+        // the bridge projects depend on the host crate by its absolute path.
+        let prj_path = self.prj_path.canonicalize()?;
+        fingerprint.add(prj_path.display().to_string().as_bytes());
+        fingerprint.add(
+            format!(
+                "{:?} {} {} {:?}",
+                ast_result.wire_format, &ast_result.contract_mod, &ast_result.imp_mod, config
+            )
+            .as_bytes(),
+        );
+        for source in ast_result.sources.iter() {
+            fingerprint.add_file(source)?;
+        }
+        Ok(fingerprint)
+    }
+
+    ///
+    /// run `gen` unless all the `outputs` exist and were generated from the same fingerprint,
+    /// which is saved after generating succeeds.
+    ///
+    fn gen_if_changed<F>(
+        &self,
+        target: &str,
+        fingerprint: &Fingerprint,
+        outputs: &[&PathBuf],
+        gen: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let path = self.fingerprint_path(target);
+        if outputs.iter().all(|output| output.exists()) && fingerprint.matches(&path) {
+            info!(
+                "contracts and configs are unchanged, skip generating for {}.",
+                target
+            );
+            return Ok(());
+        }
+
+        // the outputs are half generated until `gen` succeeds.
+        self.remove_fingerprint(target)?;
+        gen()?;
+        fingerprint.save(&path)
+    }
+
+    ///
+    /// forget the fingerprint of a target, whose outputs are generated again by steps.
+    ///
+    fn remove_fingerprint(&self, target: &str) -> Result<()> {
+        let path = self.fingerprint_path(target);
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    }
+
+    fn fingerprint_path(&self, target: &str) -> PathBuf {
+        self.gen_path.join(format!("{}.fingerprint", target))
+    }
+
+    ///
+    /// the config file, and the files and directories of contract and imp mods, which are
+    /// watched by `Action::Watch`. The mods are read from the config every time, as it may
+    /// change while watching.
+    ///
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        let config_path = match self.config_path {
+            Some(ref config_path) => config_path.clone(),
+            None => self.prj_path.join("Rsbind.toml"),
+        };
+        // a broken config is reported by generating, the default mods are watched until then.
+        let common = if config_path.exists() {
+            config::parse_file(&config_path)
+                .map(|config| config.common())
+                .unwrap_or_default()
+        } else {
+            Common::default()
         };
 
+        let src_path = self.prj_path.join("src");
+        let mut paths = vec![config_path];
+        for mod_path in [common.contract_mod(), common.imp_mod()].iter() {
+            let mod_dir = mod_path
+                .split("::")
+                .fold(src_path.clone(), |dir, name| dir.join(name));
+            paths.push(mod_dir.with_extension("rs"));
+            paths.push(mod_dir);
+        }
+        paths
+    }
+
+    ///
+    /// remove the generated files, the outputs of building are left to `cargo clean`.
+    ///
+    fn clean(&self) -> Result<()> {
+        if self.gen_path.exists() {
+            info!("remove {:?}", &self.gen_path);
+            fs::remove_dir_all(&self.gen_path)?;
+        }
         Ok(())
     }
 
@@ -279,7 +747,120 @@ impl Bind {
     fn parse_crate_name(&self) -> Result<String> {
         let toml_path = PathBuf::from(&self.prj_path).join("Cargo.toml");
         let manifest = cargo::manifest(toml_path.as_path())?;
-        println!("parse project name = {}", manifest.package.name);
+        info!("parse project name = {}", manifest.package.name);
         Ok(manifest.package.name)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use temp_dir::TempDir;
+
+    ///
+    /// A process recording the steps run by it, generating the bridge creates its output.
+    ///
+    struct FakeProcess {
+        output: PathBuf,
+        steps: RefCell<Vec<&'static str>>,
+    }
+
+    impl FakeProcess {
+        fn step(&self, name: &'static str) -> Result<()> {
+            self.steps.borrow_mut().push(name);
+            Ok(())
+        }
+    }
+
+    impl BuildProcess for FakeProcess {
+        fn unpack(&self) -> Result<()> {
+            self.step("unpack")
+        }
+
+        fn gen_bridge_src(&self) -> Result<()> {
+            fs::create_dir_all(&self.output)?;
+            self.step("bridge")
+        }
+
+        fn build_bridge_prj(&self) -> Result<()> {
+            self.step("build bridge")
+        }
+
+        fn copy_bridge_outputs(&self) -> Result<()> {
+            self.step("copy")
+        }
+
+        fn gen_bind_code(&self) -> Result<()> {
+            self.step("bind")
+        }
+
+        fn build_dest_prj(&self) -> Result<()> {
+            self.step("build dest")
+        }
+    }
+
+    fn fingerprint(input: &str) -> Fingerprint {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add(input.as_bytes());
+        fingerprint
+    }
+
+    ///
+    /// the steps run by `action`, with the outputs generated from `fingerprint`.
+    ///
+    fn run(dir: &TempDir, action: Action, fingerprint: &Fingerprint) -> Vec<&'static str> {
+        let bind = Bind::from(dir.path().display().to_string(), Target::C, action);
+        let output = bind.gen_path.join("fake_bridge");
+        let process = FakeProcess {
+            output: output.clone(),
+            steps: RefCell::new(vec![]),
+        };
+        bind.run_process("fake", &process, fingerprint, &[&output])
+            .unwrap();
+        process.steps.into_inner()
+    }
+
+    #[test]
+    fn runs_the_steps_of_actions() {
+        let dir = TempDir::new("lib_steps");
+        let contract = fingerprint("contract");
+        assert_eq!(run(&dir, Action::Gen, &contract), vec!["bridge", "bind"]);
+        assert_eq!(
+            run(&dir, Action::Build, &contract),
+            vec!["build bridge", "copy", "build dest"]
+        );
+        assert_eq!(run(&dir, Action::GenBridge, &contract), vec!["bridge"]);
+        assert_eq!(run(&dir, Action::GenBindSrc, &contract), vec!["bind"]);
+        for action in vec![Action::GenAst, Action::GenCHeader, Action::Check] {
+            assert!(run(&dir, action, &contract).is_empty());
+        }
+    }
+
+    #[test]
+    fn skips_generating_the_unchanged_outputs() {
+        let dir = TempDir::new("lib_unchanged");
+        let contract = fingerprint("contract");
+        assert_eq!(run(&dir, Action::Gen, &contract), vec!["bridge", "bind"]);
+        assert!(run(&dir, Action::Gen, &contract).is_empty());
+        assert_eq!(
+            run(&dir, Action::All, &contract),
+            vec!["build bridge", "copy", "build dest"]
+        );
+
+        // the inputs change.
+        let changed = fingerprint("changed contract");
+        assert_eq!(
+            run(&dir, Action::All, &changed),
+            vec!["bridge", "bind", "build bridge", "copy", "build dest"]
+        );
+
+        // the outputs are removed.
+        fs::remove_dir_all(dir.path().join(GEN_DIR_NAME).join("fake_bridge")).unwrap();
+        assert_eq!(run(&dir, Action::Gen, &changed), vec!["bridge", "bind"]);
+
+        // generating by steps forgets the fingerprint, as the other steps may be left.
+        assert_eq!(run(&dir, Action::GenBridge, &changed), vec!["bridge"]);
+        assert_eq!(run(&dir, Action::Gen, &changed), vec!["bridge", "bind"]);
+    }
+}
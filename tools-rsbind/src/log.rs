@@ -0,0 +1,49 @@
+//!
+//! Print the progress as per the verbosity chosen in the command line.
+//!
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+///
+/// How much is printed while binding.
+///
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd)]
+pub enum Verbosity {
+    /// only errors.
+    Quiet = 0,
+    /// the steps of binding.
+    Normal = 1,
+    /// the details of parsing and generating, and the outputs of building commands.
+    Verbose = 2,
+}
+
+static VERBOSITY: AtomicUsize = AtomicUsize::new(Verbosity::Normal as usize);
+
+pub(crate) fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as usize, Ordering::Relaxed);
+}
+
+pub(crate) fn enabled(verbosity: Verbosity) -> bool {
+    verbosity as usize <= VERBOSITY.load(Ordering::Relaxed)
+}
+
+///
+/// Print the steps of binding, unless `--quiet` is given.
+///
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Verbosity::Normal) {
+            println!($($arg)*);
+        }
+    };
+}
+
+///
+/// Print the details, only if `--verbose` is given.
+///
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled($crate::log::Verbosity::Verbose) {
+            println!($($arg)*);
+        }
+    };
+}
@@ -1,66 +1,243 @@
+extern crate clap;
 extern crate rsbind_core as gen;
 
-use gen::Action;
-use gen::Target;
-use std::env;
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind, SubCommand};
+use gen::{Action, Bind, Target, Verbosity};
+use std::path::PathBuf;
 use std::process;
 
-fn main() {
-    let mut args = env::args();
-    let _ = args.next(); // path
-    let path = match args.next() {
-        Some(real) => real,
-        _ => {
-            eprintln!(
-                "Usage: rsbind path-to-project java/ios/all ast/bridge/dest/header/build/all"
-            );
-            process::exit(1);
-        }
-    };
+/// the exit code for wrong arguments, the others are decided by the stage where rsbind fails.
+const USAGE_EXIT_CODE: i32 = 2;
 
-    let target = match args.next() {
-        Some(option) => option,
-        _ => "all".to_string(),
+fn main() {
+    let matches = match app().get_matches_safe() {
+        Ok(matches) => matches,
+        Err(err) => match err.kind {
+            ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => err.exit(),
+            _ => {
+                eprintln!("{}", err.message);
+                process::exit(USAGE_EXIT_CODE);
+            }
+        },
     };
 
-    let target_enum = match target.as_ref() {
-        "android" => Target::Android,
-        "ios" => Target::Ios,
-        "all" => Target::All,
+    let (action, args) = match matches.subcommand() {
+        ("gen", Some(args)) => {
+            let action = match args.value_of("step") {
+                Some("ast") => Action::GenAst,
+                Some("bridge") => Action::GenBridge,
+                Some("dest") => Action::GenBindSrc,
+                Some("header") => Action::GenCHeader,
+                _ => Action::Gen,
+            };
+            (action, args)
+        }
+        ("build", Some(args)) => {
+            let action = if args.is_present("skip-gen") {
+                Action::Build
+            } else {
+                Action::All
+            };
+            (action, args)
+        }
+        ("clean", Some(args)) => (Action::Clean, args),
+        ("check", Some(args)) => (Action::Check, args),
+        ("init", Some(args)) => (Action::Init, args),
+        ("watch", Some(args)) => (Action::Watch, args),
         _ => {
-            eprintln!(
-                "Usage: rsbind path-to-project java/ios/all ast/bridge/dest/header/build/all"
-            );
-            process::exit(1);
+            eprintln!("{}", matches.usage());
+            process::exit(USAGE_EXIT_CODE);
         }
     };
 
-    let action = match args.next() {
-        Some(option) => option,
-        _ => {
-            eprintln!(
-                "Usage: rsbind path-to-project java/ios/all ast/bridge/dest/header/build/all"
-            );
-            process::exit(1);
-        }
+    if let Err(err) = bind(action, args).gen_all() {
+        eprint!("{}", err.report());
+        process::exit(err.exit_code());
+    }
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    let path = Arg::with_name("path")
+        .help("The rust project to bind")
+        .default_value(".");
+
+    App::new("rsbind")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Generate android and iOS bindings for rust projects")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::VersionlessSubcommands)
+        .arg(
+            Arg::with_name("target")
+                .long("target")
+                .short("t")
+                .takes_value(true)
+                .value_name("TARGETS")
+                .use_delimiter(true)
+                .possible_values(&["android", "ios", "jvm", "c", "linux", "python", "node", "all"])
+                .global(true)
+                .help("The targets to bind, separated by commas, all is android and ios [default: all]"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .value_name("PATH")
+                .global(true)
+                .help("The config file to use instead of Rsbind.toml in the project"),
+        )
+        .arg(
+            Arg::with_name("out-dir")
+                .long("out-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .global(true)
+                .help("The directory for generated files instead of _gen in the project"),
+        )
+        .arg(
+            Arg::with_name("release")
+                .long("release")
+                .global(true)
+                .help("Build in release mode, overriding `release` in the config"),
+        )
+        .arg(
+            Arg::with_name("debug")
+                .long("debug")
+                .conflicts_with("release")
+                .global(true)
+                .help("Build in debug mode, overriding `release` in the config"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .global(true)
+                .help("Print the details of parsing, generating and building"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .conflicts_with("verbose")
+                .global(true)
+                .help("Print nothing but errors"),
+        )
+        .subcommand(
+            SubCommand::with_name("gen")
+                .about("Generate the bridge projects and the java/swift sources")
+                .arg(path.clone())
+                .arg(
+                    Arg::with_name("step")
+                        .long("step")
+                        .takes_value(true)
+                        .possible_values(&["ast", "bridge", "dest", "header"])
+                        .help("Run only one step of generating"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("build")
+                .about("Generate and build the aar for android, the framework for iOS, the jar for jvm, the libraries for c or the packages for python and node")
+                .arg(path.clone())
+                .arg(
+                    Arg::with_name("skip-gen")
+                        .long("skip-gen")
+                        .help("Build the projects generated before without generating again"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clean")
+                .about("Remove the generated files")
+                .arg(path.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Check the contracts without generating anything")
+                .arg(path.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about("Generate again whenever the contracts, the implementations or the config change")
+                .arg(path.clone()),
+        )
+        .subcommand(
+            SubCommand::with_name("init")
+                .about("Create a sample contract, implementation and Rsbind.toml in a crate")
+                .arg(path.help("The crate to initialize, created if it doesn't exist"))
+                .arg(
+                    Arg::with_name("name")
+                        .long("name")
+                        .takes_value(true)
+                        .value_name("NAME")
+                        .help("The name of the new crate [default: the directory name]"),
+                ),
+        )
+}
+
+fn bind(action: Action, args: &ArgMatches) -> Bind {
+    let path = args.value_of("path").unwrap_or(".").to_owned();
+    let mut bind = Bind::from(path, target(args), action).verbosity(verbosity(args));
+
+    if let Some(config) = args.value_of("config") {
+        bind = bind.config_path(PathBuf::from(config));
+    }
+    if let Some(out_dir) = args.value_of("out-dir") {
+        bind = bind.out_dir(PathBuf::from(out_dir));
+    }
+    if let Some(name) = args.value_of("name") {
+        bind = bind.crate_name(name.to_owned());
+    }
+    if args.is_present("release") {
+        bind = bind.release(true);
+    } else if args.is_present("debug") {
+        bind = bind.release(false);
+    }
+    bind
+}
+
+fn target(args: &ArgMatches) -> Target {
+    let targets = match args.values_of("target") {
+        Some(values) => values.collect::<Vec<&str>>(),
+        None => return Target::All,
     };
 
-    let action_enum = match action.as_ref() {
-        "ast" => Action::GenAst,
-        "bridge" => Action::GenBridge,
-        "dest" => Action::GenBindSrc,
-        "header" => Action::GenCHeader,
-        "build" => Action::Build,
-        "all" => Action::All,
-        _ => {
-            eprintln!(
-                "Usage: rsbind path-to-project java/ios/all ast/bridge/dest/header/build/all"
-            );
-            process::exit(1);
-        }
+    // the jvm, c, python and node targets run on the host, which are bound alone.
+    let host_target = match targets[0] {
+        "jvm" => Some(Target::Jvm),
+        "c" | "linux" => Some(Target::C),
+        "python" => Some(Target::Python),
+        "node" => Some(Target::Node),
+        _ => None,
     };
+    let host_names = ["jvm", "c", "linux", "python", "node"];
+    if targets.iter().any(|target| host_names.contains(target)) {
+        let alone = targets.iter().all(|target| *target == targets[0]);
+        match host_target {
+            Some(target) if alone => return target,
+            _ => {
+                eprintln!(
+                    "error: the jvm, c, python and node targets can't be bound with other targets"
+                );
+                process::exit(USAGE_EXIT_CODE);
+            }
+        }
+    }
+
+    let android = targets.contains(&"android");
+    let ios = targets.contains(&"ios");
+    if targets.contains(&"all") || (android && ios) {
+        Target::All
+    } else if android {
+        Target::Android
+    } else {
+        Target::Ios
+    }
+}
 
-    gen::Bind::from(path, target_enum, action_enum)
-        .gen_all()
-        .expect("generate failed");
+fn verbosity(args: &ArgMatches) -> Verbosity {
+    if args.is_present("verbose") {
+        Verbosity::Verbose
+    } else if args.is_present("quiet") {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    }
 }
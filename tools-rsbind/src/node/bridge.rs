@@ -0,0 +1,1222 @@
+use super::callback::*;
+use ast::contract::desc::*;
+use ast::imp::desc::*;
+use ast::types::*;
+use bridge::file::*;
+use errors::ErrorKind::*;
+use errors::*;
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use std::path::PathBuf;
+
+///
+/// create a new N-API bridges generator.
+///
+pub(crate) fn new_gen<'a>(
+    out_dir: &'a PathBuf,
+    trait_descs: &'a Vec<TraitDesc>,
+    struct_descs: &'a Vec<StructDesc>,
+    enum_descs: &'a Vec<EnumDesc>,
+    imp_desc: &'a Vec<ImpDesc>,
+    foreign_types: &'a Vec<ForeignType>,
+) -> BridgeFileGen<'a, NapiFileGenStrategy> {
+    return BridgeFileGen {
+        out_dir,
+        trait_descs,
+        struct_descs,
+        enum_descs,
+        imp_desc,
+        foreign_types,
+        strategy: NapiFileGenStrategy {
+            callback_strategy: NapiCallbackStrategy {},
+        },
+    };
+}
+
+///
+/// N-API bridge file generate strategy.
+///
+/// Every mod registers its traits on the exports of the addon, static traits as objects of
+/// functions, and objects as classes.
+///
+pub(crate) struct NapiFileGenStrategy {
+    pub(crate) callback_strategy: NapiCallbackStrategy,
+}
+
+impl NapiFileGenStrategy {
+    ///
+    /// the value returned to javascript when an error is thrown, which is ignored by node.
+    ///
+    fn quote_placeholder(&self, method: &MethodDesc) -> TokenStream {
+        match method.kind {
+            MethodKind::Constructor => quote!(0),
+            _ => quote!(std::ptr::null_mut()),
+        }
+    }
+
+    ///
+    /// the functions and the names of methods registered on javascript.
+    ///
+    fn quote_fun_entries(
+        &self,
+        trait_desc: &TraitDesc,
+        methods: &Vec<&MethodDesc>,
+    ) -> (Vec<String>, Vec<Ident>) {
+        let names = methods
+            .iter()
+            .map(|method| method.name.clone())
+            .collect::<Vec<String>>();
+        let funs = methods
+            .iter()
+            .map(|method| quote_fun_ident(trait_desc, method))
+            .collect::<Vec<Ident>>();
+        (names, funs)
+    }
+}
+
+impl FileGenStrategy for NapiFileGenStrategy {
+    fn gen_sdk_file(&self, mod_names: &Vec<String>) -> Result<TokenStream> {
+        let mod_idents = mod_names
+            .iter()
+            .map(|name| Ident::new(&name, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let values = quote_sdk_values();
+        let json = quote_sdk_json();
+        let errors = quote_sdk_errors();
+        let classes = quote_sdk_classes();
+        let objects = quote_sdk_objects();
+        let callbacks = quote_sdk_callbacks();
+        Ok(quote! {
+            use napi_sys::*;
+            use std::cell::RefCell;
+            use std::collections::HashMap;
+            use std::convert::TryFrom;
+            use std::ffi::CString;
+            use std::os::raw::{c_char, c_void};
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+            use std::ptr;
+            use std::sync::mpsc;
+            use std::thread::{self, ThreadId};
+            use super::common::panic_message;
+
+            ///
+            /// the entry of the addon, which is called by node when it is loaded.
+            ///
+            #[no_mangle]
+            pub unsafe extern "C" fn napi_register_module_v1(
+                env: napi_env,
+                exports: napi_value,
+            ) -> napi_value {
+                let catch_result = catch_unwind(AssertUnwindSafe(|| {
+                    #(super::#mod_idents::register(env, exports);)*
+                }));
+
+                match catch_result {
+                    Ok(_) => exports,
+                    Err(err) => {
+                        let panic_str = format!("rsbind panic in loading the addon: {}", panic_message(&err));
+                        throw_error(env, &panic_str);
+                        ptr::null_mut()
+                    }
+                }
+            }
+
+            thread_local! {
+                static CLASSES: RefCell<HashMap<String, napi_ref>> = RefCell::new(HashMap::new());
+            }
+
+            ///
+            /// `this` and the arguments of a call from javascript, missing arguments are undefined.
+            ///
+            pub struct CallInfo {
+                pub this: napi_value,
+                pub args: Vec<napi_value>,
+                pub data: *mut c_void,
+            }
+
+            pub fn callback_info(env: napi_env, info: napi_callback_info, argc: usize) -> CallInfo {
+                let mut call_info = CallInfo {
+                    this: ptr::null_mut(),
+                    args: vec![ptr::null_mut(); argc],
+                    data: ptr::null_mut(),
+                };
+                let mut count = argc;
+                let status = unsafe {
+                    napi_get_cb_info(
+                        env,
+                        info,
+                        &mut count,
+                        call_info.args.as_mut_ptr(),
+                        &mut call_info.this,
+                        &mut call_info.data,
+                    )
+                };
+                check_status(status, "get the arguments");
+                call_info
+            }
+
+            ///
+            /// panic if a N-API call failed, the panic is thrown to javascript by the bridge functions.
+            ///
+            pub fn check_status(status: napi_status, action: &str) {
+                if status == Status::napi_ok {
+                    return;
+                }
+
+                let reason = match status {
+                    Status::napi_object_expected => "an object is expected",
+                    Status::napi_string_expected => "a string is expected",
+                    Status::napi_function_expected => "a function is expected",
+                    Status::napi_number_expected => "a number is expected",
+                    Status::napi_boolean_expected => "a boolean is expected",
+                    Status::napi_pending_exception => "an exception is pending",
+                    _ => "N-API call failed",
+                };
+                panic!("can't {}, {} (status {})", action, reason, status);
+            }
+
+            #values
+
+            #json
+
+            #errors
+
+            #classes
+
+            #objects
+
+            #callbacks
+        })
+    }
+
+    fn quote_common_use_part(&self) -> Result<TokenStream> {
+        Ok(quote! {
+            use super::common::*;
+            use super::sdk::*;
+            use napi_sys::*;
+            use std::panic::{catch_unwind, AssertUnwindSafe};
+            use std::os::raw::c_void;
+        })
+    }
+
+    ///
+    /// the `register` function of the mod, which defines the traits on the exports of the addon.
+    ///
+    fn quote_common_part(&self, traits: &Vec<TraitDesc>) -> Result<TokenStream> {
+        let mut finalizers = TokenStream::new();
+        let mut registers = TokenStream::new();
+        // traits implemented in rust, and the wrappers of callbacks returned from rust.
+        for trait_desc in traits.iter().filter(|desc| !desc.is_callback) {
+            let name = &trait_desc.name;
+            let statics = trait_desc
+                .methods
+                .iter()
+                .filter(|method| match method.kind {
+                    MethodKind::Static | MethodKind::Constructor => true,
+                    _ => false,
+                })
+                .collect::<Vec<&MethodDesc>>();
+            let (static_names, static_funs) = self.quote_fun_entries(trait_desc, &statics);
+
+            if !trait_desc.is_object {
+                registers = quote! {
+                    #registers
+                    define_functions(env, exports, #name, &[#((#static_names, Some(#static_funs))),*]);
+                };
+                continue;
+            }
+
+            let methods = trait_desc
+                .methods
+                .iter()
+                .filter(|method| match method.kind {
+                    MethodKind::Ref | MethodKind::RefMut => true,
+                    _ => false,
+                })
+                .collect::<Vec<&MethodDesc>>();
+            let (method_names, method_funs) = self.quote_fun_entries(trait_desc, &methods);
+            let finalize_ident = Ident::new(&format!("{}_finalize", name), Span::call_site());
+            let free_ident = Ident::new(&format!("{}_free_object", name), Span::call_site());
+            finalizers = quote! {
+                #finalizers
+
+                #[allow(non_snake_case)]
+                unsafe extern "C" fn #finalize_ident(_env: napi_env, data: *mut c_void, _hint: *mut c_void) {
+                    #free_ident(data as i64);
+                }
+            };
+            registers = quote! {
+                #registers
+                define_class(
+                    env,
+                    exports,
+                    #name,
+                    Some(#finalize_ident),
+                    &[#((#static_names, Some(#static_funs))),*],
+                    &[#((#method_names, Some(#method_funs))),*],
+                );
+            };
+        }
+
+        Ok(quote! {
+            #finalizers
+
+            pub fn register(env: napi_env, exports: napi_value) {
+                #registers
+            }
+        })
+    }
+
+    fn quote_callback_structures(&self, trait_desc: &TraitDesc) -> Result<TokenStream> {
+        self.callback_strategy.quote_callback_struct(trait_desc)
+    }
+
+    fn quote_for_structures(&self, struct_desc: &StructDesc) -> Result<TokenStream> {
+        let struct_name = Ident::new(&format!("Struct_{}", &struct_desc.name), Span::call_site());
+        let origin_struct_name = Ident::new(&struct_desc.name, Span::call_site());
+        let names = struct_desc
+            .fields
+            .iter()
+            .map(|field| Ident::new(&field.name, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(field.rust_name(), Span::call_site());
+                quote_mirror_field_value(field, quote!(origin.#name))
+            })
+            .collect::<Vec<TokenStream>>();
+        let mirror_values = struct_desc
+            .fields
+            .iter()
+            .map(|field| {
+                let name = Ident::new(&field.name, Span::call_site());
+                quote_mirror_field_value(field, quote!(mirror.#name))
+            })
+            .collect::<Vec<TokenStream>>();
+        let orgin_arg_names = names.clone();
+        // mirrors use the foreign names of renamed fields, origin structs keep the rust names.
+        let mirror_arg_names = struct_desc
+            .fields
+            .iter()
+            .map(|field| Ident::new(field.rust_name(), Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let skipped_names = struct_desc
+            .skipped_fields
+            .iter()
+            .map(|field| Ident::new(field, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let tys = struct_desc
+            .fields
+            .iter()
+            .map(|field| quote_mirror_field_ty(field))
+            .collect::<Vec<TokenStream>>();
+        Ok(quote! {
+            #[derive(Serialize, Deserialize)]
+            pub struct #struct_name {
+                #(pub #names: #tys),*
+            }
+
+            impl From<#origin_struct_name> for #struct_name {
+                fn from(origin: #origin_struct_name) -> Self {
+                    #struct_name{#(#orgin_arg_names: #values),*}
+                }
+            }
+
+            impl From<#struct_name> for #origin_struct_name {
+                fn from(mirror: #struct_name) -> Self {
+                    #origin_struct_name{
+                        #(#mirror_arg_names: #mirror_values,)*
+                        #(#skipped_names: Default::default(),)*
+                    }
+                }
+            }
+        })
+    }
+
+    fn quote_for_enums(&self, enum_desc: &EnumDesc) -> Result<TokenStream> {
+        quote_enum_mirror(enum_desc)
+    }
+
+    ///
+    /// every method has an entry called by node, which takes the arguments and the object from
+    /// the call info, and the function converting the values, whose signature is quoted here.
+    ///
+    fn quote_method_sig(
+        &self,
+        trait_desc: &TraitDesc,
+        _impl_desc: &ImpDesc,
+        method: &MethodDesc,
+        _callbacks: &Vec<&TraitDesc>,
+        _structs: &Vec<StructDesc>,
+    ) -> Result<TokenStream> {
+        let fun_ident = quote_fun_ident(trait_desc, method);
+        let inner_ident = Ident::new(&format!("{}_inner", &fun_ident), Span::call_site());
+        let arg_names = method
+            .args
+            .iter()
+            .filter(|arg| match arg.ty {
+                AstType::Void => false,
+                _ => true,
+            })
+            .map(|arg| Ident::new(&arg.name, Span::call_site()))
+            .collect::<Vec<Ident>>();
+        let arg_indexes = (0..arg_names.len())
+            .map(|index| Literal::usize_unsuffixed(index))
+            .collect::<Vec<Literal>>();
+        let argc = Literal::usize_unsuffixed(arg_names.len());
+
+        // methods of objects are called with the handle wrapped in `this`.
+        let (handle_param, handle_arg, get_handle) = match method.kind {
+            MethodKind::Ref | MethodKind::RefMut => {
+                let closed_msg = format!("{} is closed", &trait_desc.name);
+                (
+                    quote!(handle: i64,),
+                    quote!(handle,),
+                    quote! {
+                        let handle = match object_handle(env, call_info.this) {
+                            Some(handle) => handle,
+                            None => {
+                                throw_error(env, #closed_msg);
+                                return std::ptr::null_mut();
+                            }
+                        };
+                    },
+                )
+            }
+            _ => (quote!(), quote!(), quote!()),
+        };
+
+        // constructors return the handle of the object, which is owned by the javascript object.
+        let (ret_ty, call) = match method.kind {
+            MethodKind::Constructor => {
+                let class_name = &trait_desc.name;
+                (
+                    quote!(i64),
+                    quote! {
+                        let handle = #inner_ident(env, #(call_info.args[#arg_indexes]),*);
+                        if handle == 0 {
+                            return std::ptr::null_mut();
+                        }
+                        new_instance(env, #class_name, handle)
+                    },
+                )
+            }
+            _ => (
+                quote!(napi_value),
+                quote!(#inner_ident(env, #handle_arg #(call_info.args[#arg_indexes]),*)),
+            ),
+        };
+
+        Ok(quote! {
+            #[allow(non_snake_case)]
+            unsafe extern "C" fn #fun_ident(env: napi_env, info: napi_callback_info) -> napi_value {
+                let call_info = callback_info(env, info, #argc);
+                #get_handle
+                #call
+            }
+
+            #[allow(non_snake_case)]
+            fn #inner_ident(env: napi_env, #handle_param #(#arg_names: napi_value),*) -> #ret_ty
+        })
+    }
+
+    fn quote_arg_convert(
+        &self,
+        trait_desc: &TraitDesc,
+        method: &MethodDesc,
+        arg: &ArgDesc,
+        callbacks: &Vec<&TraitDesc>,
+    ) -> Result<TokenStream> {
+        let rust_arg_name = Ident::new(
+            &format!("{}_{}", TMP_ARG_PREFIX, &arg.name),
+            Span::call_site(),
+        );
+        let arg_name_ident = Ident::new(&arg.name, Span::call_site());
+
+        Ok(match arg.ty {
+            AstType::Void => quote!(),
+            AstType::Callback => {
+                debug!("callback in argument found, {}", arg.origin_ty);
+                self.callback_strategy
+                    .arg_convert(arg, trait_desc, callbacks)?
+            }
+            _ => {
+                let rust_ty = quote_callback_ty(&arg.ty, &arg.origin_ty);
+                // values which can't be converted are thrown as TypeErrors naming the argument.
+                let type_error = format!("can't convert the argument `{}`", &arg.name);
+                let placeholder = self.quote_placeholder(method);
+                let value = quote_from_js(
+                    &arg.ty,
+                    &arg.origin_ty,
+                    quote!(#arg_name_ident),
+                    quote! {{
+                        throw_type_error(env, &format!("{}: {}", #type_error, err));
+                        return #placeholder;
+                    }},
+                )?;
+                quote! {
+                    let #rust_arg_name: #rust_ty = #value;
+                }
+            }
+        })
+    }
+
+    fn quote_return_convert(&self, method: &MethodDesc, ret_name: &str) -> Result<TokenStream> {
+        let ret_name_ident = Ident::new(ret_name, Span::call_site());
+        if method.kind == MethodKind::Constructor {
+            return Ok(quote!(#ret_name_ident));
+        }
+
+        quote_to_js(
+            &method.return_type,
+            &method.origin_return_ty,
+            quote!(#ret_name_ident),
+        )
+    }
+
+    ///
+    /// throw the error as an Error with the `error` property, and return a placeholder value.
+    ///
+    fn quote_error_return(&self, method: &MethodDesc) -> Result<TokenStream> {
+        let error_value = quote_to_js(&method.error_type, &method.origin_error_ty, quote!(err))?;
+        let error_name = &method.origin_error_ty;
+        let placeholder = self.quote_placeholder(method);
+
+        Ok(quote! {
+            let error_value = #error_value;
+            throw_contract_error(env, #error_name, error_value);
+            return #placeholder;
+        })
+    }
+
+    ///
+    /// throw the panic message as an Error, unless an exception thrown by a callback is pending.
+    ///
+    fn quote_panic_return(
+        &self,
+        trait_desc: &TraitDesc,
+        method: &MethodDesc,
+    ) -> Result<TokenStream> {
+        let panic_prefix = format!("rsbind panic in {}.{}", &trait_desc.name, &method.name);
+        let placeholder = self.quote_placeholder(method);
+
+        Ok(quote! {
+            let panic_str = format!("{}: {}", #panic_prefix, panic_message(&err));
+            throw_error(env, &panic_str);
+            #placeholder
+        })
+    }
+
+    fn quote_free_object_sig(&self, trait_desc: &TraitDesc) -> Result<TokenStream> {
+        let fun_name = Ident::new(
+            &format!("{}_free_object", &trait_desc.name),
+            Span::call_site(),
+        );
+        Ok(quote! {
+            #[allow(non_snake_case)]
+            pub fn #fun_name(handle: i64)
+        })
+    }
+
+    fn ty_to_tokens(&self, _ast_type: &AstType, _direction: TypeDirection) -> Result<TokenStream> {
+        // all the values are passed as javascript values.
+        Ok(quote!(napi_value))
+    }
+}
+
+///
+/// the conversions of numbers, booleans and strings.
+///
+fn quote_sdk_values() -> TokenStream {
+    quote! {
+        pub fn undefined_to_js(env: napi_env) -> napi_value {
+            let mut result = ptr::null_mut();
+            check_status(unsafe { napi_get_undefined(env, &mut result) }, "get undefined");
+            result
+        }
+
+        pub fn i32_to_js(env: napi_env, value: i32) -> napi_value {
+            let mut result = ptr::null_mut();
+            check_status(unsafe { napi_create_int32(env, value, &mut result) }, "create a number");
+            result
+        }
+
+        pub fn i64_to_js(env: napi_env, value: i64) -> napi_value {
+            let mut result = ptr::null_mut();
+            check_status(unsafe { napi_create_int64(env, value, &mut result) }, "create a number");
+            result
+        }
+
+        pub fn f64_to_js(env: napi_env, value: f64) -> napi_value {
+            let mut result = ptr::null_mut();
+            check_status(unsafe { napi_create_double(env, value, &mut result) }, "create a number");
+            result
+        }
+
+        pub fn bool_to_js(env: napi_env, value: bool) -> napi_value {
+            let mut result = ptr::null_mut();
+            check_status(unsafe { napi_get_boolean(env, value, &mut result) }, "create a boolean");
+            result
+        }
+
+        pub fn string_to_js(env: napi_env, value: &str) -> napi_value {
+            let mut result = ptr::null_mut();
+            let status = unsafe {
+                napi_create_string_utf8(env, value.as_ptr() as *const c_char, value.len(), &mut result)
+            };
+            check_status(status, "create a string");
+            result
+        }
+
+        ///
+        /// get an integer, the ones out of the range of `T` are rejected instead of truncated.
+        ///
+        pub fn js_to_int<T: TryFrom<i64>>(env: napi_env, value: napi_value) -> Result<T, String> {
+            let value = js_to_i64(env, value);
+            T::try_from(value).map_err(|_| {
+                format!("{} is out of the range of {}", value, std::any::type_name::<T>())
+            })
+        }
+
+        pub fn js_to_i64(env: napi_env, value: napi_value) -> i64 {
+            let mut result = 0;
+            check_status(unsafe { napi_get_value_int64(env, value, &mut result) }, "get an integer");
+            result
+        }
+
+        pub fn js_to_f64(env: napi_env, value: napi_value) -> f64 {
+            let mut result = 0.0;
+            check_status(unsafe { napi_get_value_double(env, value, &mut result) }, "get a number");
+            result
+        }
+
+        pub fn js_to_bool(env: napi_env, value: napi_value) -> bool {
+            let mut result = false;
+            check_status(unsafe { napi_get_value_bool(env, value, &mut result) }, "get a boolean");
+            result
+        }
+
+        pub fn js_to_string(env: napi_env, value: napi_value) -> String {
+            let mut len = 0;
+            let status = unsafe { napi_get_value_string_utf8(env, value, ptr::null_mut(), 0, &mut len) };
+            check_status(status, "get a string");
+            let mut buf = vec![0u8; len + 1];
+            let status = unsafe {
+                napi_get_value_string_utf8(env, value, buf.as_mut_ptr() as *mut c_char, len + 1, &mut len)
+            };
+            check_status(status, "get a string");
+            buf.truncate(len);
+            String::from_utf8(buf).unwrap()
+        }
+    }
+}
+
+///
+/// the helpers of properties and functions, and the json transferring other values.
+///
+fn quote_sdk_json() -> TokenStream {
+    quote! {
+        fn value_type(env: napi_env, value: napi_value) -> napi_valuetype {
+            let mut result = ValueType::napi_undefined;
+            check_status(unsafe { napi_typeof(env, value, &mut result) }, "get the type of a value");
+            result
+        }
+
+        fn get_property(env: napi_env, object: napi_value, name: &str) -> napi_value {
+            let name = CString::new(name).unwrap();
+            let mut result = ptr::null_mut();
+            let status = unsafe { napi_get_named_property(env, object, name.as_ptr(), &mut result) };
+            check_status(status, "get a property");
+            result
+        }
+
+        fn set_property(env: napi_env, object: napi_value, name: &str, value: napi_value) {
+            let name = CString::new(name).unwrap();
+            let status = unsafe { napi_set_named_property(env, object, name.as_ptr(), value) };
+            check_status(status, "set a property");
+        }
+
+        fn reference_value(env: napi_env, reference: napi_ref) -> napi_value {
+            let mut result = ptr::null_mut();
+            let status = unsafe { napi_get_reference_value(env, reference, &mut result) };
+            check_status(status, "get a referenced value");
+            result
+        }
+
+        ///
+        /// call a javascript function, the exception thrown by it is kept pending.
+        ///
+        pub fn call_function(env: napi_env, this: napi_value, function: napi_value, args: &[napi_value]) -> napi_value {
+            let mut result = ptr::null_mut();
+            let status = unsafe {
+                napi_call_function(env, this, function, args.len(), args.as_ptr(), &mut result)
+            };
+            if status == Status::napi_pending_exception {
+                panic!("the javascript function threw an exception");
+            }
+            check_status(status, "call a javascript function");
+            result
+        }
+
+        fn call_json(env: napi_env, method: &str, value: napi_value) -> napi_value {
+            let mut global = ptr::null_mut();
+            check_status(unsafe { napi_get_global(env, &mut global) }, "get the global object");
+            let json = get_property(env, global, "JSON");
+            let function = get_property(env, json, method);
+            call_function(env, json, function, &[value])
+        }
+
+        ///
+        /// values other than numbers, booleans and strings are transferred by json, undefined
+        /// is taken as null.
+        ///
+        pub fn js_to_json(env: napi_env, value: napi_value) -> String {
+            if value_type(env, value) == ValueType::napi_undefined {
+                return "null".to_owned();
+            }
+            let json = call_json(env, "stringify", value);
+            js_to_string(env, json)
+        }
+
+        pub fn json_to_js(env: napi_env, json: &str) -> napi_value {
+            let json = string_to_js(env, json);
+            call_json(env, "parse", json)
+        }
+    }
+}
+
+///
+/// throwing the errors and panics of rust to javascript.
+///
+fn quote_sdk_errors() -> TokenStream {
+    quote! {
+        ///
+        /// throw an Error to javascript, nothing is thrown if an exception is pending, like
+        /// the one thrown by a callback.
+        ///
+        pub fn throw_error(env: napi_env, msg: &str) {
+            let msg = CString::new(msg.replace("\0", "")).unwrap();
+            unsafe {
+                napi_throw_error(env, ptr::null(), msg.as_ptr());
+            }
+        }
+
+        ///
+        /// throw a TypeError to javascript, for the values which can't be converted to rust.
+        ///
+        pub fn throw_type_error(env: napi_env, msg: &str) {
+            let msg = CString::new(msg.replace("\0", "")).unwrap();
+            unsafe {
+                napi_throw_type_error(env, ptr::null(), msg.as_ptr());
+            }
+        }
+
+        ///
+        /// throw the error of a method returning `Result<T, E>`, the error is kept in the
+        /// `error` property of the thrown Error.
+        ///
+        pub fn throw_contract_error(env: napi_env, name: &str, error: napi_value) {
+            let msg = string_to_js(env, &format!("{}: {}", name, js_to_json(env, error)));
+            let mut js_error = ptr::null_mut();
+            let status = unsafe { napi_create_error(env, ptr::null_mut(), msg, &mut js_error) };
+            check_status(status, "create an error");
+            set_property(env, js_error, "error", error);
+            unsafe {
+                napi_throw(env, js_error);
+            }
+        }
+
+        ///
+        /// take the pending exception, as they can't be thrown to other threads.
+        ///
+        fn take_exception(env: napi_env) -> Option<String> {
+            let mut pending = false;
+            unsafe {
+                if napi_is_exception_pending(env, &mut pending) != Status::napi_ok || !pending {
+                    return None;
+                }
+                let mut exception = ptr::null_mut();
+                let mut msg = ptr::null_mut();
+                if napi_get_and_clear_last_exception(env, &mut exception) != Status::napi_ok
+                    || napi_coerce_to_string(env, exception, &mut msg) != Status::napi_ok
+                {
+                    return Some("unknown exception".to_owned());
+                }
+                Some(js_to_string(env, msg))
+            }
+        }
+    }
+}
+
+///
+/// the classes of objects and the objects of static functions.
+///
+fn quote_sdk_classes() -> TokenStream {
+    quote! {
+        ///
+        /// define a class of objects, which are created by the constructors in rust, and freed by
+        /// `close` or when they are collected.
+        ///
+        pub fn define_class(
+            env: napi_env,
+            exports: napi_value,
+            name: &str,
+            finalize: napi_finalize,
+            statics: &[(&str, napi_callback)],
+            methods: &[(&str, napi_callback)],
+        ) {
+            // the finalizer is kept as the data of the class, for the constructor and `close`.
+            let finalize_data = match finalize {
+                Some(finalize) => finalize as *mut c_void,
+                None => ptr::null_mut(),
+            };
+            let close: napi_callback = Some(close_object);
+            let entries = statics
+                .iter()
+                .map(|&(name, method)| (name, method, PropertyAttributes::static_))
+                .chain(methods.iter().map(|&(name, method)| (name, method, PropertyAttributes::default)))
+                .chain(Some(("close", close, PropertyAttributes::default)))
+                .collect::<Vec<_>>();
+            let names = entries
+                .iter()
+                .map(|entry| CString::new(entry.0).unwrap())
+                .collect::<Vec<CString>>();
+            let properties = entries
+                .iter()
+                .zip(names.iter())
+                .map(|(entry, name)| napi_property_descriptor {
+                    utf8name: name.as_ptr(),
+                    name: ptr::null_mut(),
+                    method: entry.1,
+                    getter: None,
+                    setter: None,
+                    value: ptr::null_mut(),
+                    attributes: entry.2,
+                    data: finalize_data,
+                })
+                .collect::<Vec<napi_property_descriptor>>();
+
+            let class_name = CString::new(name).unwrap();
+            let mut constructor = ptr::null_mut();
+            let status = unsafe {
+                napi_define_class(
+                    env,
+                    class_name.as_ptr(),
+                    name.len(),
+                    Some(construct_object),
+                    finalize_data,
+                    properties.len(),
+                    properties.as_ptr(),
+                    &mut constructor,
+                )
+            };
+            check_status(status, "define a class");
+
+            let mut reference = ptr::null_mut();
+            check_status(unsafe { napi_create_reference(env, constructor, 1, &mut reference) }, "create a reference");
+            CLASSES.with(|classes| classes.borrow_mut().insert(name.to_owned(), reference));
+            set_property(env, exports, name, constructor);
+        }
+
+        ///
+        /// define an object of functions for the static methods of a trait.
+        ///
+        pub fn define_functions(env: napi_env, exports: napi_value, name: &str, functions: &[(&str, napi_callback)]) {
+            let mut object = ptr::null_mut();
+            check_status(unsafe { napi_create_object(env, &mut object) }, "create an object");
+            for &(function_name, function) in functions.iter() {
+                let c_name = CString::new(function_name).unwrap();
+                let mut value = ptr::null_mut();
+                let status = unsafe {
+                    napi_create_function(env, c_name.as_ptr(), function_name.len(), function, ptr::null_mut(), &mut value)
+                };
+                check_status(status, "create a function");
+                set_property(env, object, function_name, value);
+            }
+            set_property(env, exports, name, object);
+        }
+    }
+}
+
+///
+/// creating, wrapping and closing the objects of classes.
+///
+fn quote_sdk_objects() -> TokenStream {
+    quote! {
+        fn finalize_of(data: *mut c_void) -> napi_finalize {
+            if data.is_null() {
+                None
+            } else {
+                Some(unsafe { std::mem::transmute::<*mut c_void, unsafe extern "C" fn(napi_env, *mut c_void, *mut c_void)>(data) })
+            }
+        }
+
+        ///
+        /// the constructor of classes, the handle of the object is passed in an external value
+        /// by `new_instance`, so the classes can't be created by `new` in javascript.
+        ///
+        unsafe extern "C" fn construct_object(env: napi_env, info: napi_callback_info) -> napi_value {
+            let mut this = ptr::null_mut();
+            let mut data = ptr::null_mut();
+            let mut argc = 1;
+            let mut external = ptr::null_mut();
+            let mut handle = ptr::null_mut();
+            if napi_get_cb_info(env, info, &mut argc, &mut external, &mut this, &mut data) != Status::napi_ok
+                || argc < 1
+                || napi_get_value_external(env, external, &mut handle) != Status::napi_ok
+            {
+                throw_error(env, "objects of rust are created by their constructors");
+                return ptr::null_mut();
+            }
+
+            if napi_wrap(env, this, handle, finalize_of(data), ptr::null_mut(), ptr::null_mut()) != Status::napi_ok {
+                throw_error(env, "can't wrap the object of rust");
+                return ptr::null_mut();
+            }
+            this
+        }
+
+        ///
+        /// `close` of classes, which frees the object at once.
+        ///
+        unsafe extern "C" fn close_object(env: napi_env, info: napi_callback_info) -> napi_value {
+            let mut this = ptr::null_mut();
+            let mut data = ptr::null_mut();
+            let mut argc = 0;
+            let mut handle = ptr::null_mut();
+            if napi_get_cb_info(env, info, &mut argc, ptr::null_mut(), &mut this, &mut data) == Status::napi_ok
+                && napi_remove_wrap(env, this, &mut handle) == Status::napi_ok
+            {
+                if let Some(finalize) = finalize_of(data) {
+                    finalize(env, handle, ptr::null_mut());
+                }
+            }
+
+            let mut result = ptr::null_mut();
+            napi_get_undefined(env, &mut result);
+            result
+        }
+
+        ///
+        /// the handle of an object, None if it is closed.
+        ///
+        pub fn object_handle(env: napi_env, this: napi_value) -> Option<i64> {
+            let mut handle = ptr::null_mut();
+            let status = unsafe { napi_unwrap(env, this, &mut handle) };
+            if status == Status::napi_ok && !handle.is_null() {
+                Some(handle as i64)
+            } else {
+                None
+            }
+        }
+
+        ///
+        /// create the javascript object of a class, which owns the object of the handle.
+        ///
+        pub fn new_instance(env: napi_env, name: &str, handle: i64) -> napi_value {
+            let reference = CLASSES.with(|classes| classes.borrow().get(name).cloned());
+            let mut constructor = ptr::null_mut();
+            let mut external = ptr::null_mut();
+            let mut instance = ptr::null_mut();
+            let created = match reference {
+                Some(reference) => unsafe {
+                    napi_get_reference_value(env, reference, &mut constructor) == Status::napi_ok
+                        && napi_create_external(env, handle as *mut c_void, None, ptr::null_mut(), &mut external) == Status::napi_ok
+                        && napi_new_instance(env, constructor, 1, &external, &mut instance) == Status::napi_ok
+                },
+                None => false,
+            };
+
+            if !created {
+                throw_error(env, &format!("can't create the object of {}", name));
+                return ptr::null_mut();
+            }
+            instance
+        }
+    }
+}
+
+///
+/// the javascript objects implementing callbacks, which may be called from other threads.
+///
+fn quote_sdk_callbacks() -> TokenStream {
+    quote! {
+        ///
+        /// the method of a callback, callbacks with one method may be passed as functions.
+        ///
+        pub fn callback_method(env: napi_env, object: napi_value, name: &str) -> napi_value {
+            if value_type(env, object) == ValueType::napi_function {
+                object
+            } else {
+                get_property(env, object, name)
+            }
+        }
+
+        type JsJob = Box<dyn FnOnce(napi_env) + Send>;
+
+        struct JsRef(napi_ref);
+
+        unsafe impl Send for JsRef {}
+
+        ///
+        /// a javascript object implementing a callback, which is called directly on the javascript
+        /// thread, and through a threadsafe function from other threads. Only the calls returning
+        /// values wait for the javascript thread, so it mustn't wait for the threads making them.
+        ///
+        pub struct JsCallback {
+            env: napi_env,
+            reference: napi_ref,
+            function: napi_threadsafe_function,
+            thread: ThreadId,
+        }
+
+        // the javascript object is only touched on the javascript thread.
+        unsafe impl Send for JsCallback {}
+        unsafe impl Sync for JsCallback {}
+
+        impl JsCallback {
+            pub fn new(env: napi_env, value: napi_value) -> JsCallback {
+                let mut reference = ptr::null_mut();
+                check_status(unsafe { napi_create_reference(env, value, 1, &mut reference) }, "create a reference");
+
+                let name = string_to_js(env, "rsbind callback");
+                let mut function = ptr::null_mut();
+                let status = unsafe {
+                    napi_create_threadsafe_function(
+                        env,
+                        ptr::null_mut(),
+                        ptr::null_mut(),
+                        name,
+                        0,
+                        1,
+                        ptr::null_mut(),
+                        None,
+                        ptr::null_mut(),
+                        Some(call_js_job),
+                        &mut function,
+                    )
+                };
+                check_status(status, "create a threadsafe function");
+                // callbacks kept by rust don't keep node running.
+                check_status(unsafe { napi_unref_threadsafe_function(env, function) }, "unref a threadsafe function");
+
+                JsCallback {
+                    env,
+                    reference,
+                    function,
+                    thread: thread::current().id(),
+                }
+            }
+
+            ///
+            /// call `f` with the javascript object on the javascript thread without waiting for it,
+            /// the errors of calls from other threads are uncaught exceptions of node.
+            ///
+            pub fn post<F>(&self, f: F)
+            where
+                F: FnOnce(napi_env, napi_value) + Send + 'static,
+            {
+                if thread::current().id() == self.thread {
+                    let object = reference_value(self.env, self.reference);
+                    return f(self.env, object);
+                }
+
+                let reference = JsRef(self.reference);
+                self.queue(Box::new(move |env| {
+                    let catch_result = catch_unwind(AssertUnwindSafe(|| f(env, reference_value(env, reference.0))));
+                    if let Err(err) = catch_result {
+                        throw_error(env, &format!("rsbind panic in callback: {}", panic_message(&err)));
+                    }
+                }));
+            }
+
+            ///
+            /// call `f` with the javascript object on the javascript thread, and return its result.
+            ///
+            pub fn call<R, F>(&self, f: F) -> R
+            where
+                R: Send + 'static,
+                F: FnOnce(napi_env, napi_value) -> R + Send + 'static,
+            {
+                if thread::current().id() == self.thread {
+                    let object = reference_value(self.env, self.reference);
+                    return f(self.env, object);
+                }
+
+                let (sender, receiver) = mpsc::channel();
+                let reference = JsRef(self.reference);
+                self.queue(Box::new(move |env| {
+                    let catch_result = catch_unwind(AssertUnwindSafe(|| f(env, reference_value(env, reference.0))));
+                    let result = match catch_result {
+                        Ok(ret) => Ok(ret),
+                        Err(err) => Err(take_exception(env).unwrap_or_else(|| panic_message(&err))),
+                    };
+                    let _ = sender.send(result);
+                }));
+
+                match receiver.recv() {
+                    Ok(Ok(ret)) => ret,
+                    Ok(Err(msg)) => panic!("the callback failed: {}", msg),
+                    Err(_) => panic!("the callback can't be called as node is closing"),
+                }
+            }
+
+            fn queue(&self, job: JsJob) {
+                let data = Box::into_raw(Box::new(job));
+                let status = unsafe {
+                    napi_call_threadsafe_function(self.function, data as *mut c_void, ThreadsafeFunctionCallMode::blocking)
+                };
+                if status != Status::napi_ok {
+                    unsafe {
+                        drop(Box::from_raw(data));
+                    }
+                }
+            }
+        }
+
+        impl Drop for JsCallback {
+            fn drop(&mut self) {
+                // queued even on the javascript thread, so the calls posted before still find it.
+                let reference = JsRef(self.reference);
+                self.queue(Box::new(move |env| unsafe {
+                    napi_delete_reference(env, reference.0);
+                }));
+                unsafe {
+                    napi_release_threadsafe_function(self.function, ThreadsafeFunctionReleaseMode::release);
+                }
+            }
+        }
+
+        unsafe extern "C" fn call_js_job(env: napi_env, _function: napi_value, _context: *mut c_void, data: *mut c_void) {
+            let job: JsJob = *Box::from_raw(data as *mut JsJob);
+            // env is null when the jobs are dropped as node is closing.
+            if !env.is_null() {
+                job(env);
+            }
+        }
+    }
+}
+
+///
+/// the entry of a method called by node, named after the trait, as methods of different traits
+/// in one mod may share names.
+///
+fn quote_fun_ident(trait_desc: &TraitDesc, method: &MethodDesc) -> Ident {
+    Ident::new(
+        &format!("{}_{}", &trait_desc.name, &method.name),
+        Span::call_site(),
+    )
+}
+
+///
+/// a field like description of a value, so it is converted by its json mirror.
+///
+fn mirror_desc(ty: &AstType, origin_ty: &str) -> ArgDesc {
+    ArgDesc {
+        name: "value".to_owned(),
+        ty: *ty,
+        origin_ty: origin_ty.to_owned(),
+        ty_path: "".to_owned(),
+        binary: false,
+        origin_name: None,
+        location: Location::default(),
+    }
+}
+
+///
+/// Convert a javascript value to rust.
+///
+/// Numbers, booleans and strings are converted directly, enums, structs, vectors, options and
+/// maps are transferred by json through their mirrors, C-like enums as the names of variants.
+/// `on_error` is evaluated with the `err` of json which can't be converted, it must diverge.
+///
+pub(crate) fn quote_from_js(
+    ty: &AstType,
+    origin_ty: &str,
+    value: TokenStream,
+    on_error: TokenStream,
+) -> Result<TokenStream> {
+    let origin_ident = || Ident::new(origin_ty, Span::call_site());
+    Ok(match *ty {
+        AstType::Byte | AstType::Int | AstType::Long => {
+            let origin_ident = origin_ident();
+            quote! {
+                match js_to_int::<#origin_ident>(env, #value) {
+                    Ok(value) => value,
+                    Err(err) => #on_error,
+                }
+            }
+        }
+        AstType::Float | AstType::Double => {
+            let origin_ident = origin_ident();
+            quote!(js_to_f64(env, #value) as #origin_ident)
+        }
+        AstType::Boolean => quote!(js_to_bool(env, #value)),
+        AstType::String => quote!(js_to_string(env, #value)),
+        AstType::Enum
+        | AstType::Struct
+        | AstType::Vec(_)
+        | AstType::Optional(_)
+        | AstType::Map(_, _) => {
+            let desc = mirror_desc(ty, origin_ty);
+            let mirror_ty = quote_mirror_field_ty(&desc);
+            let mirror_value = quote_mirror_field_value(&desc, quote!(mirror));
+            quote! {
+                {
+                    let mirror = match serde_json::from_str::<#mirror_ty>(&js_to_json(env, #value)) {
+                        Ok(mirror) => mirror,
+                        Err(err) => #on_error,
+                    };
+                    #mirror_value
+                }
+            }
+        }
+        _ => {
+            return Err(GenerateError(format!(
+                "find unsupported type from javascript, {}",
+                origin_ty
+            ))
+            .into());
+        }
+    })
+}
+
+///
+/// Convert a rust value to javascript, the reverse of `quote_from_js`.
+///
+/// Callbacks returned from rust are wrapped by the objects of their `Rust` wrapper classes.
+///
+pub(crate) fn quote_to_js(
+    ty: &AstType,
+    origin_ty: &str,
+    value: TokenStream,
+) -> Result<TokenStream> {
+    Ok(match *ty {
+        AstType::Void => quote!(undefined_to_js(env)),
+        AstType::Byte | AstType::Int => quote!(i32_to_js(env, #value as i32)),
+        AstType::Long => quote!(i64_to_js(env, #value as i64)),
+        AstType::Float | AstType::Double => quote!(f64_to_js(env, #value as f64)),
+        AstType::Boolean => quote!(bool_to_js(env, #value)),
+        AstType::String => quote!(string_to_js(env, &#value)),
+        AstType::Enum
+        | AstType::Struct
+        | AstType::Vec(_)
+        | AstType::Optional(_)
+        | AstType::Map(_, _) => {
+            let desc = mirror_desc(ty, origin_ty);
+            let mirror_ty = quote_mirror_field_ty(&desc);
+            let mirror_value = quote_mirror_field_value(&desc, value);
+            quote! {
+                {
+                    let mirror_value: #mirror_ty = #mirror_value;
+                    json_to_js(env, &serde_json::to_string(&mirror_value).unwrap())
+                }
+            }
+        }
+        AstType::Callback => {
+            let wrapper_name = format!("Rust{}", origin_ty);
+            quote!(new_instance(env, #wrapper_name, Box::into_raw(Box::new(#value)) as i64))
+        }
+    })
+}
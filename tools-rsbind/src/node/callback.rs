@@ -0,0 +1,129 @@
+use super::bridge::{quote_from_js, quote_to_js};
+use ast::contract::desc::*;
+use ast::types::*;
+use bridge::file::*;
+use errors::ErrorKind::*;
+use errors::*;
+use proc_macro2::{Ident, Span, TokenStream};
+
+pub(crate) struct NapiCallbackStrategy {}
+
+impl NapiCallbackStrategy {
+    ///
+    /// quote `Js_xxx`, which implements the callback by calling the methods of a javascript object.
+    ///
+    pub(crate) fn quote_callback_struct(&self, trait_desc: &TraitDesc) -> Result<TokenStream> {
+        let struct_ident = Ident::new(&format!("Js_{}", &trait_desc.name), Span::call_site());
+        let trait_ident = Ident::new(&trait_desc.name, Span::call_site());
+
+        let mut methods = TokenStream::new();
+        for method in trait_desc.methods.iter() {
+            let method_ident = Ident::new(method.rust_name(), Span::call_site());
+            let js_method_name = &method.name;
+            let args = method
+                .args
+                .iter()
+                .filter(|arg| match arg.ty {
+                    AstType::Void => false,
+                    _ => true,
+                })
+                .collect::<Vec<&ArgDesc>>();
+            let arg_names = args
+                .iter()
+                .map(|arg| Ident::new(&arg.name, Span::call_site()))
+                .collect::<Vec<Ident>>();
+            let arg_tys = args
+                .iter()
+                .map(|arg| quote_callback_ty(&arg.ty, &arg.origin_ty))
+                .collect::<Vec<TokenStream>>();
+            let js_args = args
+                .iter()
+                .map(|arg| {
+                    let arg_name = Ident::new(&arg.name, Span::call_site());
+                    quote_to_js(&arg.ty, &arg.origin_ty, quote!(#arg_name))
+                })
+                .collect::<Result<Vec<TokenStream>>>()
+                .chain_err(|| {
+                    GenerateError(format!(
+                        "can't convert the arguments of callback {}.{}",
+                        &trait_desc.name, &method.name
+                    ))
+                })?;
+            let ret_ty = quote_callback_ty(&method.return_type, &method.origin_return_ty);
+            let ret_value = match method.return_type {
+                AstType::Void => quote!(),
+                _ => {
+                    let type_error = format!(
+                        "can't convert the result of callback {}.{}",
+                        &trait_desc.name, &method.name
+                    );
+                    quote_from_js(
+                        &method.return_type,
+                        &method.origin_return_ty,
+                        quote!(js_ret),
+                        quote!(panic!("{}: {}", #type_error, err)),
+                    )?
+                }
+            };
+
+            // callbacks returning nothing don't wait for the javascript thread.
+            let call_ident = match method.return_type {
+                AstType::Void => Ident::new("post", Span::call_site()),
+                _ => Ident::new("call", Span::call_site()),
+            };
+
+            methods = quote! {
+                #methods
+
+                fn #method_ident(&self, #(#arg_names: #arg_tys),*) -> #ret_ty {
+                    self.0.#call_ident(move |env, object| {
+                        let method = callback_method(env, object, #js_method_name);
+                        let js_ret = call_function(env, object, method, &[#(#js_args),*]);
+                        #ret_value
+                    })
+                }
+            };
+        }
+
+        Ok(quote! {
+            pub struct #struct_ident(pub JsCallback);
+
+            impl #trait_ident for #struct_ident {
+                #methods
+            }
+        })
+    }
+}
+
+impl CallbackGenStrategy for NapiCallbackStrategy {
+    ///
+    /// javascript objects passed as callbacks are referenced until the callback is dropped.
+    ///
+    fn arg_convert(
+        &self,
+        arg: &ArgDesc,
+        _trait_desc: &TraitDesc,
+        callbacks: &Vec<&TraitDesc>,
+    ) -> Result<TokenStream> {
+        let callback_desc = callbacks
+            .iter()
+            .find(|desc| desc.name == arg.origin_ty)
+            .ok_or_else(|| {
+                GenerateError(format!(
+                    "can't find the callback {} of argument {}",
+                    &arg.origin_ty, &arg.name
+                ))
+            })?;
+        let rust_arg_name = Ident::new(
+            &format!("{}_{}", TMP_ARG_PREFIX, &arg.name),
+            Span::call_site(),
+        );
+        let arg_name_ident = Ident::new(&arg.name, Span::call_site());
+        let callback_ident = Ident::new(&callback_desc.name, Span::call_site());
+        let struct_ident = Ident::new(&format!("Js_{}", &callback_desc.name), Span::call_site());
+
+        Ok(quote! {
+            let #rust_arg_name: Box<#callback_ident> = Box::new(#struct_ident(JsCallback::new(env, #arg_name_ident)));
+        })
+    }
+}
@@ -0,0 +1,49 @@
+///
+/// Node Configuration struct, for the package loading the N-API bridge.
+///
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct Node {
+    pub rustc_param: Option<String>,
+    pub release: Option<bool>,
+    /// the name of the npm package, the crate name by default.
+    pub package_name: Option<String>,
+    pub features_def: Option<Vec<String>>,
+}
+
+impl Node {
+    pub fn rustc_param(&self) -> String {
+        match self.rustc_param {
+            Some(ref rustc) => rustc.clone(),
+            None => "".to_owned(),
+        }
+    }
+
+    pub fn release_str(&self) -> String {
+        if self.is_release() {
+            "--release".to_owned()
+        } else {
+            "".to_owned()
+        }
+    }
+
+    pub fn is_release(&self) -> bool {
+        match self.release {
+            Some(is_release) => is_release,
+            None => true,
+        }
+    }
+
+    pub fn package_name(&self, crate_name: &str) -> String {
+        match self.package_name {
+            Some(ref package_name) => package_name.clone(),
+            None => crate_name.to_owned(),
+        }
+    }
+
+    pub fn features(&self) -> Vec<String> {
+        match self.features_def {
+            Some(ref features) => features.clone(),
+            None => vec![],
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//!
+//! The npm package loading the N-API bridge, `index.js` requires the addon, and `index.d.ts`
+//! declares the contract types and traits for typescript.
+//!
+use ast::contract::desc::ArgDesc;
+use ast::contract::desc::EnumDesc;
+use ast::contract::desc::MethodDesc;
+use ast::contract::desc::MethodKind;
+use ast::contract::desc::StructDesc;
+use ast::contract::desc::TraitDesc;
+use ast::types::split_map_origin_ty;
+use ast::types::AstBaseType;
+use ast::types::AstType;
+use ast::AstResult;
+use errors::ErrorKind::*;
+use errors::*;
+use incremental::write_if_changed;
+use std::path::PathBuf;
+
+/// reserved words of javascript which are not keywords of rust, so they can be names in contracts.
+const RESERVED: [&str; 22] = [
+    "arguments",
+    "case",
+    "catch",
+    "class",
+    "debugger",
+    "default",
+    "delete",
+    "eval",
+    "export",
+    "extends",
+    "finally",
+    "function",
+    "import",
+    "instanceof",
+    "interface",
+    "new",
+    "null",
+    "package",
+    "switch",
+    "this",
+    "throw",
+    "var",
+];
+
+pub(crate) struct NodeCodeGen<'a> {
+    pub package_dir: &'a PathBuf,
+    pub ast: &'a AstResult,
+    pub crate_name: String,
+    /// the file name of the addon in the package, like `demo.node`.
+    pub addon_file: String,
+}
+
+impl<'a> NodeCodeGen<'a> {
+    pub(crate) fn gen_node_code(&self) -> Result<()> {
+        let index = format!(
+            "// generated by rsbind, do not edit.\n\
+             module.exports = require(\"./{}\");\n",
+            &self.addon_file
+        );
+        self.write("index.js", index)?;
+        let declarations = self
+            .gen_declarations()
+            .chain_err(|| GenerateError("can't generate typescript declarations".to_string()))?;
+        self.write("index.d.ts", declarations)
+    }
+
+    fn write(&self, name: &str, content: String) -> Result<()> {
+        let path = self.package_dir.join(name);
+        write_if_changed(&path, content.as_bytes())
+            .chain_err(|| FileError(format!("can't write {:?}", &path)))?;
+        Ok(())
+    }
+
+    fn gen_declarations(&self) -> Result<String> {
+        // the mods are sorted, so the same contracts are generated to the same file.
+        let mut mod_names = self.ast.trait_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let traits = mod_names
+            .iter()
+            .flat_map(|name| self.ast.trait_descs[*name].iter())
+            .collect::<Vec<&TraitDesc>>();
+
+        let mut mod_names = self.ast.struct_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let structs = mod_names
+            .iter()
+            .flat_map(|name| self.ast.struct_descs[*name].iter())
+            .collect::<Vec<&StructDesc>>();
+
+        let mut mod_names = self.ast.enum_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let enums = mod_names
+            .iter()
+            .flat_map(|name| self.ast.enum_descs[*name].iter())
+            .collect::<Vec<&EnumDesc>>();
+
+        let mut file = TsFile::new();
+        file.line("// generated by rsbind, do not edit.");
+        file.line(format!(
+            "// typescript declarations of the rust crate {}.",
+            &self.crate_name
+        ));
+
+        // errors of `Result<T, E>` are thrown as Errors, which hold the error value.
+        file.separate();
+        file.line("/** thrown by methods returning `Result<T, E>`, with the error in `error`. */");
+        file.open("export interface ContractError<E> extends Error {");
+        file.line("error: E;");
+        file.close("}");
+
+        for enum_desc in enums.iter() {
+            file.separate();
+            push_enum(&mut file, enum_desc);
+        }
+
+        for struct_desc in structs.iter() {
+            file.separate();
+            file.open(format!("export interface {} {{", &struct_desc.name));
+            push_fields(&mut file, &struct_desc.fields);
+            file.close("}");
+        }
+
+        for callback in traits.iter().filter(|desc| desc.is_callback) {
+            file.separate();
+            file.open(format!("export interface {} {{", &callback.name));
+            for method in callback.methods.iter() {
+                file.line(format!(
+                    "{}({}): {};",
+                    &method.name,
+                    typed_args(method, &traits).join(", "),
+                    ts_type(method.return_type, &method.origin_return_ty)
+                ));
+            }
+            file.close("}");
+        }
+
+        for trait_desc in traits.iter().filter(|desc| !desc.is_callback) {
+            file.separate();
+            push_trait(&mut file, trait_desc, &traits);
+        }
+
+        Ok(file.to_ts())
+    }
+}
+
+///
+/// C-like enums are the names of their variants, data-carrying enums are objects tagged by `type`.
+///
+fn push_enum(file: &mut TsFile, desc: &EnumDesc) {
+    if desc.is_c_like {
+        let variants = desc
+            .variants
+            .iter()
+            .map(|variant| format!("\"{}\"", &variant.name))
+            .collect::<Vec<String>>();
+        file.line(format!(
+            "export type {} = {};",
+            &desc.name,
+            variants.join(" | ")
+        ));
+        return;
+    }
+
+    file.line(format!("export type {} =", &desc.name));
+    file.level += 1;
+    for variant in desc.variants.iter() {
+        let mut fields = vec![format!("type: \"{}\"", &variant.name)];
+        fields.extend(
+            variant
+                .fields
+                .iter()
+                .map(|field| format!("{}: {}", &field.name, ts_type(field.ty, &field.origin_ty))),
+        );
+        file.line(format!("| {{ {} }}", fields.join("; ")));
+    }
+    file.level -= 1;
+    if let Some(last) = file.lines.last_mut() {
+        last.push(';');
+    }
+}
+
+fn push_fields(file: &mut TsFile, fields: &Vec<ArgDesc>) {
+    for field in fields.iter() {
+        file.line(format!(
+            "{}: {};",
+            &field.name,
+            ts_type(field.ty, &field.origin_ty)
+        ));
+    }
+}
+
+///
+/// static traits are objects of functions, objects and the wrappers of returned callbacks are
+/// classes, which are created by the constructors in rust.
+///
+fn push_trait(file: &mut TsFile, desc: &TraitDesc, traits: &Vec<&TraitDesc>) {
+    if !desc.is_object {
+        file.open(format!("export declare const {}: {{", &desc.name));
+        for method in desc.methods.iter() {
+            push_method(file, desc, method, traits, "");
+        }
+        file.close("};");
+        return;
+    }
+
+    let implements = match desc.wraps_callback {
+        Some(ref callback) => format!(" implements {}", callback),
+        None => "".to_owned(),
+    };
+    file.open(format!(
+        "export declare class {}{} {{",
+        &desc.name, &implements
+    ));
+    file.line("private constructor();");
+    for method in desc.methods.iter() {
+        let prefix = match method.kind {
+            MethodKind::Static | MethodKind::Constructor => "static ",
+            _ => "",
+        };
+        push_method(file, desc, method, traits, prefix);
+    }
+    file.line("/** free the object in rust at once, or it is freed when it is collected. */");
+    file.line("close(): void;");
+    file.close("}");
+}
+
+fn push_method(
+    file: &mut TsFile,
+    desc: &TraitDesc,
+    method: &MethodDesc,
+    traits: &Vec<&TraitDesc>,
+    prefix: &str,
+) {
+    if method.error_type != AstType::Void {
+        file.line(format!(
+            "/** @throws {{ContractError<{}>}} */",
+            &method.origin_error_ty
+        ));
+    }
+    // callbacks returned from rust are objects of their wrapper classes.
+    let return_ty = match method.kind {
+        MethodKind::Constructor => desc.name.clone(),
+        _ if method.return_type == AstType::Callback => {
+            format!("Rust{}", &method.origin_return_ty)
+        }
+        _ => ts_type(method.return_type, &method.origin_return_ty),
+    };
+    file.line(format!(
+        "{}{}({}): {};",
+        prefix,
+        &method.name,
+        typed_args(method, traits).join(", "),
+        return_ty
+    ));
+}
+
+///
+/// callbacks with only one method may be passed as functions.
+///
+fn typed_args(method: &MethodDesc, traits: &Vec<&TraitDesc>) -> Vec<String> {
+    method
+        .args
+        .iter()
+        .filter(|arg| arg.ty != AstType::Void)
+        .map(|arg| {
+            let mut ty = ts_type(arg.ty, &arg.origin_ty);
+            if arg.ty == AstType::Callback {
+                let callback = traits.iter().find(|desc| desc.name == arg.origin_ty);
+                if let Some(callback) = callback {
+                    if callback.methods.len() == 1 {
+                        ty = format!("{} | {}[\"{}\"]", &ty, &ty, &callback.methods[0].name);
+                    }
+                }
+            }
+            format!("{}: {}", ident(&arg.name), ty)
+        })
+        .collect()
+}
+
+fn ts_type(ty: AstType, origin_ty: &str) -> String {
+    match ty {
+        AstType::Void => "void".to_owned(),
+        AstType::Byte | AstType::Int | AstType::Long | AstType::Float | AstType::Double => {
+            "number".to_owned()
+        }
+        AstType::Boolean => "boolean".to_owned(),
+        AstType::String => "string".to_owned(),
+        AstType::Vec(base) => {
+            let inner = ts_type(AstType::from(base), &inner_ty(origin_ty, "Vec<"));
+            format!("{}[]", inner)
+        }
+        AstType::Optional(base) => format!(
+            "{} | null",
+            ts_type(AstType::from(base), &inner_ty(origin_ty, "Option<"))
+        ),
+        AstType::Map(key, value) => {
+            let (_, _, value_ty) = split_map_origin_ty(origin_ty);
+            let key_ty = match key {
+                AstBaseType::String => "string",
+                _ => "number",
+            };
+            format!(
+                "Record<{}, {}>",
+                key_ty,
+                ts_type(AstType::from(value), &value_ty)
+            )
+        }
+        AstType::Callback | AstType::Struct | AstType::Enum => origin_ty.to_owned(),
+    }
+}
+
+fn inner_ty(origin_ty: &str, outer: &str) -> String {
+    origin_ty.replace(outer, "").replace(">", "")
+}
+
+///
+/// names which are reserved by javascript are followed by an underscore.
+///
+fn ident(name: &str) -> String {
+    if RESERVED.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+///
+/// A typescript declaration file, the lines are indented by the blocks they are in.
+///
+struct TsFile {
+    lines: Vec<String>,
+    level: usize,
+}
+
+impl TsFile {
+    fn new() -> Self {
+        TsFile {
+            lines: vec![],
+            level: 0,
+        }
+    }
+
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        let line = line.as_ref();
+        if line.is_empty() {
+            self.lines.push(String::new());
+        } else {
+            self.lines
+                .push(format!("{}{}", "  ".repeat(self.level), line));
+        }
+    }
+
+    fn open<S: AsRef<str>>(&mut self, line: S) {
+        self.line(line);
+        self.level += 1;
+    }
+
+    fn close<S: AsRef<str>>(&mut self, line: S) {
+        self.level -= 1;
+        self.line(line);
+    }
+
+    ///
+    /// separate top level declarations by an empty line.
+    ///
+    fn separate(&mut self) {
+        match self.lines.last() {
+            Some(last) if !last.is_empty() => (),
+            _ => return,
+        }
+        self.lines.push(String::new());
+    }
+
+    fn to_ts(&self) -> String {
+        let mut ts = self.lines.join("\n");
+        ts.push('\n');
+        ts
+    }
+}
@@ -0,0 +1,5 @@
+pub(crate) mod bridge;
+mod callback;
+pub(crate) mod config;
+mod dest;
+pub(crate) mod process;
@@ -0,0 +1,335 @@
+use ast::AstResult;
+use bridge::prj::Unpack;
+use bridges::BridgeGen::NodeGen;
+use cargo;
+use errors::ErrorKind::*;
+use errors::*;
+use incremental::write_if_changed;
+use process::{print_output, BuildProcess};
+use std::env::consts::{ARCH, DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use super::config::Node;
+use super::dest::NodeCodeGen;
+
+///
+/// Build the N-API bridge for the host, and an npm package loading it, with the typescript
+/// declarations of the contracts.
+///
+pub(crate) struct NodeProcess<'a> {
+    origin_prj_path: &'a PathBuf,
+    dest_prj_path: &'a PathBuf,
+    bridge_prj_path: &'a PathBuf,
+    host_crate_name: &'a str,
+    ast_result: &'a AstResult,
+    config: Option<Node>,
+}
+
+impl<'a> NodeProcess<'a> {
+    pub fn new(
+        origin_prj_path: &'a PathBuf,
+        dest_prj_path: &'a PathBuf,
+        bridge_prj_path: &'a PathBuf,
+        host_crate_name: &'a str,
+        ast_result: &'a AstResult,
+        config: Option<Node>,
+    ) -> Self {
+        NodeProcess {
+            origin_prj_path,
+            dest_prj_path,
+            bridge_prj_path,
+            host_crate_name,
+            ast_result,
+            config,
+        }
+    }
+}
+
+impl<'a> NodeProcess<'a> {
+    fn config(&self) -> Node {
+        match self.config {
+            Some(ref config) => config.to_owned(),
+            None => Node::default(),
+        }
+    }
+
+    fn package_name(&self) -> String {
+        self.config().package_name(self.host_crate_name)
+    }
+
+    ///
+    /// the version of the package is the version of the crate.
+    ///
+    fn version(&self) -> Result<String> {
+        let toml_path = self.origin_prj_path.join("Cargo.toml");
+        let manifest = cargo::manifest(toml_path.as_path())?;
+        Ok(manifest
+            .package
+            .version
+            .unwrap_or_else(|| "0.0.0".to_owned()))
+    }
+
+    fn bridge_lib_name(&self) -> String {
+        format!(
+            "{}{}_node_bridge_prj{}",
+            DLL_PREFIX,
+            &self.host_crate_name.replace("-", "_"),
+            DLL_SUFFIX
+        )
+    }
+
+    ///
+    /// the addon in the package, which is required by `index.js`.
+    ///
+    fn addon_name(&self) -> String {
+        format!("{}.node", self.host_crate_name.replace("-", "_"))
+    }
+
+    ///
+    /// `os` and `cpu` of package.json in the names of node, only linux and macOS are supported.
+    ///
+    fn platform(&self) -> (&'static str, &'static str) {
+        let os = if cfg!(target_os = "macos") {
+            "darwin"
+        } else {
+            "linux"
+        };
+        let cpu = match ARCH {
+            "x86_64" => "x64",
+            "aarch64" => "arm64",
+            "x86" => "ia32",
+            "arm" => "arm",
+            _ => ARCH,
+        };
+        (os, cpu)
+    }
+
+    fn gen_package_json(&self) -> Result<()> {
+        let (os, cpu) = self.platform();
+        let package_json = format!(
+            "{{\n  \"name\": \"{name}\",\n  \"version\": \"{version}\",\n  \
+             \"description\": \"node bindings of the rust crate {crate_name}\",\n  \
+             \"main\": \"index.js\",\n  \"types\": \"index.d.ts\",\n  \
+             \"os\": [\"{os}\"],\n  \"cpu\": [\"{cpu}\"],\n  \
+             \"engines\": {{\n    \"node\": \">=12\"\n  }}\n}}\n",
+            name = &self.package_name(),
+            version = &self.version()?,
+            crate_name = &self.host_crate_name,
+            os = os,
+            cpu = cpu
+        );
+        let path = self.dest_prj_path.join("package.json");
+        write_if_changed(&path, package_json.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'a> BuildProcess for NodeProcess<'a> {
+    fn unpack(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn gen_bridge_src(&self) -> Result<()> {
+        info!("begin unzip rust template for node");
+        let buf: &[u8] = include_bytes!("res/template_bridge_node.zip");
+        let unpack = Unpack {
+            path: self.bridge_prj_path,
+            host_crate: self.host_crate_name,
+            host_path: self.origin_prj_path,
+            contract_mod: &self.ast_result.contract_mod,
+            imp_mod: &self.ast_result.imp_mod,
+            buf,
+            features: &self.config().features(),
+        };
+
+        unpack.unpack()?;
+
+        let bridge_node_src_path = self.bridge_prj_path.join("src").join("node").join("bridge");
+        fs::create_dir_all(&bridge_node_src_path)?;
+        NodeGen(
+            self.host_crate_name.to_owned(),
+            self.ast_result,
+            &bridge_node_src_path,
+        )
+        .gen_bridges()
+        .chain_err(|| GenerateError("can't generate node bridges".to_string()))?;
+
+        let _ = Command::new("cargo")
+            .arg("fmt")
+            .current_dir(&self.bridge_prj_path)
+            .output();
+
+        Ok(())
+    }
+
+    fn build_bridge_prj(&self) -> Result<()> {
+        info!("building node bridge project");
+
+        let build_cmd = format!(
+            "cargo rustc --lib {} --target-dir {} {}",
+            self.config().release_str(),
+            "target",
+            &self.config().rustc_param()
+        );
+
+        debug!("run building => {}", &build_cmd);
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(build_cmd)
+            .current_dir(self.bridge_prj_path)
+            .output()?;
+
+        print_output(&output)?;
+
+        if !output.status.success() {
+            return Err(CommandError(format!("run build node rust project build failed.")).into());
+        }
+
+        Ok(())
+    }
+
+    fn copy_bridge_outputs(&self) -> Result<()> {
+        info!("copy output files to node package.");
+
+        let debug_release = if self.config().is_release() {
+            "release"
+        } else {
+            "debug"
+        };
+        let lib_src = self
+            .bridge_prj_path
+            .join("target")
+            .join(debug_release)
+            .join(self.bridge_lib_name());
+        fs::create_dir_all(&self.dest_prj_path)?;
+        let lib_dest = self.dest_prj_path.join(self.addon_name());
+        fs::copy(&lib_src, &lib_dest)
+            .chain_err(|| FileError(format!("copy node bridge output {:?} failed.", &lib_src)))?;
+
+        Ok(())
+    }
+
+    fn gen_bind_code(&self) -> Result<()> {
+        info!("generate node package.");
+        fs::create_dir_all(&self.dest_prj_path)?;
+        NodeCodeGen {
+            package_dir: self.dest_prj_path,
+            ast: self.ast_result,
+            crate_name: self.host_crate_name.to_owned(),
+            addon_file: self.addon_name(),
+        }
+        .gen_node_code()?;
+
+        self.gen_package_json()
+    }
+
+    fn build_dest_prj(&self) -> Result<()> {
+        // nothing to build for node, the package is copied to the origin project.
+        let target = self.origin_prj_path.join("target").join("node");
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::create_dir_all(&target)?;
+
+        // the package has no sub directories.
+        for entry in fs::read_dir(self.dest_prj_path)? {
+            let from = entry?.path();
+            let to = target.join(from.file_name().unwrap_or_default());
+            fs::copy(&from, &to)
+                .chain_err(|| FileError(format!("copy node output {:?} failed.", &from)))?;
+        }
+
+        info!("node package is in {:?}", &target);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::AstHandler;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn generates_the_bridge_and_package_of_a_crate() {
+        let prj = TempDir::with_files(
+            "node_process",
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"demo-sdk\"\nversion = \"1.2.3\"\n",
+                ),
+                ("src/contract/mod.rs", "pub mod demo;"),
+                (
+                    "src/contract/demo.rs",
+                    r#"
+                    pub trait Greeter {
+                        fn greet(name: String, times: u8, listener: Box<dyn Listener>) -> String;
+                    }
+                    pub trait Listener: Sync {
+                        fn on_greeted(&self, times: i32);
+                    }
+                    "#,
+                ),
+                ("src/imp/mod.rs", "pub mod demo;"),
+                (
+                    "src/imp/demo.rs",
+                    r#"
+                    use contract::demo::*;
+                    pub struct GreeterImp {}
+                    impl Greeter for GreeterImp {
+                        fn greet(name: String, times: u8, listener: Box<dyn Listener>) -> String {
+                            listener.on_greeted(times as i32);
+                            format!("hello {}", name)
+                        }
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let ast_result = AstHandler::new("demo-sdk".to_owned(), &Common::default())
+            .parse(prj.path())
+            .unwrap();
+        let dest = prj.path().join("node_dest");
+        let bridge = prj.path().join("node_bridge");
+        let process = NodeProcess::new(prj.path(), &dest, &bridge, "demo-sdk", &ast_result, None);
+
+        process.gen_bridge_src().unwrap();
+        process.gen_bind_code().unwrap();
+        let code = fs::read_to_string(bridge.join("src/node/bridge/node_demo.rs"))
+            .unwrap()
+            .replace(char::is_whitespace, "");
+        assert!(code.contains(
+            "define_functions(env,exports,\"Greeter\",&[(\"greet\",Some(Greeter_greet))]);"
+        ));
+        // integers out of the range of their types are rejected with a TypeError.
+        assert!(code.contains("letr_times:u8=matchjs_to_int::<u8>(env,times){"));
+        assert!(code.contains(
+            "throw_type_error(env,&format!(\"{}:{}\",\"can'tconverttheargument`times`\",err));"
+        ));
+        // callbacks returning nothing are posted to javascript without waiting.
+        assert!(code.contains("fnon_greeted(&self,times:i32)->(){self.0.post(move|env,object|{"));
+
+        assert_eq!(
+            fs::read_to_string(dest.join("index.js")).unwrap(),
+            "// generated by rsbind, do not edit.\n\
+             module.exports = require(\"./demo_sdk.node\");\n"
+        );
+        let declarations = fs::read_to_string(dest.join("index.d.ts")).unwrap();
+        assert!(declarations.contains(
+            "export interface Listener {\n  \
+             on_greeted(times: number): void;\n\
+             }\n"
+        ));
+        assert!(declarations.contains(
+            "export declare const Greeter: {\n  \
+             greet(name: string, times: number, listener: Listener | Listener[\"on_greeted\"]): string;\n\
+             };\n"
+        ));
+        let package_json = fs::read_to_string(dest.join("package.json")).unwrap();
+        assert!(package_json.contains("\"name\": \"demo-sdk\",\n  \"version\": \"1.2.3\",\n"));
+    }
+}
@@ -1,4 +1,7 @@
 use errors::*;
+use log::{self, Verbosity};
+use std::io::{self, Write};
+use std::process::Output;
 
 pub(crate) trait BuildProcess {
     fn unpack(&self) -> Result<()>;
@@ -8,3 +11,16 @@ pub(crate) trait BuildProcess {
     fn gen_bind_code(&self) -> Result<()>;
     fn build_dest_prj(&self) -> Result<()>;
 }
+
+///
+/// Print the outputs of a building command, they are shown only with `--verbose`, or when the
+/// command fails.
+///
+pub(crate) fn print_output(output: &Output) -> Result<()> {
+    if output.status.success() && !log::enabled(Verbosity::Verbose) {
+        return Ok(());
+    }
+    io::stdout().write_all(&output.stdout)?;
+    io::stderr().write_all(&output.stderr)?;
+    Ok(())
+}
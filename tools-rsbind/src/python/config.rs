@@ -0,0 +1,49 @@
+///
+/// Python Configuration struct, for the package calling the c bridge by ctypes.
+///
+#[derive(Clone, Deserialize, Debug, Default)]
+pub struct Python {
+    pub rustc_param: Option<String>,
+    pub release: Option<bool>,
+    /// the name of the python package, the crate name by default.
+    pub package_name: Option<String>,
+    pub features_def: Option<Vec<String>>,
+}
+
+impl Python {
+    pub fn rustc_param(&self) -> String {
+        match self.rustc_param {
+            Some(ref rustc) => rustc.clone(),
+            None => "".to_owned(),
+        }
+    }
+
+    pub fn release_str(&self) -> String {
+        if self.is_release() {
+            "--release".to_owned()
+        } else {
+            "".to_owned()
+        }
+    }
+
+    pub fn is_release(&self) -> bool {
+        match self.release {
+            Some(is_release) => is_release,
+            None => true,
+        }
+    }
+
+    pub fn package_name(&self, crate_name: &str) -> String {
+        match self.package_name {
+            Some(ref package_name) => package_name.clone(),
+            None => crate_name.replace("-", "_"),
+        }
+    }
+
+    pub fn features(&self) -> Vec<String> {
+        match self.features_def {
+            Some(ref features) => features.clone(),
+            None => vec![],
+        }
+    }
+}
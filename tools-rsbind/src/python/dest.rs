@@ -0,0 +1,1267 @@
+//!
+//! Python bindings calling the c bridge by ctypes, they are a package of `_ffi.py`, which loads
+//! the library and holds the helpers, and `__init__.py` with the contract types and traits.
+//!
+use ast::contract::desc::ArgDesc;
+use ast::contract::desc::EnumDesc;
+use ast::contract::desc::MethodDesc;
+use ast::contract::desc::MethodKind;
+use ast::contract::desc::StructDesc;
+use ast::contract::desc::TraitDesc;
+use ast::types::split_map_origin_ty;
+use ast::types::AstBaseType;
+use ast::types::AstType;
+use ast::AstResult;
+use bridge::gen::C_OPTIONAL_TYS;
+use errors::ErrorKind::*;
+use errors::*;
+use incremental::write_if_changed;
+use ios::bridge::c_optional_ident;
+use std::path::PathBuf;
+
+/// keywords of python which are not keywords of rust, so they can be names in contracts.
+const KEYWORDS: [&str; 25] = [
+    "False", "None", "True", "and", "assert", "async", "await", "class", "def", "del", "elif",
+    "except", "finally", "from", "global", "import", "is", "lambda", "nonlocal", "not", "or",
+    "pass", "raise", "try", "with",
+];
+
+pub(crate) struct PythonCodeGen<'a> {
+    pub package_dir: &'a PathBuf,
+    pub ast: &'a AstResult,
+    pub crate_name: String,
+    /// the file name of the library in the package, like `libdemo.so`.
+    pub lib_file: String,
+}
+
+impl<'a> PythonCodeGen<'a> {
+    pub(crate) fn gen_python_code(&self) -> Result<()> {
+        self.write("_ffi.py", self.gen_ffi())?;
+        let bindings = self
+            .gen_bindings()
+            .chain_err(|| GenerateError("can't generate python bindings".to_string()))?;
+        self.write("__init__.py", bindings)
+    }
+
+    fn write(&self, name: &str, content: String) -> Result<()> {
+        let path = self.package_dir.join(name);
+        write_if_changed(&path, content.as_bytes())
+            .chain_err(|| FileError(format!("can't write {:?}", &path)))?;
+        Ok(())
+    }
+
+    ///
+    /// `_ffi.py` loads the library next to it, and converts the strings passed through the c
+    /// bridge, the functions freeing them are named after the crate.
+    ///
+    fn gen_ffi(&self) -> String {
+        let crate_name = self.crate_name.replace("-", "_");
+        // the layouts of the structs passing `Option<T>` of primitives in the c bridge.
+        let optionals = C_OPTIONAL_TYS
+            .iter()
+            .map(|&(name, ty)| {
+                let c_ty = match ty {
+                    "f32" => "c_float".to_owned(),
+                    "f64" => "c_double".to_owned(),
+                    _ => format!("c_int{}", &ty[1..]),
+                };
+                format!(
+                    "class {}(ctypes.Structure):\n    _fields_ = [(\"is_some\", ctypes.c_int32), (\"value\", ctypes.{})]\n",
+                    name, c_ty
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n\n");
+        format!(
+            r#"# generated by rsbind, do not edit.
+import ctypes
+import json
+import os
+import threading
+
+lib = ctypes.CDLL(os.path.join(os.path.dirname(os.path.abspath(__file__)), "{lib_file}"))
+
+lib.{crate_name}_free_str.argtypes = [ctypes.c_void_p]
+lib.{crate_name}_free_str.restype = None
+lib.{crate_name}_take_panic.argtypes = []
+lib.{crate_name}_take_panic.restype = ctypes.c_void_p
+
+
+class RsbindPanic(Exception):
+    """raised when rust panics in a call, with the message of the panic."""
+
+
+def declare(name, restype, *argtypes):
+    function = getattr(lib, name)
+    function.argtypes = list(argtypes)
+    function.restype = restype
+    return function
+
+
+def take_str(ptr):
+    """read a string returned from rust and free it, None for null."""
+    if not ptr:
+        return None
+    try:
+        return ctypes.string_at(ptr).decode("utf-8")
+    finally:
+        lib.{crate_name}_free_str(ptr)
+
+
+def take_json(ptr):
+    return json.loads(take_str(ptr))
+
+
+def dumps(value):
+    return json.dumps(value)
+
+
+def loads(value):
+    return json.loads(value)
+
+
+def check_panic():
+    message = take_str(lib.{crate_name}_take_panic())
+    if message is not None:
+        raise RsbindPanic(message)
+
+
+def new_err():
+    """the out parameter of methods returning Result, which rust sets to the error."""
+    return ctypes.c_void_p()
+
+
+def take_err(err):
+    return take_str(err.value)
+
+
+{optionals}
+
+def to_optional(cls, value):
+    """the struct of an `Option<T>` of primitives, with a presence flag and the value."""
+    return cls(0, 0) if value is None else cls(1, value)
+
+
+def from_optional(value, convert=lambda each: each):
+    return convert(value.value) if value.is_some else None
+
+
+class Registry:
+    """objects kept alive for rust by their indexes, until rust frees them."""
+
+    def __init__(self):
+        self._lock = threading.Lock()
+        self._index = 0
+        self._objects = {{}}
+
+    def put(self, value):
+        with self._lock:
+            self._index += 1
+            self._objects[self._index] = value
+            return self._index
+
+    def get(self, index):
+        with self._lock:
+            return self._objects[index]
+
+    def remove(self, index):
+        with self._lock:
+            return self._objects.pop(index, None)
+
+
+# callbacks passed to rust, and strings returned to rust by callbacks.
+callbacks = Registry()
+_returned = {{}}
+_returned_lock = threading.Lock()
+
+
+def return_str(value):
+    """copy a string returned by a callback, which is kept until rust calls `free_ptr`."""
+    buffer = ctypes.create_string_buffer(value.encode("utf-8"))
+    address = ctypes.addressof(buffer)
+    with _returned_lock:
+        _returned[address] = buffer
+    return address
+
+
+FREE_CALLBACK = ctypes.CFUNCTYPE(None, ctypes.c_int64)
+FREE_PTR = ctypes.CFUNCTYPE(None, ctypes.c_void_p)
+
+
+@FREE_CALLBACK
+def free_callback(index):
+    callbacks.remove(index)
+
+
+@FREE_PTR
+def free_ptr(ptr):
+    with _returned_lock:
+        _returned.pop(ptr, None)
+"#,
+            lib_file = &self.lib_file,
+            crate_name = &crate_name,
+            optionals = &optionals
+        )
+    }
+
+    fn gen_bindings(&self) -> Result<String> {
+        // the mods are sorted, so the same contracts are generated to the same file.
+        let mut mod_names = self.ast.trait_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let traits = mod_names
+            .iter()
+            .flat_map(|name| self.ast.trait_descs[*name].iter())
+            .collect::<Vec<&TraitDesc>>();
+        let callbacks = traits
+            .iter()
+            .filter(|desc| desc.is_callback)
+            .map(|desc| *desc)
+            .collect::<Vec<&TraitDesc>>();
+
+        let mut mod_names = self.ast.struct_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let structs = mod_names
+            .iter()
+            .flat_map(|name| self.ast.struct_descs[*name].iter())
+            .collect::<Vec<&StructDesc>>();
+
+        let mut mod_names = self.ast.enum_descs.keys().collect::<Vec<&String>>();
+        mod_names.sort();
+        let enums = mod_names
+            .iter()
+            .flat_map(|name| self.ast.enum_descs[*name].iter())
+            .cloned()
+            .collect::<Vec<EnumDesc>>();
+
+        let mut file = PyFile::new();
+        file.line("# generated by rsbind, do not edit.");
+        file.line(format!(
+            "\"\"\"python bindings of the rust crate {}.\"\"\"",
+            &self.crate_name
+        ));
+        // annotations are not evaluated, so the types can be used before they are defined.
+        file.line("from __future__ import annotations");
+        file.line("");
+        file.line("import abc");
+        file.line("import ctypes");
+        file.line("import enum");
+        file.line("from dataclasses import dataclass, field");
+        file.line("from typing import Dict, List, Optional");
+        file.line("");
+        file.line("from . import _ffi");
+        file.line("from ._ffi import RsbindPanic");
+
+        // C-like enums are first, as they are the defaults of fields.
+        for enum_desc in enums.iter().filter(|desc| desc.is_c_like) {
+            file.separate();
+            push_c_like_enum(&mut file, enum_desc);
+        }
+
+        for struct_desc in structs.iter() {
+            file.separate();
+            file.line("@dataclass");
+            push_data_class(
+                &mut file,
+                &struct_desc.name,
+                "",
+                &struct_desc.fields,
+                None,
+                &enums,
+            );
+        }
+
+        for enum_desc in enums.iter().filter(|desc| !desc.is_c_like) {
+            file.separate();
+            push_data_enum(&mut file, enum_desc, &enums);
+        }
+
+        // errors of `Result<T, E>` are raised as exceptions, which hold the error value.
+        let mut error_tys: Vec<&str> = vec![];
+        for method in traits
+            .iter()
+            .filter(|desc| !desc.is_callback)
+            .flat_map(|desc| desc.methods.iter())
+        {
+            if method.error_type != AstType::Void
+                && !error_tys.contains(&method.origin_error_ty.as_str())
+            {
+                error_tys.push(&method.origin_error_ty);
+                file.separate();
+                push_exception(&mut file, &method.origin_error_ty);
+            }
+        }
+
+        for callback in callbacks.iter() {
+            push_callback(&mut file, callback)?;
+        }
+
+        for trait_desc in traits.iter().filter(|desc| !desc.is_callback) {
+            let gen = TraitGen {
+                desc: trait_desc,
+                callbacks: &callbacks,
+            };
+            gen.push(&mut file).chain_err(|| {
+                GenerateError(format!("can't generate python class {}", &trait_desc.name))
+            })?;
+        }
+
+        Ok(file.to_python())
+    }
+}
+
+///
+/// C-like enums are transferred by their discriminant values, and by their names inside json.
+///
+fn push_c_like_enum(file: &mut PyFile, desc: &EnumDesc) {
+    let name = &desc.name;
+    file.open(format!("class {}(enum.IntEnum):", name));
+    for variant in desc.variants.iter() {
+        file.line(format!("{} = {}", ident(&variant.name), variant.value));
+    }
+
+    let escaped = desc
+        .variants
+        .iter()
+        .filter(|variant| KEYWORDS.contains(&variant.name.as_str()))
+        .map(|variant| format!("\"{}\"", &variant.name))
+        .collect::<Vec<String>>();
+    file.separate();
+    file.open("def _to_json(self) -> str:");
+    if escaped.is_empty() {
+        file.line("return self.name");
+    } else {
+        file.line(format!(
+            "return self.name[:-1] if self.name[:-1] in ({},) else self.name",
+            escaped.join(", ")
+        ));
+    }
+    file.close();
+
+    file.separate();
+    file.line("@staticmethod");
+    file.open(format!("def _from_json(value: str) -> {}:", name));
+    if escaped.is_empty() {
+        file.line(format!("return {}[value]", name));
+    } else {
+        file.line(format!(
+            "return {}[value + \"_\" if value in ({},) else value]",
+            name,
+            escaped.join(", ")
+        ));
+    }
+    file.close();
+    file.close();
+}
+
+///
+/// Data-carrying enums are transferred by json with a "type" tag, every variant is a data class
+/// extending the base class, and is set to it as an attribute, like `EnumWithData.Named`.
+///
+fn push_data_enum(file: &mut PyFile, desc: &EnumDesc, enums: &[EnumDesc]) {
+    let name = &desc.name;
+    file.open(format!("class {}:", name));
+    file.line("@staticmethod");
+    file.open(format!("def _from_json(value: dict) -> {}:", name));
+    file.line("_type = value[\"type\"]");
+    for variant in desc.variants.iter() {
+        file.open(format!("if _type == \"{}\":", &variant.name));
+        file.line(format!(
+            "return {}._from_json(value)",
+            variant_class(name, &variant.name)
+        ));
+        file.close();
+    }
+    file.line(format!(
+        "raise ValueError(\"unknown type %s for enum {}\" % _type)",
+        name
+    ));
+    file.close();
+    file.close();
+
+    for variant in desc.variants.iter() {
+        file.separate();
+        file.line("@dataclass");
+        push_data_class(
+            file,
+            &variant_class(name, &variant.name),
+            &format!("({})", name),
+            &variant.fields,
+            Some(&variant.name),
+            enums,
+        );
+    }
+
+    file.separate();
+    for variant in desc.variants.iter() {
+        file.line(format!(
+            "{}.{} = {}",
+            name,
+            ident(&variant.name),
+            variant_class(name, &variant.name)
+        ));
+    }
+}
+
+fn variant_class(enum_name: &str, variant_name: &str) -> String {
+    format!("{}_{}", enum_name, variant_name)
+}
+
+///
+/// push a data class converted from and to json, the variants of data-carrying enums are
+/// tagged by `variant`.
+///
+fn push_data_class(
+    file: &mut PyFile,
+    name: &str,
+    supers: &str,
+    fields: &[ArgDesc],
+    variant: Option<&str>,
+    enums: &[EnumDesc],
+) {
+    file.open(format!("class {}{}:", name, supers));
+    for field in fields.iter() {
+        file.line(format!(
+            "{}: {} = {}",
+            ident(&field.name),
+            py_type(field.ty, &field.origin_ty),
+            default_value(field.ty, &field.origin_ty, enums)
+        ));
+    }
+    if !fields.is_empty() {
+        file.separate();
+    }
+
+    file.open("def _to_json(self) -> dict:");
+    file.open("return {");
+    if let Some(variant) = variant {
+        file.line(format!("\"type\": \"{}\",", variant));
+    }
+    for field in fields.iter() {
+        let value = format!("self.{}", ident(&field.name));
+        file.line(format!(
+            "\"{}\": {},",
+            &field.name,
+            to_json(field.ty, &field.origin_ty, &value)
+        ));
+    }
+    file.close();
+    file.line("}");
+    file.close();
+
+    file.separate();
+    file.line("@staticmethod");
+    file.open(format!("def _from_json(value: dict) -> {}:", name));
+    if fields.is_empty() {
+        file.line(format!("return {}()", name));
+    } else {
+        file.open(format!("return {}(", name));
+        for field in fields.iter() {
+            let value = format!("value[\"{}\"]", &field.name);
+            file.line(format!(
+                "{}={},",
+                ident(&field.name),
+                from_json(field.ty, &field.origin_ty, &value)
+            ));
+        }
+        file.close();
+        file.line(")");
+    }
+    file.close();
+    file.close();
+}
+
+///
+/// the defaults of fields, the first variant is taken for enums.
+///
+fn default_value(ty: AstType, origin_ty: &str, enums: &[EnumDesc]) -> String {
+    match ty {
+        AstType::Byte | AstType::Int | AstType::Long => "0".to_owned(),
+        AstType::Float | AstType::Double => "0.0".to_owned(),
+        AstType::Boolean => "False".to_owned(),
+        AstType::String => "\"\"".to_owned(),
+        AstType::Vec(AstBaseType::Byte) if is_bytes(origin_ty) => "b\"\"".to_owned(),
+        AstType::Vec(_) => "field(default_factory=list)".to_owned(),
+        AstType::Map(_, _) => "field(default_factory=dict)".to_owned(),
+        AstType::Optional(_) => "None".to_owned(),
+        _ => {
+            let desc = enums.iter().find(|each| each.name == origin_ty);
+            let variant = desc.and_then(|each| each.variants.first());
+            match (desc, variant) {
+                (Some(desc), Some(variant)) if desc.is_c_like => {
+                    format!("{}.{}", origin_ty, ident(&variant.name))
+                }
+                (Some(_), Some(variant)) => format!(
+                    "field(default_factory=lambda: {}())",
+                    variant_class(origin_ty, &variant.name)
+                ),
+                _ => format!("field(default_factory=lambda: {}())", origin_ty),
+            }
+        }
+    }
+}
+
+fn push_exception(file: &mut PyFile, origin_error_ty: &str) {
+    file.open(format!(
+        "class {}(Exception):",
+        exception_name(origin_error_ty)
+    ));
+    file.open(format!("def __init__(self, error: {}):", origin_error_ty));
+    file.line("super().__init__(error)");
+    file.line("self.error = error");
+    file.close();
+    file.close();
+}
+
+///
+/// Callbacks are implemented by python objects with the methods, or by callables for the ones
+/// with one method. They are passed to rust by a struct of function pointers, which are the
+/// `_invoke_*` functions finding the callback by the index in the struct.
+///
+fn push_callback(file: &mut PyFile, desc: &TraitDesc) -> Result<()> {
+    let name = &desc.name;
+    file.separate();
+    file.open(format!("class {}(abc.ABC):", name));
+    if desc.methods.len() == 1 {
+        file.line(format!(
+            "\"\"\"a callable can be passed instead, which is called as `{}`.\"\"\"",
+            ident(&desc.methods[0].name)
+        ));
+    }
+    for method in desc.methods.iter() {
+        file.separate();
+        file.line("@abc.abstractmethod");
+        let mut args = vec!["self".to_owned()];
+        args.extend(typed_args(method));
+        file.open(format!(
+            "def {}({}){}:",
+            ident(&method.name),
+            args.join(", "),
+            returns_str(method.return_type, &method.origin_return_ty)
+        ));
+        file.line("pass");
+        file.close();
+    }
+    file.close();
+
+    let mut fields = vec![];
+    for method in desc.methods.iter() {
+        let fun_type = callback_fun_type(method);
+        let invoke_name = format!("_invoke_{}_{}", name, &method.name);
+
+        let mut args = vec!["_index".to_owned()];
+        let mut call_args = vec![];
+        for arg in method.args.iter().filter(|arg| arg.ty != AstType::Void) {
+            let arg_name = ident(&arg.name);
+            call_args.push(callback_arg(arg.ty, &arg.origin_ty, &arg_name)?);
+            args.push(arg_name);
+        }
+        let optional_return = primitive_optional(method.return_type);
+        if optional_return.is_some() {
+            args.push("_out".to_owned());
+        }
+
+        let callee = if desc.methods.len() == 1 {
+            format!("getattr(_callback, \"{}\", _callback)", ident(&method.name))
+        } else {
+            format!("_callback.{}", ident(&method.name))
+        };
+
+        file.separate();
+        file.line(format!("@{}", &fun_type));
+        file.open(format!("def {}({}):", &invoke_name, args.join(", ")));
+        file.line("_callback = _ffi.callbacks.get(_index)");
+        let call = format!("{}({})", callee, call_args.join(", "));
+        match (method.return_type, optional_return) {
+            (AstType::Void, _) => file.line(call),
+            (_, Some(base)) => {
+                file.line(format!("_result = {}", call));
+                file.line(format!(
+                    "_out[0] = _ffi.to_optional(_ffi.{}, _result)",
+                    c_optional_ident(&base)
+                ));
+            }
+            _ => {
+                file.line(format!("_result = {}", call));
+                let value = callback_return(method.return_type, &method.origin_return_ty)?;
+                file.line(format!("return {}", value));
+            }
+        }
+        file.close();
+        fields.push((method.name.clone(), fun_type, invoke_name));
+    }
+
+    // the layout of `<mod>_<callback>_Model` in the c bridge.
+    let model = format!("_{}Model", name);
+    file.separate();
+    file.open(format!("class {}(ctypes.Structure):", &model));
+    file.open("_fields_ = [");
+    for &(ref method_name, ref fun_type, _) in fields.iter() {
+        file.line(format!("(\"{}\", {}),", method_name, fun_type));
+    }
+    file.line("(\"free_callback\", _ffi.FREE_CALLBACK),");
+    file.line("(\"free_ptr\", _ffi.FREE_PTR),");
+    file.line("(\"index\", ctypes.c_int64),");
+    file.close();
+    file.line("]");
+    file.close();
+
+    file.separate();
+    file.open(format!("def _{}_model(callback) -> {}:", name, &model));
+    file.open(format!("return {}(", &model));
+    for &(_, _, ref invoke_name) in fields.iter() {
+        file.line(format!("{},", invoke_name));
+    }
+    file.line("_ffi.free_callback,");
+    file.line("_ffi.free_ptr,");
+    file.line("_ffi.callbacks.put(callback),");
+    file.close();
+    file.line(")");
+    file.close();
+    Ok(())
+}
+
+///
+/// the type of the function pointer of a callback method, strings returned to rust are
+/// pointers, which are freed by `free_ptr`. Options of primitives are returned by an out
+/// pointer, as ctypes can't return structures from callbacks.
+///
+fn callback_fun_type(method: &MethodDesc) -> String {
+    let ret = match method.return_type {
+        AstType::Void => "None".to_owned(),
+        AstType::Optional(base) if base.is_primitive() => "None".to_owned(),
+        AstType::String | AstType::Vec(_) | AstType::Struct | AstType::Optional(_) => {
+            "ctypes.c_void_p".to_owned()
+        }
+        _ => c_type(method.return_type, TypeDirection::Argument),
+    };
+    let mut types = vec![ret, "ctypes.c_int64".to_owned()];
+    for arg in method.args.iter().filter(|arg| arg.ty != AstType::Void) {
+        types.push(c_type(arg.ty, TypeDirection::Argument));
+    }
+    if let Some(base) = primitive_optional(method.return_type) {
+        types.push(format!("ctypes.POINTER(_ffi.{})", c_optional_ident(&base)));
+    }
+    format!("ctypes.CFUNCTYPE({})", types.join(", "))
+}
+
+///
+/// Convert an argument of a callback from rust, strings are bytes, or None for null.
+///
+fn callback_arg(ty: AstType, origin_ty: &str, value: &str) -> Result<String> {
+    Ok(match ty {
+        AstType::Boolean => format!("{} > 0", value),
+        AstType::Enum => format!("{}({})", origin_ty, value),
+        AstType::String => format!("{}.decode(\"utf-8\")", value),
+        AstType::Vec(_) | AstType::Struct | AstType::Map(_, _) => {
+            from_json(ty, origin_ty, &format!("_ffi.loads({})", value))
+        }
+        AstType::Optional(base) if base.is_primitive() => from_optional(base, value),
+        AstType::Optional(base) => format!(
+            "None if {} is None else {}",
+            value,
+            optional_from_str(
+                base,
+                &inner_ty(origin_ty, "Option<"),
+                &format!("{}.decode(\"utf-8\")", value)
+            )?
+        ),
+        _ => value.to_owned(),
+    })
+}
+
+///
+/// Convert `_result` of a callback to the value returned to rust.
+///
+fn callback_return(ty: AstType, origin_ty: &str) -> Result<String> {
+    let value = "_result";
+    Ok(match ty {
+        AstType::Boolean => format!("1 if {} else 0", value),
+        AstType::Enum => format!("int({})", value),
+        AstType::String => format!("_ffi.return_str({})", value),
+        AstType::Vec(_) | AstType::Struct => format!(
+            "_ffi.return_str(_ffi.dumps({}))",
+            to_json(ty, origin_ty, value)
+        ),
+        AstType::Optional(base) => format!(
+            "None if {} is None else _ffi.return_str({})",
+            value,
+            optional_to_str(base, &inner_ty(origin_ty, "Option<"), value)?
+        ),
+        AstType::Map(_, _) => {
+            return Err(GenerateError(format!(
+                "find unsupported return type in callback, {}",
+                origin_ty
+            ))
+            .into());
+        }
+        _ => value.to_owned(),
+    })
+}
+
+///
+/// Traits are classes with static methods, the objects of traits with constructors hold the
+/// handle of a boxed rust object, which is freed by `close`.
+///
+struct TraitGen<'a> {
+    desc: &'a TraitDesc,
+    callbacks: &'a [&'a TraitDesc],
+}
+
+impl<'a> TraitGen<'a> {
+    fn push(&self, file: &mut PyFile) -> Result<()> {
+        let name = &self.desc.name;
+
+        // the functions of the c bridge are declared before the class.
+        file.separate();
+        for method in self.desc.methods.iter() {
+            self.push_declaration(file, method)?;
+        }
+        if self.desc.is_object {
+            file.line(format!(
+                "_{}_free_object = _ffi.declare(\"{}_{}_free_object\", None, ctypes.c_int64)",
+                name, &self.desc.mod_name, name
+            ));
+        }
+
+        // callbacks returned from rust are called like the ones implemented in python.
+        let supers = match self.desc.wraps_callback {
+            Some(ref callback) => format!("({})", callback),
+            None => "".to_owned(),
+        };
+        file.separate();
+        file.open(format!("class {}{}:", name, supers));
+        if self.desc.is_object {
+            file.open("def __init__(self, handle: int):");
+            file.line("self._handle = handle");
+            file.close();
+        }
+
+        for method in self.desc.methods.iter() {
+            file.separate();
+            self.push_method(file, method)?;
+        }
+
+        if self.desc.is_object {
+            file.separate();
+            file.open("def close(self) -> None:");
+            file.line("\"\"\"free the rust object, which can't be called any more.\"\"\"");
+            file.line("handle, self._handle = self._handle, 0");
+            file.open("if handle:");
+            file.line(format!("_{}_free_object(handle)", name));
+            file.close();
+            file.close();
+
+            file.separate();
+            file.open(format!("def __enter__(self) -> {}:", name));
+            file.line("return self");
+            file.close();
+
+            file.separate();
+            file.open("def __exit__(self, *args) -> None:");
+            file.line("self.close()");
+            file.close();
+
+            file.separate();
+            file.open("def __del__(self) -> None:");
+            file.line("self.close()");
+            file.close();
+        }
+
+        // an empty class needs a body.
+        if self.desc.methods.is_empty() && !self.desc.is_object {
+            file.line("pass");
+        }
+        file.close();
+        Ok(())
+    }
+
+    fn push_declaration(&self, file: &mut PyFile, method: &MethodDesc) -> Result<()> {
+        let mut types = vec![c_type(method.return_type, TypeDirection::Return)];
+        if is_instance(method) {
+            types.push("ctypes.c_int64".to_owned());
+        }
+        for arg in method.args.iter().filter(|arg| arg.ty != AstType::Void) {
+            let ty = match arg.ty {
+                AstType::Callback => {
+                    self.find_callback(&arg.origin_ty)?;
+                    format!("_{}Model", &arg.origin_ty)
+                }
+                _ => c_type(arg.ty, TypeDirection::Argument),
+            };
+            types.push(ty);
+        }
+        if method.error_type != AstType::Void {
+            types.push("ctypes.POINTER(ctypes.c_void_p)".to_owned());
+        }
+
+        let fun_name = self.fun_name(method);
+        file.line(format!(
+            "_{} = _ffi.declare(\"{}\", {})",
+            &fun_name,
+            &fun_name,
+            types.join(", ")
+        ));
+        Ok(())
+    }
+
+    fn push_method(&self, file: &mut PyFile, method: &MethodDesc) -> Result<()> {
+        let is_instance = is_instance(method);
+        let returns = match method.kind {
+            MethodKind::Constructor => format!(" -> {}", &self.desc.name),
+            _ => returns_str(method.return_type, &method.origin_return_ty),
+        };
+
+        if !is_instance {
+            file.line("@staticmethod");
+        }
+        let mut args = vec![];
+        if is_instance {
+            args.push("self".to_owned());
+        }
+        args.extend(typed_args(method));
+        file.open(format!(
+            "def {}({}){}:",
+            ident(&method.name),
+            args.join(", "),
+            returns
+        ));
+
+        let mut call_args = vec![];
+        if is_instance {
+            file.open("if not self._handle:");
+            file.line(format!(
+                "raise ValueError(\"{} is closed\")",
+                &self.desc.name
+            ));
+            file.close();
+            call_args.push("self._handle".to_owned());
+        }
+        for arg in method.args.iter().filter(|arg| arg.ty != AstType::Void) {
+            let arg_name = ident(&arg.name);
+            let value = match arg.ty {
+                AstType::Callback => format!("_{}_model({})", &arg.origin_ty, &arg_name),
+                _ => to_transfer(arg.ty, &arg.origin_ty, &arg_name)?,
+            };
+            call_args.push(value);
+        }
+        if method.error_type != AstType::Void {
+            file.line("_err = _ffi.new_err()");
+            call_args.push("_err".to_owned());
+        }
+
+        let call = format!("_{}({})", self.fun_name(method), call_args.join(", "));
+        match method.return_type {
+            AstType::Void => file.line(call),
+            _ => file.line(format!("_ret = {}", call)),
+        }
+        file.line("_ffi.check_panic()");
+
+        if method.error_type != AstType::Void {
+            file.line("_error = _ffi.take_err(_err)");
+            file.open("if _error is not None:");
+            let error = match method.error_type {
+                AstType::Enum => format!("{}(int(_error))", &method.origin_error_ty),
+                _ => format!("{}._from_json(_ffi.loads(_error))", &method.origin_error_ty),
+            };
+            file.line(format!(
+                "raise {}({})",
+                exception_name(&method.origin_error_ty),
+                error
+            ));
+            file.close();
+        }
+
+        match method.return_type {
+            _ if method.kind == MethodKind::Constructor => {
+                file.line(format!("return {}(_ret)", &self.desc.name));
+            }
+            AstType::Void => (),
+            AstType::Callback => {
+                file.line(format!("return Rust{}(_ret)", &method.origin_return_ty));
+            }
+            AstType::Optional(base) if base.is_primitive() => {
+                file.line(format!("return {}", from_optional(base, "_ret")));
+            }
+            AstType::Optional(base) => {
+                file.line("_ret = _ffi.take_str(_ret)");
+                file.line(format!(
+                    "return None if _ret is None else {}",
+                    optional_from_str(
+                        base,
+                        &inner_ty(&method.origin_return_ty, "Option<"),
+                        "_ret"
+                    )?
+                ));
+            }
+            ty => {
+                let value = from_transfer(ty, &method.origin_return_ty, "_ret");
+                file.line(format!("return {}", value));
+            }
+        }
+
+        file.close();
+        Ok(())
+    }
+
+    ///
+    /// methods of returned callbacks are named after the wrapper, like the c bridge.
+    ///
+    fn fun_name(&self, method: &MethodDesc) -> String {
+        match self.desc.wraps_callback {
+            Some(_) => format!(
+                "{}_{}_{}",
+                &self.desc.mod_name, &self.desc.name, &method.name
+            ),
+            None => format!("{}_{}", &self.desc.mod_name, &method.name),
+        }
+    }
+
+    fn find_callback(&self, name: &str) -> Result<&TraitDesc> {
+        self.callbacks
+            .iter()
+            .find(|callback| callback.name == name)
+            .map(|callback| *callback)
+            .ok_or_else(|| GenerateError(format!("can't find the callback {}", name)).into())
+    }
+}
+
+enum TypeDirection {
+    Argument,
+    Return,
+}
+
+///
+/// the ctypes of the c bridge, strings returned from rust are pointers, which are freed after
+/// they are read.
+///
+fn c_type(ty: AstType, direction: TypeDirection) -> String {
+    match ty {
+        AstType::Void => "None".to_owned(),
+        AstType::Byte => "ctypes.c_int8".to_owned(),
+        AstType::Int | AstType::Boolean | AstType::Enum => "ctypes.c_int32".to_owned(),
+        AstType::Long | AstType::Callback => "ctypes.c_int64".to_owned(),
+        AstType::Float => "ctypes.c_float".to_owned(),
+        AstType::Double => "ctypes.c_double".to_owned(),
+        AstType::Optional(base) if base.is_primitive() => {
+            format!("_ffi.{}", c_optional_ident(&base))
+        }
+        AstType::String
+        | AstType::Vec(_)
+        | AstType::Struct
+        | AstType::Optional(_)
+        | AstType::Map(_, _) => match direction {
+            TypeDirection::Argument => "ctypes.c_char_p".to_owned(),
+            TypeDirection::Return => "ctypes.c_void_p".to_owned(),
+        },
+    }
+}
+
+///
+/// Convert the python value of `ty` to the value passed to the c bridge, except callbacks,
+/// which are passed by their models.
+///
+fn to_transfer(ty: AstType, origin_ty: &str, value: &str) -> Result<String> {
+    Ok(match ty {
+        AstType::Boolean => format!("1 if {} else 0", value),
+        AstType::Enum => format!("int({})", value),
+        AstType::String => format!("{}.encode(\"utf-8\")", value),
+        AstType::Vec(_) | AstType::Struct | AstType::Map(_, _) => format!(
+            "_ffi.dumps({}).encode(\"utf-8\")",
+            to_json(ty, origin_ty, value)
+        ),
+        AstType::Optional(base) if base.is_primitive() => format!(
+            "_ffi.to_optional(_ffi.{}, {})",
+            c_optional_ident(&base),
+            value
+        ),
+        AstType::Optional(base) => format!(
+            "None if {} is None else {}.encode(\"utf-8\")",
+            value,
+            optional_to_str(base, &inner_ty(origin_ty, "Option<"), value)?
+        ),
+        _ => value.to_owned(),
+    })
+}
+
+///
+/// Convert the value returned from the c bridge to the python value of `ty`, except options,
+/// which may be null.
+///
+fn from_transfer(ty: AstType, origin_ty: &str, value: &str) -> String {
+    match ty {
+        AstType::Boolean => format!("{} > 0", value),
+        AstType::Enum => format!("{}({})", origin_ty, value),
+        AstType::String => format!("_ffi.take_str({})", value),
+        AstType::Vec(_) | AstType::Struct | AstType::Map(_, _) => {
+            from_json(ty, origin_ty, &format!("_ffi.take_json({})", value))
+        }
+        _ => value.to_owned(),
+    }
+}
+
+///
+/// the base of an `Option<T>` of primitives, which is passed by a struct of the c bridge.
+///
+fn primitive_optional(ty: AstType) -> Option<AstBaseType> {
+    match ty {
+        AstType::Optional(base) if base.is_primitive() => Some(base),
+        _ => None,
+    }
+}
+
+///
+/// Convert the struct of an `Option<T>` of primitives to the python value, booleans are
+/// passed as integers.
+///
+fn from_optional(base: AstBaseType, value: &str) -> String {
+    match base {
+        AstBaseType::Boolean => format!("_ffi.from_optional({}, bool)", value),
+        _ => format!("_ffi.from_optional({})", value),
+    }
+}
+
+///
+/// The inner value of an `Option<T>` is transferred as a string, C-like enums by their
+/// discriminants, and others by json.
+///
+fn optional_to_str(base: AstBaseType, inner: &str, value: &str) -> Result<String> {
+    Ok(match base {
+        AstBaseType::String => value.to_owned(),
+        AstBaseType::Enum => format!("str(int({}))", value),
+        AstBaseType::Struct => format!("_ffi.dumps({}._to_json())", value),
+        AstBaseType::Byte
+        | AstBaseType::Int
+        | AstBaseType::Long
+        | AstBaseType::Float
+        | AstBaseType::Double
+        | AstBaseType::Boolean => format!("_ffi.dumps({})", value),
+        _ => {
+            return Err(
+                GenerateError(format!("find unsupported type in Option, {}", inner)).into(),
+            );
+        }
+    })
+}
+
+fn optional_from_str(base: AstBaseType, inner: &str, value: &str) -> Result<String> {
+    Ok(match base {
+        AstBaseType::String => value.to_owned(),
+        AstBaseType::Enum => format!("{}(int({}))", inner, value),
+        AstBaseType::Struct => format!("{}._from_json(_ffi.loads({}))", inner, value),
+        AstBaseType::Byte
+        | AstBaseType::Int
+        | AstBaseType::Long
+        | AstBaseType::Float
+        | AstBaseType::Double
+        | AstBaseType::Boolean => format!("_ffi.loads({})", value),
+        _ => {
+            return Err(
+                GenerateError(format!("find unsupported type in Option, {}", inner)).into(),
+            );
+        }
+    })
+}
+
+///
+/// Convert the python value of `ty` to the value dumped to json, like serde does for the
+/// mirrors in the bridge.
+///
+fn to_json(ty: AstType, origin_ty: &str, value: &str) -> String {
+    match ty {
+        AstType::Enum | AstType::Struct => format!("{}._to_json()", value),
+        AstType::Vec(AstBaseType::Byte) if is_bytes(origin_ty) => format!("list({})", value),
+        AstType::Vec(base) => {
+            let each = to_json(AstType::from(base), &inner_ty(origin_ty, "Vec<"), "each");
+            if each == "each" {
+                value.to_owned()
+            } else {
+                format!("[{} for each in {}]", each, value)
+            }
+        }
+        AstType::Optional(base) => {
+            let inner = to_json(AstType::from(base), &inner_ty(origin_ty, "Option<"), value);
+            if inner == value {
+                value.to_owned()
+            } else {
+                format!("None if {} is None else {}", value, inner)
+            }
+        }
+        // integer keys are dumped as strings by json.
+        AstType::Map(_, value_base) => {
+            let (_, _, value_ty) = split_map_origin_ty(origin_ty);
+            let item = to_json(AstType::from(value_base), &value_ty, "item");
+            if item == "item" {
+                value.to_owned()
+            } else {
+                format!("{{key: {} for key, item in {}.items()}}", item, value)
+            }
+        }
+        _ => value.to_owned(),
+    }
+}
+
+///
+/// Convert the value loaded from json to the python value of `ty`.
+///
+fn from_json(ty: AstType, origin_ty: &str, value: &str) -> String {
+    match ty {
+        AstType::Enum | AstType::Struct => format!("{}._from_json({})", origin_ty, value),
+        AstType::Vec(AstBaseType::Byte) if is_bytes(origin_ty) => format!("bytes({})", value),
+        AstType::Vec(base) => {
+            let each = from_json(AstType::from(base), &inner_ty(origin_ty, "Vec<"), "each");
+            if each == "each" {
+                value.to_owned()
+            } else {
+                format!("[{} for each in {}]", each, value)
+            }
+        }
+        AstType::Optional(base) => {
+            let inner = from_json(AstType::from(base), &inner_ty(origin_ty, "Option<"), value);
+            if inner == value {
+                value.to_owned()
+            } else {
+                format!("None if {} is None else {}", value, inner)
+            }
+        }
+        AstType::Map(key_base, value_base) => {
+            let (_, _, value_ty) = split_map_origin_ty(origin_ty);
+            let key = match key_base {
+                AstBaseType::String => "key",
+                _ => "int(key)",
+            };
+            let item = from_json(AstType::from(value_base), &value_ty, "item");
+            if key == "key" && item == "item" {
+                value.to_owned()
+            } else {
+                format!("{{{}: {} for key, item in {}.items()}}", key, item, value)
+            }
+        }
+        _ => value.to_owned(),
+    }
+}
+
+fn py_type(ty: AstType, origin_ty: &str) -> String {
+    match ty {
+        AstType::Void => "None".to_owned(),
+        AstType::Byte | AstType::Int | AstType::Long => "int".to_owned(),
+        AstType::Float | AstType::Double => "float".to_owned(),
+        AstType::Boolean => "bool".to_owned(),
+        AstType::String => "str".to_owned(),
+        AstType::Vec(AstBaseType::Byte) if is_bytes(origin_ty) => "bytes".to_owned(),
+        AstType::Vec(base) => format!(
+            "List[{}]",
+            py_type(AstType::from(base), &inner_ty(origin_ty, "Vec<"))
+        ),
+        AstType::Optional(base) => format!(
+            "Optional[{}]",
+            py_type(AstType::from(base), &inner_ty(origin_ty, "Option<"))
+        ),
+        AstType::Map(key, value) => {
+            let (_, key_ty, value_ty) = split_map_origin_ty(origin_ty);
+            format!(
+                "Dict[{}, {}]",
+                py_type(AstType::from(key), &key_ty),
+                py_type(AstType::from(value), &value_ty)
+            )
+        }
+        AstType::Callback | AstType::Struct | AstType::Enum => origin_ty.to_owned(),
+    }
+}
+
+fn typed_args(method: &MethodDesc) -> Vec<String> {
+    method
+        .args
+        .iter()
+        .filter(|arg| arg.ty != AstType::Void)
+        .map(|arg| format!("{}: {}", ident(&arg.name), py_type(arg.ty, &arg.origin_ty)))
+        .collect()
+}
+
+fn returns_str(ty: AstType, origin_ty: &str) -> String {
+    format!(" -> {}", py_type(ty, origin_ty))
+}
+
+fn is_instance(method: &MethodDesc) -> bool {
+    method.kind == MethodKind::Ref || method.kind == MethodKind::RefMut
+}
+
+///
+/// `Vec<u8>` is `bytes`, other vectors of bytes are lists of integers.
+///
+fn is_bytes(origin_ty: &str) -> bool {
+    inner_ty(origin_ty, "Vec<") == "u8"
+}
+
+fn inner_ty(origin_ty: &str, outer: &str) -> String {
+    origin_ty.replace(outer, "").replace(">", "")
+}
+
+///
+/// names which are keywords of python are followed by an underscore.
+///
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+fn exception_name(origin_error_ty: &str) -> String {
+    format!("{}Exception", origin_error_ty)
+}
+
+///
+/// A python source file, the lines are indented by the blocks they are in.
+///
+struct PyFile {
+    body: String,
+    level: usize,
+}
+
+impl PyFile {
+    fn new() -> Self {
+        PyFile {
+            body: String::new(),
+            level: 0,
+        }
+    }
+
+    fn line<S: AsRef<str>>(&mut self, line: S) {
+        let line = line.as_ref();
+        if !line.is_empty() {
+            for _ in 0..self.level {
+                self.body.push_str("    ");
+            }
+            self.body.push_str(line);
+        }
+        self.body.push('\n');
+    }
+
+    fn open<S: AsRef<str>>(&mut self, line: S) {
+        self.line(line);
+        self.level += 1;
+    }
+
+    fn close(&mut self) {
+        self.level -= 1;
+    }
+
+    ///
+    /// separate definitions by two empty lines at the top level, and by one in classes, unless
+    /// it is the first one of a block.
+    ///
+    fn separate(&mut self) {
+        if self.body.is_empty() || self.body.ends_with(":\n") {
+            return;
+        }
+        let blank_lines = if self.level == 0 { 2 } else { 1 };
+        while !self.body.ends_with(&"\n".repeat(blank_lines + 1)) {
+            self.body.push('\n');
+        }
+    }
+
+    fn to_python(&self) -> String {
+        self.body.clone()
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod config;
+mod dest;
+pub(crate) mod process;
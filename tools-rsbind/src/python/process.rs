@@ -0,0 +1,387 @@
+use ast::AstResult;
+use bridge::prj::Unpack;
+use bridges::BridgeGen::CGen;
+use cargo;
+use errors::ErrorKind::*;
+use errors::*;
+use incremental::write_if_changed;
+use process::{print_output, BuildProcess};
+use std::env::consts::{ARCH, DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use super::config::Python;
+use super::dest::PythonCodeGen;
+
+///
+/// Build the c bridge for the host, and a python package calling it by ctypes, which is laid
+/// out like an unpacked wheel.
+///
+pub(crate) struct PythonProcess<'a> {
+    origin_prj_path: &'a PathBuf,
+    dest_prj_path: &'a PathBuf,
+    bridge_prj_path: &'a PathBuf,
+    host_crate_name: &'a str,
+    ast_result: &'a AstResult,
+    config: Option<Python>,
+}
+
+impl<'a> PythonProcess<'a> {
+    pub fn new(
+        origin_prj_path: &'a PathBuf,
+        dest_prj_path: &'a PathBuf,
+        bridge_prj_path: &'a PathBuf,
+        host_crate_name: &'a str,
+        ast_result: &'a AstResult,
+        config: Option<Python>,
+    ) -> Self {
+        PythonProcess {
+            origin_prj_path,
+            dest_prj_path,
+            bridge_prj_path,
+            host_crate_name,
+            ast_result,
+            config,
+        }
+    }
+}
+
+impl<'a> PythonProcess<'a> {
+    fn config(&self) -> Python {
+        match self.config {
+            Some(ref config) => config.to_owned(),
+            None => Python::default(),
+        }
+    }
+
+    fn package_name(&self) -> String {
+        self.config().package_name(self.host_crate_name)
+    }
+
+    fn package_path(&self) -> PathBuf {
+        self.dest_prj_path.join(self.package_name())
+    }
+
+    ///
+    /// the version of the package is the version of the crate.
+    ///
+    fn version(&self) -> Result<String> {
+        let toml_path = self.origin_prj_path.join("Cargo.toml");
+        let manifest = cargo::manifest(toml_path.as_path())?;
+        Ok(manifest
+            .package
+            .version
+            .unwrap_or_else(|| "0.0.0".to_owned()))
+    }
+
+    fn dist_info_name(&self) -> Result<String> {
+        Ok(format!(
+            "{}-{}.dist-info",
+            self.package_name(),
+            self.version()?
+        ))
+    }
+
+    fn bridge_lib_name(&self) -> String {
+        format!(
+            "{}{}_c_bridge_prj{}",
+            DLL_PREFIX,
+            &self.host_crate_name.replace("-", "_"),
+            DLL_SUFFIX
+        )
+    }
+
+    ///
+    /// the library in the package, which is loaded by `_ffi.py`.
+    ///
+    fn dest_lib_name(&self) -> String {
+        format!("{}{}{}", DLL_PREFIX, self.package_name(), DLL_SUFFIX)
+    }
+
+    ///
+    /// the platform tag of wheels for the host, only linux and macOS are supported.
+    ///
+    fn platform_tag(&self) -> String {
+        if cfg!(target_os = "macos") {
+            match ARCH {
+                "aarch64" => "macosx_11_0_arm64".to_owned(),
+                arch => format!("macosx_10_9_{}", arch),
+            }
+        } else {
+            format!("linux_{}", ARCH)
+        }
+    }
+
+    ///
+    /// METADATA, WHEEL and top_level.txt of the package, RECORD is written after the library
+    /// is copied.
+    ///
+    fn gen_dist_info(&self) -> Result<()> {
+        let dist_info_path = self.dest_prj_path.join(self.dist_info_name()?);
+        fs::create_dir_all(&dist_info_path)?;
+
+        let metadata = format!(
+            "Metadata-Version: 2.1\n\
+             Name: {name}\n\
+             Version: {version}\n\
+             Summary: python bindings of the rust crate {crate_name}\n\
+             Requires-Python: >=3.7\n",
+            name = &self.package_name(),
+            version = &self.version()?,
+            crate_name = &self.host_crate_name
+        );
+        write_if_changed(&dist_info_path.join("METADATA"), metadata.as_bytes())?;
+
+        let wheel = format!(
+            "Wheel-Version: 1.0\n\
+             Generator: rsbind\n\
+             Root-Is-Purelib: false\n\
+             Tag: py3-none-{}\n",
+            self.platform_tag()
+        );
+        write_if_changed(&dist_info_path.join("WHEEL"), wheel.as_bytes())?;
+
+        let top_level = format!("{}\n", self.package_name());
+        write_if_changed(&dist_info_path.join("top_level.txt"), top_level.as_bytes())?;
+        Ok(())
+    }
+
+    ///
+    /// list the files of the package and the dist-info, without hashes.
+    ///
+    fn gen_record(&self) -> Result<()> {
+        let dist_info_name = self.dist_info_name()?;
+        let mut record = String::new();
+        for dir in [self.package_name(), dist_info_name.clone()].iter() {
+            let mut names = fs::read_dir(self.dest_prj_path.join(dir))?
+                .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().into_owned()))
+                .collect::<::std::io::Result<Vec<String>>>()?;
+            names.sort();
+            for name in names.iter().filter(|name| *name != "RECORD") {
+                record.push_str(&format!("{}/{},,\n", dir, name));
+            }
+        }
+        record.push_str(&format!("{}/RECORD,,\n", &dist_info_name));
+
+        let record_path = self.dest_prj_path.join(&dist_info_name).join("RECORD");
+        write_if_changed(&record_path, record.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl<'a> BuildProcess for PythonProcess<'a> {
+    fn unpack(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn gen_bridge_src(&self) -> Result<()> {
+        // python calls the same c bridge as c programs.
+        info!("begin unzip rust template for python");
+        let buf: &[u8] = include_bytes!("../c/res/template_bridge_c.zip");
+        let unpack = Unpack {
+            path: self.bridge_prj_path,
+            host_crate: self.host_crate_name,
+            host_path: self.origin_prj_path,
+            contract_mod: &self.ast_result.contract_mod,
+            imp_mod: &self.ast_result.imp_mod,
+            buf,
+            features: &self.config().features(),
+        };
+
+        unpack.unpack()?;
+
+        let bridge_c_src_path = self.bridge_prj_path.join("src").join("c").join("bridge");
+        fs::create_dir_all(&bridge_c_src_path)?;
+        CGen(
+            self.host_crate_name.to_owned(),
+            self.ast_result,
+            &bridge_c_src_path,
+        )
+        .gen_bridges()
+        .chain_err(|| GenerateError("can't generate c bridges for python".to_string()))?;
+
+        let _ = Command::new("cargo")
+            .arg("fmt")
+            .current_dir(&self.bridge_prj_path)
+            .output();
+
+        Ok(())
+    }
+
+    fn build_bridge_prj(&self) -> Result<()> {
+        info!("building c bridge project for python");
+
+        let build_cmd = format!(
+            "cargo rustc --lib {} --target-dir {} {}",
+            self.config().release_str(),
+            "target",
+            &self.config().rustc_param()
+        );
+
+        debug!("run building => {}", &build_cmd);
+
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(build_cmd)
+            .current_dir(self.bridge_prj_path)
+            .output()?;
+
+        print_output(&output)?;
+
+        if !output.status.success() {
+            return Err(
+                CommandError(format!("run build python rust project build failed.")).into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    fn copy_bridge_outputs(&self) -> Result<()> {
+        info!("copy output files to python package.");
+
+        let debug_release = if self.config().is_release() {
+            "release"
+        } else {
+            "debug"
+        };
+        let lib_src = self
+            .bridge_prj_path
+            .join("target")
+            .join(debug_release)
+            .join(self.bridge_lib_name());
+        let package_path = self.package_path();
+        fs::create_dir_all(&package_path)?;
+        let lib_dest = package_path.join(self.dest_lib_name());
+        fs::copy(&lib_src, &lib_dest)
+            .chain_err(|| FileError(format!("copy python bridge output {:?} failed.", &lib_src)))?;
+
+        Ok(())
+    }
+
+    fn gen_bind_code(&self) -> Result<()> {
+        info!("generate python package.");
+        let package_path = self.package_path();
+        fs::create_dir_all(&package_path)?;
+        PythonCodeGen {
+            package_dir: &package_path,
+            ast: self.ast_result,
+            crate_name: self.host_crate_name.to_owned(),
+            lib_file: self.dest_lib_name(),
+        }
+        .gen_python_code()?;
+
+        self.gen_dist_info()
+    }
+
+    fn build_dest_prj(&self) -> Result<()> {
+        // nothing to build for python, the package is copied to the origin project.
+        self.gen_record()?;
+
+        let target = self.origin_prj_path.join("target").join("python");
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        fs::create_dir_all(&target)?;
+
+        // the package and the dist-info have no sub directories.
+        for dir in [self.package_name(), self.dist_info_name()?].iter() {
+            fs::create_dir_all(&target.join(dir))?;
+            for entry in fs::read_dir(self.dest_prj_path.join(dir))? {
+                let from = entry?.path();
+                let to = target.join(dir).join(from.file_name().unwrap_or_default());
+                fs::copy(&from, &to)
+                    .chain_err(|| FileError(format!("copy python output {:?} failed.", &from)))?;
+            }
+        }
+
+        info!("python package is in {:?}", &target);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ast::AstHandler;
+    use config::Common;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn generates_the_bridge_and_package_of_a_crate() {
+        let prj = TempDir::with_files(
+            "python_process",
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"demo-sdk\"\nversion = \"1.2.3\"\n",
+                ),
+                ("src/contract/mod.rs", "pub mod demo;"),
+                (
+                    "src/contract/demo.rs",
+                    r#"
+                    pub trait Greeter {
+                        fn greet(name: String, person: Person) -> Option<String>;
+                    }
+                    pub struct Person {
+                        pub name: String,
+                        pub age: i32,
+                    }
+                    "#,
+                ),
+                ("src/imp/mod.rs", "pub mod demo;"),
+                (
+                    "src/imp/demo.rs",
+                    r#"
+                    use contract::demo::*;
+                    pub struct GreeterImp {}
+                    impl Greeter for GreeterImp {
+                        fn greet(name: String, person: Person) -> Option<String> {
+                            Some(format!("hello {} {}", name, person.name))
+                        }
+                    }
+                    "#,
+                ),
+            ],
+        );
+        let ast_result = AstHandler::new("demo-sdk".to_owned(), &Common::default())
+            .parse(prj.path())
+            .unwrap();
+        let dest = prj.path().join("python_dest");
+        let bridge = prj.path().join("python_bridge");
+        let process = PythonProcess::new(prj.path(), &dest, &bridge, "demo-sdk", &ast_result, None);
+
+        process.gen_bridge_src().unwrap();
+        let code = fs::read_to_string(bridge.join("src/c/bridge/c_demo.rs"))
+            .unwrap()
+            .replace(char::is_whitespace, "");
+        assert!(code.contains(
+            "pubextern\"C\"fndemo_greet(name:*constc_char,person:*constc_char)->*mutc_char{"
+        ));
+
+        process.gen_bind_code().unwrap();
+        let package = fs::read_to_string(dest.join("demo_sdk/__init__.py")).unwrap();
+        assert!(package.contains(
+            "@dataclass\n\
+             class Person:\n    \
+             name: str = \"\"\n    \
+             age: int = 0\n"
+        ));
+        assert!(package.contains(
+            "_demo_greet = _ffi.declare(\"demo_greet\", ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p)\n"
+        ));
+        assert!(package.contains(
+            "    def greet(name: str, person: Person) -> Optional[str]:\n        \
+             _ret = _demo_greet(name.encode(\"utf-8\"), _ffi.dumps(person._to_json()).encode(\"utf-8\"))\n        \
+             _ffi.check_panic()\n"
+        ));
+        assert_eq!(
+            fs::read_to_string(dest.join("demo_sdk-1.2.3.dist-info/METADATA")).unwrap(),
+            "Metadata-Version: 2.1\n\
+             Name: demo_sdk\n\
+             Version: 1.2.3\n\
+             Summary: python bindings of the rust crate demo-sdk\n\
+             Requires-Python: >=3.7\n"
+        );
+    }
+}
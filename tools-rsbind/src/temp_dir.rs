@@ -0,0 +1,53 @@
+//!
+//! Directories of the files written by tests, which are removed when dropped.
+//!
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    ///
+    /// create an empty directory, `name` must be unique among the tests run together.
+    ///
+    pub(crate) fn new(name: &str) -> TempDir {
+        let path = env::temp_dir().join(format!("rsbind_{}_{}", process::id(), name));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    ///
+    /// create a directory with the files given by their relative paths and contents.
+    ///
+    pub(crate) fn with_files(name: &str, files: &[(&str, &str)]) -> TempDir {
+        let dir = TempDir::new(name);
+        for &(path, content) in files.iter() {
+            dir.write(path, content);
+        }
+        dir
+    }
+
+    pub(crate) fn path(&self) -> &PathBuf {
+        &self.path
+    }
+
+    ///
+    /// write a file, the directories containing it are created.
+    ///
+    pub(crate) fn write(&self, path: &str, content: &str) {
+        let file = self.path.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, content).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
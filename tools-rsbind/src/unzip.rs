@@ -1,55 +1,50 @@
 use errors::ErrorKind::*;
 use errors::*;
+use incremental::write_if_changed;
 use std::fs;
-use std::fs::File;
 use std::io::Cursor;
-use std::io::Write;
 use std::path::PathBuf;
 
 use std::io::Read;
 use zip::ZipArchive;
 
 pub(crate) fn unzip_to(buf: &[u8], path: &PathBuf) -> Result<()> {
+    unzip_with(buf, path, |_, content| Ok(content))
+}
+
+///
+/// Unzip `buf` into `path`, the content of each file is passed to `replace` with its name in
+/// the zip, like `src/lib.rs`, before being written. Files existing in `path` are kept, and
+/// only rewritten if their contents change.
+///
+pub(crate) fn unzip_with<F>(buf: &[u8], path: &PathBuf, replace: F) -> Result<()>
+where
+    F: Fn(&str, Vec<u8>) -> Result<Vec<u8>>,
+{
     let reader = Cursor::new(buf);
     let mut archive = ZipArchive::new(reader).map_err(|e| ZipError(e.to_string()))?;
 
-    println!("begin unzip every file. len = {}", archive.len());
+    debug!("begin unzip every file. len = {}", archive.len());
     for i in 0..archive.len() {
         let mut zip_file = archive.by_index(i).map_err(|e| ZipError(e.to_string()))?;
 
-        println!("unzip file name = {}", &zip_file.name());
+        debug!("unzip file name = {}", &zip_file.name());
         let file_path = path.join(&zip_file.name());
         if zip_file.name().ends_with("/") {
-            if file_path.exists() {
-                fs::remove_dir_all(&file_path)?;
-            }
             fs::create_dir_all(&file_path)?;
             continue;
         }
 
-        let parent_path = file_path.parent().ok_or(ZipError(format!(
-            "can't find parent path for {:?}",
-            &file_path
-        )))?;
-
-        fs::create_dir_all(&parent_path)?;
-
-        let mut file = File::create(&file_path).map_err(|e| {
+        let mut content = vec![];
+        zip_file.read_to_end(&mut content).map_err(|e| {
             ZipError(format!(
-                "can't create file for {:?}, with error => {:?}",
+                "read bytes from zip error, file = {:?}, error => {:?}",
                 &file_path, e
             ))
         })?;
 
-        for byte in zip_file.bytes() {
-            let byte = byte.map_err(|e| {
-                ZipError(format!(
-                    "read bytes from zip error, file = {:?}, error => {:?}",
-                    &file_path, e
-                ))
-            })?;
-            file.write_all(&[byte])?;
-        }
+        let content = replace(zip_file.name(), content)?;
+        write_if_changed(&file_path, &content)?;
     }
 
     Ok(())
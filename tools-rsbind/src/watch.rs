@@ -0,0 +1,218 @@
+//!
+//! Generate again whenever the contracts, the implementations or the config change.
+//!
+//! There is no portable file notification in std, so the watched paths are polled, and
+//! their contents are compared, so saving a file without changing it triggers nothing.
+//!
+use errors::*;
+use incremental::Fingerprint;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+const POLL_INTERVAL_MS: u64 = 500;
+/// the directories of building outputs in generated projects, which are not generated by us.
+const OUTPUT_SKIP_DIRS: [&str; 3] = ["target", "build", ".gradle"];
+const MAX_LISTED: usize = 20;
+
+///
+/// The hashes of the contents of the files under some paths.
+///
+#[derive(PartialEq)]
+pub(crate) struct Snapshot {
+    files: BTreeMap<PathBuf, String>,
+}
+
+impl Snapshot {
+    ///
+    /// hash the files under `roots`, which can be files or directories. Paths which don't
+    /// exist or can't be read are left out, they may be removed while walking.
+    ///
+    pub(crate) fn take(roots: &[PathBuf], skip_dirs: &[&str]) -> Snapshot {
+        let mut snapshot = Snapshot {
+            files: BTreeMap::new(),
+        };
+        for root in roots.iter() {
+            snapshot.walk(root, skip_dirs);
+        }
+        snapshot
+    }
+
+    fn walk(&mut self, path: &PathBuf, skip_dirs: &[&str]) {
+        if path.is_dir() {
+            let entries = match fs::read_dir(path) {
+                Ok(entries) => entries,
+                Err(_) => return,
+            };
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let child = entry.path();
+                let skipped = child.is_dir()
+                    && child
+                        .file_name()
+                        .and_then(|name| name.to_str())
+                        .map_or(false, |name| skip_dirs.contains(&name));
+                if !skipped {
+                    self.walk(&child, skip_dirs);
+                }
+            }
+        } else if let Ok(content) = fs::read(path) {
+            let mut fingerprint = Fingerprint::new();
+            fingerprint.add(&content);
+            self.files.insert(path.clone(), fingerprint.to_hex());
+        }
+    }
+
+    ///
+    /// the files added, changed and removed from this snapshot to `newer`, marked by `+`, `~`
+    /// and `-`.
+    ///
+    fn changes<'a>(&'a self, newer: &'a Snapshot) -> Vec<(char, &'a PathBuf)> {
+        let mut changes = vec![];
+        for (path, hash) in newer.files.iter() {
+            match self.files.get(path) {
+                None => changes.push(('+', path)),
+                Some(old) if old != hash => changes.push(('~', path)),
+                _ => (),
+            }
+        }
+        for path in self.files.keys() {
+            if !newer.files.contains_key(path) {
+                changes.push(('-', path));
+            }
+        }
+        changes
+    }
+
+    ///
+    /// print how the files changed from this snapshot to `newer`, relative to `base`.
+    ///
+    pub(crate) fn print_changes(&self, newer: &Snapshot, base: &PathBuf) {
+        let changes = self.changes(newer);
+        if changes.is_empty() {
+            info!("generated files are unchanged.");
+            return;
+        }
+
+        let count = |mark: char| changes.iter().filter(|change| change.0 == mark).count();
+        info!(
+            "generated files: {} added, {} changed, {} removed.",
+            count('+'),
+            count('~'),
+            count('-')
+        );
+        for &(mark, path) in changes.iter().take(MAX_LISTED) {
+            let relative = path.strip_prefix(base).unwrap_or(path);
+            info!("  {} {}", mark, relative.display());
+        }
+        if changes.len() > MAX_LISTED {
+            info!("  ... and {} more", changes.len() - MAX_LISTED);
+        }
+    }
+}
+
+///
+/// Run `gen` once, then again whenever the files under the paths returned by `sources`
+/// change, until the process is killed. Errors of `gen` are printed, and the changes of the
+/// files under `outputs` are summarized after each run.
+///
+pub(crate) fn watch<S, G>(sources: S, outputs: &PathBuf, gen: G) -> Result<()>
+where
+    S: Fn() -> Vec<PathBuf>,
+    G: Fn() -> Result<()>,
+{
+    let interval = Duration::from_millis(POLL_INTERVAL_MS);
+    let mut watched = Snapshot::take(&sources(), &[]);
+    loop {
+        let before = Snapshot::take(&[outputs.clone()], &OUTPUT_SKIP_DIRS);
+        match gen() {
+            Ok(_) => {
+                let after = Snapshot::take(&[outputs.clone()], &OUTPUT_SKIP_DIRS);
+                before.print_changes(&after, outputs);
+            }
+            Err(err) => eprint!("{}", err.report()),
+        }
+        info!("watching for changes, press ctrl-c to stop.");
+
+        loop {
+            thread::sleep(interval);
+            let current = Snapshot::take(&sources(), &[]);
+            if current != watched {
+                watched = current;
+                break;
+            }
+        }
+
+        // editors may save several files in a row, wait until they settle.
+        loop {
+            thread::sleep(interval);
+            let current = Snapshot::take(&sources(), &[]);
+            if current == watched {
+                break;
+            }
+            watched = current;
+        }
+        info!("changes found, generating again.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use temp_dir::TempDir;
+
+    fn changes(older: &Snapshot, newer: &Snapshot, base: &PathBuf) -> Vec<String> {
+        older
+            .changes(newer)
+            .into_iter()
+            .map(|(mark, path)| format!("{} {}", mark, path.strip_prefix(base).unwrap().display()))
+            .collect()
+    }
+
+    #[test]
+    fn compares_contents_of_files() {
+        let dir = TempDir::with_files(
+            "watch_contents",
+            &[("src/user.rs", "pub struct User {}"), ("src/group.rs", "")],
+        );
+        let base = dir.path();
+        let older = Snapshot::take(&[base.join("src")], &[]);
+
+        dir.write("src/user.rs", "pub struct User {}");
+        assert!(Snapshot::take(&[base.join("src")], &[]) == older);
+
+        dir.write("src/user.rs", "pub struct User { pub id: i64 }");
+        dir.write("src/net/http.rs", "");
+        fs::remove_file(base.join("src/group.rs")).unwrap();
+        let newer = Snapshot::take(&[base.join("src")], &[]);
+        assert!(newer != older);
+        assert_eq!(
+            changes(&older, &newer, base),
+            vec!["+ src/net/http.rs", "~ src/user.rs", "- src/group.rs"]
+        );
+    }
+
+    #[test]
+    fn takes_files_and_skips_dirs() {
+        let dir = TempDir::with_files(
+            "watch_skips",
+            &[
+                ("Rsbind.toml", ""),
+                ("gen/bridge.rs", ""),
+                ("gen/target/debug/lib.so", ""),
+                ("gen/rustlib/build/classes.jar", ""),
+            ],
+        );
+        let base = dir.path();
+        let empty = Snapshot::take(&[base.join("missing")], &[]);
+        let snapshot = Snapshot::take(
+            &[base.join("Rsbind.toml"), base.join("gen")],
+            &OUTPUT_SKIP_DIRS,
+        );
+        assert_eq!(
+            changes(&empty, &snapshot, base),
+            vec!["+ Rsbind.toml", "+ gen/bridge.rs"]
+        );
+    }
+}
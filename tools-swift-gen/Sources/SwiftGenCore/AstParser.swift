@@ -18,6 +18,7 @@ class AstParser {
         let dic = loadAstJson()
         var traits: [TraitDesc] = []
         var structs: [StructDesc] = []
+        var enums: [EnumDesc] = []
         
         let ty = dic["ty"] as! String
         if ty == "struct" {
@@ -26,9 +27,33 @@ class AstParser {
         } else if ty == "trait" {
             let trait = parseTrait(dic: dic)
             traits.append(trait)
+        } else if ty == "enum" {
+            let enum_ = parseEnum(dic: dic)
+            enums.append(enum_)
         }
         
-        return AstResult(traits: traits, structs: structs)
+        return AstResult(traits: traits, structs: structs, enums: enums)
+    }
+    
+    private func parseEnum(dic: [String:Any]) -> EnumDesc {
+        let enumName = dic["name"] as! String
+        let modName = dic["mod_name"] as! String
+        let crateName = dic["crate_name"] as! String
+        let isCLike = dic["is_c_like"] as! Bool
+        let variants = dic["variants"] as! [Any]
+        let binary = dic["binary"] as? Bool ?? false
+        
+        var variantDescs: [VariantDesc] = []
+        for variant in variants {
+            let variant = variant as! [String: Any]
+            let variantName = variant["name"] as! String
+            let value = variant["value"] as! Int
+            let fields = variant["fields"] as! [Any]
+            let args = parseArgs(args: fields)
+            variantDescs.append(VariantDesc(name: variantName, value: Int32(value), fields: args))
+        }
+        
+        return EnumDesc(name: enumName, mod_name: modName, crate_name: crateName, is_c_like: isCLike, variants: variantDescs, binary: binary)
     }
     
     private func parseStruct(dic: [String:Any]) -> StructDesc {
@@ -37,8 +62,9 @@ class AstParser {
         let crateName = dic["crate_name"] as! String
         let fields = dic["fields"] as! [Any]
         let args = parseArgs(args: fields)
+        let binary = dic["binary"] as? Bool ?? false
         
-        return StructDesc(name: structName, mod_name: modName, crate_name:crateName,  fields: args)
+        return StructDesc(name: structName, mod_name: modName, crate_name:crateName,  fields: args, binary: binary)
     }
     
     private func parseTrait(dic: [String:Any]) -> TraitDesc {
@@ -47,30 +73,39 @@ class AstParser {
         let crateName = dic["crate_name"] as! String
         let methods = dic["methods"] as! [Any]
         let isCallback = dic["is_callback"] as! Bool
+        let isObject = dic["is_object"] as! Bool
+        let wrapsCallback = dic["wraps_callback"] as? String
         
         var methodDescs: [MethodDesc] = []
         for method in methods {
             let method = method as! [String: Any]
             
             let methodName = method["name"] as! String
+            let kind = MethodKind(rawValue: method["kind"] as! String)!
             let orginReturnType = method["origin_return_ty"] as! String
             let args = method["args"] as! [Any]
+            let binaryReturn = method["binary_return"] as? Bool ?? false
             
             var returnType = AstType.VOID
             if let returnTy = method["return_type"] as? String {
                 returnType = AstType.fromStr(ty: returnTy, originTy: orginReturnType)
             } else {
-                if let vecType = method["return_type"] as? [String: String] {
-                    let baseType = vecType["Vec"]!
-                    returnType = AstType.VEC(AstBaseType.fromStr(str: baseType))
+                if let genericType = method["return_type"] as? [String: Any] {
+                    returnType = parseGenericType(genericType: genericType)
                 }
             }
             
+            let originErrorType = method["origin_error_ty"] as! String
+            var errorType = AstType.VOID
+            if let errorTy = method["error_type"] as? String {
+                errorType = AstType.fromStr(ty: errorTy, originTy: originErrorType)
+            }
+            
             var argDescs:[ArgDesc] = parseArgs(args: args)
-            let methodDesc = MethodDesc(name: methodName, return_type: returnType, origin_return_ty: orginReturnType, args: argDescs)
+            let methodDesc = MethodDesc(name: methodName, kind: kind, return_type: returnType, origin_return_ty: orginReturnType, error_type: errorType, origin_error_ty: originErrorType, args: argDescs, binary_return: binaryReturn)
             methodDescs.append(methodDesc)
         }
-        let traitDesc = TraitDesc(name: traitName, mod_name: modName, crate_name:crateName, is_callback: isCallback,  methods: methodDescs)
+        let traitDesc = TraitDesc(name: traitName, mod_name: modName, crate_name:crateName, is_callback: isCallback, is_object: isObject, wraps_callback: wrapsCallback, methods: methodDescs)
         return traitDesc
     }
     
@@ -80,7 +115,8 @@ class AstParser {
             let arg = arg as! [String: Any]
             let argName = arg["name"] as! String
             let (argType, argOriginType) = parseType(arg: arg)
-            let argDesc = ArgDesc(name: argName, ty: argType, origin_ty: argOriginType)
+            let binary = arg["binary"] as? Bool ?? false
+            let argDesc = ArgDesc(name: argName, ty: argType, origin_ty: argOriginType, binary: binary)
             argDescs.append(argDesc)
         }
         
@@ -93,15 +129,25 @@ class AstParser {
         if let argTy = arg["ty"] as? String  {
             argType = AstType.fromStr(ty: argTy, originTy: argOriginType)
         } else {
-            if let vecType = arg["ty"] as? [String: String] {
-                let baseType = vecType["Vec"]!
-                argType = AstType.VEC(AstBaseType.fromStr(str: baseType))
+            if let genericType = arg["ty"] as? [String: Any] {
+                argType = parseGenericType(genericType: genericType)
             }
         }
         
         return (argType, argOriginType)
     }
     
+    private func parseGenericType(genericType: [String: Any]) -> AstType {
+        if let baseType = genericType["Vec"] as? String {
+            return AstType.VEC(AstBaseType.fromStr(str: baseType))
+        } else if let baseType = genericType["Optional"] as? String {
+            return AstType.OPTIONAL(AstBaseType.fromStr(str: baseType))
+        } else if let baseTypes = genericType["Map"] as? [String], baseTypes.count == 2 {
+            return AstType.MAP(AstBaseType.fromStr(str: baseTypes[0]), AstBaseType.fromStr(str: baseTypes[1]))
+        }
+        return AstType.VOID
+    }
+    
     private func loadAstJson() -> [String:Any] {
         print("begin load ast json for \(self.astPath).")
         
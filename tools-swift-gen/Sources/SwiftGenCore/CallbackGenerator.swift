@@ -36,6 +36,11 @@ class CallbackGenerator {
         case .VOID:
             // skip
             break
+        case .OPTIONAL(let base):
+            methodSpec.add(returnType: base.toOptionalTypeName(originTy: method.origin_return_ty))
+        case .VEC(let base) where base == AstBaseType.STRUCT || base == AstBaseType.ENUM:
+            let structName = method.origin_return_ty.replacingOccurrences(of: "Vec<", with: "").replacingOccurrences(of: ">", with: "")
+            methodSpec.add(returnType: TypeName.init(keyword: "[\(structName)]"))
         default:
             methodSpec.add(returnType: method.return_type.toTypeName())
         }
@@ -43,7 +48,7 @@ class CallbackGenerator {
         method.args.forEach({ arg in
             switch arg.ty {
             case .VEC(let base):
-                if base == AstBaseType.STRUCT {
+                if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
                     let structName = arg.origin_ty.replacingOccurrences(of: "Vec<", with: "").replacingOccurrences(of: ">", with: "")
                     let argSpec = ParameterSpec.builder(for: arg.name, type: TypeName.init(keyword: "[\(structName)]")).build()
                     methodSpec.add(parameter: argSpec)
@@ -51,6 +56,12 @@ class CallbackGenerator {
                     let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toTypeName()).build()
                     methodSpec.add(parameter: argSpec)
                 }
+            case .OPTIONAL(let base):
+                let argSpec = ParameterSpec.builder(for: arg.name, type: base.toOptionalTypeName(originTy: arg.origin_ty)).build()
+                methodSpec.add(parameter: argSpec)
+            case .MAP(_, _):
+                let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toMapTypeName(originTy: arg.origin_ty)).build()
+                methodSpec.add(parameter: argSpec)
             default:
                 let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toTypeName()).build()
                 methodSpec.add(parameter: argSpec)
@@ -0,0 +1,241 @@
+//
+//  CodecGenerator.swift
+//  SwiftGenCore
+//
+
+import Foundation
+import SwiftPoet
+
+// Swift side of the binary wire format, values are written in big-endian, integers narrower
+// than Int32 are written as Int32, strings and arrays are prefixed by their Int32 lengths, and
+// optionals by a byte telling whether there is a value.
+class CodecGenerator {
+    static let writerClass = "RsbindWriter"
+    static let readerClass = "RsbindReader"
+
+    private let libModName: String
+
+    init(libModName: String) {
+        self.libModName = libModName
+    }
+
+    public func generateWriter() -> ClassSpecBuilder {
+        let classBuilder = ClassSpec.builder(for: CodecGenerator.writerClass)
+            .add(import: self.libModName)
+            .add(modifier: .Public)
+
+        let bytesSpec = FieldSpec.builder(for: "bytes", type: TypeName.init(keyword: "[UInt8]"), construct: .mutableField)
+            .add(initializer: CodeBlock.builder().add(literal: "[UInt8]()").build())
+        classBuilder.add(field: bytesSpec.build())
+
+        classBuilder.add(method: quoteMethod(name: "writeByte", valueType: "Int8", lines: [
+            "bytes.append(UInt8(bitPattern: value))"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeBool", valueType: "Bool", lines: [
+            "bytes.append(value ? 1 : 0)"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeInt", valueType: "Int32", lines: [
+            "var bigEndian = value.bigEndian",
+            "withUnsafeBytes(of: &bigEndian) { bytes.append(contentsOf: $0) }"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeLong", valueType: "Int64", lines: [
+            "var bigEndian = value.bigEndian",
+            "withUnsafeBytes(of: &bigEndian) { bytes.append(contentsOf: $0) }"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeFloat", valueType: "Float32", lines: [
+            "writeInt(value: Int32(bitPattern: value.bitPattern))"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeDouble", valueType: "Float64", lines: [
+            "writeLong(value: Int64(bitPattern: value.bitPattern))"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "writeString", valueType: "String", lines: [
+            "let utf8 = Array(value.utf8)",
+            "writeInt(value: Int32(utf8.count))",
+            "bytes.append(contentsOf: utf8)"
+        ]))
+        return classBuilder
+    }
+
+    public func generateReader() -> ClassSpecBuilder {
+        let classBuilder = ClassSpec.builder(for: CodecGenerator.readerClass)
+            .add(import: self.libModName)
+            .add(modifier: .Public)
+
+        classBuilder.add(field: FieldSpec.builder(for: "bytes", type: TypeName.init(keyword: "[UInt8]")).build())
+        let offsetSpec = FieldSpec.builder(for: "offset", type: TypeName.IntegerType, construct: .mutableField)
+            .add(initializer: CodeBlock.builder().add(literal: "0").build())
+        classBuilder.add(field: offsetSpec.build())
+
+        let initSpec = MethodSpec.builder(for: "init")
+            .add(parameter: ParameterSpec.builder(for: "bytes", type: TypeName.init(keyword: "[UInt8]")).build())
+            .add(codeBlock: CodeBlock.builder().add(codeLine: "self.bytes = bytes").build())
+        classBuilder.add(method: initSpec.build())
+
+        classBuilder.add(method: quoteMethod(name: "readByte", returnType: "Int8", lines: [
+            "let value = Int8(bitPattern: bytes[offset])",
+            "offset += 1",
+            "return value"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readBool", returnType: "Bool", lines: [
+            "return readByte() != 0"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readInt", returnType: "Int32", lines: [
+            "var value: Int32 = 0",
+            "for _ in 0..<4 {",
+            "value = (value << 8) | Int32(bytes[offset])",
+            "offset += 1",
+            "}",
+            "return value"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readLong", returnType: "Int64", lines: [
+            "var value: Int64 = 0",
+            "for _ in 0..<8 {",
+            "value = (value << 8) | Int64(bytes[offset])",
+            "offset += 1",
+            "}",
+            "return value"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readFloat", returnType: "Float32", lines: [
+            "return Float32(bitPattern: UInt32(bitPattern: readInt()))"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readDouble", returnType: "Float64", lines: [
+            "return Float64(bitPattern: UInt64(bitPattern: readLong()))"
+        ]))
+        classBuilder.add(method: quoteMethod(name: "readString", returnType: "String", lines: [
+            "let len = Int(readInt())",
+            "let value = String(decoding: bytes[offset..<offset + len], as: UTF8.self)",
+            "offset += len",
+            "return value"
+        ]))
+        return classBuilder
+    }
+
+    private func quoteMethod(name: String, valueType: String? = nil, returnType: String? = nil, lines: [String]) -> MethodSpec {
+        let methodSpec = MethodSpec.builder(for: name)
+        if let valueType = valueType {
+            methodSpec.add(parameter: ParameterSpec.builder(for: "value", type: TypeName.init(keyword: valueType)).build())
+        }
+        if let returnType = returnType {
+            methodSpec.add(returnType: TypeName.init(keyword: returnType))
+        }
+        let codeBuilder = CodeBlock.builder()
+        for line in lines {
+            codeBuilder.add(codeLine: line)
+        }
+        methodSpec.add(codeBlock: codeBuilder.build())
+        return methodSpec.build()
+    }
+
+    // The lines writing `value` to the writer named `writer`.
+    static func encodeLines(ty: AstType, originTy: String, value: String, writer: String) -> [String] {
+        switch ty {
+        case .VEC(let base):
+            let innerTy = originTy.replacingOccurrences(of: "Vec<", with: "").replacingOccurrences(of: ">", with: "")
+            return [
+                "\(writer).writeInt(value: Int32(\(value).count))",
+                "for each in \(value) {",
+                encodeBase(base: base, innerTy: innerTy, value: "each", writer: writer),
+                "}"
+            ]
+        case .OPTIONAL(let base):
+            let innerTy = originTy.replacingOccurrences(of: "Option<", with: "").replacingOccurrences(of: ">", with: "")
+            return [
+                "if let some = \(value) {",
+                "\(writer).writeBool(value: true)",
+                encodeBase(base: base, innerTy: innerTy, value: "some", writer: writer),
+                "} else {",
+                "\(writer).writeBool(value: false)",
+                "}"
+            ]
+        default:
+            let base = toBaseType(ty: ty)
+            return [encodeBase(base: base, innerTy: originTy, value: value, writer: writer)]
+        }
+    }
+
+    // The expression reading a value from the reader named `reader`.
+    static func decodeExpr(ty: AstType, originTy: String, reader: String) -> String {
+        switch ty {
+        case .VEC(let base):
+            let innerTy = originTy.replacingOccurrences(of: "Vec<", with: "").replacingOccurrences(of: ">", with: "")
+            return "(0..<Int(\(reader).readInt())).map { _ in \(decodeBase(base: base, innerTy: innerTy, reader: reader)) }"
+        case .OPTIONAL(let base):
+            let innerTy = originTy.replacingOccurrences(of: "Option<", with: "").replacingOccurrences(of: ">", with: "")
+            return "\(reader).readBool() ? \(decodeBase(base: base, innerTy: innerTy, reader: reader)) : nil"
+        default:
+            return decodeBase(base: toBaseType(ty: ty), innerTy: originTy, reader: reader)
+        }
+    }
+
+    private static func toBaseType(ty: AstType) -> AstBaseType {
+        switch ty {
+        case .BYTE:
+            return .BYTE
+        case .INT:
+            return .INT
+        case .LONG:
+            return .LONG
+        case .FLOAT:
+            return .FLOAT
+        case .DOUBLE:
+            return .DOUBLE
+        case .STRING:
+            return .STRING
+        case .BOOLEAN:
+            return .BOOLEAN
+        case .ENUM(_):
+            return .ENUM
+        case .STRUCT(_):
+            return .STRUCT
+        default:
+            return .VOID
+        }
+    }
+
+    // Swift integers are Int and floats are Double, so they are converted to the wire types.
+    private static func encodeBase(base: AstBaseType, innerTy: String, value: String, writer: String) -> String {
+        switch base {
+        case .BYTE:
+            return "\(writer).writeByte(value: \(value))"
+        case .INT:
+            return "\(writer).writeInt(value: Int32(truncatingIfNeeded: \(value)))"
+        case .LONG:
+            return "\(writer).writeLong(value: Int64(\(value)))"
+        case .FLOAT:
+            return "\(writer).writeFloat(value: Float32(\(value)))"
+        case .DOUBLE:
+            return "\(writer).writeDouble(value: Float64(\(value)))"
+        case .BOOLEAN:
+            return "\(writer).writeBool(value: \(value))"
+        case .STRING:
+            return "\(writer).writeString(value: \(value))"
+        case .ENUM:
+            return "\(writer).writeInt(value: \(value).rawValue)"
+        default:
+            return "\(value).encodeTo(writer: \(writer))"
+        }
+    }
+
+    private static func decodeBase(base: AstBaseType, innerTy: String, reader: String) -> String {
+        switch base {
+        case .BYTE:
+            return "\(reader).readByte()"
+        case .INT:
+            return "Int(\(reader).readInt())"
+        case .LONG:
+            return "\(reader).readLong()"
+        case .FLOAT:
+            return "Double(\(reader).readFloat())"
+        case .DOUBLE:
+            return "\(reader).readDouble()"
+        case .BOOLEAN:
+            return "\(reader).readBool()"
+        case .STRING:
+            return "\(reader).readString()"
+        case .ENUM:
+            return "\(innerTy)(rawValue: \(reader).readInt())!"
+        default:
+            return "\(innerTy).decodeFrom(reader: \(reader))"
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//
+//  EnumGenerator.swift
+//  SwiftGenCore
+//
+
+import Foundation
+import SwiftPoet
+
+class EnumGenerator {
+    private let enumDesc: EnumDesc
+    private let libModName: String
+    private let isError: Bool
+
+    init(desc: EnumDesc, libModName: String, isError: Bool) {
+        self.enumDesc = desc
+        self.libModName = libModName
+        self.isError = isError
+    }
+
+    public func generate() -> EnumSpecBuilder {
+        let enumBuilder = self.enumDesc.is_c_like ? generateCLike() : generateWithData()
+        if self.isError {
+            enumBuilder.add(protocol: TypeName.init(keyword: "Error"))
+        }
+        return enumBuilder
+    }
+
+    // C-like enums are transferred by their discriminant, but encoded by name in json like serde does.
+    private func generateCLike() -> EnumSpecBuilder {
+        let enumBuilder = EnumSpec.builder(for: self.enumDesc.name)
+            .add(import: self.libModName)
+            .add(modifier: .Public)
+            .add(superType: TypeName.init(keyword: "Int32"))
+            .add(protocol: TypeName.init(keyword: "Codable"))
+
+        for variant in self.enumDesc.variants {
+            let field = FieldSpec.builder(for: variant.name)
+                .add(initializer: CodeBlock.builder().add(literal: "\(variant.value)").build())
+            enumBuilder.add(field: field.build())
+        }
+
+        let decodeBuilder = CodeBlock.builder()
+        decodeBuilder.add(codeLine: "let container = try decoder.singleValueContainer()")
+            .add(codeLine: "let name = try container.decode(String.self)")
+            .add(codeLine: "switch name {")
+        for variant in self.enumDesc.variants {
+            decodeBuilder.add(codeLine: "case \"\(variant.name)\": self = .\(variant.name)")
+        }
+        decodeBuilder.add(codeLine: "default: throw DecodingError.dataCorruptedError(in: container, debugDescription: \"unknown variant \\(name) for \(self.enumDesc.name)\")")
+            .add(codeLine: "}")
+
+        let encodeBuilder = CodeBlock.builder()
+        encodeBuilder.add(codeLine: "var container = encoder.singleValueContainer()")
+            .add(codeLine: "switch self {")
+        for variant in self.enumDesc.variants {
+            encodeBuilder.add(codeLine: "case .\(variant.name): try container.encode(\"\(variant.name)\")")
+        }
+        encodeBuilder.add(codeLine: "}")
+
+        enumBuilder.add(method: quoteDecodeMethod(codeBlock: decodeBuilder.build()).build())
+        enumBuilder.add(method: quoteEncodeMethod(codeBlock: encodeBuilder.build()).build())
+        return enumBuilder
+    }
+
+    // Data-carrying enums are transferred by json, tagged by "type" like serde does.
+    private func generateWithData() -> EnumSpecBuilder {
+        let enumBuilder = EnumSpec.builder(for: self.enumDesc.name)
+            .add(import: self.libModName)
+            .add(modifier: .Public)
+            .add(protocol: TypeName.init(keyword: "Codable"))
+
+        var keys = ["type"]
+        for variant in self.enumDesc.variants {
+            let field = FieldSpec.builder(for: variant.name)
+            if variant.fields.count > 0 {
+                field.add(associatedValues: variant.fields.map { arg -> TypeName in
+                    return TypeName.init(keyword: "\(arg.name): \(fieldType(field: arg))")
+                })
+            }
+            enumBuilder.add(field: field.build())
+
+            for arg in variant.fields {
+                if !keys.contains(arg.name) {
+                    keys.append(arg.name)
+                }
+            }
+        }
+
+        let keysBuilder = EnumSpec.builder(for: "CodingKeys")
+            .add(modifier: .Private)
+            .add(superType: TypeName.init(keyword: "String"))
+            .add(protocol: TypeName.init(keyword: "CodingKey"))
+        for key in keys {
+            keysBuilder.add(field: FieldSpec.builder(for: key).build())
+        }
+        enumBuilder.add(nestedType: keysBuilder.build())
+
+        let decodeBuilder = CodeBlock.builder()
+        decodeBuilder.add(codeLine: "let container = try decoder.container(keyedBy: CodingKeys.self)")
+            .add(codeLine: "let type = try container.decode(String.self, forKey: .type)")
+            .add(codeLine: "switch type {")
+        for variant in self.enumDesc.variants {
+            decodeBuilder.add(codeLine: "case \"\(variant.name)\":")
+            if variant.fields.count > 0 {
+                let values = variant.fields.map { arg -> String in
+                    return "\(arg.name): try container.decode(\(fieldType(field: arg)).self, forKey: .\(arg.name))"
+                }.joined(separator: ", ")
+                decodeBuilder.add(codeLine: "self = .\(variant.name)(\(values))")
+            } else {
+                decodeBuilder.add(codeLine: "self = .\(variant.name)")
+            }
+        }
+        decodeBuilder.add(codeLine: "default: throw DecodingError.dataCorruptedError(forKey: .type, in: container, debugDescription: \"unknown variant \\(type) for \(self.enumDesc.name)\")")
+            .add(codeLine: "}")
+
+        let encodeBuilder = CodeBlock.builder()
+        encodeBuilder.add(codeLine: "var container = encoder.container(keyedBy: CodingKeys.self)")
+            .add(codeLine: "switch self {")
+        for variant in self.enumDesc.variants {
+            if variant.fields.count > 0 {
+                let bindings = variant.fields.map { arg -> String in
+                    return "let \(arg.name)"
+                }.joined(separator: ", ")
+                encodeBuilder.add(codeLine: "case .\(variant.name)(\(bindings)):")
+            } else {
+                encodeBuilder.add(codeLine: "case .\(variant.name):")
+            }
+            encodeBuilder.add(codeLine: "try container.encode(\"\(variant.name)\", forKey: .type)")
+            for arg in variant.fields {
+                encodeBuilder.add(codeLine: "try container.encode(\(arg.name), forKey: .\(arg.name))")
+            }
+        }
+        encodeBuilder.add(codeLine: "}")
+
+        enumBuilder.add(method: quoteDecodeMethod(codeBlock: decodeBuilder.build()).build())
+        enumBuilder.add(method: quoteEncodeMethod(codeBlock: encodeBuilder.build()).build())
+        return enumBuilder
+    }
+
+    private func quoteDecodeMethod(codeBlock: CodeBlock) -> MethodSpecBuilder {
+        return MethodSpec.builder(for: "init")
+            .add(modifier: .Public)
+            .add(parameter: ParameterSpec.builder(for: "decoder", label: "from", type: TypeName.init(keyword: "Decoder")).build())
+            .add(throwable: true)
+            .add(codeBlock: codeBlock)
+    }
+
+    private func quoteEncodeMethod(codeBlock: CodeBlock) -> MethodSpecBuilder {
+        return MethodSpec.builder(for: "encode")
+            .add(modifier: .Public)
+            .add(parameter: ParameterSpec.builder(for: "encoder", label: "to", type: TypeName.init(keyword: "Encoder")).build())
+            .add(throwable: true)
+            .add(codeBlock: codeBlock)
+    }
+
+    private func fieldType(field: ArgDesc) -> String {
+        switch field.ty {
+        case .VEC(let base):
+            if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
+                return field.origin_ty.replacingOccurrences(of: "Vec", with: "Array")
+            }
+            return field.ty.toStr()
+        case .OPTIONAL(let base):
+            return base.toOptionalTypeName(originTy: field.origin_ty).toString()
+        case .MAP(_, _):
+            return field.ty.toMapTypeName(originTy: field.origin_ty).toString()
+        default:
+            return field.ty.toTypeName().toString()
+        }
+    }
+}
@@ -11,10 +11,12 @@ import SwiftPoet
 class StructGenerator {
     private let structDesc: StructDesc
     private let libModName: String
+    private let isError: Bool
     
-    init(desc: StructDesc, libModName: String) {
+    init(desc: StructDesc, libModName: String, isError: Bool) {
         self.structDesc = desc
         self.libModName = libModName
+        self.isError = isError
     }
     
     public func generate() -> StructSpecBuilder {
@@ -22,11 +24,70 @@ class StructGenerator {
             .add(import: self.libModName)
             .add(modifier: .Public)
             .add(superType: TypeName.init(keyword: "Codable"))
+        if self.isError {
+            classBuilder.add(superType: TypeName.init(keyword: "Error"))
+        }
         
+        // memberwise initializers are internal, so structs returned from callbacks need a public one.
+        let initBuilder = MethodSpec.builder(for: "init")
+            .add(modifier: .Public)
+        let initCodeBuilder = CodeBlock.builder()
         for field in self.structDesc.fields {
-            classBuilder.add(field: FieldSpec.builder(for: field.name, type: field.ty.toTypeName()).add(modifier: Modifier.Public).build())
+            let fieldType = fieldTypeName(field: field)
+            classBuilder.add(field: FieldSpec.builder(for: field.name, type: fieldType).add(modifier: Modifier.Public).build())
+            initBuilder.add(parameter: ParameterSpec.builder(for: field.name, type: fieldType).build())
+            initCodeBuilder.add(codeLine: "self.\(field.name) = \(field.name)")
+        }
+        initBuilder.add(codeBlock: initCodeBuilder.build())
+        classBuilder.add(method: initBuilder.build())
+        
+        if self.structDesc.binary {
+            quoteBinaryMethods(classBuilder: classBuilder)
         }
         
         return classBuilder
     }
+    
+    // Fields are written and read in the order they are declared, like the rust side does.
+    func quoteBinaryMethods(classBuilder: StructSpecBuilder) {
+        let encodeBuilder = MethodSpec.builder(for: "encodeTo")
+            .add(modifier: .Public)
+            .add(parameter: ParameterSpec.builder(for: "writer", type: TypeName.init(keyword: CodecGenerator.writerClass)).build())
+        let encodeCodeBuilder = CodeBlock.builder()
+        for field in self.structDesc.fields {
+            for line in CodecGenerator.encodeLines(ty: field.ty, originTy: field.origin_ty, value: "self.\(field.name)", writer: "writer") {
+                encodeCodeBuilder.add(codeLine: line)
+            }
+        }
+        encodeBuilder.add(codeBlock: encodeCodeBuilder.build())
+        classBuilder.add(method: encodeBuilder.build())
+        
+        let decodeBuilder = MethodSpec.builder(for: "decodeFrom")
+            .add(modifier: .Public)
+            .add(modifier: .Static)
+            .add(parameter: ParameterSpec.builder(for: "reader", type: TypeName.init(keyword: CodecGenerator.readerClass)).build())
+            .add(returnType: TypeName.init(keyword: self.structDesc.name))
+        let decodeCodeBuilder = CodeBlock.builder()
+        var initArgs = [String]()
+        for field in self.structDesc.fields {
+            decodeCodeBuilder.add(codeLine: "let v_\(field.name): \(fieldTypeName(field: field).toString()) = \(CodecGenerator.decodeExpr(ty: field.ty, originTy: field.origin_ty, reader: "reader"))")
+            initArgs.append("\(field.name): v_\(field.name)")
+        }
+        decodeCodeBuilder.add(codeLine: "return \(self.structDesc.name)(\(initArgs.joined(separator: ", ")))")
+        decodeBuilder.add(codeBlock: decodeCodeBuilder.build())
+        classBuilder.add(method: decodeBuilder.build())
+    }
+    
+    func fieldTypeName(field: ArgDesc) -> TypeName {
+        switch field.ty {
+        case .OPTIONAL(let base):
+            return base.toOptionalTypeName(originTy: field.origin_ty)
+        case .VEC(let base) where base == AstBaseType.STRUCT || base == AstBaseType.ENUM:
+            return TypeName.init(keyword: field.origin_ty.replacingOccurrences(of: "Vec", with: "Array"))
+        case .MAP(_, _):
+            return field.ty.toMapTypeName(originTy: field.origin_ty)
+        default:
+            return field.ty.toTypeName()
+        }
+    }
 }
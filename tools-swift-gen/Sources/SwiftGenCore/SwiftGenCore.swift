@@ -36,6 +36,7 @@ public final class SwiftGen {
         var callback_dic = [String: TraitDesc]()
         var allTraitDescs = [TraitDesc]()
         var allStructDescs = [StructDesc]()
+        var allEnumDescs = [EnumDesc]()
         
         let globalIndexSpec = FieldSpec.builder(for: "globalIndex", type: TypeName.init(keyword: "Int64"), construct: .mutableField)
             .add(parentType: .mutableField)
@@ -50,6 +51,15 @@ public final class SwiftGen {
         poetFile.append(globalCallbacksSpec.build())
         
         print("appedning global callbacks over")
+        
+        // panics in rust are thrown as RsbindError.panic.
+        let rsbindErrorSpec = EnumSpec.builder(for: "RsbindError")
+            .add(modifier: .Public)
+            .add(protocol: TypeName.init(keyword: "Error"))
+            .add(field: FieldSpec.builder(for: "panic")
+                .add(associatedValues: [TypeName.init(keyword: "message: String")])
+                .build())
+        poetFile.append(rsbindErrorSpec.build())
 
         enumerator?.forEach({ file in
             let filePath = (self.astDir as NSString).strings(byAppendingPaths: [file as! String])[0]
@@ -75,8 +85,22 @@ public final class SwiftGen {
                 allStructDescs.append(struct_)
             }
             
+            let enums = astResult.enums
+            for enum_ in enums {
+                allEnumDescs.append(enum_)
+            }
         })
         
+        // the codec classes are only needed by the binary wire format.
+        let hasBinary = allStructDescs.contains { $0.binary } || allTraitDescs.contains { desc in
+            desc.methods.contains { method in method.binary_return || method.args.contains { $0.binary } }
+        }
+        if hasBinary {
+            let codecGenerator = CodecGenerator(libModName: self.libModuleName)
+            poetFile.append(codecGenerator.generateWriter().build())
+            poetFile.append(codecGenerator.generateReader().build())
+        }
+        
         for desc in allTraitDescs {
             if desc.is_callback {
                 let traitGenerator = CallbackGenerator(desc: desc, libModName: self.libModuleName)
@@ -89,11 +113,25 @@ public final class SwiftGen {
             }
         }
         
+        // types used as error of Result<T, E> are thrown in swift.
+        var errorTypes = Set<String>()
+        for desc in allTraitDescs where !desc.is_callback {
+            for method in desc.methods where !method.origin_error_ty.isEmpty {
+                errorTypes.insert(method.origin_error_ty)
+            }
+        }
+        
         for struct_ in allStructDescs {
-            let structGenerator = StructGenerator(desc: struct_, libModName: self.libModuleName)
+            let structGenerator = StructGenerator(desc: struct_, libModName: self.libModuleName, isError: errorTypes.contains(struct_.name))
             let result = structGenerator.generate()
             poetFile.append(result.build())
         }
+        
+        for enum_ in allEnumDescs {
+            let enumGenerator = EnumGenerator(desc: enum_, libModName: self.libModuleName, isError: errorTypes.contains(enum_.name))
+            let result = enumGenerator.generate()
+            poetFile.append(result.build())
+        }
 
         
         let outFilePath = (self.outputDir as NSString).strings(byAppendingPaths: ["ffi.swift"])[0]
@@ -18,6 +18,7 @@ enum AstBaseType: String {
     case STRING = "String"
     case BOOLEAN = "Boolean"
     case STRUCT = "Struct"
+    case ENUM = "Enum"
 }
 
 extension AstBaseType {
@@ -41,12 +42,78 @@ extension AstBaseType {
             return AstBaseType.BOOLEAN
         case "Struct":
             return AstBaseType.STRUCT
+        case "Enum":
+            return AstBaseType.ENUM
         default:
             return AstBaseType.VOID
         }
     }
 }
 
+extension AstBaseType {
+    // Option<T> of primitives is transferred by a C struct of a presence flag and the value.
+    var isPrimitive: Bool {
+        switch self {
+        case .BYTE, .INT, .LONG, .FLOAT, .DOUBLE, .BOOLEAN:
+            return true
+        default:
+            return false
+        }
+    }
+    
+    // The C struct of Option<T> of primitives, booleans are transferred as Int32.
+    func toCOptionalStruct() -> String {
+        switch self {
+        case .BYTE:
+            return "RsbindOptionalI8"
+        case .LONG:
+            return "RsbindOptionalI64"
+        case .FLOAT:
+            return "RsbindOptionalF32"
+        case .DOUBLE:
+            return "RsbindOptionalF64"
+        default:
+            return "RsbindOptionalI32"
+        }
+    }
+    
+    func toCOptionalValueType() -> String {
+        switch self {
+        case .BYTE:
+            return "Int8"
+        case .LONG:
+            return "Int64"
+        case .FLOAT:
+            return "Float32"
+        case .DOUBLE:
+            return "Float64"
+        default:
+            return "Int32"
+        }
+    }
+    
+    // Swift type of Option<T>, the origin type is used for structs and enums.
+    func toOptionalTypeName(originTy: String) -> TypeName {
+        switch self {
+        case .BYTE:
+            return TypeName.init(keyword: "Int8", optional: true)
+        case .INT:
+            return TypeName.IntegerOptional
+        case .LONG:
+            return TypeName.LongOptional
+        case .FLOAT, .DOUBLE:
+            return TypeName.DoubleOptional
+        case .STRING:
+            return TypeName.StringOptional
+        case .BOOLEAN:
+            return TypeName.BooleanOptional
+        default:
+            let innerTy = originTy.replacingOccurrences(of: "Option<", with: "").replacingOccurrences(of: ">", with: "")
+            return TypeName.init(keyword: innerTy, optional: true)
+        }
+    }
+}
+
 enum AstType {
     case VOID
     case BYTE
@@ -57,8 +124,14 @@ enum AstType {
     case STRING
     case BOOLEAN
     case VEC(AstBaseType)
+    // Option<T>, None is transferred as nil pointer.
+    case OPTIONAL(AstBaseType)
+    // HashMap<K, V> or BTreeMap<K, V>, transferred by json.
+    case MAP(AstBaseType, AstBaseType)
     case CALLBACK(String)
     case STRUCT(String)
+    // Only C-like enums, data-carrying enums are transferred like structs.
+    case ENUM(String)
 }
 
 extension AstType {
@@ -84,6 +157,8 @@ extension AstType {
             return AstType.STRUCT(originTy)
         case "Callback":
             return AstType.CALLBACK(originTy)
+        case "Enum":
+            return AstType.ENUM(originTy)
         default:
             return AstType.VOID
         }
@@ -109,10 +184,16 @@ extension AstType {
             return "Boolean"
         case AstType.VEC(let baseType):
             return "[\(baseType.rawValue)]"
+        case AstType.OPTIONAL(let baseType):
+            return "\(baseType.rawValue)?"
+        case AstType.MAP(let keyType, let valueType):
+            return "[\(keyType.rawValue): \(valueType.rawValue)]"
         case AstType.CALLBACK(let str):
             return "\(str)"
         case AstType.STRUCT(let str):
             return "\(str)"
+        case AstType.ENUM(let str):
+            return "\(str)"
         }
     }
     
@@ -136,11 +217,48 @@ extension AstType {
                 return TypeName.AnyType
             case .VEC(_):
                 return TypeName.init(keyword: self.toStr())
+            case .OPTIONAL(let base):
+                return base.toOptionalTypeName(originTy: base.rawValue)
+            case .MAP(_, _):
+                return TypeName.init(keyword: self.toStr())
             case .CALLBACK(let str):
                 return TypeName.init(keyword: str)
             case .STRUCT(let str):
                 return TypeName.init(keyword: str)
+            case .ENUM(let str):
+                return TypeName.init(keyword: str)
+        }
+    }
+}
+
+extension AstType {
+    // Swift type of a map, integer keys are Int, which is encoded as json keys like String.
+    func toMapTypeName(originTy: String) -> TypeName {
+        guard case .MAP(let keyType, let valueType) = self else {
+            return self.toTypeName()
+        }
+        let generics = originTy.drop(while: { $0 != "<" }).dropFirst().dropLast()
+            .split(separator: ",")
+            .map { $0.trimmingCharacters(in: .whitespaces) }
+        let keyName = keyType == .STRING ? "String" : "Int"
+        var valueName = generics.count == 2 ? generics[1] : valueType.rawValue
+        switch valueType {
+        case .BYTE:
+            valueName = "Int8"
+        case .INT:
+            valueName = "Int"
+        case .LONG:
+            valueName = "Int64"
+        case .FLOAT, .DOUBLE:
+            valueName = "Double"
+        case .STRING:
+            valueName = "String"
+        case .BOOLEAN:
+            valueName = "Bool"
+        default:
+            break
         }
+        return TypeName.init(keyword: "[\(keyName): \(valueName)]")
     }
 }
 
@@ -148,13 +266,29 @@ struct ArgDesc {
     let name: String
     let ty: AstType
     let origin_ty: String
+    // encoded in the binary wire format instead of json.
+    let binary: Bool
+}
+
+// Static methods and constructors are called on the type, the others on an object.
+enum MethodKind: String {
+    case Static = "Static"
+    case Constructor = "Constructor"
+    case Ref = "Ref"
+    case RefMut = "RefMut"
 }
 
 struct MethodDesc {
     let name: String
+    let kind: MethodKind
     let return_type: AstType
     let origin_return_ty: String
+    // error type of Result<T, E>, VOID if the method can't fail.
+    let error_type: AstType
+    let origin_error_ty: String
     let args: [ArgDesc]
+    // the return value is encoded in the binary wire format instead of json.
+    let binary_return: Bool
 }
 
 struct TraitDesc {
@@ -162,6 +296,9 @@ struct TraitDesc {
     let mod_name: String
     let crate_name: String
     let is_callback: Bool
+    let is_object: Bool
+    // the callback protocol implemented by objects wrapping callbacks returned from rust.
+    let wraps_callback: String?
     let methods: [MethodDesc]
 }
 
@@ -170,9 +307,26 @@ struct StructDesc {
     let mod_name: String
     let crate_name: String
     let fields:[ArgDesc]
+    let binary: Bool
+}
+
+struct VariantDesc {
+    let name: String
+    let value: Int32
+    let fields: [ArgDesc]
+}
+
+struct EnumDesc {
+    let name: String
+    let mod_name: String
+    let crate_name: String
+    let is_c_like: Bool
+    let variants: [VariantDesc]
+    let binary: Bool
 }
 
 struct AstResult {
     let traits: [TraitDesc]
     let structs: [StructDesc]
+    let enums: [EnumDesc]
 }
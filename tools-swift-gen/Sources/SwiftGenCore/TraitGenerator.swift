@@ -24,16 +24,32 @@ class TraitGenerator {
             .add(import: self.libModName)
             .add(modifier: .Public);
         
+        if self.traitDesc.is_object {
+            quoteObjectMembers(classBuilder: classBuilder)
+        }
+        
+        // callbacks returned from rust are called like the ones implemented in swift.
+        if let callback = self.traitDesc.wraps_callback {
+            classBuilder.add(protocol: TypeName.init(keyword: callback))
+        }
+        
         for method in self.traitDesc.methods {
             print("generate swift codes for \(method.name)")
 
             let methodSpec = quoteMethodSig(method: method)
+            let isInstance = method.kind == .Ref || method.kind == .RefMut
 
             let codeBlockBuilder = CodeBlock.builder()
             let _ = quoteArgConvert(builder: codeBlockBuilder, methodDesc: method, callbacks: callbacks)
             
-            let impMethodName = "\(self.traitDesc.mod_name)_\(method.name)"
-            var argCalls = ""
+            var impMethodName = "\(self.traitDesc.mod_name)_\(method.name)"
+            if self.traitDesc.wraps_callback != nil {
+                impMethodName = "\(self.traitDesc.mod_name)_\(self.traitDesc.name)_\(method.name)"
+            }
+            var argCalls = isInstance ? "handle" : ""
+            if isInstance && method.args.count > 0 {
+                argCalls += ","
+            }
             var index = 0
             for arg in method.args {
                 if index != method.args.count - 1 {
@@ -43,6 +59,13 @@ class TraitGenerator {
                 }
                 index = index + 1
             }
+            
+            // errors of Result<T, E> are written to an out parameter.
+            let hasError = method.origin_error_ty != ""
+            if hasError {
+                codeBlockBuilder.add(codeLine: "var s_err: UnsafeMutablePointer<Int8>? = nil")
+                argCalls += argCalls.isEmpty ? "&s_err" : ", &s_err"
+            }
 
             switch method.return_type {
                 case AstType.VOID:
@@ -53,7 +76,19 @@ class TraitGenerator {
                         .add(codeLine: "let result = \(impMethodName)(\(argCalls))")
             }
             
-            let _ = quoteResultConvert(builder: codeBlockBuilder, methodDesc: method)
+            let _ = quotePanicThrow(builder: codeBlockBuilder)
+            if hasError {
+                let _ = quoteErrorThrow(builder: codeBlockBuilder, methodDesc: method)
+            }
+            
+            switch method.kind {
+            case .Constructor where method.name == "new":
+                codeBlockBuilder.add(codeLine: "self.init(handle: result)")
+            case .Constructor:
+                codeBlockBuilder.add(codeLine: "return \(self.traitDesc.name)(handle: result)")
+            default:
+                let _ = quoteResultConvert(builder: codeBlockBuilder, methodDesc: method)
+            }
 
             methodSpec.add(codeBlock: codeBlockBuilder.build())
             classBuilder.add(method: methodSpec.build())
@@ -62,37 +97,103 @@ class TraitGenerator {
         return classBuilder;
     }
     
+    // Objects hold the handle of a boxed rust object, which is freed in deinit.
+    func quoteObjectMembers(classBuilder: ClassSpecBuilder) {
+        let handleSpec = FieldSpec.builder(for: "handle", type: TypeName.LongType, construct: .mutableField)
+            .add(modifier: Modifier.Private)
+        classBuilder.add(field: handleSpec.build())
+        
+        let initSpec = MethodSpec.builder(for: "init")
+            .add(parameter: ParameterSpec.builder(for: "handle", type: TypeName.LongType).build())
+            .add(codeBlock: CodeBlock.builder().add(codeLine: "self.handle = handle").build())
+        classBuilder.add(method: initSpec.build())
+        
+        let freeMethod = "\(self.traitDesc.mod_name)_\(self.traitDesc.name)_free_object"
+        let deinitSpec = MethodSpec.builder(for: "deinit")
+            .add(codeBlock: CodeBlock.builder().add(codeLine: "\(freeMethod)(handle)").build())
+        classBuilder.add(method: deinitSpec.build())
+    }
+    
     func quoteMethodSig(method: MethodDesc) -> MethodSpecBuilder {
+        // `new` constructors are swift initializers, others are static factory methods.
+        if method.kind == .Constructor {
+            let isInit = method.name == "new"
+            let methodSpec = MethodSpec.builder(for: isInit ? "init" : method.name)
+                .add(modifier: .Public)
+                .add(throwable: true)
+            if isInit {
+                methodSpec.add(modifier: .Convenience)
+            } else {
+                methodSpec.add(modifier: .Static)
+                    .add(returnType: TypeName.init(keyword: self.traitDesc.name))
+            }
+            quoteArgsSig(methodSpec: methodSpec, method: method)
+            return methodSpec
+        }
+        
+        // methods of returned callbacks can't throw, as the callback protocol doesn't.
         let methodSpec = MethodSpec.builder(for: method.name)
             .add(modifier: .Public)
-            .add(modifier: .Static)
+            .add(throwable: self.traitDesc.wraps_callback == nil)
+        if method.kind == .Static {
+            methodSpec.add(modifier: .Static)
+        }
         
         var return_type = method.return_type.toTypeName()
         switch method.return_type {
             case AstType.VOID:
                 {}()
             case AstType.VEC(let base):
-                if base == AstBaseType.STRUCT {
+                if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
                     let return_ty_str = method.origin_return_ty.replacingOccurrences(of: "Vec", with: "Array")
                     return_type = TypeName.init(keyword: return_ty_str)
                 }
                 methodSpec.add(returnType: return_type)
+            case AstType.OPTIONAL(let base):
+                methodSpec.add(returnType: base.toOptionalTypeName(originTy: method.origin_return_ty))
+            case AstType.MAP(_, _):
+                methodSpec.add(returnType: method.return_type.toMapTypeName(originTy: method.origin_return_ty))
             default:
                 methodSpec.add(returnType: return_type)
         }
         
+        quoteArgsSig(methodSpec: methodSpec, method: method)
+        return methodSpec
+    }
+    
+    func quoteArgsSig(methodSpec: MethodSpecBuilder, method: MethodDesc) {
         method.args.forEach({ arg in
-            let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toTypeName()).build()
-            methodSpec.add(parameter: argSpec)
+            switch arg.ty {
+            case .VEC(let base):
+                if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
+                    let arg_ty_str = arg.origin_ty.replacingOccurrences(of: "Vec", with: "Array")
+                    let argSpec = ParameterSpec.builder(for: arg.name, type: TypeName.init(keyword: arg_ty_str)).build()
+                    methodSpec.add(parameter: argSpec)
+                } else {
+                    let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toTypeName()).build()
+                    methodSpec.add(parameter: argSpec)
+                }
+            case .OPTIONAL(let base):
+                let argSpec = ParameterSpec.builder(for: arg.name, type: base.toOptionalTypeName(originTy: arg.origin_ty)).build()
+                methodSpec.add(parameter: argSpec)
+            case .MAP(_, _):
+                let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toMapTypeName(originTy: arg.origin_ty)).build()
+                methodSpec.add(parameter: argSpec)
+            default:
+                let argSpec = ParameterSpec.builder(for: arg.name, type: arg.ty.toTypeName()).build()
+                methodSpec.add(parameter: argSpec)
+            }
         })
-        
-        return methodSpec
     }
     
     func quoteArgConvert(builder: CodeBlockBuilder, methodDesc: MethodDesc, callbacks: [String: TraitDesc]) -> CodeBlockBuilder {
         let crateName = self.traitDesc.crate_name.replacingOccurrences(of: "-", with: "_")    
         for arg in methodDesc.args {
             print("quoteArgConvert for \(arg.name)")
+            if arg.binary {
+                let _ = quoteBinaryArg(builder: builder, arg: arg)
+                continue
+            }
             switch arg.ty {
                 case AstType.BOOLEAN:
                      builder.add(codeLine: "let s_\(arg.name): Int32 = \(arg.name) ? 1 : 0")
@@ -108,6 +209,13 @@ class TraitGenerator {
                     builder.add(codeLine: "let s_\(arg.name) = Float64(\(arg.name))")
                 case AstType.STRING:
                     builder.add(codeLine: "let s_\(arg.name) = \(arg.name)")
+                case AstType.ENUM(_):
+                    builder.add(codeLine: "let s_\(arg.name) = \(arg.name).rawValue")
+                case AstType.OPTIONAL(let base) where base.isPrimitive:
+                    builder.add(codeLine: "let s_\(arg.name) = \(quoteOptionalToC(base: base, value: arg.name))")
+                case AstType.OPTIONAL(let base):
+                    // nil is transferred as nil pointer, and values are transferred as strings.
+                    builder.add(codeLine: "let s_\(arg.name) = \(quoteOptionalToStr(base: base, value: arg.name))")
                 case AstType.CALLBACK(let str):
                     print("found callback \(str)")
                     builder.add(codeLine: "let \(arg.name)_index = globalIndex + 1")
@@ -141,10 +249,15 @@ class TraitGenerator {
                             index = index + 1
                         }
                         
+                        // options of primitives are returned by an out pointer.
+                        if case AstType.OPTIONAL(let base) = method.return_type, base.isPrimitive {
+                            args_str = "\(args_str), UnsafeMutablePointer<\(base.toCOptionalStruct())>?"
+                            arg_params = "\(arg_params), out"
+                        }
                         args_str = "\(args_str))"
                         arg_params = "\(arg_params))"
 
-                        let return_type = mapCallbackType(type: method.return_type)
+                        let return_type = mapCallbackReturnType(type: method.return_type)
                         closue = "\(args_str) -> \(return_type)"
                         arg_params = "\(arg_params) -> \(return_type)"
 
@@ -171,6 +284,17 @@ class TraitGenerator {
                                     closureBuilder.add(codeLine: "let c_\(arg.name) = Double(\(arg.name))")
                                 case AstType.STRING:
                                     closureBuilder.add(codeLine: "let c_\(arg.name) = String(cString: \(arg.name)!)")
+                                case AstType.OPTIONAL(let base) where base.isPrimitive:
+                                    closureBuilder.add(codeLine: "let c_\(arg.name) = \(quoteOptionalFromC(base: base, value: arg.name))")
+                                case AstType.OPTIONAL(let base):
+                                    let optionalType = base.toOptionalTypeName(originTy: arg.origin_ty).toString()
+                                    closureBuilder.add(codeLine: "var c_\(arg.name): \(optionalType) = nil")
+                                        .add(codeLine: "if let \(arg.name) = \(arg.name) {")
+                                        .add(codeLine: "let c_tmp_\(arg.name) = String(cString: \(arg.name))")
+                                        .add(codeLine: "c_\(arg.name) = \(quoteOptionalParse(base: base, originTy: arg.origin_ty, value: "c_tmp_\(arg.name)"))")
+                                        .add(codeLine: "}")
+                                case AstType.ENUM(let str):
+                                    closureBuilder.add(codeLine: "let c_\(arg.name) = \(str)(rawValue: \(arg.name))!")
                                 case AstType.STRUCT(_):
                                     closureBuilder.add(codeLine: "let c_tmp_\(arg.name) = String(cString:\(arg.name)!)")
                                         .add(codeLine: "var c_option_\(arg.name): \(arg.ty.toStr())?")
@@ -182,7 +306,7 @@ class TraitGenerator {
                                         .add(codeLine: "let c_\(arg.name) = c_option_\(arg.name)!")
                                 case AstType.VEC(let base):
                                     var vecType = arg.ty.toStr()
-                                    if base == AstBaseType.STRUCT {
+                                    if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
                                         vecType = arg.origin_ty.replacingOccurrences(of: "Vec", with: "Array")
                                     }
                                     
@@ -194,6 +318,10 @@ class TraitGenerator {
                                         .add(codeLine: "c_option_\(arg.name) = try! decoder.decode(\(vecType).self, from: c_tmp_json_\(arg.name))")
                                         .add(codeLine: "}")
                                         .add(codeLine: "let c_\(arg.name) = c_option_\(arg.name)!")
+                                case AstType.MAP(_, _):
+                                    let mapType = arg.ty.toMapTypeName(originTy: arg.origin_ty).toString()
+                                    closureBuilder.add(codeLine: "let c_tmp_\(arg.name) = String(cString:\(arg.name)!)")
+                                        .add(codeLine: "let c_\(arg.name) = try! JSONDecoder().decode(\(mapType).self, from: c_tmp_\(arg.name).data(using: .utf8)!)")
                                 default:
                                     print("don't support \(arg.origin_ty) in callback")
                                     assert(false)
@@ -227,8 +355,19 @@ class TraitGenerator {
                                 closureBuilder.add(codeLine: "return Float32(result)")
                             case AstType.DOUBLE:
                                 closureBuilder.add(codeLine: "return Float64(result)")
+                            // strings are copied by strdup, and freed by callback_free_ptr after rust copies them.
                             case AstType.STRING:
-                                closureBuilder.add(codeLine: "return result")
+                                closureBuilder.add(codeLine: "return strdup(result)")
+                            case AstType.VEC(_), AstType.STRUCT(_):
+                                closureBuilder.add(codeLine: "let result_data = try! JSONEncoder().encode(result)")
+                                    .add(codeLine: "return strdup(String(data: result_data, encoding: .utf8)!)")
+                            case AstType.OPTIONAL(let base) where base.isPrimitive:
+                                closureBuilder.add(codeLine: "out!.pointee = \(quoteOptionalToC(base: base, value: "result"))")
+                            case AstType.OPTIONAL(let base):
+                                closureBuilder.add(codeLine: "let result_str = \(quoteOptionalToStr(base: base, value: "result"))")
+                                    .add(codeLine: "return result_str.flatMap { strdup($0) }")
+                            case AstType.ENUM(_):
+                                closureBuilder.add(codeLine: "return result.rawValue")
                             case AstType.VOID:
                                 break
                             default:
@@ -247,25 +386,43 @@ class TraitGenerator {
                     builder.add(codeLine: "(index) in")
                     builder.add(codeLine: "globalCallbacks.removeValue(forKey: index)")
                     builder.add(codeLine: "}")                
-                    builder.add(codeLine: "let s_\(arg.name) = \(traitDesc.mod_name)_\(callback!.name)_Model(\(modelArgs)free_callback: callback_free, index: \(arg.name)_index)")
+                    builder.add(codeLine: "let callback_free_ptr : @convention(c)(UnsafeMutablePointer<Int8>?) -> () = {")
+                    builder.add(codeLine: "(ptr) in")
+                    builder.add(codeLine: "free(ptr)")
+                    builder.add(codeLine: "}")
+                    builder.add(codeLine: "let s_\(arg.name) = \(traitDesc.mod_name)_\(callback!.name)_Model(\(modelArgs)free_callback: callback_free, free_ptr: callback_free_ptr, index: \(arg.name)_index)")
 
-                case AstType.VEC(_):
+                case AstType.VEC(_), AstType.STRUCT(_), AstType.MAP(_, _):
                     builder.add(codeLine: "let encoder = JSONEncoder()")
                     builder.add(codeLine: "let data_\(arg.name) = try! encoder.encode(\(arg.name))")
                     builder.add(codeLine: "let s_\(arg.name) = String(data: data_\(arg.name), encoding: .utf8)!")
                 case AstType.VOID:
                     {}()
-                case AstType.STRUCT(_):
-                    {}()
             }
         }
         
         return builder
     }
     
+    // Binary args are written to a buffer only borrowed by rust, so it is freed after the call.
+    func quoteBinaryArg(builder: CodeBlockBuilder, arg: ArgDesc) -> CodeBlockBuilder {
+        builder.add(codeLine: "let \(arg.name)_writer = \(CodecGenerator.writerClass)()")
+        for line in CodecGenerator.encodeLines(ty: arg.ty, originTy: arg.origin_ty, value: arg.name, writer: "\(arg.name)_writer") {
+            builder.add(codeLine: line)
+        }
+        builder.add(codeLine: "let \(arg.name)_bytes = \(arg.name)_writer.bytes")
+            .add(codeLine: "let \(arg.name)_ptr = UnsafeMutablePointer<UInt8>.allocate(capacity: \(arg.name)_bytes.count)")
+            .add(codeLine: "\(arg.name)_ptr.initialize(from: \(arg.name)_bytes, count: \(arg.name)_bytes.count)")
+            .add(codeLine: "defer { \(arg.name)_ptr.deallocate() }")
+            .add(codeLine: "let s_\(arg.name) = RsbindBuffer(ptr: \(arg.name)_ptr, len: Int32(\(arg.name)_bytes.count))")
+        return builder
+    }
+    
     func mapCallbackType(type: AstType) -> String {
         switch type {
-        case AstType.BOOLEAN:
+        case AstType.OPTIONAL(let base) where base.isPrimitive:
+            return base.toCOptionalStruct()
+        case AstType.BOOLEAN, AstType.ENUM(_):
             return "Int32"
         case AstType.BYTE:
             return "Int8"
@@ -281,7 +438,7 @@ class TraitGenerator {
             return "UnsafePointer<Int8>?"
         case AstType.VOID:
             return "()"
-        case AstType.VEC(_), AstType.STRUCT(_):
+        case AstType.VEC(_), AstType.STRUCT(_), AstType.OPTIONAL(_), AstType.MAP(_, _):
             return "UnsafePointer<Int8>?"
         default:
             print("don't support \(type) in callback")
@@ -290,8 +447,120 @@ class TraitGenerator {
         }
     }
     
+    // Strings returned from callbacks are allocated in swift, and freed after rust copies them.
+    func mapCallbackReturnType(type: AstType) -> String {
+        switch type {
+        case AstType.OPTIONAL(let base) where base.isPrimitive:
+            return "()"
+        case AstType.STRING, AstType.VEC(_), AstType.STRUCT(_), AstType.OPTIONAL(_):
+            return "UnsafeMutablePointer<Int8>?"
+        default:
+            return mapCallbackType(type: type)
+        }
+    }
+    
+    // Panics caught in rust are kept until they are taken and thrown here.
+    func quotePanicThrow(builder: CodeBlockBuilder) -> CodeBlockBuilder {
+        let crateName = self.traitDesc.crate_name.replacingOccurrences(of: "-", with: "_")
+        builder.add(codeLine: "if let panic = \(crateName)_take_panic() {")
+            .add(codeLine: "let panic_str = String(cString: panic)")
+            .add(codeLine: "\(crateName)_free_str(panic)")
+        if self.traitDesc.wraps_callback != nil {
+            builder.add(codeLine: "fatalError(panic_str)")
+        } else {
+            builder.add(codeLine: "throw RsbindError.panic(message: panic_str)")
+        }
+        builder.add(codeLine: "}")
+        return builder
+    }
+    
+    func quoteErrorThrow(builder: CodeBlockBuilder, methodDesc: MethodDesc) -> CodeBlockBuilder {
+        let crateName = self.traitDesc.crate_name.replacingOccurrences(of: "-", with: "_")
+        let errorTy = methodDesc.origin_error_ty
+        builder.add(codeLine: "if let err = s_err {")
+            .add(codeLine: "let err_str = String(cString: err)")
+            .add(codeLine: "\(crateName)_free_str(err)")
+        switch methodDesc.error_type {
+        case AstType.ENUM(_):
+            builder.add(codeLine: "throw \(errorTy)(rawValue: Int32(err_str)!)!")
+        default:
+            builder.add(codeLine: "throw try JSONDecoder().decode(\(errorTy).self, from: err_str.data(using: .utf8)!)")
+        }
+        builder.add(codeLine: "}")
+        return builder
+    }
+    
+    // Convert an Option<T> of primitives to the C struct of a presence flag and the value.
+    func quoteOptionalToC(base: AstBaseType, value: String) -> String {
+        let cValue: String
+        switch base {
+        case .BOOLEAN:
+            cValue = "(\(value) ?? false) ? 1 : 0"
+        default:
+            cValue = "\(base.toCOptionalValueType())(\(value) ?? 0)"
+        }
+        return "\(base.toCOptionalStruct())(is_some: \(value) == nil ? 0 : 1, value: \(cValue))"
+    }
+    
+    func quoteOptionalFromC(base: AstBaseType, value: String) -> String {
+        switch base {
+        case .BOOLEAN:
+            return "\(value).is_some != 0 ? \(value).value > 0 : nil"
+        default:
+            let swiftType = base.toOptionalTypeName(originTy: "").keyword
+            return "\(value).is_some != 0 ? \(swiftType)(\(value).value) : nil"
+        }
+    }
+    
+    // Parse the string transferred from rust to the inner value of an Option<T>.
+    func quoteOptionalToStr(base: AstBaseType, value: String) -> String {
+        switch base {
+        case .STRING:
+            return value
+        case .ENUM:
+            return "\(value).map { String($0.rawValue) }"
+        case .BOOLEAN:
+            return "\(value).map { $0 ? \"true\" : \"false\" }"
+        case .STRUCT:
+            return "\(value).map { String(data: try! JSONEncoder().encode($0), encoding: .utf8)! }"
+        default:
+            return "\(value).map { String($0) }"
+        }
+    }
+    
+    func quoteOptionalParse(base: AstBaseType, originTy: String, value: String) -> String {
+        let innerTy = originTy.replacingOccurrences(of: "Option<", with: "").replacingOccurrences(of: ">", with: "")
+        switch base {
+        case .STRING:
+            return value
+        case .BYTE:
+            return "Int8(\(value))!"
+        case .INT:
+            return "Int(\(value))!"
+        case .LONG:
+            return "Int64(\(value))!"
+        case .FLOAT, .DOUBLE:
+            return "Double(\(value))!"
+        case .BOOLEAN:
+            return "\(value) == \"true\""
+        case .ENUM:
+            return "\(innerTy)(rawValue: Int32(\(value))!)!"
+        default:
+            return "try! JSONDecoder().decode(\(innerTy).self, from: \(value).data(using: .utf8)!)"
+        }
+    }
+    
     func quoteResultConvert(builder: CodeBlockBuilder, methodDesc: MethodDesc) -> CodeBlockBuilder {
         let crateName = self.traitDesc.crate_name.replacingOccurrences(of: "-", with: "_")
+        // binary results are copied out of the buffer allocated by rust before it is freed.
+        if methodDesc.binary_return {
+            builder.add(codeLine: "let ret_bytes = result.ptr == nil ? [UInt8]() : Array(UnsafeBufferPointer(start: result.ptr, count: Int(result.len)))")
+                .add(codeLine: "\(crateName)_free_buffer(result)")
+                .add(codeLine: "let reader = \(CodecGenerator.readerClass)(bytes: ret_bytes)")
+                .add(codeLine: "let s_result = \(CodecGenerator.decodeExpr(ty: methodDesc.return_type, originTy: methodDesc.origin_return_ty, reader: "reader"))")
+                .add(codeLine: "return s_result")
+            return builder
+        }
         switch methodDesc.return_type {
             case AstType.BOOLEAN:
                 builder.add(codeLine: "let s_result = result > 0 ? true : false")
@@ -310,7 +579,7 @@ class TraitGenerator {
                     .add(codeLine: "\(crateName)_free_str(result!)")
             case AstType.VEC(let base):
                 var vecType = methodDesc.return_type.toStr()
-                if base == AstBaseType.STRUCT {
+                if base == AstBaseType.STRUCT || base == AstBaseType.ENUM {
                     vecType = methodDesc.origin_return_ty.replacingOccurrences(of: "Vec", with: "Array")
                 }
                 builder.add(codeLine: "let ret_str = String(cString:result!)")
@@ -333,10 +602,28 @@ class TraitGenerator {
                     .add(codeLine: "}")
                     .add(codeLine: "let s_result = s_tmp_result!")
 
+            case AstType.ENUM(let str):
+                builder.add(codeLine: "let s_result = \(str)(rawValue: result)!")
+            case AstType.OPTIONAL(let base) where base.isPrimitive:
+                let optionalType = base.toOptionalTypeName(originTy: methodDesc.origin_return_ty).toString()
+                builder.add(codeLine: "let s_result: \(optionalType) = \(quoteOptionalFromC(base: base, value: "result"))")
+            case AstType.OPTIONAL(let base):
+                let optionalType = base.toOptionalTypeName(originTy: methodDesc.origin_return_ty).toString()
+                builder.add(codeLine: "var s_result: \(optionalType) = nil")
+                    .add(codeLine: "if let result = result {")
+                    .add(codeLine: "let ret_str = String(cString:result)")
+                    .add(codeLine: "\(crateName)_free_str(result)")
+                    .add(codeLine: "s_result = \(quoteOptionalParse(base: base, originTy: methodDesc.origin_return_ty, value: "ret_str"))")
+                    .add(codeLine: "}")
+            case AstType.MAP(_, _):
+                let mapType = methodDesc.return_type.toMapTypeName(originTy: methodDesc.origin_return_ty).toString()
+                builder.add(codeLine: "let ret_str = String(cString:result!)")
+                    .add(codeLine: "\(crateName)_free_str(result!)")
+                    .add(codeLine: "let s_result = try! JSONDecoder().decode(\(mapType).self, from: ret_str.data(using: .utf8)!)")
             case AstType.VOID:
                 {}()
-            case AstType.CALLBACK(_):
-                {}()
+            case AstType.CALLBACK(let str):
+                builder.add(codeLine: "let s_result = Rust\(str)(handle: result)")
         }
 
         switch methodDesc.return_type {
@@ -90,6 +90,12 @@ open class MethodSpec: PoetSpec, MethodSpecProtocol {
         emitter.emit(modifiers: modifiers)
 
         let cbBuilder = CodeBlock.builder()
+        if name == "deinit" {
+            cbBuilder.add(literal: name)
+            emitter.emit(codeBlock: cbBuilder.build())
+            return
+        }
+
         if name != "init" && name != "init?" {
             cbBuilder.add(literal: construct)
         }